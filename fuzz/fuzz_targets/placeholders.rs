@@ -0,0 +1,29 @@
+#![no_main]
+
+use std::collections::BTreeMap;
+
+use libfuzzer_sys::fuzz_target;
+use pacs_core::{Segment, placeholder_segments, render_placeholders};
+
+fuzz_target!(|src: &str| {
+    let segments: Vec<Segment> = placeholder_segments(src).collect();
+
+    let joined: String = segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => (*text).to_string(),
+            Segment::Placeholder(key) => format!("{{{{{key}}}}}"),
+        })
+        .collect();
+    assert_eq!(joined, src);
+
+    let (rendered, unresolved) = render_placeholders(src, &BTreeMap::new());
+    assert_eq!(rendered, src);
+    assert_eq!(
+        unresolved.len(),
+        segments
+            .iter()
+            .filter(|s| matches!(s, Segment::Placeholder(_)))
+            .count()
+    );
+});
@@ -1,20 +1,38 @@
 #![allow(dead_code)]
 #![allow(clippy::missing_errors_doc)]
 #![allow(clippy::too_many_lines)]
+#![allow(clippy::struct_excessive_bools)]
+mod messages;
+
 use std::collections::BTreeMap;
 use std::env;
 use std::fmt::Write;
 use std::fs;
-use std::io::{self, Write as IoWrite};
+use std::io::{self, IsTerminal as _, Write as IoWrite};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum, ValueHint};
 use clap_complete::{ArgValueCandidates, CompletionCandidate};
 
-use pacs_core::{Pacs, PacsCommand};
+use serde::{Deserialize, Serialize};
+use tracing::level_filters::LevelFilter;
+
+use messages::{Locale, Msg};
+
+use pacs_core::{
+    BodyCleanup, Bundle, BundleConflict, CWD_KEY, CacheState, CommandView, DiffLine, ExpiryStatus,
+    ExportFormat, HookOutput, Pacs, PacsCommand, PacsError, Project, ProjectNames, Registry,
+    RegistrySource, Resolution, SearchMatch, Setting, SettingSource, Shell, TagStyle, TokenKind,
+    Versioned, explain_command, flatten_command, parse_aliases, parse_date, parse_env_file,
+    parse_interval, project_dir, render_placeholders, resolve_date, search_templates, shell_quote,
+    templates, today, tokenize_shell_line, unified_diff, unix_now, variable_name,
+};
 
 const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
 const GREEN: &str = "\x1b[32m";
 const BLUE: &str = "\x1b[34m";
 const YELLOW: &str = "\x1b[33m";
@@ -24,6 +42,14 @@ const WHITE: &str = "\x1b[37m";
 const GREY: &str = "\x1b[90m";
 const RESET: &str = "\x1b[0m";
 
+/// Minimum similarity of a new body to an existing one for `pacs add` to warn.
+const SIMILAR_BODY_THRESHOLD: f64 = 0.9;
+
+/// Registries are refetched when listing or searching if their copy is older than this.
+const REGISTRY_MAX_AGE: Duration = Duration::from_hours(24);
+/// Largest output `pacs run --copy-output` puts on the clipboard.
+const COPY_OUTPUT_LIMIT: usize = 1024 * 1024;
+
 /// A command-line tool for managing and running saved shell commands.
 #[derive(Parser, Debug)]
 #[command(name = "pacs")]
@@ -33,6 +59,29 @@ pub struct Cli {
     #[arg(long)]
     pub ui: bool,
 
+    /// Log loaded and saved files and spawned processes, -vv also logs how placeholders resolved
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Hide logs and warnings
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Show commands resolved instead of running them, e.g. on demo machines
+    #[arg(long, global = true)]
+    pub safe: bool,
+
+    /// Show secret values instead of their references and masks
+    #[arg(long, global = true)]
+    pub reveal: bool,
+
+    // Not `global`, as `pacs project add -p` is the project's path. The `-p`
+    // of subcommands that take a project after their name stays for scripts
+    // using that spelling, and scopes the same way.
+    /// Project to use instead of the active one, for any subcommand
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -45,6 +94,9 @@ pub enum Commands {
     /// Add a new command
     Add(AddArgs),
 
+    /// Draft a command from a description with the endpoint in config.toml, then review it in $EDITOR
+    Suggest(SuggestArgs),
+
     /// Remove a command
     #[command(visible_alias = "rm")]
     Remove(RemoveArgs),
@@ -55,8 +107,14 @@ pub enum Commands {
     /// Rename a command
     Rename(RenameArgs),
 
-    /// List commands
-    #[command(visible_alias = "ls")]
+    /// Protect a command from edits, renames and removal
+    Lock(LockArgs),
+
+    /// Allow changing a locked command again
+    Unlock(LockArgs),
+
+    /// List commands, or show one
+    #[command(visible_aliases = ["ls", "show"])]
     List(ListArgs),
 
     /// Run a saved command
@@ -66,9 +124,57 @@ pub enum Commands {
     #[command(visible_alias = "cp")]
     Copy(CopyArgs),
 
+    /// Print one field of a command without decoration, for scripts
+    Get(GetArgs),
+
     /// Search commands by name or content
     Search(SearchArgs),
 
+    /// Explain what the programs, subcommands and flags of a command do
+    Explain(ExplainArgs),
+
+    /// Export a project's commands to another format
+    Export(ExportArgs),
+
+    /// Import projects exported with `export --all`, or shell aliases
+    Import(ImportArgs),
+
+    /// Find commands with identical or similar bodies and merge them
+    Dedupe(DedupeArgs),
+
+    /// Report names that more than one command or alias answers to
+    Conflicts,
+
+    /// List commands that have not been run recently
+    Prune(PruneArgs),
+
+    /// Report commands that use keys missing from their `requires`
+    Lint(LintArgs),
+
+    /// Report commands that have expired or are due for review
+    Doctor,
+
+    /// Apply the history retention limits and compact the history file
+    Gc,
+
+    /// Show the settings in config.toml
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Manage the cache of names used by shell completion
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+
+    /// Start a subshell that uses a project and environment without switching them
+    Shell(ShellArgs),
+
+    /// Edit the file of a project or the config in $EDITOR
+    Open(OpenArgs),
+
     /// Manage projects
     #[command(visible_alias = "p")]
     Project {
@@ -82,6 +188,61 @@ pub enum Commands {
         #[command(subcommand)]
         command: EnvCommands,
     },
+
+    /// Manage read-only remote command registries
+    Registry {
+        #[command(subcommand)]
+        command: RegistryCommands,
+    },
+
+    /// Review a registry command and allow it to run
+    Approve(ApproveArgs),
+
+    /// Browse and copy built-in command templates
+    #[command(visible_alias = "t")]
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommands,
+    },
+
+    /// Manage the run history
+    History {
+        #[command(subcommand)]
+        command: HistoryCommands,
+    },
+
+    /// Resolve git merge conflicts in synced project files
+    Sync {
+        #[command(subcommand)]
+        command: SyncCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Print the settings and where their values come from
+    Show(ConfigShowArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ConfigShowArgs {
+    /// Include defaults, environment variables and flags, not only config.toml
+    #[arg(long)]
+    pub effective: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommands {
+    /// Regenerate the cache from all projects
+    Rebuild,
+    /// Show whether the cache is up to date
+    Status,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryCommands {
+    /// Remove recorded runs
+    Clear(HistoryClearArgs),
 }
 
 #[derive(Subcommand, Debug)]
@@ -95,7 +256,16 @@ pub enum ProjectCommands {
 
     /// List all projects
     #[command(visible_alias = "ls")]
-    List,
+    List(ProjectListArgs),
+
+    /// Hide a finished project from listings, the TUI and completion
+    Archive(ProjectArchiveArgs),
+
+    /// Show an archived project again
+    Unarchive(ProjectArchiveArgs),
+
+    /// Set the color and icon a project is shown with
+    Style(ProjectStyleArgs),
 
     /// Switch to a project
     Switch(ProjectSwitchArgs),
@@ -105,6 +275,18 @@ pub enum ProjectCommands {
 
     /// Show the current active project
     Active,
+
+    /// Publish a project to a secret GitHub gist
+    Publish(ProjectPublishArgs),
+
+    /// Create or replace a project from a GitHub gist
+    Pull(ProjectPullArgs),
+
+    /// Show or edit the markdown notes of a project
+    Notes(ProjectNotesArgs),
+
+    /// Open the directory of a project in an editor or the file manager
+    Open(ProjectOpenArgs),
 }
 
 #[derive(Subcommand, Debug)]
@@ -116,6 +298,10 @@ pub enum EnvCommands {
     #[command(visible_alias = "rm")]
     Remove(EnvRemoveArgs),
 
+    /// Copy an environment with its values to another project
+    #[command(visible_alias = "cp")]
+    Copy(EnvCopyArgs),
+
     /// Edit an environment's values (opens editor)
     Edit(EnvEditArgs),
 
@@ -128,6 +314,99 @@ pub enum EnvCommands {
 
     /// Show the active environment for a project
     Active(EnvActiveArgs),
+
+    /// Export environment values for other tools
+    Export(EnvExportArgs),
+
+    /// List the commands that use a key
+    Uses(EnvUsesArgs),
+
+    /// Rename a key in all environments and commands of a project
+    RenameKey(EnvRenameKeyArgs),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RegistryCommands {
+    /// Subscribe to a registry
+    Add(RegistryAddArgs),
+
+    /// Unsubscribe from a registry
+    #[command(visible_alias = "rm")]
+    Remove(RegistryRemoveArgs),
+
+    /// List subscribed registries
+    #[command(visible_alias = "ls")]
+    List,
+
+    /// Fetch the latest commands of all registries
+    Update,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TemplateCommands {
+    /// List built-in templates, optionally filtered by a search query
+    #[command(visible_alias = "ls")]
+    List(TemplateListArgs),
+
+    /// Copy a template into a project
+    Add(TemplateAddArgs),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SyncCommands {
+    /// Merge project files with git conflict markers command by command
+    Resolve,
+}
+
+#[derive(Args, Debug)]
+pub struct TemplateListArgs {
+    /// Fuzzy search query matched against name, tag, description and command
+    pub query: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct TemplateAddArgs {
+    /// Name of the template
+    #[arg(add = ArgValueCandidates::new(complete_templates))]
+    pub template: String,
+
+    /// Name for the command (defaults to the template name)
+    pub name: Option<String>,
+
+    /// Add to a specific project
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct RegistryAddArgs {
+    /// HTTP(S) URL of a TOML file, or a git repository containing pacs.toml
+    pub url: String,
+
+    /// Name of the registry (derived from the URL if omitted)
+    #[arg(short, long)]
+    pub name: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct RegistryRemoveArgs {
+    /// Name of the registry to remove
+    #[arg(add = ArgValueCandidates::new(complete_registries))]
+    pub name: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ApproveArgs {
+    /// Name of the registry command to approve
+    pub name: String,
+
+    /// Registry providing the command (the first one that has it if omitted)
+    #[arg(short, long, add = ArgValueCandidates::new(complete_registries))]
+    pub registry: Option<String>,
+
+    /// Approve without asking, e.g. after reviewing the registry file
+    #[arg(short, long)]
+    pub yes: bool,
 }
 
 #[derive(Args, Debug)]
@@ -135,9 +414,22 @@ pub struct ProjectAddArgs {
     /// Name of the project
     pub name: String,
 
-    /// Path associated with the project
-    #[arg(short, long)]
+    /// Directory of the project, stored as an absolute path
+    #[arg(short, long, value_hint = ValueHint::DirPath)]
     pub path: Option<String>,
+
+    /// Create the directory given with --path if it does not exist
+    #[arg(long, requires = "path")]
+    pub create: bool,
+
+    /// Seed the project from a template: rust, node, python, a template in
+    /// ~/.pacs/templates or a path to a TOML file
+    #[arg(short, long, add = ArgValueCandidates::new(complete_project_templates))]
+    pub template: Option<String>,
+
+    /// Keep the project out of bundles and gists
+    #[arg(long)]
+    pub local_only: bool,
 }
 
 #[derive(Args, Debug)]
@@ -147,11 +439,100 @@ pub struct ProjectRemoveArgs {
     pub name: String,
 }
 
+#[derive(Args, Debug)]
+pub struct ProjectListArgs {
+    /// List the most recently switched to projects first
+    #[arg(short, long)]
+    pub recent: bool,
+
+    /// Include archived projects
+    #[arg(short, long)]
+    pub archived: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectStyleArgs {
+    /// Name of the project (defaults to active project if omitted)
+    #[arg(add = ArgValueCandidates::new(complete_projects))]
+    pub name: Option<String>,
+
+    /// A color name like red or bright-blue, or a hex color like #ff8800 ("" removes it)
+    #[arg(short, long)]
+    pub color: Option<String>,
+
+    /// Text shown before the name, e.g. an emoji or nerd font glyph ("" removes it)
+    #[arg(short, long)]
+    pub icon: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectArchiveArgs {
+    /// Name of the project
+    #[arg(add = ArgValueCandidates::new(complete_projects))]
+    pub name: String,
+}
+
 #[derive(Args, Debug)]
 pub struct ProjectSwitchArgs {
-    /// Name of the project to switch to
+    /// Name of the project to switch to, or - for the previous one
     #[arg(add = ArgValueCandidates::new(complete_projects))]
     pub name: String,
+
+    /// Skip the command the project runs when switched to
+    #[arg(long)]
+    pub no_hooks: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectPublishArgs {
+    /// Name of the project to publish (defaults to active project if omitted)
+    #[arg(add = ArgValueCandidates::new(complete_projects))]
+    pub name: Option<String>,
+
+    /// Publish to a secret gist (requires a GitHub token in config.toml or the environment)
+    #[arg(long, required = true)]
+    pub gist: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectPullArgs {
+    /// Id of the gist to pull the project from
+    #[arg(long)]
+    pub gist: String,
+
+    /// Replace a local project of the same name
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectNotesArgs {
+    /// Open the notes in $EDITOR instead of printing them
+    pub action: Option<NotesAction>,
+
+    /// Target project (defaults to active project if omitted)
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectOpenArgs {
+    /// Name of the project (defaults to active project if omitted)
+    #[arg(add = ArgValueCandidates::new(complete_projects))]
+    pub name: Option<String>,
+
+    /// Open it in $VISUAL, $EDITOR or `code` (the default)
+    #[arg(long, conflicts_with = "finder")]
+    pub editor: bool,
+
+    /// Open it in the file manager
+    #[arg(long)]
+    pub finder: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum NotesAction {
+    Edit,
 }
 
 #[derive(Args, Debug)]
@@ -174,6 +555,24 @@ pub struct EnvRemoveArgs {
     pub project: Option<String>,
 }
 
+#[derive(Args, Debug)]
+pub struct EnvCopyArgs {
+    /// Environment name to copy
+    pub name: String,
+
+    /// Source project (defaults to active project if omitted)
+    #[arg(long, add = ArgValueCandidates::new(complete_projects))]
+    pub from: Option<String>,
+
+    /// Target project
+    #[arg(long, add = ArgValueCandidates::new(complete_projects))]
+    pub to: String,
+
+    /// Replace an environment of the same name in the target project
+    #[arg(short, long)]
+    pub force: bool,
+}
+
 #[derive(Args, Debug)]
 pub struct EnvEditArgs {
     /// Target project (defaults to active project if omitted)
@@ -186,6 +585,37 @@ pub struct EnvListArgs {
     /// Target project (defaults to active project if omitted)
     #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
     pub project: Option<String>,
+
+    /// List the environments of all projects in one table
+    #[arg(short, long, conflicts_with = "project")]
+    pub all: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvUsesArgs {
+    /// Key as written in placeholders, e.g. host for {{host}}
+    pub key: String,
+
+    /// Target project (defaults to active project if omitted)
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvRenameKeyArgs {
+    /// Current key
+    pub old: String,
+
+    /// New key
+    pub new: String,
+
+    /// Target project (defaults to active project if omitted)
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
+    /// Only show the changes to the project file
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
 }
 
 #[derive(Args, Debug)]
@@ -205,10 +635,42 @@ pub struct EnvActiveArgs {
     pub project: Option<String>,
 }
 
+#[derive(Args, Debug)]
+pub struct EnvExportArgs {
+    /// Export as a direnv .envrc block
+    #[arg(long, required = true)]
+    pub direnv: bool,
+
+    /// Environment to export (defaults to the active environment)
+    #[arg(short = 'e', long = "env", add = ArgValueCandidates::new(complete_environments))]
+    pub environment: Option<String>,
+
+    /// Target project (defaults to active project if omitted)
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
+    /// Write the block into an .envrc file instead of printing it
+    #[arg(
+        short,
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        default_missing_value = ".envrc",
+        conflicts_with = "environment"
+    )]
+    pub write: Option<PathBuf>,
+
+    /// Rewrite the file whenever the active environment is switched.
+    /// Writing without this flag removes an earlier hook.
+    #[arg(long, requires = "write")]
+    pub hook: bool,
+}
+
 #[derive(Args, Debug)]
 pub struct AddArgs {
     /// Name for the command
-    pub name: String,
+    #[arg(required_unless_present = "batch")]
+    pub name: Option<String>,
 
     /// The shell command to save
     pub command: Option<String>,
@@ -224,42 +686,383 @@ pub struct AddArgs {
     /// Tag for organizing commands
     #[arg(short, long, default_value = "", add = ArgValueCandidates::new(complete_tags))]
     pub tag: String,
-}
 
-#[derive(Args, Debug)]
-pub struct CopyArgs {
-    /// Name of the command to copy
-    #[arg(add = ArgValueCandidates::new(complete_commands))]
-    pub name: String,
+    /// Date after which the command is flagged as expired, as YYYY-MM-DD or e.g. 30d
+    #[arg(long, value_name = "DATE")]
+    pub expires: Option<String>,
 
-    /// Use a specific environment when expanding placeholders
-    #[arg(short = 'e', long = "env", add = ArgValueCandidates::new(complete_environments))]
-    pub environment: Option<String>,
-}
+    /// Date after which the command is flagged for review, as YYYY-MM-DD or e.g. 12w
+    #[arg(long, value_name = "DATE")]
+    pub review_after: Option<String>,
 
-#[derive(Args, Debug)]
-pub struct SearchArgs {
-    /// Search query (fuzzy matched against name and command)
-    pub query: String,
+    /// Keep the command out of gists, bundles and exports
+    #[arg(long)]
+    pub local_only: bool,
+
+    /// Always show the resolved command and ask before running it
+    #[arg(long)]
+    pub confirm: bool,
+
+    /// Add all commands of a TOML or YAML file, or none if any is invalid
+    #[arg(long, value_name = "FILE", conflicts_with_all = [
+        "name", "command", "cwd", "tag", "expires", "review_after", "local_only", "confirm",
+    ])]
+    pub batch: Option<PathBuf>,
 }
 
 #[derive(Args, Debug)]
-pub struct RemoveArgs {
-    /// Name of the command to remove
-    #[arg(add = ArgValueCandidates::new(complete_commands))]
-    pub name: String,
+pub struct SuggestArgs {
+    /// What the command should do, e.g. "tail the logs of the api pod"
+    pub description: String,
+
+    /// Name for the command (asked for after review if omitted)
+    #[arg(short, long)]
+    pub name: Option<String>,
+
+    /// Add to a specific project
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
+    /// Tag for organizing commands
+    #[arg(short, long, default_value = "", add = ArgValueCandidates::new(complete_tags))]
+    pub tag: String,
 }
 
 #[derive(Args, Debug)]
-pub struct EditArgs {
-    /// Name of the command to edit
+pub struct ExplainArgs {
+    /// Name of the command to explain
     #[arg(add = ArgValueCandidates::new(complete_commands))]
     pub name: String,
 
-    /// Set or update the tag for this command.
-    /// Use an empty string to remove the tag: --tag ""
-    #[arg(short, long, add = ArgValueCandidates::new(complete_tags))]
-    pub tag: Option<String>,
+    /// Command from a specific project
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
+    /// Also ask the endpoint configured for `pacs suggest`
+    #[arg(long)]
+    pub ai: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct CopyArgs {
+    /// Names of the commands to copy, joined by newlines
+    #[arg(required = true, add = ArgValueCandidates::new(complete_commands))]
+    pub names: Vec<String>,
+
+    /// Use a specific environment when expanding placeholders
+    #[arg(short = 'e', long = "env", add = ArgValueCandidates::new(complete_environments))]
+    pub environment: Option<String>,
+
+    /// Quote the result for pasting into this shell, multi-line commands become one line
+    #[arg(long, value_name = "SHELL")]
+    pub escape: Option<EscapeShell>,
+
+    /// Join the lines of each command with && into a single line
+    #[arg(long)]
+    pub oneline: bool,
+
+    /// Leave out comments
+    #[arg(long)]
+    pub strip_comments: bool,
+
+    /// Remove common indentation, trailing whitespace and repeated blank lines
+    #[arg(long)]
+    pub normalize: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct GetArgs {
+    /// Name of the command
+    #[arg(add = ArgValueCandidates::new(complete_commands))]
+    pub name: String,
+
+    /// Field to print
+    #[arg(short, long, value_enum, default_value_t = CommandField::Command)]
+    pub field: CommandField,
+
+    /// Expand placeholders, failing if any is left without a value
+    #[arg(short, long)]
+    pub resolved: bool,
+
+    /// Command from a specific project
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
+    /// Use a specific environment when expanding placeholders
+    #[arg(short = 'e', long = "env", requires = "resolved", add = ArgValueCandidates::new(complete_environments))]
+    pub environment: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandField {
+    /// The body
+    Command,
+    /// The working directory
+    Cwd,
+    Tag,
+    /// The comment lines at the start of the body
+    Description,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum EscapeShell {
+    Bash,
+    Zsh,
+    Fish,
+    #[value(alias = "pwsh")]
+    Powershell,
+}
+
+impl From<EscapeShell> for Shell {
+    fn from(shell: EscapeShell) -> Self {
+        match shell {
+            EscapeShell::Bash => Self::Bash,
+            EscapeShell::Zsh => Self::Zsh,
+            EscapeShell::Fish => Self::Fish,
+            EscapeShell::Powershell => Self::PowerShell,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct SearchArgs {
+    /// Search query (fuzzy matched against name and command)
+    pub query: String,
+
+    /// Show at most this many results
+    #[arg(short = 'n', long)]
+    pub limit: Option<usize>,
+
+    /// Print the results as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    /// Run the only confident match, or pick one of the results to run
+    #[arg(short = 'x', long, conflicts_with = "json")]
+    pub run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct DedupeArgs {
+    /// Minimum similarity of two bodies, from 0 to 1 (1 only finds identical bodies)
+    #[arg(short, long, default_value_t = 0.9)]
+    pub threshold: f64,
+
+    /// Only report duplicates without offering to merge them
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct PruneArgs {
+    /// List commands not run within this window, e.g. 90d, 12w or 48h
+    #[arg(long, value_name = "WINDOW", value_parser = parse_window)]
+    pub unused: Duration,
+
+    /// Only consider commands of a specific project
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
+    /// Move the listed commands to ~/.pacs/archive instead of only listing them
+    #[arg(long)]
+    pub archive: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct HistoryClearArgs {
+    /// Only remove runs of a specific project
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
+    /// Only remove runs started before this date, as YYYY-MM-DD
+    #[arg(long, value_name = "DATE", value_parser = parse_day)]
+    pub before: Option<i64>,
+}
+
+#[derive(Args, Debug)]
+pub struct LintArgs {
+    /// Lint a specific project (defaults to active project if omitted)
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
+    /// Add the missing keys to `requires`
+    #[arg(long)]
+    pub fix: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct OpenArgs {
+    /// Project to open (defaults to active project if omitted)
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
+    /// Open ~/.pacs/config.toml instead of a project
+    #[arg(long, conflicts_with = "project")]
+    pub config: bool,
+
+    /// Allow changing locked commands
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ShellArgs {
+    /// Project to use in the subshell (defaults to active project if omitted)
+    #[arg(add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
+    /// Environment to use in the subshell
+    #[arg(short = 'e', long = "env", add = ArgValueCandidates::new(complete_environments))]
+    pub environment: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// Output format (inferred from the output file name if omitted, toml otherwise)
+    #[arg(short, long, value_enum)]
+    pub format: Option<Format>,
+
+    /// Export a specific project (defaults to active project if omitted)
+    #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
+    /// Environment whose values become just/make/gha variables (defaults to the active environment)
+    #[arg(short = 'e', long = "env", add = ArgValueCandidates::new(complete_environments))]
+    pub environment: Option<String>,
+
+    /// Only export commands with this tag
+    #[arg(short, long, add = ArgValueCandidates::new(complete_tags))]
+    pub tag: Option<String>,
+
+    /// Write to a file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Export all projects with their environments as a bundle for `pacs import`
+    #[arg(long, conflicts_with_all = ["project", "environment", "tag"])]
+    pub all: bool,
+
+    /// Leave out comments of the command bodies
+    #[arg(long, conflicts_with = "all")]
+    pub strip_comments: bool,
+
+    /// Remove common indentation, trailing whitespace and repeated blank lines of the bodies
+    #[arg(long, conflicts_with = "all")]
+    pub normalize: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ImportArgs {
+    /// Import something else than a bundle written by `pacs export --all`
+    #[arg(long, value_enum)]
+    pub from: Option<ImportSource>,
+
+    /// Files to read (defaults to stdin if piped, for aliases to the shell rc files in the home directory otherwise)
+    pub files: Vec<PathBuf>,
+
+    /// Import aliases into a specific project (defaults to active project if omitted)
+    #[arg(short, long, requires = "from", add = ArgValueCandidates::new(complete_projects))]
+    pub project: Option<String>,
+
+    /// Keep local commands that clash with imported ones (default)
+    #[arg(long, conflicts_with_all = ["replace", "interactive", "from"])]
+    pub merge: bool,
+
+    /// Replace existing projects by the imported ones
+    #[arg(long, conflicts_with_all = ["interactive", "from"])]
+    pub replace: bool,
+
+    /// Ask for each clashing command whether to keep yours, take theirs or rename theirs
+    #[arg(short, long, conflicts_with = "from")]
+    pub interactive: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ImportSource {
+    /// `alias name=value` definitions, e.g. from .bashrc or `alias -L`
+    Aliases,
+}
+
+/// Shell rc files read by `pacs import --from aliases` if no files are given.
+const ALIAS_FILES: [&str; 4] = [".bash_aliases", ".bashrc", ".zshrc", ".aliases"];
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    Toml,
+    Json,
+    #[value(alias = "md")]
+    Markdown,
+    #[value(alias = "justfile")]
+    Just,
+    #[value(alias = "makefile")]
+    Make,
+    /// GitHub Actions workflow
+    #[value(alias = "github-actions")]
+    Gha,
+}
+
+impl From<Format> for ExportFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Toml => Self::Toml,
+            Format::Json => Self::Json,
+            Format::Markdown => Self::Markdown,
+            Format::Just => Self::Just,
+            Format::Make => Self::Make,
+            Format::Gha => Self::Gha,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct RemoveArgs {
+    /// Name of the command to remove
+    #[arg(add = ArgValueCandidates::new(complete_commands))]
+    pub name: String,
+
+    /// Remove the command even if it is locked
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct EditArgs {
+    /// Name of the command to edit
+    #[arg(add = ArgValueCandidates::new(complete_commands))]
+    pub name: String,
+
+    /// Set or update the tag for this command.
+    /// Use an empty string to remove the tag: --tag ""
+    #[arg(short, long, add = ArgValueCandidates::new(complete_tags))]
+    pub tag: Option<String>,
+
+    /// Set the expiry date (YYYY-MM-DD or e.g. 30d), an empty string removes it
+    #[arg(long, value_name = "DATE")]
+    pub expires: Option<String>,
+
+    /// Set the review date (YYYY-MM-DD or e.g. 12w), an empty string removes it
+    #[arg(long, value_name = "DATE")]
+    pub review_after: Option<String>,
+
+    /// Keep the command out of gists, bundles and exports, or share it again with false
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    pub local_only: Option<bool>,
+
+    /// Run without confirmation even if the body matches a danger pattern, or ask again with false
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    pub safe: Option<bool>,
+
+    /// Always ask before running, even with --force, or stop asking with false
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    pub confirm: Option<bool>,
+
+    /// Only print the changes to the body without saving them
+    #[arg(long, conflicts_with_all = ["tag", "expires", "review_after", "local_only", "safe", "confirm"])]
+    pub diff_only: bool,
+
+    /// Edit the command even if it is locked
+    #[arg(long)]
+    pub force: bool,
 }
 
 #[derive(Args, Debug)]
@@ -270,6 +1073,17 @@ pub struct RenameArgs {
 
     /// New name for the command
     pub new_name: String,
+
+    /// Rename the command even if it is locked
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct LockArgs {
+    /// Name of the command
+    #[arg(add = ArgValueCandidates::new(complete_commands))]
+    pub name: String,
 }
 
 #[derive(Args, Debug)]
@@ -293,13 +1107,96 @@ pub struct ListArgs {
     /// Show only command names (no bodies)
     #[arg(short, long)]
     pub names: bool,
+
+    /// Show the keys the command uses with their values instead of its body
+    #[arg(short, long, requires = "name")]
+    pub keys: bool,
+
+    /// Print only the body, joined with && into a single line
+    #[arg(long, requires = "name", conflicts_with = "keys")]
+    pub oneline: bool,
+
+    /// Leave out comments of the body
+    #[arg(long, requires = "name", conflicts_with = "keys")]
+    pub strip_comments: bool,
+
+    /// Remove common indentation, trailing whitespace and repeated blank lines of the body
+    #[arg(long, requires = "name", conflicts_with = "keys")]
+    pub normalize: bool,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = ListFormat::Text)]
+    pub format: ListFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListFormat {
+    /// Human readable listing
+    Text,
+    /// JSON items for Alfred and Raycast script filters
+    ScriptFilter,
+}
+
+/// Root object of an Alfred/Raycast script filter response.
+#[derive(Serialize)]
+struct ScriptFilter<'a> {
+    items: Vec<ScriptFilterItem<'a>>,
+}
+
+#[derive(Serialize)]
+struct ScriptFilterItem<'a> {
+    uid: &'a str,
+    title: &'a str,
+    subtitle: &'a str,
+    arg: &'a str,
+    autocomplete: &'a str,
+    #[serde(rename = "match")]
+    match_text: String,
+}
+
+impl<'a> ScriptFilterItem<'a> {
+    fn new(cmd: &'a PacsCommand) -> Self {
+        Self {
+            uid: &cmd.name,
+            title: &cmd.name,
+            subtitle: cmd.command.lines().next().unwrap_or_default(),
+            arg: &cmd.name,
+            autocomplete: &cmd.name,
+            match_text: format!("{} {} {}", cmd.name, cmd.tag, cmd.command.trim()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SearchItem<'a> {
+    command: CommandView,
+    scope: &'a str,
+    registry: bool,
+    score: i64,
+    preview: &'a str,
+}
+
+impl<'a> From<&SearchMatch<'a>> for SearchItem<'a> {
+    fn from(m: &SearchMatch<'a>) -> Self {
+        Self {
+            command: CommandView::from(m.command),
+            scope: m.scope,
+            registry: m.registry,
+            score: m.score,
+            preview: m.preview,
+        }
+    }
 }
 
 #[derive(Args, Debug)]
 pub struct RunArgs {
-    /// Name of the command to run
-    #[arg(add = ArgValueCandidates::new(complete_commands))]
-    pub name: String,
+    /// Names of the commands to run, one after another
+    #[arg(required = true, add = ArgValueCandidates::new(complete_commands))]
+    pub names: Vec<String>,
+
+    /// Run the remaining commands when one fails
+    #[arg(short, long)]
+    pub keep_going: bool,
 
     /// Run from a specific project instead of global
     #[arg(short, long, add = ArgValueCandidates::new(complete_projects))]
@@ -308,48 +1205,258 @@ pub struct RunArgs {
     /// Use a specific environment for this run
     #[arg(short = 'e', long = "env", add = ArgValueCandidates::new(complete_environments))]
     pub environment: Option<String>,
+
+    /// Run once in each of these environments, e.g. --envs dev,stg
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["environment", "all_envs"], add = ArgValueCandidates::new(complete_environments))]
+    pub envs: Option<Vec<String>>,
+
+    /// Run once in every environment of the project
+    #[arg(long, conflicts_with = "environment")]
+    pub all_envs: bool,
+
+    /// Read placeholder values for this run from a .env file, overriding the environment's
+    #[arg(long, value_name = "FILE")]
+    pub env_file: Option<PathBuf>,
+
+    /// Set a placeholder value for this run, overriding the environment and --env-file
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_key_value, add = ArgValueCandidates::new(complete_placeholders))]
+    pub values: Vec<(String, String)>,
+
+    /// Working directory of commands that ask for one, instead of asking
+    #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub cwd: Option<PathBuf>,
+
+    /// Copy what the commands print to stdout to the clipboard instead of showing it
+    #[arg(long, conflicts_with_all = ["envs", "all_envs"])]
+    pub copy_output: bool,
+
+    /// Run without asking even if a command matches a danger pattern
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// Never ask, print no colors and wrap each command's output in a log section of the CI system
+    #[arg(long, conflicts_with = "copy_output")]
+    pub ci: bool,
+}
+
+/// Completes names of the active project from the completion cache, which
+/// avoids parsing every project on each TAB press.
+fn complete_active(names: impl Fn(&ProjectNames) -> &[String]) -> Vec<CompletionCandidate> {
+    let Ok(cache) = Pacs::completions() else {
+        return vec![];
+    };
+    cache
+        .project(None)
+        .map(|project| {
+            names(project)
+                .iter()
+                .map(CompletionCandidate::new)
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 fn complete_commands() -> Vec<CompletionCandidate> {
-    let Ok(pacs) = Pacs::init_home() else {
+    complete_active(|p| &p.commands)
+}
+
+fn complete_projects() -> Vec<CompletionCandidate> {
+    let Ok(cache) = Pacs::completions() else {
         return vec![];
     };
-    pacs.suggest_command_names()
+    cache
+        .projects()
         .into_iter()
         .map(CompletionCandidate::new)
         .collect()
 }
 
-fn complete_projects() -> Vec<CompletionCandidate> {
+fn complete_tags() -> Vec<CompletionCandidate> {
+    complete_active(|p| &p.tags)
+}
+
+fn complete_environments() -> Vec<CompletionCandidate> {
+    complete_active(|p| &p.environments)
+}
+
+fn complete_registries() -> Vec<CompletionCandidate> {
     let Ok(pacs) = Pacs::init_home() else {
         return vec![];
     };
-    pacs.suggest_projects()
-        .into_iter()
-        .map(CompletionCandidate::new)
+    pacs.registries()
+        .iter()
+        .map(|r| CompletionCandidate::new(&r.source.name))
         .collect()
 }
 
-fn complete_tags() -> Vec<CompletionCandidate> {
+fn complete_project_templates() -> Vec<CompletionCandidate> {
     let Ok(pacs) = Pacs::init_home() else {
         return vec![];
     };
-    pacs.suggest_tags(None)
+    pacs.project_templates()
         .into_iter()
         .map(CompletionCandidate::new)
         .collect()
 }
 
-fn complete_environments() -> Vec<CompletionCandidate> {
+/// Completes `--set` with the keys of the commands named on the command line
+/// being completed, e.g. `pacs run deploy --set <TAB>`.
+fn complete_placeholders() -> Vec<CompletionCandidate> {
     let Ok(pacs) = Pacs::init_home() else {
         return vec![];
     };
-    pacs.suggest_environments(None)
+    // The shell passes the words being completed after `--`
+    let words: Vec<String> = env::args()
+        .skip_while(|arg| arg != "--")
+        .skip_while(|arg| arg != "run")
+        .skip(1)
+        .collect();
+
+    let mut names = Vec::new();
+    let mut project = None;
+    let mut words = words.iter();
+    while let Some(word) = words.next() {
+        match word.as_str() {
+            "-p" | "--project" => project = words.next(),
+            "-e" | "--env" | "--envs" | "--env-file" | "--set" => {
+                words.next();
+            }
+            flag if flag.starts_with('-') => {}
+            name => names.push(name),
+        }
+    }
+
+    pacs.suggest_placeholders(project.map(String::as_str), &names)
         .into_iter()
-        .map(CompletionCandidate::new)
+        .map(|key| CompletionCandidate::new(format!("{key}=")))
+        .collect()
+}
+
+fn complete_templates() -> Vec<CompletionCandidate> {
+    templates()
+        .into_iter()
+        .map(|t| CompletionCandidate::new(t.name).help(Some(t.description.into())))
         .collect()
 }
 
+/// Prints settings as TOML with their sources as comments.
+fn print_settings(settings: &[Setting]) {
+    for setting in settings {
+        let source = match setting.source {
+            SettingSource::Default => Msg::DefaultSource.to_string(),
+            SettingSource::ConfigFile => "config.toml".into(),
+            SettingSource::Env(var) => format!("${var}"),
+            SettingSource::Flag(flag) => flag.into(),
+            SettingSource::Git => "git config".into(),
+        };
+        println!(
+            "{} = {}  {GREY}# {source}{RESET}",
+            setting.key, setting.value
+        );
+    }
+}
+
+/// Lines of hook output shown after a project switch.
+const HOOK_SUMMARY_LINES: usize = 3;
+
+/// Prints whether a hook succeeded and the end of its output.
+fn print_hook_summary(hook: &HookOutput) {
+    let lines: Vec<&str> = hook.output.lines().collect();
+    if hook.success() {
+        println!("{}", Msg::SwitchHookFinished { lines: lines.len() });
+    } else {
+        eprintln!(
+            "{YELLOW}{}{RESET} {}",
+            Msg::Warning,
+            Msg::SwitchHookFailed {
+                code: hook.exit_code
+            }
+        );
+    }
+    let mut summary = String::new();
+    if lines.len() > HOOK_SUMMARY_LINES {
+        writeln!(summary, "  {GREY}…{RESET}").unwrap();
+    }
+    for line in &lines[lines.len().saturating_sub(HOOK_SUMMARY_LINES)..] {
+        writeln!(summary, "  {GREY}{line}{RESET}").unwrap();
+    }
+    // Failures go to stderr with their output, so the two stay together
+    if hook.success() {
+        print!("{summary}");
+    } else {
+        eprint!("{summary}");
+    }
+}
+
+/// Prints the environments of every project as a table, marking the active
+/// ones with `*`.
+fn print_all_environments(pacs: &Pacs) {
+    let all = pacs.all_environments();
+    if all.is_empty() {
+        println!("{}", Msg::NoProjects);
+        return;
+    }
+    let header = Msg::ProjectColumn.to_string();
+    let width = all
+        .iter()
+        .map(|p| p.project.chars().count())
+        .chain([header.chars().count()])
+        .max()
+        .unwrap_or_default();
+    println!("{BOLD}{header:width$}  {}{RESET}", Msg::EnvironmentsColumn);
+    for project in all {
+        let environments = if project.environments.is_empty() {
+            format!("{GREY}-{RESET}")
+        } else {
+            project
+                .environments
+                .iter()
+                .map(|env| {
+                    if project.active == Some(env.name.as_str()) {
+                        format!("{CYAN}{}{RESET}{GREEN}*{RESET}", env.name)
+                    } else {
+                        format!("{CYAN}{}{RESET}", env.name)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+        };
+        println!("{BLUE}{:width$}{RESET}  {environments}", project.project);
+    }
+}
+
+/// Refetches outdated registries, warning about the ones that could not be fetched.
+fn refresh_registries(pacs: &mut Pacs) {
+    for (name, err) in pacs.update_registries(Some(REGISTRY_MAX_AGE)) {
+        let error = err.to_string();
+        eprintln!(
+            "{YELLOW}{}{RESET} {}",
+            Msg::Warning,
+            Msg::RegistryUpdateFailed {
+                name: &name,
+                error: &error
+            }
+        );
+    }
+    warn_changed_since_approval(pacs);
+}
+
+/// Warns about approved registry commands whose registry changed them since.
+fn warn_changed_since_approval(pacs: &Pacs) {
+    for registry in pacs.registries() {
+        for cmd in registry.changed_since_approval() {
+            eprintln!(
+                "{BOLD}{RED}{}{RESET} {}",
+                Msg::Warning,
+                Msg::RegistryCommandChanged {
+                    name: &cmd.name,
+                    registry: &registry.source.name
+                }
+            );
+        }
+    }
+}
+
 pub fn run(cli: Cli) -> Result<()> {
     if cli.ui {
         return Ok(());
@@ -362,20 +1469,49 @@ pub fn run(cli: Cli) -> Result<()> {
         return Ok(());
     };
 
+    init_logging(cli.verbose, cli.quiet);
+
     let mut pacs = Pacs::init_home().context("Failed to initialize pacs")?;
+    if cli.safe {
+        pacs.set_safe_mode();
+    }
+    if cli.reveal {
+        pacs.set_reveal_secrets();
+    }
+    if let Some(project) = &cli.project {
+        pacs.scope_to_project(project)?;
+    }
+
+    if let Some(message) = pacs.config_error().filter(|_| !cli.quiet) {
+        eprintln!(
+            "{YELLOW}{}{RESET} {}",
+            Msg::Warning,
+            Msg::BrokenConfig { message }
+        );
+    }
+    for warning in pacs.load_warnings().iter().filter(|_| !cli.quiet) {
+        eprintln!(
+            "{YELLOW}{}{RESET} {}",
+            Msg::Warning,
+            Msg::SkippedProjectFile {
+                path: &warning.path,
+                message: &warning.message
+            }
+        );
+    }
 
     match command {
         Commands::Init => {
-            println!("Pacs initialized at ~/.pacs/");
+            println!("{}", Msg::Initialized);
 
-            print!("Enter a name for your first project: ");
+            print!("{}", Msg::FirstProjectPrompt);
             io::stdout().flush()?;
             let mut project_name = String::new();
             io::stdin().read_line(&mut project_name)?;
             let project_name = project_name.trim();
 
             if project_name.is_empty() {
-                anyhow::bail!("No project name entered");
+                anyhow::bail!("{}", Msg::NoProjectName);
             }
 
             pacs.init_project(project_name, None)?;
@@ -383,9 +1519,30 @@ pub fn run(cli: Cli) -> Result<()> {
         }
 
         Commands::Add(args) => {
-            let command = if let Some(cmd) = args.command {
-                cmd
-            } else {
+            if let Some(path) = &args.batch {
+                let commands = read_batch(path)?;
+                let count = commands.len();
+                pacs.add_commands(commands, args.project.as_deref())
+                    .with_context(|| format!("Failed to add commands from {}", path.display()))?;
+                let project = match args.project {
+                    Some(project) => project,
+                    None => pacs.get_active_project_name()?,
+                };
+                println!(
+                    "{}",
+                    Msg::BatchAdded {
+                        count,
+                        project: &project
+                    }
+                );
+                return Ok(());
+            }
+            // clap requires a name without --batch
+            let name = args.name.unwrap_or_default();
+
+            let command = if let Some(cmd) = args.command {
+                cmd
+            } else {
                 let editor = env::var("VISUAL")
                     .ok()
                     .or_else(|| env::var("EDITOR").ok())
@@ -403,7 +1560,7 @@ pub fn run(cli: Cli) -> Result<()> {
 
                 if !status.success() {
                     fs::remove_file(&temp_file).ok();
-                    anyhow::bail!("Editor exited with non-zero status");
+                    anyhow::bail!("{}", Msg::EditorFailed);
                 }
 
                 let content = fs::read_to_string(&temp_file)?;
@@ -412,21 +1569,74 @@ pub fn run(cli: Cli) -> Result<()> {
                 let command = content.trim().to_string();
 
                 if command.is_empty() {
-                    anyhow::bail!("No command entered");
+                    anyhow::bail!("{}", Msg::NoCommandEntered);
                 }
 
                 command + "\n"
             };
 
+            // Aliases live in one project, so a match in the target project
+            // is preferred and the only one offered to alias.
+            let target = match &args.project {
+                Some(project) => pacs
+                    .projects()
+                    .iter()
+                    .find(|p| p.name.eq_ignore_ascii_case(project))
+                    .map(|p| p.name.clone()),
+                None => pacs.get_active_project_name().ok(),
+            };
+            let similar = pacs.similar_commands(&command, SIMILAR_BODY_THRESHOLD);
+            let existing = similar
+                .iter()
+                .find(|(c, _)| Some(&c.project) == target.as_ref())
+                .or(similar.first())
+                .map(|(c, _)| c);
+            if let Some(existing) = existing {
+                if !cli.quiet {
+                    eprintln!(
+                        "{YELLOW}{}{RESET} {}",
+                        Msg::Warning,
+                        Msg::SimilarCommand {
+                            name: &existing.name,
+                            project: &existing.project
+                        }
+                    );
+                }
+                if Some(&existing.project) == target.as_ref() && confirm_alias(&existing.name)? {
+                    pacs.add_alias(&existing.project, &existing.name, &name)
+                        .with_context(|| format!("Failed to add alias '{name}'"))?;
+                    println!(
+                        "{}",
+                        Msg::AliasAdded {
+                            alias: &name,
+                            name: &existing.name,
+                            project: &existing.project
+                        }
+                    );
+                    return Ok(());
+                }
+            }
+
             let pacs_cmd = PacsCommand {
-                name: args.name.clone(),
+                name: name.clone(),
                 command,
                 cwd: args.cwd,
                 tag: args.tag,
+                aliases: Vec::new(),
+                expires: args.expires.as_deref().map(resolve_date).transpose()?,
+                review_after: args.review_after.as_deref().map(resolve_date).transpose()?,
+                author: None,
+                requires: Vec::new(),
+                local_only: args.local_only,
+                overrides: BTreeMap::new(),
+                safe: false,
+                confirm: args.confirm,
+                locked: false,
+                sandbox: None,
             };
 
             pacs.add_command(pacs_cmd, args.project.as_deref())
-                .with_context(|| format!("Failed to add command '{}'", args.name))?;
+                .with_context(|| format!("Failed to add command '{name}'"))?;
 
             let project_name = if let Some(ref p) = args.project {
                 p.clone()
@@ -435,18 +1645,112 @@ pub fn run(cli: Cli) -> Result<()> {
             };
 
             println!(
-                "Command '{}' added to project '{}'.",
-                args.name, project_name
+                "{}",
+                Msg::CommandAdded {
+                    name: &name,
+                    project: &project_name
+                }
+            );
+        }
+
+        Commands::Suggest(args) => {
+            if pacs.is_readonly() {
+                return Err(PacsError::ReadOnly.into());
+            }
+            let draft = pacs
+                .suggest_command(&args.description, args.project.as_deref())
+                .context("Failed to draft a command")?;
+
+            let editor = env::var("VISUAL")
+                .ok()
+                .or_else(|| env::var("EDITOR").ok())
+                .unwrap_or_else(|| "vi".to_string());
+
+            let temp_file =
+                std::env::temp_dir().join(format!("pacs-suggest-{}.sh", std::process::id()));
+            fs::write(&temp_file, draft)?;
+
+            let status = Command::new(&editor)
+                .arg(&temp_file)
+                .status()
+                .with_context(|| format!("Failed to open editor '{editor}'"))?;
+
+            if !status.success() {
+                fs::remove_file(&temp_file).ok();
+                anyhow::bail!("{}", Msg::EditorFailed);
+            }
+
+            let content = fs::read_to_string(&temp_file)?;
+            fs::remove_file(&temp_file).ok();
+
+            let command = content.trim().to_string();
+            if command.is_empty() {
+                anyhow::bail!("{}", Msg::NoCommandEntered);
+            }
+
+            let name = if let Some(name) = args.name {
+                name
+            } else {
+                print!("{}", Msg::CommandNamePrompt);
+                io::stdout().flush()?;
+                let mut name = String::new();
+                io::stdin().read_line(&mut name)?;
+                let name = name.trim().to_string();
+                if name.is_empty() {
+                    anyhow::bail!("{}", Msg::NoCommandName);
+                }
+                name
+            };
+
+            let pacs_cmd = PacsCommand {
+                name: name.clone(),
+                command: command + "\n",
+                cwd: None,
+                tag: args.tag,
+                aliases: Vec::new(),
+                expires: None,
+                review_after: None,
+                author: None,
+                requires: Vec::new(),
+                local_only: false,
+                overrides: BTreeMap::new(),
+                safe: false,
+                confirm: false,
+                locked: false,
+                sandbox: None,
+            };
+            pacs.add_command(pacs_cmd, args.project.as_deref())
+                .with_context(|| format!("Failed to add command '{name}'"))?;
+
+            let project_name = resolve_project_name(&pacs, args.project)?;
+            println!(
+                "{}",
+                Msg::CommandAdded {
+                    name: &name,
+                    project: &project_name
+                }
             );
         }
 
         Commands::Remove(args) => {
+            if args.force {
+                pacs.set_ignore_locks();
+            }
             pacs.delete_command_auto(&args.name)
                 .with_context(|| format!("Failed to remove command '{}'", args.name))?;
-            println!("Command '{}' removed.", args.name);
+            println!("{}", Msg::CommandRemoved { name: &args.name });
         }
 
         Commands::Edit(args) => {
+            if pacs.is_readonly() && !args.diff_only {
+                return Err(PacsError::ReadOnly.into());
+            }
+            if args.force {
+                pacs.set_ignore_locks();
+            } else if !args.diff_only {
+                // Refuse before the editor opens, not after the changes are made
+                pacs.ensure_unlocked(None, &args.name)?;
+            }
             let cmd = pacs
                 .get_command_auto(&args.name)
                 .with_context(|| format!("Command '{}' not found", args.name))?;
@@ -468,33 +1772,71 @@ pub fn run(cli: Cli) -> Result<()> {
 
             if !status.success() {
                 fs::remove_file(&temp_file).ok();
-                anyhow::bail!("Editor exited with non-zero status");
+                anyhow::bail!("{}", Msg::EditorFailed);
             }
 
             let new_command = fs::read_to_string(&temp_file)?;
             fs::remove_file(&temp_file).ok();
 
             if new_command.trim().is_empty() {
-                anyhow::bail!("Command cannot be empty");
+                anyhow::bail!("{}", Msg::EmptyCommand);
+            }
+
+            if args.diff_only {
+                if !print_diff(&cmd.command, &new_command) {
+                    println!("{}", Msg::NoChanges);
+                }
+                return Ok(());
             }
 
-            pacs.update_command_auto(&args.name, new_command)
+            let old_command = pacs
+                .update_command_auto(&args.name, new_command.clone())
                 .with_context(|| format!("Failed to update command '{}'", args.name))?;
+            print_diff(&old_command, &new_command);
+
+            pacs.set_command_dates_auto(
+                &args.name,
+                args.expires.as_deref(),
+                args.review_after.as_deref(),
+            )
+            .with_context(|| format!("Failed to update dates for command '{}'", args.name))?;
+
+            if let Some(local_only) = args.local_only {
+                pacs.set_command_local_only_auto(&args.name, local_only)
+                    .with_context(|| format!("Failed to update command '{}'", args.name))?;
+            }
+            if let Some(safe) = args.safe {
+                pacs.set_command_safe_auto(&args.name, safe)
+                    .with_context(|| format!("Failed to update command '{}'", args.name))?;
+            }
+            if let Some(confirm) = args.confirm {
+                pacs.set_command_confirm_auto(&args.name, confirm)
+                    .with_context(|| format!("Failed to update command '{}'", args.name))?;
+            }
 
             if let Some(tag) = args.tag {
                 pacs.tag_command_auto(&args.name, tag.clone())
                     .with_context(|| format!("Failed to update tag for command '{}'", args.name))?;
                 if tag.is_empty() {
-                    println!("Command '{}' updated, tag removed.", args.name);
+                    println!("{}", Msg::CommandUpdatedTagRemoved { name: &args.name });
                 } else {
-                    println!("Command '{}' updated with tag '{}'.", args.name, tag);
+                    println!(
+                        "{}",
+                        Msg::CommandUpdatedWithTag {
+                            name: &args.name,
+                            tag: &tag
+                        }
+                    );
                 }
             } else {
-                println!("Command '{}' updated.", args.name);
+                println!("{}", Msg::CommandUpdated { name: &args.name });
             }
         }
 
         Commands::Rename(args) => {
+            if args.force {
+                pacs.set_ignore_locks();
+            }
             pacs.rename_command_auto(&args.old_name, &args.new_name)
                 .with_context(|| {
                     format!(
@@ -503,39 +1845,104 @@ pub fn run(cli: Cli) -> Result<()> {
                     )
                 })?;
             println!(
-                "Command '{}' renamed to '{}'.",
-                args.old_name, args.new_name
+                "{}",
+                Msg::CommandRenamed {
+                    old: &args.old_name,
+                    new: &args.new_name
+                }
             );
         }
 
+        Commands::Lock(args) => {
+            pacs.set_command_locked_auto(&args.name, true)
+                .with_context(|| format!("Failed to lock command '{}'", args.name))?;
+            println!("{}", Msg::CommandLocked { name: &args.name });
+        }
+
+        Commands::Unlock(args) => {
+            pacs.set_command_locked_auto(&args.name, false)
+                .with_context(|| format!("Failed to unlock command '{}'", args.name))?;
+            println!("{}", Msg::CommandUnlocked { name: &args.name });
+        }
+
         Commands::List(args) => {
+            refresh_registries(&mut pacs);
+
+            if args.format == ListFormat::ScriptFilter {
+                let commands = pacs.list(args.project.as_deref(), args.environment.as_deref())?;
+                let items = commands
+                    .iter()
+                    .filter(|c| args.name.as_ref().is_none_or(|n| &c.name == n))
+                    .filter(|c| args.tag.as_ref().is_none_or(|t| &c.tag == t))
+                    .map(ScriptFilterItem::new)
+                    .collect();
+                println!("{}", serde_json::to_string(&ScriptFilter { items })?);
+                return Ok(());
+            }
+
+            if let (Some(name), true) = (&args.name, args.keys) {
+                let cmd = pacs
+                    .get_command_auto(name)
+                    .with_context(|| format!("Command '{name}' not found"))?;
+                print_command_keys(&pacs, cmd, args.environment.as_deref())?;
+                return Ok(());
+            }
+
             if let Some(ref name) = args.name {
                 let cmd = pacs
                     .resolve_command(name, None, args.environment.as_deref())
                     .with_context(|| format!("Command '{name}' not found"))?;
+                let cleanup = BodyCleanup {
+                    strip_comments: args.strip_comments,
+                    normalize: args.normalize,
+                };
+                let cmd = PacsCommand {
+                    command: cleanup.apply(&cmd.command),
+                    ..cmd
+                };
+                if args.oneline {
+                    let line = flatten_command(&cmd.command)
+                        .ok_or_else(|| anyhow::anyhow!("{}", Msg::NoOneline { name }))?;
+                    println!("{line}");
+                    return Ok(());
+                }
                 let tag_badge = if cmd.tag.is_empty() {
                     String::new()
                 } else {
-                    format!(" {BOLD}{YELLOW}[{}]{RESET}", cmd.tag)
+                    format!(" {}", tag_label(&cmd.tag, &pacs.tag_styles()))
                 };
                 let cwd_badge = if let Some(ref cwd) = cmd.cwd {
                     format!(" {GREY}({cwd}){RESET}")
                 } else {
                     String::new()
                 };
-                println!("{BOLD}{CYAN}{}{RESET}{}{}", cmd.name, tag_badge, cwd_badge);
-                for line in cmd.command.lines() {
-                    println!("{WHITE}{line}{RESET}");
-                }
+                println!(
+                    "{BOLD}{CYAN}{}{RESET}{}{}{}{}{}{}{}{}",
+                    cmd.name,
+                    aliases_badge(&cmd),
+                    tag_badge,
+                    cwd_badge,
+                    author_badge(&cmd),
+                    local_badge(cmd.local_only),
+                    lock_badge(cmd.locked),
+                    expiry_badge(&cmd),
+                    keys_badge(&pacs, &cmd, None, args.environment.as_deref())
+                );
+                print_command_body(&cmd.command);
                 return Ok(());
             }
 
             let filter_tag =
                 |cmd: &PacsCommand| -> bool { args.tag.as_ref().is_none_or(|t| &cmd.tag == t) };
+            let tag_styles = pacs.tag_styles();
 
-            let print_tagged = |commands: &[PacsCommand], scope_name: &str| {
+            // Registry commands are not checked for missing keys
+            let print_tagged = |commands: &[PacsCommand],
+                                scope_name: &str,
+                                project: Option<&str>,
+                                registry: Option<&Registry>| {
                 if commands.is_empty() {
-                    println!("No commands found. Use 'pacs add <name> <cmd>' to add one.");
+                    println!("{}", Msg::NoCommands);
                     return;
                 }
 
@@ -556,12 +1963,32 @@ pub fn run(cli: Cli) -> Result<()> {
                 println!("{BOLD}{GREEN}{scope_name}{RESET}{RESET}");
                 println!();
 
+                let recent = project
+                    .filter(|_| args.tag.is_none())
+                    .and_then(|p| pacs.recent_commands(Some(p)).ok())
+                    .unwrap_or_default();
+                if !recent.is_empty() {
+                    println!("{BOLD}{MAGENTA}{}{RESET}", Msg::Recent);
+                    for cmd in recent {
+                        println!("{BOLD}{CYAN}{}{RESET}", cmd.name);
+                    }
+                    println!();
+                }
+
                 for (tag, cmds) in tags {
                     if let Some(name) = tag {
-                        println!("{BOLD}{YELLOW}[{name}]{RESET}");
+                        println!("{}", tag_label(name, &tag_styles));
                     }
 
+                    let mut open: Vec<&str> = Vec::new();
                     for cmd in cmds {
+                        let groups: Vec<&str> = cmd.groups().collect();
+                        let shared = open.iter().zip(&groups).take_while(|(a, b)| a == b).count();
+                        for group in &groups[shared..] {
+                            println!("{BOLD}{BLUE}{group}{RESET}");
+                        }
+                        open = groups;
+
                         if args.names {
                             println!("{BOLD}{CYAN}{}{RESET}", cmd.name);
                         } else {
@@ -570,10 +1997,32 @@ pub fn run(cli: Cli) -> Result<()> {
                             } else {
                                 String::new()
                             };
-                            println!("{BOLD}{CYAN}{}{RESET}{}", cmd.name, cwd_badge);
-                            for line in cmd.command.lines() {
-                                println!("{WHITE}{line}{RESET}");
-                            }
+                            let keys_badge = project
+                                .map(|p| {
+                                    keys_badge(&pacs, cmd, Some(p), args.environment.as_deref())
+                                })
+                                .unwrap_or_default();
+                            let approval_badge = match registry {
+                                Some(r) if r.is_approved(cmd) => String::new(),
+                                Some(r) if r.approved_version(&cmd.name).is_some() => {
+                                    format!(" {RED}{}{RESET}", Msg::ChangedSinceApproval)
+                                }
+                                Some(_) => format!(" {YELLOW}{}{RESET}", Msg::Unapproved),
+                                None => String::new(),
+                            };
+                            println!(
+                                "{BOLD}{CYAN}{}{RESET}{}{}{}{}{}{}{}{}",
+                                cmd.name,
+                                aliases_badge(cmd),
+                                cwd_badge,
+                                author_badge(cmd),
+                                local_badge(cmd.local_only),
+                                lock_badge(cmd.locked),
+                                expiry_badge(cmd),
+                                keys_badge,
+                                approval_badge
+                            );
+                            print_command_body(&cmd.command);
                             println!();
                         }
                     }
@@ -582,264 +2031,2589 @@ pub fn run(cli: Cli) -> Result<()> {
 
             if let Some(ref project) = args.project {
                 let commands = pacs.list(Some(project), args.environment.as_deref())?;
-                print_tagged(&commands, project);
+                print_tagged(&commands, project, Some(project), None);
             } else {
                 let active_project =   pacs.get_active_project_name().context("No active project. Use 'pacs project add' to create one or 'pacs project switch' to activate one.")?;
                 let commands = pacs.list(None, args.environment.as_deref())?;
-                print_tagged(&commands, &active_project);
+                print_tagged(&commands, &active_project, Some(&active_project), None);
+            }
+
+            for registry in pacs.registries() {
+                if !registry.commands.is_empty() {
+                    print_tagged(
+                        &registry.commands,
+                        &format!("{} (registry)", registry.source.name),
+                        None,
+                        Some(registry),
+                    );
+                }
             }
         }
 
         Commands::Run(args) => {
-            pacs.run(
-                &args.name,
-                args.project.as_deref(),
-                args.environment.as_deref(),
-            )
-            .with_context(|| format!("Failed to run command '{}'", args.name))?;
+            let mut values = match &args.env_file {
+                Some(path) => parse_env_file(
+                    &fs::read_to_string(path)
+                        .with_context(|| format!("Failed to read {}", path.display()))?,
+                ),
+                None => BTreeMap::new(),
+            };
+            values.extend(args.values.iter().cloned());
+            if let Some(cwd) = &args.cwd {
+                values.insert(CWD_KEY.to_string(), cwd.display().to_string());
+            }
+            let mut names = Vec::with_capacity(args.names.len());
+            for name in &args.names {
+                if !name.ends_with('/') {
+                    names.push(name.clone());
+                } else if args.ci {
+                    anyhow::bail!("{}", Msg::GroupInCi { group: name });
+                } else if let Some(chosen) = pick_in_group(&pacs, name, args.project.as_deref())? {
+                    names.push(chosen);
+                } else {
+                    println!("{}", Msg::NothingRun);
+                    return Ok(());
+                }
+            }
+            let matrix = args.all_envs || args.envs.is_some();
+            let environments: Vec<Option<String>> = if args.all_envs {
+                pacs.list_environments(args.project.as_deref())?
+                    .iter()
+                    .map(|e| Some(e.name.clone()))
+                    .collect()
+            } else if let Some(envs) = &args.envs {
+                envs.iter().cloned().map(Some).collect()
+            } else {
+                vec![args.environment.clone()]
+            };
+            if environments.is_empty() {
+                anyhow::bail!("{}", Msg::ProjectHasNoEnvironments);
+            }
+
+            if pacs.is_safe_mode() {
+                eprintln!("{YELLOW}{}{RESET}", Msg::SafeModeShowing);
+                for environment in &environments {
+                    for name in &names {
+                        if matrix || names.len() > 1 {
+                            let label = match environment {
+                                Some(env) if matrix => format!("{name} [{env}]"),
+                                _ => name.clone(),
+                            };
+                            println!("{BOLD}{BLUE}==> {label}{RESET}");
+                        }
+                        let cmd = pacs.resolve_command_with_values(
+                            name,
+                            args.project.as_deref(),
+                            environment.as_deref(),
+                            &values,
+                        )?;
+                        print_command_body(&cmd.command);
+                    }
+                }
+                return Ok(());
+            }
+            if args.ci {
+                return run_ci(&pacs, &args, &names, &environments, &values);
+            }
+
+            let mut failed = Vec::new();
+            let mut summary = Vec::new();
+            let mut output = Vec::new();
+            // Exit codes and durations of the runs, for humans only
+            let summarize = !cli.quiet && pacs.shows_run_summary() && io::stderr().is_terminal();
+            let mut runs = Vec::new();
+            'environments: for environment in &environments {
+                let mut outcome = format!("{GREEN}{}{RESET}", Msg::RunOk);
+                for name in &names {
+                    let label = match environment {
+                        Some(env) if matrix => format!("{name} [{env}]"),
+                        _ => name.clone(),
+                    };
+                    if matrix || names.len() > 1 {
+                        println!("{BOLD}{BLUE}==> {label}{RESET}");
+                    }
+                    let (project, env) = (args.project.as_deref(), environment.as_deref());
+                    let values = ask_cwd(&pacs, name, project, env, &values)
+                        .with_context(|| format!("Failed to run command '{name}'"))?;
+                    let marked = confirm_marked(&pacs, name, project, env, &values)
+                        .with_context(|| format!("Failed to run command '{name}'"))?;
+                    let declined = match marked {
+                        Some(true) => None,
+                        Some(false) => Some(PacsError::NotConfirmed(name.clone())),
+                        None if args.force
+                            || confirm_danger(&pacs, name, project, env, &values)? =>
+                        {
+                            None
+                        }
+                        None => Some(PacsError::DangerousCommand(name.clone())),
+                    };
+                    let started = std::time::Instant::now();
+                    let result = if let Some(e) = declined {
+                        Err(e)
+                    } else if args.copy_output {
+                        pacs.run_capturing_output(
+                            name,
+                            args.project.as_deref(),
+                            environment.as_deref(),
+                            &values,
+                        )
+                        .map(|stdout| output.extend(stdout))
+                    } else {
+                        pacs.run_with_values(
+                            name,
+                            args.project.as_deref(),
+                            environment.as_deref(),
+                            &values,
+                        )
+                    }
+                    .with_context(|| format!("Failed to run command '{label}'"));
+                    let code = match &result {
+                        Ok(()) => Some(0),
+                        Err(e) => match e.downcast_ref::<PacsError>() {
+                            Some(PacsError::CommandFailed(code)) => Some(*code),
+                            _ => None,
+                        },
+                    };
+                    if let Some(code) = code.filter(|_| summarize) {
+                        let elapsed = format_elapsed(started.elapsed());
+                        if names.len() * environments.len() == 1 {
+                            print_run_footer(&pacs, name, project, env, &values, code, &elapsed);
+                        }
+                        runs.push((label.clone(), code, elapsed));
+                    }
+                    let Err(e) = result else {
+                        continue;
+                    };
+                    outcome = match e.downcast_ref::<PacsError>() {
+                        Some(PacsError::CommandFailed(code)) => {
+                            let msg = Msg::ExitedWith { name, code: *code };
+                            format!("{YELLOW}{msg}{RESET}")
+                        }
+                        _ => format!("{YELLOW}{}{RESET}", Msg::FailedToStart { name }),
+                    };
+                    failed.push(label);
+                    if !matrix && !args.keep_going {
+                        print_run_table(&runs);
+                        return Err(e);
+                    }
+                    eprintln!("Error: {e:#}");
+                    if !args.keep_going {
+                        summary.push((environment.clone(), outcome));
+                        break 'environments;
+                    }
+                }
+                summary.push((environment.clone(), outcome));
+            }
+
+            if matrix {
+                println!();
+                let width = environments
+                    .iter()
+                    .map(|e| e.as_deref().unwrap_or_default().len())
+                    .max()
+                    .unwrap_or_default();
+                for environment in &environments {
+                    let outcome = summary
+                        .iter()
+                        .find(|(env, _)| env == environment)
+                        .map_or_else(
+                            || format!("{GREY}{}{RESET}", Msg::RunSkipped),
+                            |(_, o)| o.clone(),
+                        );
+                    println!(
+                        "{BOLD}{:width$}{RESET}  {outcome}",
+                        environment.as_deref().unwrap_or_default()
+                    );
+                }
+            }
+            print_run_table(&runs);
+            if !failed.is_empty() {
+                anyhow::bail!(
+                    "{}",
+                    Msg::RunsFailed {
+                        count: failed.len(),
+                        names: &failed.join(", ")
+                    }
+                );
+            }
+            if args.copy_output {
+                let text = String::from_utf8_lossy(&output);
+                let text = text.trim_end_matches(['\r', '\n']);
+                if text.len() > COPY_OUTPUT_LIMIT {
+                    println!("{}", pacs.redact(text));
+                    anyhow::bail!(
+                        "{}",
+                        Msg::OutputTooLarge {
+                            bytes: text.len(),
+                            limit: COPY_OUTPUT_LIMIT
+                        }
+                    );
+                }
+                arboard::Clipboard::new()
+                    .and_then(|mut cb| cb.set_text(text))
+                    .map_err(|e| anyhow::anyhow!("Failed to copy to clipboard: {e}"))?;
+                println!("{}", Msg::OutputCopied { bytes: text.len() });
+            }
         }
 
         Commands::Copy(args) => {
-            let cmd = pacs
-                .copy(&args.name, None, args.environment.as_deref())
-                .with_context(|| format!("Command '{}' not found", args.name))?;
+            let mut bodies = Vec::with_capacity(args.names.len());
+            for name in &args.names {
+                let cmd = pacs
+                    .copy(name, None, args.environment.as_deref())
+                    .with_context(|| format!("Command '{name}' not found"))?;
+                let cleanup = BodyCleanup {
+                    strip_comments: args.strip_comments,
+                    normalize: args.normalize,
+                };
+                let body = cleanup.apply(&cmd.command);
+                if args.oneline {
+                    let line = flatten_command(&body)
+                        .ok_or_else(|| anyhow::anyhow!("{}", Msg::NoOneline { name }))?;
+                    bodies.push(line);
+                } else {
+                    bodies.push(body.trim().to_string());
+                }
+            }
+            let separator = if args.oneline { " && " } else { "\n" };
+            let mut text = bodies.join(separator);
+            if let Some(shell) = args.escape {
+                text = Shell::from(shell).escape_command(&text);
+            }
             arboard::Clipboard::new()
-                .and_then(|mut cb| cb.set_text(cmd.command.trim()))
+                .and_then(|mut cb| cb.set_text(text))
                 .map_err(|e| anyhow::anyhow!("Failed to copy to clipboard: {e}"))?;
-            println!("Copied '{}' to clipboard.", args.name);
+            println!(
+                "{}",
+                Msg::Copied {
+                    names: &args.names.join("', '")
+                }
+            );
         }
 
-        Commands::Search(args) => {
-            let matches = pacs.search(&args.query);
-            if matches.is_empty() {
-                println!("No matches found.");
+        Commands::Get(args) => {
+            let cmd = pacs
+                .get_project_or_active(args.project.as_deref())?
+                .commands
+                .iter()
+                .find(|c| c.matches(&args.name))
+                .with_context(|| format!("Command '{}' not found", args.name))?;
+            let cmd = if args.resolved {
+                pacs.copy(
+                    &cmd.name,
+                    args.project.as_deref(),
+                    args.environment.as_deref(),
+                )?
             } else {
-                for cmd in matches {
-                    println!("{}", cmd.name);
+                pacs.on_this_machine(cmd)
+            };
+
+            let value = match args.field {
+                CommandField::Command => Some(cmd.command.clone()),
+                CommandField::Cwd => cmd.cwd.clone(),
+                CommandField::Tag => Some(cmd.tag.clone()).filter(|tag| !tag.is_empty()),
+                CommandField::Description => cmd.description(),
+            };
+            // A missing field prints nothing, like an empty one
+            let Some(value) = value else {
+                return Ok(());
+            };
+            if args.resolved && matches!(args.field, CommandField::Command | CommandField::Cwd) {
+                let (_, mut unresolved) = render_placeholders(&value, &BTreeMap::new());
+                if args.field == CommandField::Cwd && cmd.asks_cwd() {
+                    unresolved.push(CWD_KEY.to_string());
+                }
+                if !unresolved.is_empty() {
+                    return Err(PacsError::UnresolvedPlaceholders {
+                        command: cmd.name,
+                        keys: unresolved.join(", "),
+                    }
+                    .into());
                 }
             }
+            println!("{}", value.trim_end_matches('\n'));
         }
 
-        Commands::Project { command } => match command {
-            ProjectCommands::Add(args) => {
-                pacs.init_project(&args.name, args.path)
-                    .with_context(|| format!("Failed to create project '{}'", args.name))?;
-                pacs.set_active_project(&args.name)
-                    .with_context(|| format!("Failed to switch to project '{}'", args.name))?;
-                println!("Project '{}' created and activated.", args.name);
+        Commands::Explain(args) => {
+            let cmd = pacs
+                .get_project_or_active(args.project.as_deref())?
+                .commands
+                .iter()
+                .find(|c| c.matches(&args.name))
+                .ok_or_else(|| PacsError::CommandNotFound(args.name.clone()))?;
+
+            for segment in explain_command(&cmd.command) {
+                match segment.operator {
+                    Some(op) => println!("{MAGENTA}{op}{RESET} {WHITE}{}{RESET}", segment.text),
+                    None => println!("{WHITE}{}{RESET}", segment.text),
+                }
+                let Some(program) = segment.program else {
+                    continue;
+                };
+                let width = segment
+                    .notes
+                    .iter()
+                    .map(|(part, _)| part.chars().count())
+                    .chain([program.chars().count()])
+                    .max()
+                    .unwrap_or_default();
+                match segment.summary {
+                    Some(summary) => println!("  {BLUE}{program:width$}{RESET}  {summary}"),
+                    None => println!(
+                        "  {BLUE}{program:width$}{RESET}  {GREY}{}{RESET}",
+                        Msg::UnknownProgram
+                    ),
+                }
+                for (part, meaning) in &segment.notes {
+                    println!("  {YELLOW}{part:width$}{RESET}  {meaning}");
+                }
             }
-            ProjectCommands::Remove(args) => {
-                pacs.delete_project(&args.name)
-                    .with_context(|| format!("Failed to delete project '{}'", args.name))?;
-                println!("Project '{}' deleted.", args.name);
+
+            if args.ai {
+                let explanation = pacs
+                    .explain_with_endpoint(&cmd.command)
+                    .context("Failed to explain the command")?;
+                println!("\n{explanation}");
+            }
+        }
+
+        Commands::Search(args) => {
+            refresh_registries(&mut pacs);
+            let mut matches = pacs.search(&args.query);
+            if args.run {
+                // Registry commands are not part of a project and cannot be run
+                matches.retain(|m| !m.registry);
+            }
+            if let Some(limit) = args.limit {
+                matches.truncate(limit);
             }
-            ProjectCommands::List => {
-                if pacs.projects.is_empty() {
-                    println!("No projects. Use 'pacs project add' to create one.");
+
+            if args.run && !matches.is_empty() {
+                let confident: Vec<&SearchMatch> = matches.iter().filter(|m| m.confident).collect();
+                let chosen = if let [only] = confident[..] {
+                    only
                 } else {
-                    let active = pacs.get_active_project_name().ok();
-                    for project in &pacs.projects {
-                        let path_info = project
-                            .path
-                            .as_ref()
-                            .map(|p| format!(" ({p})"))
-                            .unwrap_or_default();
-                        let active_marker = if active.as_ref() == Some(&project.name) {
-                            format!(" {GREEN}*{RESET}")
-                        } else {
-                            String::new()
-                        };
-                        println!(
-                            "{}{}{}{}{}",
-                            BLUE, project.name, RESET, path_info, active_marker
-                        );
+                    for (i, m) in matches.iter().enumerate() {
+                        print_search_match(m, Some(i + 1));
                     }
+                    print!(
+                        "{}",
+                        Msg::RunWhich {
+                            count: matches.len()
+                        }
+                    );
+                    io::stdout().flush()?;
+                    let mut answer = String::new();
+                    io::stdin().read_line(&mut answer)?;
+                    let Some(chosen) = answer
+                        .trim()
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|i| matches.get(i.wrapping_sub(1)))
+                    else {
+                        println!("{}", Msg::NothingRun);
+                        return Ok(());
+                    };
+                    chosen
+                };
+                let name = &chosen.command.name;
+                if pacs.is_safe_mode() {
+                    eprintln!("{YELLOW}{}{RESET}", Msg::SafeModeShowing);
+                    print_command_body(
+                        &pacs
+                            .resolve_command(name, Some(chosen.scope), None)?
+                            .command,
+                    );
+                    return Ok(());
+                }
+                let scope = Some(chosen.scope);
+                let no_values = BTreeMap::new();
+                let confirmed = match confirm_marked(&pacs, name, scope, None, &no_values)? {
+                    Some(answer) => answer,
+                    None => confirm_danger(&pacs, name, scope, None, &no_values)?,
+                };
+                if !confirmed {
+                    println!("{}", Msg::NothingRun);
+                    return Ok(());
+                }
+                println!("{BOLD}{BLUE}==> {name}{RESET}");
+                pacs.run(name, Some(chosen.scope), None)
+                    .with_context(|| format!("Failed to run command '{name}'"))?;
+            } else if args.json {
+                let items: Vec<SearchItem> = matches.iter().map(SearchItem::from).collect();
+                println!("{}", serde_json::to_string_pretty(&Versioned::new(items))?);
+            } else if matches.is_empty() {
+                println!("{}", Msg::NoMatches);
+            } else {
+                for m in &matches {
+                    print_search_match(m, None);
                 }
             }
-            ProjectCommands::Switch(args) => {
-                pacs.set_active_project(&args.name)
-                    .with_context(|| format!("Failed to switch to project '{}'", args.name))?;
-                println!("Switched to project '{}'.", args.name);
+        }
+
+        Commands::Export(args) if args.all => {
+            let bundle = pacs.bundle();
+            let content = match args
+                .format
+                .map(ExportFormat::from)
+                .or_else(|| args.output.as_deref().and_then(ExportFormat::from_path))
+            {
+                None | Some(ExportFormat::Toml) => bundle.to_toml(),
+                Some(ExportFormat::Json) => bundle.to_json(),
+                Some(_) => anyhow::bail!("{}", Msg::BundleFormat),
             }
-            ProjectCommands::Clear => {
-                pacs.clear_active_project()?;
-                println!("Active project cleared.");
+            .context("Failed to export projects")?;
+            if let Some(path) = args.output {
+                fs::write(&path, content)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                println!("{}", Msg::Exported { path: &path });
+            } else {
+                print!("{content}");
             }
-            ProjectCommands::Active => match pacs.get_active_project_name() {
-                Ok(active) => println!("{active}"),
-                Err(_) => println!("No active project."),
-            },
-        },
-        Commands::Env { command } => match command {
-            EnvCommands::Add(args) => {
-                let project = resolve_project_name(&pacs, args.project)?;
+        }
 
-                pacs.add_environment(&project, &args.name)
-                    .with_context(|| {
-                        format!(
-                            "Failed to add environment '{}' to project '{}'",
-                            args.name, project
-                        )
-                    })?;
-                pacs.set_active_environment(&project, &args.name)
-                    .with_context(|| {
-                        format!(
-                            "Failed to activate environment '{}' in project '{}'",
-                            args.name, project
-                        )
-                    })?;
-                println!(
-                    "Environment '{}' added and activated in project '{}'.",
-                    args.name, project
-                );
+        Commands::Export(args) => {
+            let format = args
+                .format
+                .map(ExportFormat::from)
+                .or_else(|| args.output.as_deref().and_then(ExportFormat::from_path))
+                .unwrap_or(ExportFormat::Toml);
+            let content = pacs
+                .export(
+                    args.project.as_deref(),
+                    args.environment.as_deref(),
+                    args.tag.as_deref(),
+                    format,
+                    BodyCleanup {
+                        strip_comments: args.strip_comments,
+                        normalize: args.normalize,
+                    },
+                )
+                .context("Failed to export commands")?;
+            if let Some(path) = args.output {
+                fs::write(&path, content)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                println!("{}", Msg::Exported { path: &path });
+            } else {
+                print!("{content}");
             }
-            EnvCommands::Remove(args) => {
-                let project = resolve_project_name(&pacs, args.project)?;
+        }
 
-                pacs.remove_environment(&project, &args.name)
-                    .with_context(|| {
-                        format!(
-                            "Failed to remove environment '{}' from project '{}'",
+        Commands::Import(args) if args.from.is_none() => {
+            let content = if !args.files.is_empty() {
+                read_files(&args.files)?
+            } else if io::stdin().is_terminal() {
+                anyhow::bail!("{}", Msg::NoBundleGiven);
+            } else {
+                io::read_to_string(io::stdin())?
+            };
+            let bundle = Bundle::parse(&content).context("Failed to read the bundle")?;
+
+            let mut resolutions = BTreeMap::new();
+            let conflicts = if args.replace {
+                Vec::new()
+            } else {
+                pacs.bundle_conflicts(&bundle)
+            };
+            for conflict in conflicts {
+                let BundleConflict {
+                    project,
+                    mine,
+                    theirs,
+                } = conflict;
+                if !args.interactive {
+                    eprintln!(
+                        "{YELLOW}{}{RESET} {}",
+                        Msg::Warning,
+                        Msg::KeptLocal {
+                            project: &project,
+                            name: &theirs.name
+                        }
+                    );
+                    continue;
+                }
+
+                let resolution = ask_resolution(&project, &mine, &theirs, |name| {
+                    pacs.get_project_or_active(Some(&project))
+                        .is_ok_and(|p| p.commands.iter().any(|c| c.matches(name)))
+                        || resolutions.iter().any(|((p, _), r)| {
+                            *p == project && *r == Resolution::Rename(name.to_string())
+                        })
+                })?;
+                resolutions.insert((project, theirs.name), resolution);
+            }
+
+            let summary = pacs
+                .import_bundle(bundle, args.replace, &resolutions)
+                .context("Failed to import the bundle")?;
+            for name in &summary.created {
+                println!(
+                    "{}",
+                    Msg::ProjectImported {
+                        name,
+                        created: true
+                    }
+                );
+            }
+            for name in &summary.updated {
+                println!(
+                    "{}",
+                    Msg::ProjectImported {
+                        name,
+                        created: false
+                    }
+                );
+            }
+            if summary.commands > 0 {
+                println!(
+                    "{}",
+                    Msg::CommandsMerged {
+                        count: summary.commands
+                    }
+                );
+            }
+        }
+
+        Commands::Import(args) => {
+            let content = if !args.files.is_empty() {
+                read_files(&args.files)?
+            } else if io::stdin().is_terminal() {
+                let home = env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+                let files: Vec<PathBuf> = ALIAS_FILES
+                    .iter()
+                    .map(|file| home.join(file))
+                    .filter(|path| path.exists())
+                    .collect();
+                read_files(&files)?
+            } else {
+                io::read_to_string(io::stdin())?
+            };
+
+            let commands: Vec<PacsCommand> = parse_aliases(&content)
+                .into_iter()
+                .map(|(name, command)| PacsCommand {
+                    name,
+                    command,
+                    cwd: None,
+                    tag: String::new(),
+                    aliases: Vec::new(),
+                    expires: None,
+                    review_after: None,
+                    author: None,
+                    requires: Vec::new(),
+                    local_only: false,
+                    overrides: BTreeMap::new(),
+                    safe: false,
+                    confirm: false,
+                    locked: false,
+                    sandbox: None,
+                })
+                .collect();
+            if commands.is_empty() {
+                println!("{}", Msg::NoAliasesFound);
+                return Ok(());
+            }
+
+            let count = commands.len();
+            let skipped = pacs
+                .import_commands(commands, args.project.as_deref())
+                .context("Failed to import aliases")?;
+            for name in &skipped {
+                eprintln!(
+                    "{YELLOW}{}{RESET} {}",
+                    Msg::Warning,
+                    Msg::ImportSkipped { name }
+                );
+            }
+            let project = match args.project {
+                Some(project) => project,
+                None => pacs.get_active_project_name()?,
+            };
+            println!(
+                "{}",
+                Msg::Imported {
+                    count: count - skipped.len(),
+                    project: &project
+                }
+            );
+        }
+
+        Commands::Dedupe(args) => {
+            if !(0.0..=1.0).contains(&args.threshold) {
+                anyhow::bail!("{}", Msg::InvalidThreshold);
+            }
+            let groups = pacs.find_duplicates(args.threshold);
+            if groups.is_empty() {
+                println!("{}", Msg::NoDuplicates);
+            }
+            for group in groups {
+                println!(
+                    "{BOLD}{YELLOW}{}{RESET}",
+                    Msg::DuplicateGroup {
+                        count: group.commands.len(),
+                        identical: group.identical
+                    }
+                );
+                for (i, cmd) in group.commands.iter().enumerate() {
+                    println!(
+                        "  {BOLD}{}){RESET} {GREY}{}/{RESET}{BOLD}{CYAN}{}{RESET}",
+                        i + 1,
+                        cmd.project,
+                        cmd.name
+                    );
+                    if let Ok(project) = pacs.get_project_or_active(Some(&cmd.project))
+                        && let Ok(c) = PacsCommand::find_by_name(&project.commands, &cmd.name)
+                    {
+                        print_command_body(&c.command);
+                    }
+                }
+
+                if args.dry_run {
+                    println!();
+                    continue;
+                }
+                print!(
+                    "{}",
+                    Msg::KeepWhich {
+                        count: group.commands.len()
+                    }
+                );
+                io::stdout().flush()?;
+                let mut answer = String::new();
+                io::stdin().read_line(&mut answer)?;
+                let Some(keep) = answer
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| group.commands.get(i.wrapping_sub(1)))
+                else {
+                    println!("{}", Msg::Skipped);
+                    println!();
+                    continue;
+                };
+                let (same, other): (Vec<_>, Vec<_>) = group
+                    .commands
+                    .iter()
+                    .cloned()
+                    .partition(|cmd| cmd.project == keep.project);
+                pacs.merge_commands(keep, &same)
+                    .with_context(|| format!("Failed to merge into '{}'", keep.name))?;
+                println!(
+                    "{}",
+                    Msg::Merged {
+                        project: &keep.project,
+                        name: &keep.name
+                    }
+                );
+                if !other.is_empty() {
+                    println!("{}", Msg::KeptInOtherProjects { count: other.len() });
+                }
+                println!();
+            }
+        }
+
+        Commands::Conflicts => {
+            let conflicts = pacs.find_conflicts();
+            if conflicts.is_empty() {
+                println!("{}", Msg::NoConflicts);
+            }
+            let active = pacs.get_active_project_name().ok();
+            for conflict in &conflicts {
+                println!("{BOLD}{CYAN}{}{RESET}", conflict.name);
+                for claim in &conflict.claims {
+                    let project = &claim.command.project;
+                    let alias = if claim.alias {
+                        format!(" {GREY}{}{RESET}", Msg::Alias)
+                    } else {
+                        String::new()
+                    };
+                    let resolution = if conflict.resolve(project) != Some(&claim.command) {
+                        format!("{YELLOW}{}{RESET}", Msg::ShadowedIn { project })
+                    } else if active.as_ref() == Some(project) {
+                        let command = format!("pacs run {}", conflict.name);
+                        format!("{GREEN}{}{RESET}", Msg::PickedBy { command: &command })
+                    } else {
+                        let command = format!("pacs run {} -p {project}", conflict.name);
+                        format!("{GREY}{}{RESET}", Msg::PickedBy { command: &command })
+                    };
+                    println!("  {project}/{}{alias}  {resolution}", claim.command.name);
+                }
+            }
+        }
+
+        Commands::Open(args) => {
+            if args.force {
+                pacs.set_ignore_locks();
+            }
+            let project = if args.config {
+                None
+            } else {
+                if pacs.is_readonly() {
+                    return Err(PacsError::ReadOnly.into());
+                }
+                Some(resolve_project_name(&pacs, args.project)?)
+            };
+            let path = match &project {
+                Some(project) => pacs.project_file(project)?,
+                None => pacs.config_file(),
+            };
+            let original = fs::read_to_string(&path).unwrap_or_default();
+
+            let editor = env::var("VISUAL")
+                .ok()
+                .or_else(|| env::var("EDITOR").ok())
+                .unwrap_or_else(|| "vi".to_string());
+            // Edit a private copy in ~/.pacs, as the config holds the GitHub token
+            let temp_file = tempfile::Builder::new()
+                .prefix(".pacs-open-")
+                .suffix(".toml")
+                .tempfile_in(pacs.base_dir())?
+                .into_temp_path();
+            fs::write(&temp_file, &original)?;
+
+            // Reopen the editor until the file is valid or the changes are discarded
+            let result = loop {
+                let status = Command::new(&editor)
+                    .arg(&temp_file)
+                    .status()
+                    .with_context(|| format!("Failed to open editor '{editor}'"));
+                match status {
+                    Ok(status) if status.success() => {}
+                    Ok(_) => break Err(anyhow::anyhow!("{}", Msg::EditorFailed)),
+                    Err(e) => break Err(e),
+                }
+
+                let edited = fs::read_to_string(&temp_file)?;
+                if edited == original {
+                    println!("{}", Msg::NoChanges);
+                    break Ok(());
+                }
+                let saved = match &project {
+                    Some(project) => pacs.replace_project(project, &edited),
+                    None => pacs.replace_config(&edited),
+                };
+                let Err(e) = saved else {
+                    println!("{}", Msg::FileSaved { path: &path });
+                    break Ok(());
+                };
+
+                eprintln!("{RED}{}{RESET} {e}", Msg::Error);
+                print!("{}", Msg::EditAgainPrompt);
+                io::stdout().flush()?;
+                let mut answer = String::new();
+                let read = io::stdin().read_line(&mut answer)?;
+                if read == 0 || answer.trim().eq_ignore_ascii_case("n") {
+                    break Err(anyhow::anyhow!("{}", Msg::ChangesDiscarded));
+                }
+            };
+            temp_file.close().ok();
+            result?;
+        }
+
+        Commands::Shell(args) => {
+            if pacs.is_safe_mode() {
+                return Err(PacsError::SafeMode.into());
+            }
+            let project = pacs.get_project_or_active(args.project.as_deref())?;
+            if let Some(environment) = &args.environment
+                && !project.environments.iter().any(|e| &e.name == environment)
+            {
+                anyhow::bail!(
+                    "{}",
+                    Msg::EnvironmentNotFound {
+                        environment,
+                        project: &project.name
+                    }
+                );
+            }
+
+            let environment = args
+                .environment
+                .or_else(|| pacs.get_active_environment(Some(&project.name)).ok()?);
+            let values = pacs
+                .resolved_environment_values(Some(&project.name), environment.as_deref())
+                .context("Failed to resolve environment values")?;
+
+            let shell = env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+            let mut rc_dir = tempfile::Builder::new();
+            rc_dir.prefix("pacs-shell-");
+            #[cfg(unix)]
+            rc_dir.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o700));
+            let rc_dir = rc_dir.tempdir()?;
+            let mut command = shell_with_aliases(&shell, rc_dir.path(), &alias_targets(project))?;
+
+            command.env("PACS_PROJECT", &project.name);
+            match &environment {
+                Some(environment) => command.env("PACS_ENV", environment),
+                None => command.env_remove("PACS_ENV"),
+            };
+            command.envs(
+                values
+                    .iter()
+                    .map(|(key, value)| (variable_name(key), value)),
+            );
+            if let Some(path) = &project.path {
+                command.current_dir(path);
+            }
+
+            println!(
+                "{}",
+                Msg::EnteringShell {
+                    project: &project.name,
+                    environment: environment.as_deref()
+                }
+            );
+            let status = command.status();
+            rc_dir.close().ok();
+            status.with_context(|| format!("Failed to start shell '{shell}'"))?;
+        }
+
+        Commands::Doctor => {
+            let expiring = pacs.expiring_commands();
+            if expiring.is_empty() && pacs.load_warnings().is_empty() {
+                println!("{}", Msg::NoProblems);
+            }
+            for (cmd, status) in &expiring {
+                let project = pacs.get_project_or_active(Some(&cmd.project))?;
+                let command = PacsCommand::find_by_name(&project.commands, &cmd.name)?;
+                let date = match status {
+                    ExpiryStatus::Expired => command.expires.as_deref(),
+                    ExpiryStatus::ReviewDue => command.review_after.as_deref(),
+                };
+                println!(
+                    "{GREY}{}/{RESET}{BOLD}{CYAN}{}{RESET} {} {GREY}{}{RESET}",
+                    cmd.project,
+                    cmd.name,
+                    status.label(),
+                    Msg::Since {
+                        date: date.unwrap_or_default()
+                    }
+                );
+            }
+            if !expiring.is_empty() {
+                println!("{}", Msg::ExpiryHint);
+            }
+        }
+
+        Commands::Lint(args) => {
+            let undeclared = pacs
+                .lint_required_keys(args.project.as_deref(), args.fix)
+                .context("Failed to lint commands")?;
+            if undeclared.is_empty() {
+                println!("{}", Msg::AllKeysDeclared);
+            }
+            for (name, keys) in &undeclared {
+                println!(
+                    "{BOLD}{CYAN}{name}{RESET} {}",
+                    Msg::UndeclaredKeys {
+                        keys: &keys.join(", ")
+                    }
+                );
+            }
+            if !undeclared.is_empty() {
+                if args.fix {
+                    println!("{}", Msg::KeysAdded);
+                } else {
+                    println!("{}", Msg::LintHint);
+                }
+            }
+        }
+
+        Commands::Prune(args) => {
+            let unused = pacs
+                .unused_commands(args.project.as_deref(), args.unused)
+                .context("Failed to read history")?;
+            if unused.is_empty() {
+                println!("{}", Msg::NoUnused);
+                return Ok(());
+            }
+
+            let now = unix_now();
+            let history_start = pacs.history()?.first().map_or(now, |r| r.started_at);
+            if now.saturating_sub(history_start) < args.unused.as_secs() {
+                eprintln!("{YELLOW}{}{RESET} {}", Msg::Warning, Msg::HistoryTooShort);
+            }
+
+            for (cmd, last_run) in &unused {
+                let last_run = match last_run {
+                    Some(t) => Msg::LastRun {
+                        days: now.saturating_sub(*t) / 86_400,
+                    }
+                    .to_string(),
+                    None => Msg::NeverRun.to_string(),
+                };
+                println!(
+                    "{GREY}{}/{RESET}{BOLD}{CYAN}{}{RESET} {GREY}({last_run}){RESET}",
+                    cmd.project, cmd.name
+                );
+            }
+
+            if args.archive {
+                let commands: Vec<_> = unused.into_iter().map(|(cmd, _)| cmd).collect();
+                pacs.archive_commands(&commands)
+                    .context("Failed to archive commands")?;
+                println!(
+                    "{}",
+                    Msg::Archived {
+                        count: commands.len()
+                    }
+                );
+            } else {
+                println!("{}", Msg::ArchiveHint);
+            }
+        }
+
+        Commands::Project { command } => match command {
+            ProjectCommands::Add(args) => {
+                let path = args
+                    .path
+                    .map(|path| project_dir(&path, args.create))
+                    .transpose()
+                    .with_context(|| format!("Failed to create project '{}'", args.name))?;
+                match &args.template {
+                    Some(template) => pacs.init_project_from_template(&args.name, path, template),
+                    None => pacs.init_project(&args.name, path),
+                }
+                .with_context(|| format!("Failed to create project '{}'", args.name))?;
+                if args.local_only {
+                    pacs.set_project_local_only(&args.name, true)?;
+                }
+                pacs.set_active_project(&args.name)
+                    .with_context(|| format!("Failed to switch to project '{}'", args.name))?;
+                println!("{}", Msg::ProjectCreated { name: &args.name });
+            }
+            ProjectCommands::Remove(args) => {
+                pacs.delete_project(&args.name)
+                    .with_context(|| format!("Failed to delete project '{}'", args.name))?;
+                println!("{}", Msg::ProjectDeleted { name: &args.name });
+            }
+            ProjectCommands::List(args) => {
+                if pacs.projects().is_empty() {
+                    println!("{}", Msg::NoProjects);
+                } else {
+                    let active = pacs.get_active_project_name().ok();
+                    let projects = if args.recent {
+                        pacs.projects_by_recency()?
+                    } else {
+                        pacs.projects().iter().collect()
+                    };
+                    for project in projects
+                        .into_iter()
+                        .filter(|p| args.archived || !p.archived)
+                    {
+                        let path_info = project
+                            .path
+                            .as_ref()
+                            .map(|p| format!(" ({p})"))
+                            .unwrap_or_default();
+                        let active_marker = if active.as_ref() == Some(&project.name) {
+                            format!(" {GREEN}*{RESET}")
+                        } else {
+                            String::new()
+                        };
+                        println!(
+                            "{}{}{}{}{}",
+                            project_label(project),
+                            path_info,
+                            local_badge(project.local_only),
+                            archived_badge(project.archived),
+                            active_marker
+                        );
+                    }
+                }
+            }
+            ProjectCommands::Archive(args) => {
+                pacs.set_project_archived(&args.name, true)
+                    .with_context(|| format!("Failed to archive project '{}'", args.name))?;
+                println!("{}", Msg::ProjectArchived { name: &args.name });
+            }
+            ProjectCommands::Unarchive(args) => {
+                pacs.set_project_archived(&args.name, false)
+                    .with_context(|| format!("Failed to unarchive project '{}'", args.name))?;
+                println!("{}", Msg::ProjectUnarchived { name: &args.name });
+            }
+            ProjectCommands::Style(args) => {
+                let project = resolve_project_name(&pacs, args.name)?;
+                pacs.set_project_style(&project, args.color.as_deref(), args.icon.as_deref())
+                    .with_context(|| format!("Failed to style project '{project}'"))?;
+                let project = pacs.get_project_or_active(Some(&project))?;
+                println!(
+                    "{}",
+                    Msg::ProjectStyled {
+                        project: &project_label(project)
+                    }
+                );
+            }
+            ProjectCommands::Switch(args) => {
+                let name = if args.name == "-" {
+                    pacs.previous_project()?
+                        .with_context(|| Msg::NoPreviousProject.to_string())?
+                } else {
+                    args.name
+                };
+                let hook = pacs
+                    .switch_project(&name, !args.no_hooks)
+                    .with_context(|| format!("Failed to switch to project '{name}'"))?;
+                println!("{}", Msg::SwitchedProject { name: &name });
+                if let Some(hook) = hook {
+                    print_hook_summary(&hook);
+                }
+                if let Some(project) = &pacs.session().project {
+                    println!("{}", Msg::SessionProject { project });
+                }
+            }
+            ProjectCommands::Clear => {
+                pacs.clear_active_project()?;
+                println!("{}", Msg::ProjectCleared);
+            }
+            ProjectCommands::Active => match pacs.get_active_project_name() {
+                Ok(active) => println!("{active}"),
+                Err(_) => println!("{}", Msg::NoActiveProject),
+            },
+            ProjectCommands::Publish(args) => {
+                let project = resolve_project_name(&pacs, args.name)?;
+                let url = pacs
+                    .publish_project_gist(&project)
+                    .with_context(|| format!("Failed to publish project '{project}'"))?;
+                println!(
+                    "{}",
+                    Msg::Published {
+                        project: &project,
+                        url: &url
+                    }
+                );
+            }
+            ProjectCommands::Pull(args) => {
+                let project = pacs
+                    .pull_project_gist(&args.gist, args.force)
+                    .with_context(|| format!("Failed to pull gist '{}'", args.gist))?;
+                println!(
+                    "{}",
+                    Msg::Pulled {
+                        project: &project,
+                        gist: &args.gist
+                    }
+                );
+            }
+            ProjectCommands::Notes(args) => {
+                let project = resolve_project_name(&pacs, args.project)?;
+                let notes = pacs
+                    .projects()
+                    .iter()
+                    .find(|p| p.name.eq_ignore_ascii_case(&project))
+                    .with_context(|| format!("Project '{project}' not found"))?
+                    .notes
+                    .clone();
+
+                let Some(NotesAction::Edit) = args.action else {
+                    match notes {
+                        Some(notes) => println!("{notes}"),
+                        None => println!("{}", Msg::NoNotes { project: &project }),
+                    }
+                    return Ok(());
+                };
+
+                if pacs.is_readonly() {
+                    return Err(PacsError::ReadOnly.into());
+                }
+
+                let editor = env::var("VISUAL")
+                    .ok()
+                    .or_else(|| env::var("EDITOR").ok())
+                    .unwrap_or_else(|| "vi".to_string());
+
+                let temp_file =
+                    std::env::temp_dir().join(format!("pacs-notes-{}.md", std::process::id()));
+                fs::write(&temp_file, notes.unwrap_or_default())?;
+
+                let status = Command::new(&editor)
+                    .arg(&temp_file)
+                    .status()
+                    .with_context(|| format!("Failed to open editor '{editor}'"))?;
+
+                if !status.success() {
+                    fs::remove_file(&temp_file).ok();
+                    anyhow::bail!("{}", Msg::EditorFailed);
+                }
+
+                let edited = fs::read_to_string(&temp_file)?;
+                fs::remove_file(&temp_file).ok();
+
+                pacs.set_project_notes(&project, &edited)?;
+                println!("{}", Msg::NotesSaved { project: &project });
+            }
+            ProjectCommands::Open(args) => {
+                let project = resolve_project_name(&pacs, args.name)?;
+                let path = pacs
+                    .get_project_or_active(Some(&project))?
+                    .path
+                    .clone()
+                    .with_context(|| Msg::NoProjectPath { project: &project }.to_string())?;
+                if !Path::new(&path).is_dir() {
+                    anyhow::bail!("{}", Msg::ProjectPathMissing { path: &path });
+                }
+                open_project_dir(&path, args.finder)?;
+            }
+        },
+        Commands::Env { command } => match command {
+            EnvCommands::Add(args) => {
+                let project = resolve_project_name(&pacs, args.project)?;
+
+                pacs.add_environment(&project, &args.name)
+                    .with_context(|| {
+                        format!(
+                            "Failed to add environment '{}' to project '{}'",
+                            args.name, project
+                        )
+                    })?;
+                pacs.set_active_environment(&project, &args.name)
+                    .with_context(|| {
+                        format!(
+                            "Failed to activate environment '{}' in project '{}'",
+                            args.name, project
+                        )
+                    })?;
+                println!(
+                    "{}",
+                    Msg::EnvironmentAdded {
+                        name: &args.name,
+                        project: &project
+                    }
+                );
+            }
+            EnvCommands::Remove(args) => {
+                let project = resolve_project_name(&pacs, args.project)?;
+
+                pacs.remove_environment(&project, &args.name)
+                    .with_context(|| {
+                        format!(
+                            "Failed to remove environment '{}' from project '{}'",
+                            args.name, project
+                        )
+                    })?;
+                println!(
+                    "{}",
+                    Msg::EnvironmentRemoved {
+                        name: &args.name,
+                        project: &project
+                    }
+                );
+            }
+            EnvCommands::Copy(args) => {
+                let from = resolve_project_name(&pacs, args.from)?;
+
+                pacs.copy_environment(&args.name, &from, &args.to, args.force)
+                    .with_context(|| {
+                        format!(
+                            "Failed to copy environment '{}' from project '{}' to '{}'",
+                            args.name, from, args.to
+                        )
+                    })?;
+                println!(
+                    "{}",
+                    Msg::EnvironmentCopied {
+                        name: &args.name,
+                        from: &from,
+                        to: &args.to
+                    }
+                );
+            }
+            EnvCommands::Edit(args) => {
+                #[derive(serde::Deserialize)]
+                struct EditDoc {
+                    #[serde(default)]
+                    active_environment: Option<String>,
+                    #[serde(default)]
+                    environments: std::collections::BTreeMap<String, EnvValues>,
+                }
+                #[derive(serde::Deserialize)]
+                struct EnvValues {
+                    #[serde(default)]
+                    kube_context: Option<String>,
+                    #[serde(default)]
+                    on_activate: Option<String>,
+                    #[serde(default)]
+                    on_deactivate: Option<String>,
+                    #[serde(default)]
+                    values: BTreeMap<String, String>,
+                }
+
+                if pacs.is_readonly() {
+                    return Err(PacsError::ReadOnly.into());
+                }
+
+                let editor = env::var("VISUAL")
+                    .ok()
+                    .or_else(|| env::var("EDITOR").ok())
+                    .unwrap_or_else(|| "vi".to_string());
+
+                let project = resolve_project_name(&pacs, args.project)?;
+
+                let project_ref = pacs
+                    .projects()
+                    .iter()
+                    .find(|p| p.name.eq_ignore_ascii_case(&project))
+                    .with_context(|| format!("Project '{project}' not found"))?;
+
+                let mut buf = String::new();
+                if let Some(active_env) = &project_ref.active_environment {
+                    write!(buf, "active_environment = \"{active_env}\"\n\n").unwrap();
+                }
+
+                for env in &project_ref.environments {
+                    let settings = [
+                        ("kube_context", &env.kube_context),
+                        ("on_activate", &env.on_activate),
+                        ("on_deactivate", &env.on_deactivate),
+                    ];
+                    if settings.iter().any(|(_, value)| value.is_some()) {
+                        writeln!(buf, "[environments.{}]", env.name).unwrap();
+                        for (key, value) in settings {
+                            if let Some(value) = value {
+                                writeln!(buf, "{key} = \"{}\"", value.replace('"', "\\\""))
+                                    .unwrap();
+                            }
+                        }
+                        buf.push('\n');
+                    }
+                    writeln!(buf, "[environments.{}.values]", env.name).unwrap();
+                    for (k, v) in &env.values {
+                        writeln!(buf, "{k} = \"{}\"", v.replace('"', "\\\"")).unwrap();
+                    }
+                    buf.push('\n');
+                }
+
+                let temp_file =
+                    std::env::temp_dir().join(format!("pacs-env-{}.toml", std::process::id()));
+                fs::write(&temp_file, buf)?;
+
+                let status = Command::new(&editor)
+                    .arg(&temp_file)
+                    .status()
+                    .with_context(|| format!("Failed to open editor '{editor}'"))?;
+
+                if !status.success() {
+                    fs::remove_file(&temp_file).ok();
+                    anyhow::bail!("{}", Msg::EditorFailed);
+                }
+
+                let edited = fs::read_to_string(&temp_file)?;
+                fs::remove_file(&temp_file).ok();
+
+                let doc: EditDoc =
+                    toml::from_str(&edited).with_context(|| "Failed to parse edited TOML")?;
+
+                if let Some(active_name) = doc.active_environment {
+                    pacs.set_active_environment(&project, &active_name)
+                        .with_context(|| {
+                            format!("Failed to set active environment '{active_name}'")
+                        })?;
+                }
+
+                for (env_name, env_values) in doc.environments {
+                    pacs.edit_environment_values(&project, &env_name, env_values.values.clone())
+                        .with_context(|| {
+                            format!(
+                                "Failed to update environment '{env_name}' values for project '{project}'"
+                            )
+                        })?;
+                    pacs.set_environment_kube_context(&project, &env_name, env_values.kube_context)
+                        .with_context(|| {
+                            format!(
+                                "Failed to update environment '{env_name}' kube context for project '{project}'"
+                            )
+                        })?;
+                    pacs.set_environment_hooks(
+                        &project,
+                        &env_name,
+                        env_values.on_activate,
+                        env_values.on_deactivate,
+                    )
+                    .with_context(|| {
+                        format!(
+                            "Failed to update environment '{env_name}' hooks for project '{project}'"
+                        )
+                    })?;
+                }
+                println!("{}", Msg::EnvironmentsUpdated { project: &project });
+            }
+            EnvCommands::Uses(args) => {
+                let commands = pacs
+                    .commands_using_key(args.project.as_deref(), &args.key)
+                    .context("Failed to find commands")?;
+                if commands.is_empty() {
+                    println!("{}", Msg::NoKeyUsers { key: &args.key });
+                }
+                for cmd in commands {
+                    let line = cmd.command.lines().next().unwrap_or_default();
+                    println!("{BOLD}{CYAN}{}{RESET} {GREY}{line}{RESET}", cmd.name);
+                }
+            }
+            EnvCommands::RenameKey(args) => {
+                let project = resolve_project_name(&pacs, args.project)?;
+                let (before, after) = pacs
+                    .rename_key(Some(&project), &args.old, &args.new, args.dry_run)
+                    .with_context(|| format!("Failed to rename key '{}'", args.old))?;
+                if !print_diff(&before, &after) {
+                    println!("{}", Msg::KeyUnused { key: &args.old });
+                } else if args.dry_run {
+                    println!("{}", Msg::DryRun);
+                } else {
+                    println!(
+                        "{}",
+                        Msg::KeyRenamed {
+                            old: &args.old,
+                            new: &args.new,
+                            project: &project
+                        }
+                    );
+                }
+            }
+            EnvCommands::List(args) => {
+                if args.all {
+                    print_all_environments(&pacs);
+                    return Ok(());
+                }
+                let environments = pacs
+                    .list_environments(args.project.as_deref())
+                    .context("Failed to list environments")?;
+                let active = pacs
+                    .get_active_environment(args.project.as_deref())
+                    .context("Failed to get active environment")?;
+
+                if environments.is_empty() {
+                    println!("{}", Msg::NoEnvironments);
+                } else {
+                    for env in environments {
+                        let active_marker = if active.as_deref() == Some(env.name.as_str()) {
+                            format!(" {GREEN}*{RESET}")
+                        } else {
+                            String::new()
+                        };
+                        println!("{CYAN}{BOLD}{}{active_marker}{RESET}", env.name);
+                        if let Some(context) = &env.kube_context {
+                            println!("  {GREY}kube_context{RESET} = {WHITE}{context}{RESET}");
+                        }
+                        for (key, hook) in [
+                            ("on_activate", &env.on_activate),
+                            ("on_deactivate", &env.on_deactivate),
+                        ] {
+                            if let Some(hook) = hook {
+                                println!("  {GREY}{key}{RESET} = {WHITE}{hook}{RESET}");
+                            }
+                        }
+                        if !env.values.is_empty() {
+                            for (k, v) in &env.values {
+                                println!("  {GREY}{k}{RESET} = {WHITE}{v}{RESET}");
+                            }
+                        }
+                    }
+                }
+            }
+            EnvCommands::Switch(args) => {
+                let project = resolve_project_name(&pacs, args.project)?;
+
+                pacs.set_active_environment(&project, &args.name)
+                    .with_context(|| {
+                        format!(
+                            "Failed to switch to environment '{}' in project '{}'",
                             args.name, project
                         )
                     })?;
                 println!(
-                    "Environment '{}' removed from project '{}'.",
-                    args.name, project
+                    "{}",
+                    Msg::SwitchedEnvironment {
+                        name: &args.name,
+                        project: &project
+                    }
+                );
+                if let Some(environment) = &pacs.session().environment {
+                    println!("{}", Msg::SessionEnvironment { environment });
+                }
+            }
+            EnvCommands::Active(args) => {
+                let project = resolve_project_name(&pacs, args.project)?;
+
+                match pacs.get_active_environment(Some(&project))? {
+                    Some(name) => println!("{name}"),
+                    None => println!("{}", Msg::NoActiveEnvironment),
+                }
+            }
+            EnvCommands::Export(args) => {
+                let Some(path) = args.write else {
+                    print!(
+                        "{}",
+                        pacs.direnv_export(args.project.as_deref(), args.environment.as_deref())?
+                    );
+                    return Ok(());
+                };
+                let path = env::current_dir()?.join(path);
+                pacs.write_direnv(args.project.as_deref(), path.clone(), args.hook)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                println!("{}", Msg::WroteDirenv { path: &path });
+            }
+        },
+
+        Commands::Registry { command } => match command {
+            RegistryCommands::Add(args) => {
+                let name = args
+                    .name
+                    .unwrap_or_else(|| RegistrySource::name_from_url(&args.url));
+                let count = pacs
+                    .add_registry(RegistrySource {
+                        name: name.clone(),
+                        url: args.url,
+                    })
+                    .with_context(|| format!("Failed to add registry '{name}'"))?;
+                println!("{}", Msg::RegistryAdded { name: &name, count });
+            }
+            RegistryCommands::Remove(args) => {
+                pacs.remove_registry(&args.name)
+                    .with_context(|| format!("Failed to remove registry '{}'", args.name))?;
+                println!("{}", Msg::RegistryRemoved { name: &args.name });
+            }
+            RegistryCommands::List => {
+                if pacs.registries().is_empty() {
+                    println!("{}", Msg::NoRegistries);
+                }
+                for registry in pacs.registries() {
+                    println!(
+                        "{BOLD}{CYAN}{}{RESET} {GREY}{} ({} commands){RESET}",
+                        registry.source.name,
+                        registry.source.url,
+                        registry.commands.len()
+                    );
+                }
+            }
+            RegistryCommands::Update => {
+                let errors = pacs.update_registries(None);
+                for (name, err) in &errors {
+                    let error = err.to_string();
+                    eprintln!(
+                        "{YELLOW}{}{RESET} {}",
+                        Msg::Warning,
+                        Msg::RegistryUpdateFailed {
+                            name,
+                            error: &error
+                        }
+                    );
+                }
+                let updated = pacs.registries().len() - errors.len();
+                println!("{}", Msg::RegistriesUpdated { count: updated });
+                warn_changed_since_approval(&pacs);
+            }
+        },
+
+        Commands::Approve(args) => {
+            let (registry, cmd) = pacs.registry_command(&args.name, args.registry.as_deref())?;
+            registry.verify(cmd)?;
+            if registry.is_approved(cmd) {
+                println!("{}", Msg::AlreadyApproved { name: &cmd.name });
+                return Ok(());
+            }
+            // Show what changed since the last approval, or the whole body
+            match registry.approved_version(&cmd.name) {
+                Some(approved) => {
+                    print_diff(&approved.command, &cmd.command);
+                }
+                None => print_command_body(&cmd.command),
+            }
+            let registry = registry.source.name.clone();
+            let name = cmd.name.clone();
+            if !args.yes && !confirm_approval(&name, &registry)? {
+                println!("{}", Msg::NotApproved);
+                return Ok(());
+            }
+            pacs.approve_registry_command(&name, Some(&registry))
+                .with_context(|| format!("Failed to approve command '{name}'"))?;
+            println!(
+                "{}",
+                Msg::CommandApproved {
+                    name: &name,
+                    registry: &registry
+                }
+            );
+        }
+
+        Commands::Gc => {
+            let count = pacs.gc().context("Failed to compact history")?;
+            println!("{}", Msg::HistoryCleared { count });
+        }
+
+        Commands::Config { command } => match command {
+            ConfigCommands::Show(args) => {
+                let mut settings = pacs
+                    .effective_settings()
+                    .context("Failed to read config.toml")?;
+                let source = match Locale::variable() {
+                    Some((var, value)) if Locale::parse(&value).is_some() => {
+                        SettingSource::Env(var)
+                    }
+                    _ => SettingSource::Default,
+                };
+                settings.push(Setting::new("language", Locale::current().code(), source));
+                let (level, source) = match (cli.quiet, cli.verbose) {
+                    (true, _) => ("off", SettingSource::Flag("--quiet")),
+                    (false, 0) => ("warn", SettingSource::Default),
+                    (false, 1) => ("debug", SettingSource::Flag("--verbose")),
+                    (false, _) => ("trace", SettingSource::Flag("--verbose")),
+                };
+                settings.push(Setting::new("log_level", level, source));
+
+                if !args.effective {
+                    settings.retain(|s| s.source == SettingSource::ConfigFile);
+                    if settings.is_empty() {
+                        println!("{}", Msg::NoConfigSettings);
+                    }
+                }
+                print_settings(&settings);
+            }
+        },
+
+        Commands::Cache { command } => match command {
+            CacheCommands::Rebuild => {
+                pacs.rebuild_completions()
+                    .context("Failed to rebuild the completion cache")?;
+                let status = pacs.completions_status();
+                println!(
+                    "{}",
+                    Msg::CacheRebuilt {
+                        projects: status.projects,
+                        commands: status.commands,
+                    }
+                );
+            }
+            CacheCommands::Status => {
+                let status = pacs.completions_status();
+                let path = status.path.as_path();
+                let msg = match status.state {
+                    CacheState::Fresh => Msg::CacheFresh {
+                        path,
+                        projects: status.projects,
+                        commands: status.commands,
+                    },
+                    CacheState::Outdated => Msg::CacheOutdated { path },
+                    CacheState::Missing => Msg::CacheMissing { path },
+                };
+                println!("{msg}");
+            }
+        },
+
+        Commands::History { command } => match command {
+            HistoryCommands::Clear(args) => {
+                let before = args
+                    .before
+                    .map(|day| u64::try_from(day * 86_400).unwrap_or_default());
+                let count = pacs
+                    .clear_history(args.project.as_deref(), before)
+                    .context("Failed to clear history")?;
+                println!("{}", Msg::HistoryCleared { count });
+            }
+        },
+
+        Commands::Sync { command } => match command {
+            SyncCommands::Resolve => {
+                let conflicts = pacs.sync_conflicts()?;
+                if conflicts.is_empty() {
+                    println!("{}", Msg::NoSyncConflicts);
+                }
+                for conflict in conflicts {
+                    println!(
+                        "{BOLD}{}{RESET}",
+                        Msg::ResolvingFile {
+                            path: &conflict.path.display().to_string()
+                        }
+                    );
+                    let mut resolutions = BTreeMap::new();
+                    for BundleConflict {
+                        project,
+                        mine,
+                        theirs,
+                    } in &conflict.conflicts
+                    {
+                        let resolution = ask_resolution(project, mine, theirs, |name| {
+                            conflict
+                                .ours
+                                .commands
+                                .iter()
+                                .chain(&conflict.theirs.commands)
+                                .any(|c| c.matches(name))
+                                || resolutions
+                                    .values()
+                                    .any(|r| *r == Resolution::Rename(name.to_string()))
+                        })?;
+                        resolutions.insert(theirs.name.clone(), resolution);
+                    }
+                    let name = conflict.ours.name.clone();
+                    pacs.resolve_sync_conflict(conflict, &resolutions)
+                        .with_context(|| format!("Failed to resolve '{name}'"))?;
+                    println!("{}", Msg::SyncResolved { name: &name });
+                }
+            }
+        },
+
+        Commands::Template { command } => match command {
+            TemplateCommands::List(args) => {
+                let templates = match &args.query {
+                    Some(query) => search_templates(query),
+                    None => templates(),
+                };
+                if templates.is_empty() {
+                    println!("{}", Msg::NoMatches);
+                }
+                let mut current_tag = None;
+                for template in &templates {
+                    // Without a query templates are grouped by tag
+                    if args.query.is_none() && current_tag != Some(&template.tag) {
+                        if current_tag.is_some() {
+                            println!();
+                        }
+                        println!("{BOLD}{MAGENTA}[{}]{RESET}", template.tag);
+                        current_tag = Some(&template.tag);
+                    }
+                    println!(
+                        "{BOLD}{CYAN}{}{RESET} {GREY}{}{RESET}",
+                        template.name, template.description
+                    );
+                }
+            }
+            TemplateCommands::Add(args) => {
+                let cmd = pacs
+                    .add_template(
+                        &args.template,
+                        args.name.as_deref(),
+                        args.project.as_deref(),
+                    )
+                    .with_context(|| format!("Failed to add template '{}'", args.template))?;
+                let project_name = match args.project {
+                    Some(p) => p,
+                    None => pacs.get_active_project_name()?,
+                };
+                println!(
+                    "{}",
+                    Msg::CommandAdded {
+                        name: &cmd.name,
+                        project: &project_name
+                    }
                 );
+                print_command_body(&cmd.command);
+                let placeholders = cmd.placeholders();
+                if !placeholders.is_empty() {
+                    println!(
+                        "{GREY}{}{RESET}",
+                        Msg::SetPlaceholders {
+                            keys: &placeholders.join(", ")
+                        }
+                    );
+                }
+            }
+        },
+    }
+
+    Ok(())
+}
+
+/// Logs to stderr at a level chosen by the number of `-v` flags.
+fn init_logging(verbose: u8, quiet: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::OFF,
+        (false, 0) => LevelFilter::WARN,
+        (false, 1) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_target(false)
+        .without_time()
+        .init();
+}
+
+/// Parses a `KEY=VALUE` pair.
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{s}'"))
+}
+
+/// Parses a time window like `90d`, `12w` or `48h`.
+fn parse_window(s: &str) -> Result<Duration, String> {
+    parse_interval(s).ok_or_else(|| format!("expected e.g. 90d, 12w or 48h, got '{s}'"))
+}
+
+fn parse_day(s: &str) -> Result<i64, String> {
+    parse_date(s).ok_or_else(|| format!("expected a date like 2025-01-31, got '{s}'"))
+}
+
+/// Reads the `[[commands]]` of a file for `pacs add --batch`, as YAML for
+/// `.yaml` and `.yml` files and as TOML otherwise.
+fn read_batch(path: &Path) -> Result<Vec<PacsCommand>> {
+    #[derive(Deserialize)]
+    struct Batch {
+        #[serde(default)]
+        commands: Vec<PacsCommand>,
+    }
+
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let yaml = path
+        .extension()
+        .is_some_and(|ext| ext == "yaml" || ext == "yml");
+    let batch: Batch = if yaml {
+        serde_yaml::from_str(&content).map_err(anyhow::Error::from)
+    } else {
+        toml::from_str(&content).map_err(anyhow::Error::from)
+    }
+    .with_context(|| format!("Failed to read {}", path.display()))?;
+    if batch.commands.is_empty() {
+        anyhow::bail!("{}", Msg::EmptyBatch);
+    }
+    Ok(batch.commands)
+}
+
+/// Reads and concatenates `files`.
+fn read_files(files: &[PathBuf]) -> Result<String> {
+    let mut content = String::new();
+    for file in files {
+        content += &fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        content.push('\n');
+    }
+    Ok(content)
+}
+
+/// Formats the expiry status of a command for listings.
+fn expiry_badge(cmd: &PacsCommand) -> String {
+    match cmd.expiry_status(today()) {
+        Some(ExpiryStatus::Expired) => format!(" {BOLD}{RED}[expired]{RESET}"),
+        Some(ExpiryStatus::ReviewDue) => format!(" {BOLD}{YELLOW}[review due]{RESET}"),
+        None => String::new(),
+    }
+}
+
+/// Returns the names and aliases of a project's commands that are valid shell
+/// alias names.
+fn alias_targets(project: &Project) -> Vec<&str> {
+    project
+        .commands
+        .iter()
+        .flat_map(|cmd| std::iter::once(&cmd.name).chain(&cmd.aliases))
+        .map(String::as_str)
+        .filter(|name| {
+            !name.starts_with('-')
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        })
+        .collect()
+}
+
+/// Builds the command starting `shell` with an alias `name='pacs run name'`
+/// for each name, loaded after the user's own startup file. bash, zsh and
+/// fish are supported, other shells read the aliases from `$ENV`.
+fn shell_with_aliases(shell: &str, rc_dir: &Path, names: &[&str]) -> Result<Command> {
+    let pacs = shell_quote(&env::current_exe()?.to_string_lossy());
+    let mut posix_aliases = String::new();
+    for name in names {
+        let run = shell_quote(&format!("{pacs} run {name}"));
+        writeln!(posix_aliases, "alias {name}={run}")?;
+    }
+
+    let mut command = Command::new(shell);
+    match Path::new(shell).file_name().and_then(|n| n.to_str()) {
+        Some("bash") => {
+            let rc = rc_dir.join("bashrc");
+            fs::write(
+                &rc,
+                format!("[ -f ~/.bashrc ] && . ~/.bashrc\n{posix_aliases}"),
+            )?;
+            command.arg("--rcfile").arg(rc);
+        }
+        Some("zsh") => {
+            let zdotdir = env::var("ZDOTDIR").unwrap_or_else(|_| "$HOME".to_string());
+            fs::write(
+                rc_dir.join(".zshrc"),
+                format!(
+                    "ZDOTDIR=\"{zdotdir}\"\n[ -f \"$ZDOTDIR/.zshrc\" ] && . \"$ZDOTDIR/.zshrc\"\n{posix_aliases}"
+                ),
+            )?;
+            command.env("ZDOTDIR", rc_dir);
+        }
+        Some("fish") => {
+            let aliases: Vec<String> = names
+                .iter()
+                .map(|name| {
+                    let run = Shell::Fish.quote(&format!("{pacs} run {name}"));
+                    format!("alias {name} {run}")
+                })
+                .collect();
+            command.arg("--init-command").arg(aliases.join("; "));
+        }
+        _ => {
+            let rc = rc_dir.join("env.sh");
+            fs::write(&rc, posix_aliases)?;
+            command.env("ENV", rc);
+        }
+    }
+    Ok(command)
+}
+
+/// Formats the aliases of a command for listings.
+fn aliases_badge(cmd: &PacsCommand) -> String {
+    if cmd.aliases.is_empty() {
+        String::new()
+    } else {
+        format!(" {GREY}aka {}{RESET}", cmd.aliases.join(", "))
+    }
+}
+
+/// Marks projects and commands that are kept on this machine in listings.
+/// Renders a tag as `[tag]` in its configured color, after its icon.
+fn tag_label(tag: &str, styles: &BTreeMap<String, TagStyle>) -> String {
+    let style = styles.get(tag);
+    let color = style
+        .and_then(|s| s.color.as_deref())
+        .and_then(ansi_color)
+        .unwrap_or_else(|| YELLOW.to_string());
+    let icon = style
+        .and_then(|s| s.icon.as_deref())
+        .map_or_else(String::new, |icon| format!("{icon} "));
+    format!("{BOLD}{color}{icon}[{tag}]{RESET}")
+}
+
+/// Returns the name of a project in its color, or blue, after its icon.
+fn project_label(project: &Project) -> String {
+    let color = project
+        .color
+        .as_deref()
+        .and_then(ansi_color)
+        .unwrap_or_else(|| BLUE.to_string());
+    let icon = project
+        .icon
+        .as_deref()
+        .map_or_else(String::new, |icon| format!("{icon} "));
+    format!("{color}{icon}{}{RESET}", project.name)
+}
+
+/// Returns the escape code of a color like `red`, `bright-blue` or `#ff8800`.
+fn ansi_color(color: &str) -> Option<String> {
+    if let Some(hex) = color.strip_prefix('#') {
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        if hex.len() != 6 {
+            return None;
+        }
+        return Some(format!(
+            "\x1b[38;2;{};{};{}m",
+            channel(0)?,
+            channel(2)?,
+            channel(4)?
+        ));
+    }
+    let color = color.to_lowercase();
+    let (base, name) = match color
+        .strip_prefix("bright-")
+        .or_else(|| color.strip_prefix("light-"))
+    {
+        Some(name) => (90, name),
+        None => (30, color.as_str()),
+    };
+    let offset = match name {
+        "black" => 0,
+        "red" => 1,
+        "green" => 2,
+        "yellow" => 3,
+        "blue" => 4,
+        "magenta" => 5,
+        "cyan" => 6,
+        "white" => 7,
+        "gray" | "grey" => return Some(GREY.to_string()),
+        _ => return None,
+    };
+    Some(format!("\x1b[{}m", base + offset))
+}
+
+fn local_badge(local_only: bool) -> String {
+    if local_only {
+        format!(" {GREY}local only{RESET}")
+    } else {
+        String::new()
+    }
+}
+
+fn archived_badge(archived: bool) -> String {
+    if archived {
+        format!(" {GREY}archived{RESET}")
+    } else {
+        String::new()
+    }
+}
+
+fn lock_badge(locked: bool) -> String {
+    if locked {
+        format!(" {GREY}locked{RESET}")
+    } else {
+        String::new()
+    }
+}
+
+/// Formats the author of a command for listings.
+fn author_badge(cmd: &PacsCommand) -> String {
+    cmd.author
+        .as_ref()
+        .map(|author| format!(" {GREY}by {author}{RESET}"))
+        .unwrap_or_default()
+}
+
+/// Formats whether the environment provides all keys a command requires,
+/// listing the missing ones. Empty for commands that require no keys.
+fn keys_badge(
+    pacs: &Pacs,
+    cmd: &PacsCommand,
+    project: Option<&str>,
+    environment: Option<&str>,
+) -> String {
+    if cmd.required_keys().is_empty() {
+        return String::new();
+    }
+    match pacs.missing_keys(cmd, project, environment, &BTreeMap::new()) {
+        Ok(missing) if missing.is_empty() => format!(" {GREEN}✓{RESET}"),
+        Ok(missing) => format!(" {RED}✗ {}{RESET}", missing.join(", ")),
+        Err(_) => String::new(),
+    }
+}
+
+/// Prints the keys a command uses with their values in the environment,
+/// marking the missing ones.
+fn print_command_keys(pacs: &Pacs, cmd: &PacsCommand, environment: Option<&str>) -> Result<()> {
+    let keys = pacs
+        .command_keys(cmd, None, environment)
+        .context("Failed to resolve keys")?;
+    if keys.is_empty() {
+        println!("{}", Msg::NoKeys);
+    }
+    for (key, value) in keys {
+        match value {
+            Some(value) => println!("{GREY}{key}{RESET} = {WHITE}{value}{RESET}"),
+            None => println!("{GREY}{key}{RESET} {RED}✗ {}{RESET}", Msg::KeyMissing),
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if stdout is a terminal and `NO_COLOR` is not set.
+fn color_enabled() -> bool {
+    io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+}
+
+/// Prints a command body, syntax highlighted like in the TUI when color is enabled.
+fn print_command_body(command: &str) {
+    for line in command.lines() {
+        if !color_enabled() {
+            println!("{WHITE}{line}{RESET}");
+            continue;
+        }
+        let mut out = String::new();
+        for (kind, text) in tokenize_shell_line(line) {
+            let color = match kind {
+                TokenKind::Command => BLUE,
+                TokenKind::Flag => YELLOW,
+                TokenKind::String => GREEN,
+                TokenKind::Variable => CYAN,
+                TokenKind::Operator => MAGENTA,
+                TokenKind::Comment => GREY,
+                TokenKind::Text => WHITE,
+            };
+            let _ = write!(out, "{color}{text}{RESET}");
+        }
+        println!("{out}");
+    }
+}
+
+/// Prints a search result with its tag, scope and the matched line,
+/// optionally numbered for picking.
+fn print_search_match(m: &SearchMatch, number: Option<usize>) {
+    let number = number.map_or_else(String::new, |n| format!("{BOLD}{n}){RESET} "));
+    let tag_badge = if m.command.tag.is_empty() {
+        String::new()
+    } else {
+        format!(" {BOLD}{YELLOW}[{}]{RESET}", m.command.tag)
+    };
+    let scope = if m.registry {
+        format!("{} (registry)", m.scope)
+    } else {
+        m.scope.to_string()
+    };
+    println!(
+        "{number}{BOLD}{CYAN}{}{RESET}{tag_badge} {GREY}{scope}{RESET}",
+        highlight_matches(&m.command.name, &m.name_indices, &format!("{BOLD}{CYAN}"))
+    );
+    println!(
+        "    {}",
+        highlight_matches(m.preview, &m.preview_indices, "")
+    );
+}
+
+/// Highlights the chars of `text` at `indices`, switching back to the
+/// `style` of the surrounding text after each of them.
+fn highlight_matches(text: &str, indices: &[usize], style: &str) -> String {
+    text.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if indices.contains(&i) {
+                format!("{BOLD}{MAGENTA}{c}{RESET}{style}")
+            } else {
+                c.to_string()
             }
-            EnvCommands::Edit(args) => {
-                #[derive(serde::Deserialize)]
-                struct EditDoc {
-                    #[serde(default)]
-                    active_environment: Option<String>,
-                    #[serde(default)]
-                    environments: std::collections::BTreeMap<String, EnvValues>,
-                }
-                #[derive(serde::Deserialize)]
-                struct EnvValues {
-                    #[serde(default)]
-                    values: BTreeMap<String, String>,
-                }
+        })
+        .collect()
+}
 
-                let editor = env::var("VISUAL")
-                    .ok()
-                    .or_else(|| env::var("EDITOR").ok())
-                    .unwrap_or_else(|| "vi".to_string());
+/// CI systems whose log sections `pacs run --ci` wraps command output in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CiSystem {
+    Github,
+    Gitlab,
+    /// Any other system, which gets plain headers.
+    Other,
+}
 
-                let project = resolve_project_name(&pacs, args.project)?;
+impl CiSystem {
+    fn detect() -> Self {
+        if env::var_os("GITHUB_ACTIONS").is_some() {
+            Self::Github
+        } else if env::var_os("GITLAB_CI").is_some() {
+            Self::Gitlab
+        } else {
+            Self::Other
+        }
+    }
 
-                let project_ref = pacs
-                    .projects
-                    .iter()
-                    .find(|p| p.name.eq_ignore_ascii_case(&project))
-                    .with_context(|| format!("Project '{project}' not found"))?;
+    /// Returns the line opening a section titled `label` at `now`.
+    fn section_start(self, label: &str, now: u64) -> String {
+        match self {
+            Self::Github => format!("::group::{label}"),
+            Self::Gitlab => format!(
+                "\x1b[0Ksection_start:{now}:{}\r\x1b[0K{label}",
+                section_id(label)
+            ),
+            Self::Other => format!("==> {label}"),
+        }
+    }
 
-                let mut buf = String::new();
-                if let Some(active_env) = &project_ref.active_environment {
-                    write!(buf, "active_environment = \"{active_env}\"\n\n").unwrap();
-                }
+    /// Returns the line closing the section of `label` at `now`, if sections
+    /// need closing.
+    fn section_end(self, label: &str, now: u64) -> Option<String> {
+        match self {
+            Self::Github => Some("::endgroup::".into()),
+            Self::Gitlab => Some(format!(
+                "\x1b[0Ksection_end:{now}:{}\r\x1b[0K",
+                section_id(label)
+            )),
+            Self::Other => None,
+        }
+    }
+}
 
-                for env in &project_ref.environments {
-                    writeln!(buf, "[environments.{}.values]", env.name).unwrap();
-                    for (k, v) in &env.values {
-                        writeln!(buf, "{k} = \"{}\"", v.replace('"', "\\\"")).unwrap();
-                    }
-                    buf.push('\n');
+/// Turns a label into a GitLab section name, which allows only letters,
+/// digits, `_`, `.` and `-`.
+fn section_id(label: &str) -> String {
+    label
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "_.-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Runs commands for `pacs run --ci`. Each command gets a log section that
+/// starts with its resolved body, with secret references as they are.
+/// Commands marked `confirm` are never run, dangerous ones only with `--force`.
+fn run_ci(
+    pacs: &Pacs,
+    args: &RunArgs,
+    names: &[String],
+    environments: &[Option<String>],
+    values: &BTreeMap<String, String>,
+) -> Result<()> {
+    let ci = CiSystem::detect();
+    let matrix = args.all_envs || args.envs.is_some();
+    let mut failed = Vec::new();
+    for environment in environments {
+        for name in names {
+            let label = match environment {
+                Some(env) if matrix => format!("{name} [{env}]"),
+                _ => name.clone(),
+            };
+            let (project, env) = (args.project.as_deref(), environment.as_deref());
+            println!("{}", ci.section_start(&label, unix_now()));
+            let result = (|| -> Result<()> {
+                let cmd = pacs.resolve_command_with_values(name, project, env, values)?;
+                for line in cmd.command.trim_end().lines() {
+                    println!("$ {line}");
+                }
+                if cmd.confirm {
+                    return Err(PacsError::NotConfirmed(name.clone()).into());
                 }
+                if !args.force && pacs.danger_pattern(name, project, env, values)?.is_some() {
+                    return Err(PacsError::DangerousCommand(name.clone()).into());
+                }
+                Ok(pacs.run_with_values(name, project, env, values)?)
+            })()
+            .with_context(|| format!("Failed to run command '{label}'"));
+            if let Some(end) = ci.section_end(&label, unix_now()) {
+                println!("{end}");
+            }
+            let Err(e) = result else {
+                continue;
+            };
+            if !args.keep_going {
+                return Err(e);
+            }
+            eprintln!("Error: {e:#}");
+            failed.push(label);
+        }
+    }
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "{}",
+            Msg::RunsFailed {
+                count: failed.len(),
+                names: &failed.join(", ")
+            }
+        );
+    }
+    Ok(())
+}
 
-                let temp_file =
-                    std::env::temp_dir().join(format!("pacs-env-{}.toml", std::process::id()));
-                fs::write(&temp_file, buf)?;
+/// Lists the commands in a group like `db/` and asks which one to run.
+/// Returns `None` if none is chosen.
+fn pick_in_group(pacs: &Pacs, group: &str, project: Option<&str>) -> Result<Option<String>> {
+    let mut commands: Vec<&PacsCommand> = pacs
+        .get_project_or_active(project)?
+        .commands
+        .iter()
+        .filter(|c| c.name.starts_with(group))
+        .collect();
+    commands.sort_by(|a, b| a.name.cmp(&b.name));
+    match commands[..] {
+        [] => anyhow::bail!("{}", Msg::EmptyGroup { group }),
+        [only] => return Ok(Some(only.name.clone())),
+        _ => {}
+    }
 
-                let status = Command::new(&editor)
-                    .arg(&temp_file)
-                    .status()
-                    .with_context(|| format!("Failed to open editor '{editor}'"))?;
+    for (i, cmd) in commands.iter().enumerate() {
+        println!("{BOLD}{}){RESET} {BOLD}{CYAN}{}{RESET}", i + 1, cmd.name);
+        let first_line = cmd.command.lines().map(str::trim).find(|l| !l.is_empty());
+        println!("    {}", first_line.unwrap_or_default());
+    }
+    print!(
+        "{}",
+        Msg::RunWhich {
+            count: commands.len()
+        }
+    );
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| commands.get(i.wrapping_sub(1)))
+        .map(|cmd| cmd.name.clone()))
+}
 
-                if !status.success() {
-                    fs::remove_file(&temp_file).ok();
-                    anyhow::bail!("Editor exited with non-zero status");
-                }
+/// Asks before running a command that matches a danger pattern. Returns
+/// false if the answer is no or there is no terminal to ask on.
+fn confirm_danger(
+    pacs: &Pacs,
+    name: &str,
+    project: Option<&str>,
+    environment: Option<&str>,
+    values: &BTreeMap<String, String>,
+) -> Result<bool> {
+    let Some(pattern) = pacs.danger_pattern(name, project, environment, values)? else {
+        return Ok(true);
+    };
+    print!(
+        "{YELLOW}{}{RESET}",
+        Msg::ConfirmDanger {
+            name,
+            pattern: &pattern
+        }
+    );
+    if !io::stdin().is_terminal() {
+        println!();
+        return Ok(false);
+    }
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(
+        answer.trim().to_lowercase().as_str(),
+        "y" | "yes" | "j" | "ja"
+    ))
+}
 
-                let edited = fs::read_to_string(&temp_file)?;
-                fs::remove_file(&temp_file).ok();
+/// Asks for the working directory of a command with `cwd = "ask"` that
+/// `values` does not set, offering the project path. Leaves `values` as they
+/// are if stdin is not a terminal.
+fn ask_cwd(
+    pacs: &Pacs,
+    name: &str,
+    project: Option<&str>,
+    environment: Option<&str>,
+    values: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>> {
+    let mut values = values.clone();
+    let cmd = pacs.resolve_command_with_values(name, project, environment, &values)?;
+    if !cmd.asks_cwd() || !io::stdin().is_terminal() {
+        return Ok(values);
+    }
+    let default = match &pacs.get_project_or_active(project)?.path {
+        Some(path) => path.clone(),
+        None => env::current_dir()?.display().to_string(),
+    };
+    print!(
+        "{}",
+        Msg::AskCwd {
+            name,
+            default: &default
+        }
+    );
+    io::stdout().flush()?;
+    let answer = read_dir_line()?;
+    let dir = match answer.trim() {
+        "" => default,
+        dir => dir.to_string(),
+    };
+    values.insert(CWD_KEY.to_string(), dir);
+    Ok(values)
+}
 
-                let doc: EditDoc =
-                    toml::from_str(&edited).with_context(|| "Failed to parse edited TOML")?;
+/// Reads a directory from the terminal, completing it with Tab like the TUI
+/// prompt does.
+#[cfg(unix)]
+fn read_dir_line() -> Result<String> {
+    let line = {
+        let _raw = RawMode::enable()?;
+        edit_dir_line(io::Read::bytes(io::stdin().lock()), &mut io::stdout())?
+    };
+    println!();
+    // Ctrl-C is read as input in raw mode, so exit like the signal would
+    line.map_or_else(|| std::process::exit(130), Ok)
+}
 
-                if let Some(active_name) = doc.active_environment {
-                    pacs.set_active_environment(&project, &active_name)
-                        .with_context(|| {
-                            format!("Failed to set active environment '{active_name}'")
-                        })?;
-                }
+#[cfg(not(unix))]
+fn read_dir_line() -> Result<String> {
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer)
+}
 
-                for (env_name, env_values) in doc.environments {
-                    pacs.edit_environment_values(&project, &env_name, env_values.values.clone())
-                        .with_context(|| {
-                            format!(
-                                "Failed to update environment '{env_name}' values for project '{project}'"
-                            )
-                        })?;
+/// Edits a line read from `input` byte by byte, echoing it to `out`. Tab
+/// completes the directory typed so far, and arrow keys are ignored.
+/// Returns `None` on Ctrl-C.
+fn edit_dir_line(
+    mut input: impl Iterator<Item = io::Result<u8>>,
+    out: &mut impl IoWrite,
+) -> Result<Option<String>> {
+    let mut line = Vec::new();
+    while let Some(byte) = input.next().transpose()? {
+        match byte {
+            b'\r' | b'\n' => break,
+            0x03 => return Ok(None),
+            0x04 if line.is_empty() => break,
+            b'\t' => {
+                let typed = String::from_utf8_lossy(&line).into_owned();
+                let completed = pacs_core::common_prefix(&pacs_core::complete_dir(&typed));
+                if let Some(rest) = completed.strip_prefix(typed.as_str()) {
+                    out.write_all(rest.as_bytes())?;
+                    line.extend_from_slice(rest.as_bytes());
                 }
-                println!("All environments updated for project '{project}'.");
             }
-            EnvCommands::List(args) => {
-                let environments = pacs
-                    .list_environments(args.project.as_deref())
-                    .context("Failed to list environments")?;
-                let active = pacs
-                    .get_active_environment(args.project.as_deref())
-                    .context("Failed to get active environment")?;
-
-                if environments.is_empty() {
-                    println!("No environments.");
-                } else {
-                    for env in environments {
-                        let active_marker = if active.as_deref() == Some(env.name.as_str()) {
-                            format!(" {GREEN}*{RESET}")
-                        } else {
-                            String::new()
-                        };
-                        println!("{CYAN}{BOLD}{}{active_marker}{RESET}", env.name);
-                        if !env.values.is_empty() {
-                            for (k, v) in &env.values {
-                                println!("  {GREY}{k}{RESET} = {WHITE}{v}{RESET}");
-                            }
-                        }
+            0x7f | 0x08 if !line.is_empty() => {
+                // Remove a whole character, not just its last byte
+                while line.pop().is_some_and(|b| b & 0xc0 == 0x80) {}
+                out.write_all(b"\x08 \x08")?;
+            }
+            // Skip escape sequences like `ESC [ D`
+            0x1b if input.next().transpose()? == Some(b'[') => {
+                while let Some(b) = input.next().transpose()? {
+                    if (0x40..=0x7e).contains(&b) {
+                        break;
                     }
                 }
             }
-            EnvCommands::Switch(args) => {
-                let project = resolve_project_name(&pacs, args.project)?;
-
-                pacs.set_active_environment(&project, &args.name)
-                    .with_context(|| {
-                        format!(
-                            "Failed to switch to environment '{}' in project '{}'",
-                            args.name, project
-                        )
-                    })?;
-                println!(
-                    "Switched to environment '{}' in project '{}'.",
-                    args.name, project
-                );
+            byte if byte >= 0x20 => {
+                line.push(byte);
+                out.write_all(&[byte])?;
             }
-            EnvCommands::Active(args) => {
-                let project = resolve_project_name(&pacs, args.project)?;
+            _ => {}
+        }
+        out.flush()?;
+    }
+    Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+}
 
-                match pacs.get_active_environment(Some(&project))? {
-                    Some(name) => println!("{name}"),
-                    None => println!("No active environment."),
-                }
+/// Puts the terminal into non-canonical mode without echo while alive, so
+/// Tab reaches pacs instead of the line discipline.
+#[cfg(unix)]
+struct RawMode(libc::termios);
+
+#[cfg(unix)]
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        // SAFETY: `termios` is plain data and is filled in by `tcgetattr`
+        // before it is read.
+        let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+        // SAFETY: the pointer is valid for the duration of the call.
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &raw mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        // SAFETY: the pointer is valid for the duration of the call.
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw const raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self(original))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: the pointer is valid for the duration of the call.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw const self.0);
+        }
+    }
+}
+
+/// Formats a run's duration like `0.4s` or `2m05s`.
+fn format_elapsed(elapsed: Duration) -> String {
+    let tenths = (elapsed.as_millis() + 50) / 100;
+    if tenths < 600 {
+        format!("{}.{}s", tenths / 10, tenths % 10)
+    } else {
+        let secs = (tenths + 5) / 10;
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+/// Prints the exit code, duration, working directory and environment of a
+/// finished run to stderr.
+fn print_run_footer(
+    pacs: &Pacs,
+    name: &str,
+    project: Option<&str>,
+    environment: Option<&str>,
+    values: &BTreeMap<String, String>,
+    code: i32,
+    elapsed: &str,
+) {
+    let cwd = pacs
+        .resolve_command_with_values(name, project, environment, values)
+        .ok()
+        .and_then(|cmd| cmd.cwd)
+        .or_else(|| Some(env::current_dir().ok()?.display().to_string()))
+        .unwrap_or_default();
+    let environment = match environment {
+        Some(environment) => Some(environment.to_string()),
+        None => pacs.get_active_environment(project).ok().flatten(),
+    };
+    let footer = Msg::RunFooter {
+        code,
+        elapsed,
+        cwd: &cwd,
+        environment: environment.as_deref(),
+    };
+    eprintln!("{GREY}{footer}{RESET}");
+}
+
+/// Prints the exit code and duration of each run to stderr if there was
+/// more than one.
+fn print_run_table(runs: &[(String, i32, String)]) {
+    if runs.len() < 2 {
+        return;
+    }
+    let width = runs
+        .iter()
+        .map(|(label, ..)| label.len())
+        .max()
+        .unwrap_or(0);
+    eprintln!();
+    for (label, code, elapsed) in runs {
+        let color = if *code == 0 { GREEN } else { YELLOW };
+        let exit = Msg::RunExit { code: *code }.to_string();
+        eprintln!("{BOLD}{label:width$}{RESET}  {color}{exit:8}{RESET}  {GREY}{elapsed}{RESET}");
+    }
+}
+
+/// Shows the resolved body of a command marked `confirm` and asks whether to
+/// run it. Returns `None` for commands that are not marked. Declines if stdin
+/// is not a terminal.
+fn confirm_marked(
+    pacs: &Pacs,
+    name: &str,
+    project: Option<&str>,
+    environment: Option<&str>,
+    values: &BTreeMap<String, String>,
+) -> Result<Option<bool>> {
+    let cmd = pacs.resolve_command_with_values(name, project, environment, values)?;
+    if !cmd.confirm {
+        return Ok(None);
+    }
+    print_command_body(&cmd.command);
+    print!("{YELLOW}{}{RESET}", Msg::ConfirmRun { name });
+    if !io::stdin().is_terminal() {
+        println!();
+        return Ok(Some(false));
+    }
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(Some(matches!(
+        answer.trim().to_lowercase().as_str(),
+        "y" | "yes" | "j" | "ja"
+    )))
+}
+
+/// Asks whether to approve a registry command. Declines if stdin is not a terminal.
+/// Asks whether to add a new command as an alias of a similar one. Always
+/// declined without a terminal.
+fn confirm_alias(name: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    print!("{YELLOW}{}{RESET}", Msg::ConfirmAlias { name });
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(
+        answer.trim().to_lowercase().as_str(),
+        "y" | "yes" | "j" | "ja"
+    ))
+}
+
+fn confirm_approval(name: &str, registry: &str) -> Result<bool> {
+    print!("{YELLOW}{}{RESET}", Msg::ConfirmApproval { name, registry });
+    if !io::stdin().is_terminal() {
+        println!();
+        return Ok(false);
+    }
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(
+        answer.trim().to_lowercase().as_str(),
+        "y" | "yes" | "j" | "ja"
+    ))
+}
+
+/// Shows a command that differs between two versions and asks which to keep.
+/// A rename is asked again while `taken` reports the name as in use.
+fn ask_resolution(
+    project: &str,
+    mine: &PacsCommand,
+    theirs: &PacsCommand,
+    taken: impl Fn(&str) -> bool,
+) -> Result<Resolution> {
+    println!(
+        "{BOLD}{YELLOW}{}{RESET}",
+        Msg::BundleConflict {
+            project,
+            name: &theirs.name
+        }
+    );
+    if !print_diff(&mine.command, &theirs.command) {
+        print_command_body(&theirs.command);
+    }
+    print!("{}", Msg::ResolveConflict);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let resolution = match answer.trim() {
+        "t" | "T" => Resolution::TakeTheirs,
+        "r" | "R" => loop {
+            print!("{}", Msg::RenameTo);
+            io::stdout().flush()?;
+            let mut name = String::new();
+            if io::stdin().read_line(&mut name)? == 0 {
+                break Resolution::KeepMine;
+            }
+            let name = name.trim();
+            if name.is_empty() {
+                break Resolution::KeepMine;
+            }
+            if !taken(name) {
+                break Resolution::Rename(name.to_string());
             }
+            println!("{}", Msg::NameTaken { name });
         },
-    }
+        _ => Resolution::KeepMine,
+    };
+    println!();
+    Ok(resolution)
+}
 
+/// Opens a project directory in the file manager, or in `$VISUAL`, `$EDITOR`
+/// or `code`.
+fn open_project_dir(path: &str, finder: bool) -> Result<()> {
+    let program = if finder {
+        if cfg!(target_os = "macos") {
+            "open".to_string()
+        } else if cfg!(windows) {
+            "explorer".to_string()
+        } else {
+            "xdg-open".to_string()
+        }
+    } else {
+        env::var("VISUAL")
+            .ok()
+            .or_else(|| env::var("EDITOR").ok())
+            .unwrap_or_else(|| "code".to_string())
+    };
+    let status = Command::new(&program)
+        .arg(path)
+        .current_dir(path)
+        .status()
+        .with_context(|| format!("Failed to start '{program}'"))?;
+    if !status.success() {
+        anyhow::bail!("'{program}' exited with {status}");
+    }
     Ok(())
 }
 
+/// Prints a colored unified diff between two command bodies. Returns false
+/// if they have the same lines.
+fn print_diff(old: &str, new: &str) -> bool {
+    let hunks = unified_diff(old, new, 3);
+    if hunks.is_empty() {
+        return false;
+    }
+    println!("{BOLD}--- old{RESET}");
+    println!("{BOLD}+++ new{RESET}");
+    for hunk in hunks {
+        println!("{CYAN}{}{RESET}", hunk.header());
+        for line in hunk.lines {
+            match line {
+                DiffLine::Same(line) => println!(" {line}"),
+                DiffLine::Removed(line) => println!("{RED}-{line}{RESET}"),
+                DiffLine::Added(line) => println!("{GREEN}+{line}{RESET}"),
+            }
+        }
+    }
+    true
+}
+
 fn resolve_project_name(pacs: &Pacs, project_name: Option<String>) -> Result<String> {
     match project_name {
         Some(p) => Ok(p),
@@ -861,4 +4635,65 @@ mod tests {
     fn verify_cli() {
         Cli::command().debug_assert();
     }
+
+    #[test]
+    fn test_edit_dir_line() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("services")).unwrap();
+        let base = format!("{}/", dir.path().display());
+        let edit = |typed: &str| {
+            let mut out = Vec::new();
+            let input = typed.bytes().map(Ok);
+            let line = edit_dir_line(input, &mut out).unwrap();
+            (line, String::from_utf8(out).unwrap())
+        };
+
+        let (line, echoed) = edit(&format!("{base}se\tapi\r"));
+        assert_eq!(line.unwrap(), format!("{base}services/api"));
+        assert_eq!(echoed, format!("{base}services/api"));
+
+        let (line, _) = edit(&format!("{base}x\tdé\x7f\x7f\x1b[Dy\n"));
+        assert_eq!(line.unwrap(), format!("{base}xy"));
+        assert_eq!(edit("src\x03").0, None);
+    }
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(Duration::from_millis(420)), "0.4s");
+        assert_eq!(format_elapsed(Duration::from_millis(59_960)), "1m00s");
+        assert_eq!(format_elapsed(Duration::from_secs(125)), "2m05s");
+    }
+
+    #[test]
+    fn test_ansi_color() {
+        assert_eq!(ansi_color("red").as_deref(), Some("\x1b[31m"));
+        assert_eq!(ansi_color("Bright-Blue").as_deref(), Some("\x1b[94m"));
+        assert_eq!(
+            ansi_color("#ff8800").as_deref(),
+            Some("\x1b[38;2;255;136;0m")
+        );
+        assert_eq!(ansi_color("#ff88"), None);
+        assert_eq!(ansi_color("#ff880é"), None);
+        assert_eq!(ansi_color("teal"), None);
+    }
+
+    #[test]
+    fn test_ci_sections() {
+        let label = "deploy [prod]";
+        assert_eq!(
+            CiSystem::Github.section_start(label, 0),
+            "::group::deploy [prod]"
+        );
+        assert_eq!(
+            CiSystem::Gitlab.section_start(label, 1_700_000_000),
+            "\x1b[0Ksection_start:1700000000:deploy__prod_\r\x1b[0Kdeploy [prod]"
+        );
+        assert_eq!(
+            CiSystem::Gitlab
+                .section_end(label, 1_700_000_005)
+                .as_deref(),
+            Some("\x1b[0Ksection_end:1700000005:deploy__prod_\r\x1b[0K")
+        );
+        assert_eq!(CiSystem::Other.section_end(label, 0), None);
+    }
 }
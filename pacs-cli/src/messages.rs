@@ -0,0 +1,1076 @@
+//! User-facing messages of the CLI, in English and German.
+//!
+//! The locale is read from `PACS_LANG`, falling back to `LC_ALL`,
+//! `LC_MESSAGES` and `LANG`. Messages are printed through their `Display`
+//! implementation:
+//!
+//! ```ignore
+//! println!("{}", Msg::CommandRemoved { name: "build" });
+//! ```
+//!
+//! To add a language, add a `Locale` variant, map its language code in
+//! `Locale::parse` and translate the messages like `Msg::german` does.
+
+use std::env;
+use std::fmt;
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    English,
+    German,
+}
+
+impl Locale {
+    /// Returns the locale of the first set variable of `PACS_LANG`, `LC_ALL`,
+    /// `LC_MESSAGES` and `LANG`, or English.
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self::variable()
+            .and_then(|(_, value)| Self::parse(&value))
+            .unwrap_or(Self::English)
+    }
+
+    /// Returns the first set variable of `PACS_LANG`, `LC_ALL`, `LC_MESSAGES`
+    /// and `LANG` with its value.
+    #[must_use]
+    pub fn variable() -> Option<(&'static str, String)> {
+        ["PACS_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| Some((var, env::var(var).ok()?)))
+            .find(|(_, value)| !value.is_empty())
+    }
+
+    /// Returns the language code, e.g. `de`.
+    #[must_use]
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::German => "de",
+        }
+    }
+
+    /// Parses a locale like `de`, `de_AT.UTF-8` or `en-US`.
+    #[must_use]
+    pub fn parse(s: &str) -> Option<Self> {
+        let language = s.split(['_', '-', '.', '@']).next()?;
+        match language.to_ascii_lowercase().as_str() {
+            "en" | "c" | "posix" => Some(Self::English),
+            "de" => Some(Self::German),
+            _ => None,
+        }
+    }
+
+    /// Returns the locale of this process.
+    pub fn current() -> Self {
+        static LOCALE: OnceLock<Locale> = OnceLock::new();
+        *LOCALE.get_or_init(Self::from_env)
+    }
+}
+
+/// A message shown to the user, with its arguments.
+#[derive(Debug, Clone, Copy)]
+pub enum Msg<'a> {
+    Warning,
+    Initialized,
+    FirstProjectPrompt,
+    NoProjectName,
+    EditorFailed,
+    NoCommandEntered,
+    NoConfigSettings,
+    DefaultSource,
+    SafeModeShowing,
+    UnknownProgram,
+    CommandNamePrompt,
+    NoCommandName,
+    EmptyCommand,
+    NoChanges,
+    CommandAdded {
+        name: &'a str,
+        project: &'a str,
+    },
+    BatchAdded {
+        count: usize,
+        project: &'a str,
+    },
+    EmptyBatch,
+    SimilarCommand {
+        name: &'a str,
+        project: &'a str,
+    },
+    ConfirmAlias {
+        name: &'a str,
+    },
+    AliasAdded {
+        alias: &'a str,
+        name: &'a str,
+        project: &'a str,
+    },
+    CommandRemoved {
+        name: &'a str,
+    },
+    CommandUpdated {
+        name: &'a str,
+    },
+    CommandUpdatedWithTag {
+        name: &'a str,
+        tag: &'a str,
+    },
+    CommandUpdatedTagRemoved {
+        name: &'a str,
+    },
+    CommandRenamed {
+        old: &'a str,
+        new: &'a str,
+    },
+    CommandLocked {
+        name: &'a str,
+    },
+    CommandUnlocked {
+        name: &'a str,
+    },
+    NoCommands,
+    ProjectHasNoEnvironments,
+    RunOk,
+    RunSkipped,
+    ExitedWith {
+        name: &'a str,
+        code: i32,
+    },
+    FailedToStart {
+        name: &'a str,
+    },
+    RunsFailed {
+        count: usize,
+        names: &'a str,
+    },
+    RunExit {
+        code: i32,
+    },
+    RunFooter {
+        code: i32,
+        elapsed: &'a str,
+        cwd: &'a str,
+        environment: Option<&'a str>,
+    },
+    Copied {
+        names: &'a str,
+    },
+    OutputCopied {
+        bytes: usize,
+    },
+    OutputTooLarge {
+        bytes: usize,
+        limit: usize,
+    },
+    NoMatches,
+    NoAliasesFound,
+    BundleFormat,
+    NoBundleGiven,
+    KeptLocal {
+        project: &'a str,
+        name: &'a str,
+    },
+    BundleConflict {
+        project: &'a str,
+        name: &'a str,
+    },
+    ResolveConflict,
+    RenameTo,
+    NameTaken {
+        name: &'a str,
+    },
+    ProjectImported {
+        name: &'a str,
+        created: bool,
+    },
+    CommandsMerged {
+        count: usize,
+    },
+    NoSyncConflicts,
+    NoNotes {
+        project: &'a str,
+    },
+    NoProjectPath {
+        project: &'a str,
+    },
+    ProjectPathMissing {
+        path: &'a str,
+    },
+    NotesSaved {
+        project: &'a str,
+    },
+    ResolvingFile {
+        path: &'a str,
+    },
+    SyncResolved {
+        name: &'a str,
+    },
+    Imported {
+        count: usize,
+        project: &'a str,
+    },
+    ImportSkipped {
+        name: &'a str,
+    },
+    Exported {
+        path: &'a Path,
+    },
+    InvalidThreshold,
+    NoDuplicates,
+    DuplicateGroup {
+        count: usize,
+        identical: bool,
+    },
+    KeepWhich {
+        count: usize,
+    },
+    Skipped,
+    RunWhich {
+        count: usize,
+    },
+    GroupInCi {
+        group: &'a str,
+    },
+    EmptyGroup {
+        group: &'a str,
+    },
+    ConfirmDanger {
+        name: &'a str,
+        pattern: &'a str,
+    },
+    ConfirmRun {
+        name: &'a str,
+    },
+    AskCwd {
+        name: &'a str,
+        default: &'a str,
+    },
+    NothingRun,
+    Recent,
+    Merged {
+        project: &'a str,
+        name: &'a str,
+    },
+    KeptInOtherProjects {
+        count: usize,
+    },
+    NoConflicts,
+    Alias,
+    ShadowedIn {
+        project: &'a str,
+    },
+    PickedBy {
+        command: &'a str,
+    },
+    EnvironmentNotFound {
+        environment: &'a str,
+        project: &'a str,
+    },
+    EnteringShell {
+        project: &'a str,
+        environment: Option<&'a str>,
+    },
+    NoProblems,
+    Since {
+        date: &'a str,
+    },
+    ExpiryHint,
+    AllKeysDeclared,
+    UndeclaredKeys {
+        keys: &'a str,
+    },
+    NoKeyUsers {
+        key: &'a str,
+    },
+    NoKeys,
+    NoOneline {
+        name: &'a str,
+    },
+    KeyMissing,
+    KeyUnused {
+        key: &'a str,
+    },
+    KeyRenamed {
+        old: &'a str,
+        new: &'a str,
+        project: &'a str,
+    },
+    DryRun,
+    KeysAdded,
+    LintHint,
+    NoUnused,
+    HistoryTooShort,
+    LastRun {
+        days: u64,
+    },
+    NeverRun,
+    Archived {
+        count: usize,
+    },
+    ArchiveHint,
+    HistoryCleared {
+        count: usize,
+    },
+    CacheFresh {
+        path: &'a Path,
+        projects: usize,
+        commands: usize,
+    },
+    CacheOutdated {
+        path: &'a Path,
+    },
+    CacheMissing {
+        path: &'a Path,
+    },
+    CacheRebuilt {
+        projects: usize,
+        commands: usize,
+    },
+    SkippedProjectFile {
+        path: &'a Path,
+        message: &'a str,
+    },
+    BrokenConfig {
+        message: &'a str,
+    },
+    ProjectCreated {
+        name: &'a str,
+    },
+    ProjectDeleted {
+        name: &'a str,
+    },
+    ProjectArchived {
+        name: &'a str,
+    },
+    ProjectUnarchived {
+        name: &'a str,
+    },
+    ProjectStyled {
+        project: &'a str,
+    },
+    NoProjects,
+    ProjectColumn,
+    EnvironmentsColumn,
+    SwitchedProject {
+        name: &'a str,
+    },
+    SwitchHookFinished {
+        lines: usize,
+    },
+    SwitchHookFailed {
+        code: i32,
+    },
+    SessionProject {
+        project: &'a str,
+    },
+    ProjectCleared,
+    NoActiveProject,
+    NoPreviousProject,
+    Published {
+        project: &'a str,
+        url: &'a str,
+    },
+    Pulled {
+        project: &'a str,
+        gist: &'a str,
+    },
+    EnvironmentAdded {
+        name: &'a str,
+        project: &'a str,
+    },
+    EnvironmentRemoved {
+        name: &'a str,
+        project: &'a str,
+    },
+    EnvironmentCopied {
+        name: &'a str,
+        from: &'a str,
+        to: &'a str,
+    },
+    EnvironmentsUpdated {
+        project: &'a str,
+    },
+    NoEnvironments,
+    SwitchedEnvironment {
+        name: &'a str,
+        project: &'a str,
+    },
+    SessionEnvironment {
+        environment: &'a str,
+    },
+    NoActiveEnvironment,
+    WroteDirenv {
+        path: &'a Path,
+    },
+    RegistryAdded {
+        name: &'a str,
+        count: usize,
+    },
+    RegistryRemoved {
+        name: &'a str,
+    },
+    Unapproved,
+    ChangedSinceApproval,
+    RegistryCommandChanged {
+        name: &'a str,
+        registry: &'a str,
+    },
+    AlreadyApproved {
+        name: &'a str,
+    },
+    ConfirmApproval {
+        name: &'a str,
+        registry: &'a str,
+    },
+    NotApproved,
+    CommandApproved {
+        name: &'a str,
+        registry: &'a str,
+    },
+    RegistryUpdateFailed {
+        name: &'a str,
+        error: &'a str,
+    },
+    NoRegistries,
+    RegistriesUpdated {
+        count: usize,
+    },
+    SetPlaceholders {
+        keys: &'a str,
+    },
+    Error,
+    FileSaved {
+        path: &'a Path,
+    },
+    EditAgainPrompt,
+    ChangesDiscarded,
+}
+
+impl Msg<'_> {
+    /// Returns the message in the given locale.
+    #[must_use]
+    pub fn text(self, locale: Locale) -> String {
+        match locale {
+            Locale::English => self.english(),
+            Locale::German => self.german(),
+        }
+    }
+
+    fn english(self) -> String {
+        match self {
+            Self::Warning => "warning:".into(),
+            Self::Initialized => "Pacs initialized at ~/.pacs/".into(),
+            Self::FirstProjectPrompt => "Enter a name for your first project: ".into(),
+            Self::NoProjectName => "No project name entered".into(),
+            Self::EditorFailed => "Editor exited with non-zero status".into(),
+            Self::NoCommandEntered => "No command entered".into(),
+            Self::NoConfigSettings => {
+                "No settings in config.toml, see --effective for the defaults.".into()
+            }
+            Self::DefaultSource => "default".into(),
+            Self::SafeModeShowing => "Safe mode: showing instead of running".into(),
+            Self::UnknownProgram => "not a known program".into(),
+            Self::CommandNamePrompt => "Name for the command: ".into(),
+            Self::NoCommandName => "No command name entered".into(),
+            Self::EmptyCommand => "Command cannot be empty".into(),
+            Self::NoChanges => "No changes.".into(),
+            Self::CommandAdded { name, project } => {
+                format!("Command '{name}' added to project '{project}'.")
+            }
+            Self::BatchAdded { count, project } => {
+                format!("Added {count} commands to project '{project}'.")
+            }
+            Self::EmptyBatch => "The file has no [[commands]].".into(),
+            Self::SimilarCommand { name, project } => {
+                format!("'{name}' in project '{project}' already runs a very similar command")
+            }
+            Self::ConfirmAlias { name } => {
+                format!("Add it as an alias of '{name}' instead? [y/N]: ")
+            }
+            Self::AliasAdded {
+                alias,
+                name,
+                project,
+            } => format!("Alias '{alias}' added to '{name}' in project '{project}'."),
+            Self::CommandRemoved { name } => format!("Command '{name}' removed."),
+            Self::CommandUpdated { name } => format!("Command '{name}' updated."),
+            Self::CommandUpdatedWithTag { name, tag } => {
+                format!("Command '{name}' updated with tag '{tag}'.")
+            }
+            Self::CommandUpdatedTagRemoved { name } => {
+                format!("Command '{name}' updated, tag removed.")
+            }
+            Self::CommandRenamed { old, new } => format!("Command '{old}' renamed to '{new}'."),
+            Self::CommandLocked { name } => format!("Command '{name}' locked."),
+            Self::CommandUnlocked { name } => format!("Command '{name}' unlocked."),
+            Self::NoCommands => "No commands found. Use 'pacs add <name> <cmd>' to add one.".into(),
+            Self::ProjectHasNoEnvironments => "The project has no environments".into(),
+            Self::RunOk => "ok".into(),
+            Self::RunSkipped => "skipped".into(),
+            Self::ExitedWith { name, code } => format!("{name} exited with {code}"),
+            Self::FailedToStart { name } => format!("{name} failed to start"),
+            Self::RunsFailed { count, names } => format!("{count} failed: {names}"),
+            Self::RunExit { code } => format!("exit {code}"),
+            Self::RunFooter {
+                code,
+                elapsed,
+                cwd,
+                environment,
+            } => {
+                let environment = environment.map(|e| format!(" · env {e}")).unwrap_or_default();
+                format!("exit {code} · {elapsed} · {cwd}{environment}")
+            }
+            Self::Copied { names } => format!("Copied '{names}' to clipboard."),
+            Self::OutputCopied { bytes } => format!("Copied the output ({bytes} bytes) to clipboard."),
+            Self::OutputTooLarge { bytes, limit } => {
+                format!("The output ({bytes} bytes) is larger than {limit} bytes and was not copied.")
+            }
+            Self::NoMatches => "No matches found.".into(),
+            Self::NoAliasesFound => "No aliases found.".into(),
+            Self::BundleFormat => "Bundles can only be exported as toml or json".into(),
+            Self::NoBundleGiven => {
+                "No bundle given. Pass a file written by 'pacs export --all' or pipe it in.".into()
+            }
+            Self::KeptLocal { project, name } => format!(
+                "Kept your '{name}' in project '{project}', the imported one differs. Use --interactive to choose."
+            ),
+            Self::BundleConflict { project, name } => {
+                format!("'{name}' in project '{project}' differs from yours")
+            }
+            Self::ResolveConflict => {
+                "Keep [m]ine, take [t]heirs or [r]ename theirs? [M/t/r]: ".into()
+            }
+            Self::RenameTo => "New name for theirs (Enter to keep mine): ".into(),
+            Self::NameTaken { name } => format!("'{name}' is taken."),
+            Self::ProjectImported { name, created } => {
+                let action = if created { "created" } else { "updated" };
+                format!("Project '{name}' {action}.")
+            }
+            Self::CommandsMerged { count } => {
+                format!("Added {count} commands to existing projects.")
+            }
+            Self::NoSyncConflicts => "No project files with merge conflicts.".into(),
+            Self::NoNotes { project } => format!(
+                "Project '{project}' has no notes. Add some with 'pacs project notes edit'."
+            ),
+            Self::NoProjectPath { project } => format!(
+                "Project '{project}' has no path. Set one with 'pacs open -p {project}'."
+            ),
+            Self::ProjectPathMissing { path } => format!("The project directory {path} does not exist."),
+            Self::NotesSaved { project } => format!("Notes of project '{project}' saved."),
+            Self::ResolvingFile { path } => format!("Resolving {path}"),
+            Self::SyncResolved { name } => format!("Project '{name}' resolved."),
+            Self::Imported { count, project } => {
+                format!("Imported {count} commands into project '{project}'.")
+            }
+            Self::ImportSkipped { name } => {
+                format!("Skipped alias '{name}', a command with this name exists.")
+            }
+            Self::Exported { path } => format!("Exported commands to {}.", path.display()),
+            Self::InvalidThreshold => "Threshold must be between 0 and 1".into(),
+            Self::NoDuplicates => "No duplicates found.".into(),
+            Self::DuplicateGroup { count, identical } => {
+                let kind = if identical { "identical" } else { "similar" };
+                format!("{count} {kind} commands")
+            }
+            Self::KeepWhich { count } => format!(
+                "Keep which command? The others are removed and become its aliases [1-{count}, Enter to skip]: "
+            ),
+            Self::Skipped => "Skipped.".into(),
+            Self::RunWhich { count } => format!("Run which command? [1-{count}, Enter to cancel]: "),
+            Self::GroupInCi { group } => {
+                format!("'{group}' is a group, name one of its commands with --ci")
+            }
+            Self::EmptyGroup { group } => format!("No commands in group '{group}'"),
+            Self::ConfirmDanger { name, pattern } => {
+                format!("'{name}' matches the danger pattern '{pattern}'. Run it? [y/N]: ")
+            }
+            Self::ConfirmRun { name } => format!("Run '{name}'? [y/N]: "),
+            Self::AskCwd { name, default } => format!("Directory for '{name}' [{default}]: "),
+            Self::NothingRun => "Nothing run.".into(),
+            Self::Recent => "Recent".into(),
+            Self::Merged { project, name } => format!("Merged into '{project}/{name}'."),
+            Self::KeptInOtherProjects { count } => {
+                format!("Kept {count} commands of other projects, merge only works within one.")
+            }
+            Self::NoConflicts => "No conflicting names.".into(),
+            Self::Alias => "(alias)".into(),
+            Self::ShadowedIn { project } => format!("shadowed in {project}"),
+            Self::PickedBy { command } => format!("picked by '{command}'"),
+            Self::EnvironmentNotFound {
+                environment,
+                project,
+            } => format!("Environment '{environment}' not found in project '{project}'"),
+            Self::EnteringShell {
+                project,
+                environment: Some(environment),
+            } => format!(
+                "Entering project '{project}' with environment '{environment}', exit the shell to leave."
+            ),
+            Self::EnteringShell {
+                project,
+                environment: None,
+            } => format!("Entering project '{project}', exit the shell to leave."),
+            Self::NoProblems => "No problems found.".into(),
+            Self::Since { date } => format!("since {date}"),
+            Self::ExpiryHint => "Remove expired commands with 'pacs rm', or move the dates with 'pacs edit <name> --expires/--review-after'.".into(),
+            Self::AllKeysDeclared => "All used keys are declared.".into(),
+            Self::UndeclaredKeys { keys } => format!("uses undeclared keys: {keys}"),
+            Self::NoKeyUsers { key } => format!("No command uses '{key}'."),
+            Self::NoKeys => "The command uses no keys.".into(),
+            Self::NoOneline { name } => {
+                format!("'{name}' uses a heredoc and can't be joined into one line")
+            }
+            Self::KeyMissing => "missing".into(),
+            Self::KeyUnused { key } => format!("Nothing in the project uses '{key}'."),
+            Self::KeyRenamed { old, new, project } => {
+                format!("Key '{old}' renamed to '{new}' in project '{project}'.")
+            }
+            Self::DryRun => "Dry run, nothing was changed.".into(),
+            Self::KeysAdded => "Added the keys to requires.".into(),
+            Self::LintHint => "Run 'pacs lint --fix' to add them to requires.".into(),
+            Self::NoUnused => "No unused commands.".into(),
+            Self::HistoryTooShort => "the history is shorter than the window, commands listed as never run may just be older than it".into(),
+            Self::LastRun { days } => format!("last run {days} days ago"),
+            Self::NeverRun => "never run".into(),
+            Self::Archived { count } => format!("Archived {count} commands to ~/.pacs/archive."),
+            Self::ArchiveHint => "Run with --archive to move them to ~/.pacs/archive.".into(),
+            Self::HistoryCleared { count } => format!("Removed {count} runs from the history."),
+            Self::CacheFresh {
+                path,
+                projects,
+                commands,
+            } => format!(
+                "{}: up to date, {projects} projects and {commands} commands.",
+                path.display()
+            ),
+            Self::CacheOutdated { path } => format!(
+                "{}: outdated, rebuilt on the next completion.",
+                path.display()
+            ),
+            Self::CacheMissing { path } => {
+                format!("{}: missing, built on the next completion.", path.display())
+            }
+            Self::CacheRebuilt { projects, commands } => format!(
+                "Rebuilt the completion cache with {projects} projects and {commands} commands."
+            ),
+            Self::SkippedProjectFile { path, message } => {
+                format!("skipped project file {}: {message}", path.display())
+            }
+            Self::BrokenConfig { message } => format!(
+                "config.toml could not be loaded, using defaults until it is fixed with pacs open --config: {message}"
+            ),
+            Self::ProjectCreated { name } => format!("Project '{name}' created and activated."),
+            Self::ProjectDeleted { name } => format!("Project '{name}' deleted."),
+            Self::ProjectArchived { name } => format!(
+                "Project '{name}' archived. Restore it with 'pacs project unarchive {name}'."
+            ),
+            Self::ProjectUnarchived { name } => format!("Project '{name}' restored."),
+            Self::ProjectStyled { project } => format!("Project is now shown as {project}."),
+            Self::NoProjects => "No projects. Use 'pacs project add' to create one.".into(),
+            Self::ProjectColumn => "PROJECT".into(),
+            Self::EnvironmentsColumn => "ENVIRONMENTS".into(),
+            Self::SwitchedProject { name } => format!("Switched to project '{name}'."),
+            Self::SwitchHookFinished { lines: 0 } => "Ran on_switch.".into(),
+            Self::SwitchHookFinished { lines } => {
+                format!("Ran on_switch ({lines} lines of output):")
+            }
+            Self::SwitchHookFailed { code } => format!("on_switch exited with {code}:"),
+            Self::SessionProject { project } => {
+                format!("PACS_PROJECT keeps '{project}' active in this shell.")
+            }
+            Self::ProjectCleared => "Active project cleared.".into(),
+            Self::NoActiveProject => "No active project.".into(),
+            Self::NoPreviousProject => "No project was active before this one.".into(),
+            Self::Published { project, url } => format!("Project '{project}' published to {url}"),
+            Self::Pulled { project, gist } => format!("Project '{project}' pulled from gist {gist}."),
+            Self::EnvironmentAdded { name, project } => {
+                format!("Environment '{name}' added and activated in project '{project}'.")
+            }
+            Self::EnvironmentRemoved { name, project } => {
+                format!("Environment '{name}' removed from project '{project}'.")
+            }
+            Self::EnvironmentCopied { name, from, to } => {
+                format!("Environment '{name}' copied from project '{from}' to '{to}'.")
+            }
+            Self::EnvironmentsUpdated { project } => {
+                format!("All environments updated for project '{project}'.")
+            }
+            Self::NoEnvironments => "No environments.".into(),
+            Self::SwitchedEnvironment { name, project } => {
+                format!("Switched to environment '{name}' in project '{project}'.")
+            }
+            Self::SessionEnvironment { environment } => {
+                format!("PACS_ENV keeps '{environment}' active in this shell.")
+            }
+            Self::NoActiveEnvironment => "No active environment.".into(),
+            Self::WroteDirenv { path } => format!(
+                "Wrote environment to {}. Run 'direnv allow' to load it.",
+                path.display()
+            ),
+            Self::RegistryAdded { name, count } => {
+                format!("Registry '{name}' added with {count} commands.")
+            }
+            Self::RegistryRemoved { name } => format!("Registry '{name}' removed."),
+            Self::Unapproved => "unapproved".into(),
+            Self::ChangedSinceApproval => "changed since approval".into(),
+            Self::RegistryCommandChanged { name, registry } => format!(
+                "command '{name}' of registry '{registry}' changed since you approved it and will not run until approved again with 'pacs approve {name}'"
+            ),
+            Self::AlreadyApproved { name } => format!("Command '{name}' is already approved."),
+            Self::ConfirmApproval { name, registry } => {
+                format!("Allow '{name}' from registry '{registry}' to run? [y/N]: ")
+            }
+            Self::NotApproved => "Not approved.".into(),
+            Self::CommandApproved { name, registry } => {
+                format!("Command '{name}' from registry '{registry}' approved.")
+            }
+            Self::RegistryUpdateFailed { name, error } => {
+                format!("failed to update registry {name}: {error}")
+            }
+            Self::NoRegistries => {
+                "No registries. Use 'pacs registry add <url>' to subscribe to one.".into()
+            }
+            Self::RegistriesUpdated { count } => format!("Updated {count} registries."),
+            Self::SetPlaceholders { keys } => format!("Set {keys} with 'pacs env edit'."),
+            Self::Error => "error:".into(),
+            Self::FileSaved { path } => format!("Saved {}.", path.display()),
+            Self::EditAgainPrompt => "Edit again? [Y/n]: ".into(),
+            Self::ChangesDiscarded => "Changes discarded".into(),
+        }
+    }
+
+    fn german(self) -> String {
+        match self {
+            Self::Warning => "Warnung:".into(),
+            Self::Initialized => "Pacs wurde in ~/.pacs/ eingerichtet".into(),
+            Self::FirstProjectPrompt => "Name des ersten Projekts: ".into(),
+            Self::NoProjectName => "Kein Projektname eingegeben".into(),
+            Self::EditorFailed => "Der Editor wurde mit einem Fehler beendet".into(),
+            Self::NoCommandEntered => "Kein Befehl eingegeben".into(),
+            Self::NoConfigSettings => {
+                "Keine Einstellungen in config.toml, --effective zeigt die Standardwerte.".into()
+            }
+            Self::DefaultSource => "Standard".into(),
+            Self::SafeModeShowing => "Sicherer Modus: wird angezeigt statt ausgeführt".into(),
+            Self::UnknownProgram => "kein bekanntes Programm".into(),
+            Self::CommandNamePrompt => "Name des Befehls: ".into(),
+            Self::NoCommandName => "Kein Befehlsname eingegeben".into(),
+            Self::EmptyCommand => "Der Befehl darf nicht leer sein".into(),
+            Self::NoChanges => "Keine Änderungen.".into(),
+            Self::CommandAdded { name, project } => {
+                format!("Befehl '{name}' zum Projekt '{project}' hinzugefügt.")
+            }
+            Self::BatchAdded { count, project } => {
+                format!("{count} Befehle zum Projekt '{project}' hinzugefügt.")
+            }
+            Self::EmptyBatch => "Die Datei enthält keine [[commands]].".into(),
+            Self::SimilarCommand { name, project } => {
+                format!("'{name}' im Projekt '{project}' führt schon einen sehr ähnlichen Befehl aus")
+            }
+            Self::ConfirmAlias { name } => {
+                format!("Stattdessen als Alias von '{name}' hinzufügen? [j/N]: ")
+            }
+            Self::AliasAdded {
+                alias,
+                name,
+                project,
+            } => format!("Alias '{alias}' zu '{name}' im Projekt '{project}' hinzugefügt."),
+            Self::CommandRemoved { name } => format!("Befehl '{name}' entfernt."),
+            Self::CommandUpdated { name } => format!("Befehl '{name}' aktualisiert."),
+            Self::CommandUpdatedWithTag { name, tag } => {
+                format!("Befehl '{name}' mit Tag '{tag}' aktualisiert.")
+            }
+            Self::CommandUpdatedTagRemoved { name } => {
+                format!("Befehl '{name}' aktualisiert, Tag entfernt.")
+            }
+            Self::CommandRenamed { old, new } => {
+                format!("Befehl '{old}' in '{new}' umbenannt.")
+            }
+            Self::CommandLocked { name } => format!("Befehl '{name}' gesperrt."),
+            Self::CommandUnlocked { name } => format!("Befehl '{name}' entsperrt."),
+            Self::NoCommands => {
+                "Keine Befehle gefunden. Mit 'pacs add <name> <cmd>' einen hinzufügen.".into()
+            }
+            Self::ProjectHasNoEnvironments => "Das Projekt hat keine Umgebungen".into(),
+            Self::RunOk => "ok".into(),
+            Self::RunSkipped => "übersprungen".into(),
+            Self::ExitedWith { name, code } => format!("{name} mit Status {code} beendet"),
+            Self::FailedToStart { name } => format!("{name} konnte nicht gestartet werden"),
+            Self::RunsFailed { count, names } => format!("{count} fehlgeschlagen: {names}"),
+            Self::RunExit { code } => format!("Status {code}"),
+            Self::RunFooter {
+                code,
+                elapsed,
+                cwd,
+                environment,
+            } => {
+                let environment = environment
+                    .map(|e| format!(" · Umgebung {e}"))
+                    .unwrap_or_default();
+                format!("Status {code} · {elapsed} · {cwd}{environment}")
+            }
+            Self::Copied { names } => format!("'{names}' in die Zwischenablage kopiert."),
+            Self::OutputCopied { bytes } => {
+                format!("Die Ausgabe ({bytes} Bytes) wurde in die Zwischenablage kopiert.")
+            }
+            Self::OutputTooLarge { bytes, limit } => {
+                format!("Die Ausgabe ({bytes} Bytes) ist größer als {limit} Bytes und wurde nicht kopiert.")
+            }
+            Self::NoMatches => "Keine Treffer.".into(),
+            Self::NoAliasesFound => "Keine Aliase gefunden.".into(),
+            Self::BundleFormat => "Bündel lassen sich nur als toml oder json exportieren".into(),
+            Self::NoBundleGiven => {
+                "Kein Bündel angegeben. Übergib eine mit 'pacs export --all' geschriebene Datei oder leite sie weiter.".into()
+            }
+            Self::KeptLocal { project, name } => format!(
+                "Dein '{name}' im Projekt '{project}' wurde behalten, der importierte unterscheidet sich. Mit --interactive wählen."
+            ),
+            Self::BundleConflict { project, name } => {
+                format!("'{name}' im Projekt '{project}' unterscheidet sich von deinem")
+            }
+            Self::ResolveConflict => {
+                "Meinen behalten (m), ihren übernehmen (t) oder ihren umbenennen (r)? [M/t/r]: ".into()
+            }
+            Self::RenameTo => {
+                "Neuer Name für ihren (Enter behält meinen): ".into()
+            }
+            Self::NameTaken { name } => format!("'{name}' ist vergeben."),
+            Self::ProjectImported { name, created } => {
+                let action = if created { "angelegt" } else { "aktualisiert" };
+                format!("Projekt '{name}' {action}.")
+            }
+            Self::CommandsMerged { count } => {
+                format!("{count} Befehle zu bestehenden Projekten hinzugefügt.")
+            }
+            Self::NoSyncConflicts => "Keine Projektdateien mit Merge-Konflikten.".into(),
+            Self::NoNotes { project } => format!(
+                "Das Projekt '{project}' hat keine Notizen. Füge welche mit 'pacs project notes edit' hinzu."
+            ),
+            Self::NoProjectPath { project } => format!(
+                "Das Projekt '{project}' hat keinen Pfad. Setze einen mit 'pacs open -p {project}'."
+            ),
+            Self::ProjectPathMissing { path } => {
+                format!("Das Projektverzeichnis {path} existiert nicht.")
+            }
+            Self::NotesSaved { project } => format!("Notizen des Projekts '{project}' gespeichert."),
+            Self::ResolvingFile { path } => format!("Löse {path} auf"),
+            Self::SyncResolved { name } => format!("Projekt '{name}' aufgelöst."),
+            Self::Imported { count, project } => {
+                format!("{count} Befehle in das Projekt '{project}' importiert.")
+            }
+            Self::ImportSkipped { name } => {
+                format!("Alias '{name}' übersprungen, es gibt schon einen Befehl mit diesem Namen.")
+            }
+            Self::Exported { path } => format!("Befehle nach {} exportiert.", path.display()),
+            Self::InvalidThreshold => "Der Schwellwert muss zwischen 0 und 1 liegen".into(),
+            Self::NoDuplicates => "Keine Duplikate gefunden.".into(),
+            Self::DuplicateGroup { count, identical } => {
+                let kind = if identical { "identische" } else { "ähnliche" };
+                format!("{count} {kind} Befehle")
+            }
+            Self::KeepWhich { count } => format!(
+                "Welcher Befehl bleibt? Die anderen werden entfernt und zu seinen Aliasen [1-{count}, Enter zum Überspringen]: "
+            ),
+            Self::Skipped => "Übersprungen.".into(),
+            Self::RunWhich { count } => {
+                format!("Welcher Befehl soll laufen? [1-{count}, Enter zum Abbrechen]: ")
+            }
+            Self::ConfirmDanger { name, pattern } => {
+                format!("'{name}' passt auf das Gefahrenmuster '{pattern}'. Ausführen? [j/N]: ")
+            }
+            Self::ConfirmRun { name } => format!("'{name}' ausführen? [j/N]: "),
+            Self::AskCwd { name, default } => format!("Verzeichnis für '{name}' [{default}]: "),
+            Self::GroupInCi { group } => {
+                format!("'{group}' ist eine Gruppe, mit --ci einen ihrer Befehle angeben")
+            }
+            Self::EmptyGroup { group } => format!("Keine Befehle in der Gruppe '{group}'"),
+            Self::NothingRun => "Nichts ausgeführt.".into(),
+            Self::Recent => "Zuletzt".into(),
+            Self::Merged { project, name } => format!("In '{project}/{name}' zusammengeführt."),
+            Self::KeptInOtherProjects { count } => format!(
+                "{count} Befehle anderer Projekte behalten, zusammengeführt wird nur innerhalb eines Projekts."
+            ),
+            Self::NoConflicts => "Keine doppelten Namen.".into(),
+            Self::Alias => "(Alias)".into(),
+            Self::ShadowedIn { project } => format!("verdeckt in {project}"),
+            Self::PickedBy { command } => format!("gewählt von '{command}'"),
+            Self::EnvironmentNotFound {
+                environment,
+                project,
+            } => format!("Umgebung '{environment}' nicht im Projekt '{project}' gefunden"),
+            Self::EnteringShell {
+                project,
+                environment: Some(environment),
+            } => format!(
+                "Projekt '{project}' mit Umgebung '{environment}' geöffnet, zum Verlassen die Shell beenden."
+            ),
+            Self::EnteringShell {
+                project,
+                environment: None,
+            } => format!("Projekt '{project}' geöffnet, zum Verlassen die Shell beenden."),
+            Self::NoProblems => "Keine Probleme gefunden.".into(),
+            Self::Since { date } => format!("seit {date}"),
+            Self::ExpiryHint => "Abgelaufene Befehle mit 'pacs rm' entfernen oder die Daten mit 'pacs edit <name> --expires/--review-after' verschieben.".into(),
+            Self::AllKeysDeclared => "Alle verwendeten Schlüssel sind deklariert.".into(),
+            Self::UndeclaredKeys { keys } => {
+                format!("verwendet nicht deklarierte Schlüssel: {keys}")
+            }
+            Self::NoKeyUsers { key } => format!("Kein Befehl verwendet '{key}'."),
+            Self::NoKeys => "Der Befehl verwendet keine Schlüssel.".into(),
+            Self::NoOneline { name } => {
+                format!("'{name}' verwendet ein Heredoc und passt nicht in eine Zeile")
+            }
+            Self::KeyMissing => "fehlt".into(),
+            Self::KeyUnused { key } => format!("Nichts im Projekt verwendet '{key}'."),
+            Self::KeyRenamed { old, new, project } => {
+                format!("Schlüssel '{old}' im Projekt '{project}' in '{new}' umbenannt.")
+            }
+            Self::DryRun => "Probelauf, nichts wurde geändert.".into(),
+            Self::KeysAdded => "Die Schlüssel wurden zu requires hinzugefügt.".into(),
+            Self::LintHint => "Mit 'pacs lint --fix' zu requires hinzufügen.".into(),
+            Self::NoUnused => "Keine ungenutzten Befehle.".into(),
+            Self::HistoryTooShort => "der Verlauf ist kürzer als der Zeitraum, nie ausgeführte Befehle könnten einfach älter sein".into(),
+            Self::LastRun { days } => format!("zuletzt vor {days} Tagen ausgeführt"),
+            Self::NeverRun => "nie ausgeführt".into(),
+            Self::Archived { count } => format!("{count} Befehle nach ~/.pacs/archive verschoben."),
+            Self::ArchiveHint => "Mit --archive nach ~/.pacs/archive verschieben.".into(),
+            Self::HistoryCleared { count } => {
+                format!("{count} Ausführungen aus dem Verlauf entfernt.")
+            }
+            Self::CacheFresh {
+                path,
+                projects,
+                commands,
+            } => format!(
+                "{}: aktuell, {projects} Projekte und {commands} Befehle.",
+                path.display()
+            ),
+            Self::CacheOutdated { path } => format!(
+                "{}: veraltet, wird bei der nächsten Vervollständigung neu erstellt.",
+                path.display()
+            ),
+            Self::CacheMissing { path } => format!(
+                "{}: fehlt, wird bei der nächsten Vervollständigung erstellt.",
+                path.display()
+            ),
+            Self::CacheRebuilt { projects, commands } => format!(
+                "Vervollständigungs-Cache mit {projects} Projekten und {commands} Befehlen neu erstellt."
+            ),
+            Self::SkippedProjectFile { path, message } => {
+                format!("Projektdatei {} übersprungen: {message}", path.display())
+            }
+            Self::BrokenConfig { message } => format!(
+                "config.toml konnte nicht geladen werden, bis zur Korrektur mit pacs open --config gelten die Standardwerte: {message}"
+            ),
+            Self::ProjectCreated { name } => format!("Projekt '{name}' erstellt und aktiviert."),
+            Self::ProjectDeleted { name } => format!("Projekt '{name}' gelöscht."),
+            Self::ProjectArchived { name } => format!(
+                "Projekt '{name}' archiviert. Stelle es mit 'pacs project unarchive {name}' wieder her."
+            ),
+            Self::ProjectUnarchived { name } => format!("Projekt '{name}' wiederhergestellt."),
+            Self::ProjectStyled { project } => format!("Das Projekt wird nun als {project} angezeigt."),
+            Self::ProjectColumn => "PROJEKT".into(),
+            Self::EnvironmentsColumn => "UMGEBUNGEN".into(),
+            Self::NoProjects => {
+                "Keine Projekte. Mit 'pacs project add' eines erstellen.".into()
+            }
+            Self::SwitchedProject { name } => format!("Zu Projekt '{name}' gewechselt."),
+            Self::SwitchHookFinished { lines: 0 } => "on_switch ausgeführt.".into(),
+            Self::SwitchHookFinished { lines } => {
+                format!("on_switch ausgeführt ({lines} Zeilen Ausgabe):")
+            }
+            Self::SwitchHookFailed { code } => format!("on_switch endete mit {code}:"),
+            Self::SessionProject { project } => {
+                format!("PACS_PROJECT hält '{project}' in dieser Shell aktiv.")
+            }
+            Self::ProjectCleared => "Aktives Projekt zurückgesetzt.".into(),
+            Self::NoActiveProject => "Kein aktives Projekt.".into(),
+            Self::NoPreviousProject => "Vor diesem Projekt war kein anderes aktiv.".into(),
+            Self::Published { project, url } => {
+                format!("Projekt '{project}' nach {url} veröffentlicht")
+            }
+            Self::Pulled { project, gist } => {
+                format!("Projekt '{project}' aus Gist {gist} geladen.")
+            }
+            Self::EnvironmentAdded { name, project } => {
+                format!("Umgebung '{name}' im Projekt '{project}' hinzugefügt und aktiviert.")
+            }
+            Self::EnvironmentRemoved { name, project } => {
+                format!("Umgebung '{name}' aus Projekt '{project}' entfernt.")
+            }
+            Self::EnvironmentCopied { name, from, to } => {
+                format!("Umgebung '{name}' von Projekt '{from}' nach '{to}' kopiert.")
+            }
+            Self::EnvironmentsUpdated { project } => {
+                format!("Alle Umgebungen des Projekts '{project}' aktualisiert.")
+            }
+            Self::NoEnvironments => "Keine Umgebungen.".into(),
+            Self::SwitchedEnvironment { name, project } => {
+                format!("Zu Umgebung '{name}' im Projekt '{project}' gewechselt.")
+            }
+            Self::SessionEnvironment { environment } => {
+                format!("PACS_ENV hält '{environment}' in dieser Shell aktiv.")
+            }
+            Self::NoActiveEnvironment => "Keine aktive Umgebung.".into(),
+            Self::WroteDirenv { path } => format!(
+                "Umgebung nach {} geschrieben. Mit 'direnv allow' laden.",
+                path.display()
+            ),
+            Self::RegistryAdded { name, count } => {
+                format!("Registry '{name}' mit {count} Befehlen hinzugefügt.")
+            }
+            Self::RegistryRemoved { name } => format!("Registry '{name}' entfernt."),
+            Self::Unapproved => "nicht freigegeben".into(),
+            Self::ChangedSinceApproval => "seit Freigabe geändert".into(),
+            Self::RegistryCommandChanged { name, registry } => format!(
+                "Befehl '{name}' aus Registry '{registry}' wurde seit der Freigabe geändert und läuft erst nach erneutem 'pacs approve {name}'"
+            ),
+            Self::AlreadyApproved { name } => format!("Befehl '{name}' ist bereits freigegeben."),
+            Self::ConfirmApproval { name, registry } => {
+                format!("'{name}' aus Registry '{registry}' zum Ausführen freigeben? [j/N]: ")
+            }
+            Self::NotApproved => "Nicht freigegeben.".into(),
+            Self::CommandApproved { name, registry } => {
+                format!("Befehl '{name}' aus Registry '{registry}' freigegeben.")
+            }
+            Self::RegistryUpdateFailed { name, error } => {
+                format!("Registry {name} konnte nicht aktualisiert werden: {error}")
+            }
+            Self::NoRegistries => {
+                "Keine Registries. Mit 'pacs registry add <url>' eine abonnieren.".into()
+            }
+            Self::RegistriesUpdated { count } => format!("{count} Registries aktualisiert."),
+            Self::SetPlaceholders { keys } => format!("{keys} mit 'pacs env edit' setzen."),
+            Self::Error => "Fehler:".into(),
+            Self::FileSaved { path } => format!("{} gespeichert.", path.display()),
+            Self::EditAgainPrompt => "Erneut bearbeiten? [J/n]: ".into(),
+            Self::ChangesDiscarded => "Änderungen verworfen".into(),
+        }
+    }
+}
+
+impl fmt::Display for Msg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text(Locale::current()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale() {
+        assert_eq!(Locale::parse("de_AT.UTF-8"), Some(Locale::German));
+        assert_eq!(Locale::parse("en-US"), Some(Locale::English));
+        assert_eq!(Locale::parse("C"), Some(Locale::English));
+        assert_eq!(Locale::parse("fr_FR"), None);
+
+        let msg = Msg::CommandRemoved { name: "build" };
+        assert_eq!(msg.text(Locale::English), "Command 'build' removed.");
+        assert_eq!(msg.text(Locale::German), "Befehl 'build' entfernt.");
+    }
+}
@@ -0,0 +1,85 @@
+//! Benchmarks for a large command library: 5000 commands across 200 projects.
+//!
+//! Run with `cargo bench -p pacs-core`.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::hint::black_box;
+use std::path::Path;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use pacs_core::Pacs;
+
+const PROJECTS: usize = 200;
+const COMMANDS_PER_PROJECT: usize = 25;
+
+/// Writes the project files and a config with an active project to `base`.
+fn seed(base: &Path) {
+    let projects_dir = base.join("projects");
+    fs::create_dir_all(&projects_dir).unwrap();
+    fs::write(
+        base.join("config.toml"),
+        "active_project = \"project-100\"\n",
+    )
+    .unwrap();
+
+    for p in 0..PROJECTS {
+        let mut toml = format!("name = \"project-{p}\"\nactive_environment = \"dev\"\n");
+        for c in 0..COMMANDS_PER_PROJECT {
+            let _ = write!(
+                toml,
+                "\n[[commands]]\nname = \"cmd-{c}\"\ntag = \"tag-{}\"\n\
+                 command = \"\"\"\ncd {{{{dir}}}}\n\
+                 kubectl --context {{{{cluster}}}} -n {{{{namespace}}}} rollout restart deploy/service-{p}-{c}\n\"\"\"\n",
+                c % 5,
+            );
+        }
+        for env in ["dev", "prod"] {
+            let _ = write!(
+                toml,
+                "\n[[environments]]\nname = \"{env}\"\n\
+                 values = {{ dir = \"~/src/project-{p}\", cluster = \"{env}-cluster\", namespace = \"ns-{p}\" }}\n",
+            );
+        }
+        fs::write(projects_dir.join(format!("project-{p}.toml")), toml).unwrap();
+    }
+}
+
+fn library(c: &mut Criterion) {
+    let home = tempfile::tempdir().unwrap();
+    seed(home.path());
+    let pacs = Pacs::init_at(home.path().to_path_buf()).unwrap();
+    assert_eq!(
+        pacs.projects()
+            .iter()
+            .map(|p| p.commands.len())
+            .sum::<usize>(),
+        PROJECTS * COMMANDS_PER_PROJECT
+    );
+
+    c.bench_function("load", |b| {
+        b.iter(|| Pacs::init_at(black_box(home.path().to_path_buf())).unwrap());
+    });
+    c.bench_function("list", |b| {
+        b.iter(|| pacs.list(black_box(None), None).unwrap());
+    });
+    c.bench_function("list_all_projects", |b| {
+        b.iter(|| {
+            for project in pacs.projects() {
+                black_box(pacs.list(Some(&project.name), None).unwrap());
+            }
+        });
+    });
+    c.bench_function("search", |b| {
+        b.iter(|| pacs.search(black_box("restart service-150")));
+    });
+    c.bench_function("expand", |b| {
+        b.iter(|| {
+            pacs.resolve_command(black_box("cmd-12"), Some("project-150"), Some("prod"))
+                .unwrap()
+        });
+    });
+}
+
+criterion_group!(benches, library);
+criterion_main!(benches);
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use pacs_core::{Pacs, PacsCommand, PacsError};
 
 fn main() -> Result<(), PacsError> {
@@ -15,6 +17,17 @@ fn main() -> Result<(), PacsError> {
             command: "echo Hello World!".into(),
             cwd: None,
             tag: "misc".into(),
+            aliases: Vec::new(),
+            expires: None,
+            review_after: None,
+            author: None,
+            requires: Vec::new(),
+            local_only: false,
+            overrides: BTreeMap::new(),
+            safe: false,
+            confirm: false,
+            locked: false,
+            sandbox: None,
         },
         Some("example"),
     )?;
@@ -25,6 +38,17 @@ fn main() -> Result<(), PacsError> {
             command: "echo Deploy...".into(),
             cwd: None,
             tag: "release".into(),
+            aliases: Vec::new(),
+            expires: None,
+            review_after: None,
+            author: None,
+            requires: Vec::new(),
+            local_only: false,
+            overrides: BTreeMap::new(),
+            safe: false,
+            confirm: false,
+            locked: false,
+            sandbox: None,
         },
         Some("example"),
     )?;
@@ -35,6 +59,17 @@ fn main() -> Result<(), PacsError> {
             command: "echo Release...".into(),
             cwd: None,
             tag: "release".into(),
+            aliases: Vec::new(),
+            expires: None,
+            review_after: None,
+            author: None,
+            requires: Vec::new(),
+            local_only: false,
+            overrides: BTreeMap::new(),
+            safe: false,
+            confirm: false,
+            locked: false,
+            sandbox: None,
         },
         Some("example"),
     )?;
@@ -0,0 +1,111 @@
+//! Parsing of shell alias definitions, for importing them as commands.
+
+/// Parses `alias name=value` definitions as found in bash and zsh rc files or
+/// printed by `alias` and `alias -L`. Values may be quoted like in the shell,
+/// options such as `-g` are skipped, and a later definition of a name replaces
+/// an earlier one. Other lines are ignored.
+#[must_use]
+pub fn parse_aliases(content: &str) -> Vec<(String, String)> {
+    let mut aliases: Vec<(String, String)> = Vec::new();
+    for line in content.lines() {
+        let Some(rest) = line.trim_start().strip_prefix("alias") else {
+            continue;
+        };
+        if !rest.starts_with(char::is_whitespace) {
+            continue;
+        }
+        for word in shell_words(rest) {
+            if word.starts_with('-') {
+                continue;
+            }
+            let Some((name, value)) = word.split_once('=') else {
+                continue;
+            };
+            if name.is_empty() {
+                continue;
+            }
+            match aliases.iter_mut().find(|(n, _)| n == name) {
+                Some(alias) => alias.1 = value.to_string(),
+                None => aliases.push((name.to_string(), value.to_string())),
+            }
+        }
+    }
+    aliases
+}
+
+/// Splits a line into words with quotes removed, up to a comment or `;`.
+fn shell_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            ';' | '&' | '|' => break,
+            '#' if word.is_none() => break,
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.by_ref().take_while(|&c| c != '\''));
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => word.push('\\'),
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_aliases() {
+        let content = r#"
+# aliases
+alias ll='ls -alF'
+  alias gs="git status"  # inline comment
+alias -g G='| grep'
+alias k=kubectl
+alias quote='echo '\''hi'\'''
+alias dq="echo \"\$HOME\""
+alias a='one' b='two'; echo ignored=yes
+alias ll='ls -l'
+aliased=no
+alias ll
+export PATH="$HOME/bin:$PATH"
+"#;
+        assert_eq!(
+            parse_aliases(content),
+            [
+                ("ll", "ls -l"),
+                ("gs", "git status"),
+                ("G", "| grep"),
+                ("k", "kubectl"),
+                ("quote", "echo 'hi'"),
+                ("dq", "echo \"$HOME\""),
+                ("a", "one"),
+                ("b", "two"),
+            ]
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Bundles of whole projects, for moving them to another machine.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{PacsCommand, PacsError, Project, Versioned, check_names_available};
+
+/// Projects with their commands and environments, as written by
+/// `pacs export --all` and read by `pacs import`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Bundle {
+    #[serde(default)]
+    pub projects: Vec<Project>,
+}
+
+impl Bundle {
+    /// Parses a bundle written as TOML or as versioned JSON.
+    pub fn parse(content: &str) -> Result<Self, PacsError> {
+        if content.trim_start().starts_with('{') {
+            Ok(Versioned::<Self>::from_json(content)?.data)
+        } else {
+            Ok(toml::from_str(content)?)
+        }
+    }
+
+    pub fn to_toml(&self) -> Result<String, PacsError> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, PacsError> {
+        let mut json = serde_json::to_string_pretty(&Versioned::new(self))?;
+        json.push('\n');
+        Ok(json)
+    }
+}
+
+/// An imported command whose name or aliases are taken by a different local command.
+#[derive(Debug, Clone)]
+pub struct BundleConflict {
+    pub project: String,
+    pub mine: PacsCommand,
+    pub theirs: PacsCommand,
+}
+
+/// How to resolve a [`BundleConflict`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    KeepMine,
+    /// Replace the local command by the imported one.
+    TakeTheirs,
+    /// Add the imported command under a new name.
+    Rename(String),
+}
+
+/// Outcome of importing a bundle.
+#[derive(Debug, Default)]
+pub struct BundleImport {
+    /// Projects that did not exist before.
+    pub created: Vec<String>,
+    /// Existing projects that were merged into or replaced.
+    pub updated: Vec<String>,
+    /// Commands added to existing projects.
+    pub commands: usize,
+}
+
+/// Returns the commands of `theirs` that clash with a different command of `mine`.
+/// Commands with the same name and body are not conflicts.
+pub(crate) fn project_conflicts(mine: &Project, theirs: &Project) -> Vec<BundleConflict> {
+    theirs
+        .commands
+        .iter()
+        .filter(|cmd| !has_identical(mine, cmd))
+        .filter_map(|cmd| {
+            let local = mine.commands.iter().find(|c| clashes(c, cmd))?;
+            Some(BundleConflict {
+                project: mine.name.clone(),
+                mine: local.clone(),
+                theirs: cmd.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Merges `theirs` into `mine`. Missing commands, environments and values are
+/// added, conflicts are resolved by `resolutions` keyed by the imported name,
+/// and everything else of `mine` is kept. Returns the number of added commands.
+pub(crate) fn merge_project(
+    mine: &mut Project,
+    theirs: Project,
+    resolutions: &BTreeMap<String, Resolution>,
+) -> Result<usize, PacsError> {
+    let mut added = 0;
+    for mut cmd in theirs.commands {
+        if has_identical(mine, &cmd) {
+            continue;
+        }
+        if mine.commands.iter().any(|c| clashes(c, &cmd)) {
+            match resolutions.get(&cmd.name) {
+                None | Some(Resolution::KeepMine) => continue,
+                Some(Resolution::TakeTheirs) => mine.commands.retain(|c| !clashes(c, &cmd)),
+                Some(Resolution::Rename(name)) => {
+                    cmd.name.clone_from(name);
+                    cmd.aliases
+                        .retain(|alias| !mine.commands.iter().any(|c| c.matches(alias)));
+                    let names: Vec<&str> = std::iter::once(&cmd.name)
+                        .chain(&cmd.aliases)
+                        .map(String::as_str)
+                        .collect();
+                    check_names_available(mine, &names, None)?;
+                }
+            }
+        }
+        mine.commands.push(cmd);
+        added += 1;
+    }
+
+    for env in theirs.environments {
+        match mine.environments.iter_mut().find(|e| e.name == env.name) {
+            Some(local) => {
+                for (key, value) in env.values {
+                    local.values.entry(key).or_insert(value);
+                }
+                if local.kube_context.is_none() {
+                    local.kube_context = env.kube_context;
+                }
+            }
+            None => mine.environments.push(env),
+        }
+    }
+    if mine.path.is_none() {
+        mine.path = theirs.path;
+    }
+    if mine.active_environment.is_none() {
+        mine.active_environment = theirs.active_environment;
+    }
+    if mine.notes.is_none() {
+        mine.notes = theirs.notes;
+    }
+    Ok(added)
+}
+
+fn has_identical(project: &Project, cmd: &PacsCommand) -> bool {
+    project
+        .commands
+        .iter()
+        .any(|c| c.name == cmd.name && c.command.trim() == cmd.command.trim())
+}
+
+/// True if `cmd` answers to one of the names of `other`.
+fn clashes(cmd: &PacsCommand, other: &PacsCommand) -> bool {
+    std::iter::once(&other.name)
+        .chain(&other.aliases)
+        .any(|name| cmd.matches(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(toml: &str) -> Project {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_merge_project() {
+        let theirs = || {
+            project(
+                r#"
+                name = "api"
+                path = "~/src/api"
+                active_environment = "dev"
+
+                [[commands]]
+                name = "build"
+                command = "cargo build\n"
+
+                [[commands]]
+                name = "deploy"
+                command = "kubectl apply -f theirs.yaml"
+
+                [[commands]]
+                name = "logs"
+                command = "kubectl logs -f api"
+                aliases = ["l"]
+
+                [[environments]]
+                name = "dev"
+                values = { host = "theirs", port = "8080" }
+
+                [[environments]]
+                name = "prod"
+                "#,
+            )
+        };
+        let mine = || {
+            project(
+                r#"
+                name = "api"
+
+                [[commands]]
+                name = "build"
+                command = "cargo build"
+
+                [[commands]]
+                name = "deploy"
+                command = "kubectl apply -f mine.yaml"
+
+                [[environments]]
+                name = "dev"
+                values = { host = "mine" }
+                "#,
+            )
+        };
+
+        let conflicts = project_conflicts(&mine(), &theirs());
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].mine.command, "kubectl apply -f mine.yaml");
+        assert_eq!(conflicts[0].theirs.command, "kubectl apply -f theirs.yaml");
+
+        let mut merged = mine();
+        assert_eq!(
+            merge_project(&mut merged, theirs(), &BTreeMap::new()).unwrap(),
+            1
+        );
+        let names: Vec<&str> = merged.commands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["build", "deploy", "logs"]);
+        assert_eq!(merged.commands[1].command, "kubectl apply -f mine.yaml");
+        assert_eq!(merged.environments[0].values["host"], "mine");
+        assert_eq!(merged.environments[0].values["port"], "8080");
+        assert_eq!(merged.environments[1].name, "prod");
+        assert_eq!(merged.path.as_deref(), Some("~/src/api"));
+        assert_eq!(merged.active_environment.as_deref(), Some("dev"));
+
+        let take = BTreeMap::from([("deploy".to_string(), Resolution::TakeTheirs)]);
+        let mut merged = mine();
+        merge_project(&mut merged, theirs(), &take).unwrap();
+        let deploy = merged.commands.iter().find(|c| c.name == "deploy").unwrap();
+        assert_eq!(deploy.command, "kubectl apply -f theirs.yaml");
+
+        let rename = BTreeMap::from([("deploy".into(), Resolution::Rename("deploy2".into()))]);
+        let mut merged = mine();
+        assert_eq!(merge_project(&mut merged, theirs(), &rename).unwrap(), 2);
+        assert!(merged.commands.iter().any(|c| c.name == "deploy"));
+        assert!(merged.commands.iter().any(|c| c.name == "deploy2"));
+
+        let taken = BTreeMap::from([("deploy".into(), Resolution::Rename("build".into()))]);
+        assert!(matches!(
+            merge_project(&mut mine(), theirs(), &taken),
+            Err(PacsError::CommandExists(_))
+        ));
+    }
+
+    #[test]
+    fn test_bundle_formats() {
+        let bundle = Bundle {
+            projects: vec![project(
+                "name = \"api\"\n[[commands]]\nname = \"b\"\ncommand = \"x\"",
+            )],
+        };
+        for content in [bundle.to_toml().unwrap(), bundle.to_json().unwrap()] {
+            let parsed = Bundle::parse(&content).unwrap();
+            assert_eq!(parsed.projects[0].name, "api");
+            assert_eq!(parsed.projects[0].commands[0].command.trim_end(), "x");
+        }
+        assert!(
+            bundle
+                .to_json()
+                .unwrap()
+                .starts_with("{\n  \"version\": 1,")
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Cleaned up copies of command bodies for `pacs show`, `pacs copy` and
+//! `pacs export`. The stored body is never changed.
+
+use crate::highlight::{TokenKind, tokenize_shell_line};
+
+/// How to clean up a command body.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BodyCleanup {
+    /// Removes `#` comments, whole lines and at the end of lines.
+    pub strip_comments: bool,
+    /// Removes common indentation, trailing whitespace and repeated blank
+    /// lines, and indents continuation lines by two spaces.
+    pub normalize: bool,
+}
+
+impl BodyCleanup {
+    /// Returns true if the body is left as it is.
+    #[must_use]
+    pub fn is_noop(self) -> bool {
+        !self.strip_comments && !self.normalize
+    }
+
+    /// Returns the cleaned up body.
+    #[must_use]
+    pub fn apply(self, body: &str) -> String {
+        let mut lines: Vec<String> = body.lines().map(str::to_string).collect();
+        if self.strip_comments {
+            lines = lines
+                .iter()
+                .filter_map(|line| strip_comment(line))
+                .collect();
+        }
+        if self.normalize {
+            lines = normalize(&lines);
+        }
+        let mut out = lines.join("\n");
+        if body.ends_with('\n') && !out.is_empty() {
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Removes a comment from a line. Returns `None` if nothing else is left.
+fn strip_comment(line: &str) -> Option<String> {
+    let tokens = tokenize_shell_line(line);
+    let mut kept = String::with_capacity(line.len());
+    for (kind, text) in tokens {
+        // `#` only starts a comment at the beginning of a word
+        let starts_word = kept.is_empty() || kept.ends_with(char::is_whitespace);
+        if kind == TokenKind::Comment && (starts_word || kept.ends_with([';', '&', '|'])) {
+            if kept.trim().is_empty() {
+                return None;
+            }
+            return Some(kept.trim_end().to_string());
+        }
+        kept.push_str(text);
+    }
+    Some(kept)
+}
+
+fn normalize(lines: &[String]) -> Vec<String> {
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+    let mut statement_indent = None;
+    for line in lines {
+        let line = line.trim_end();
+        if line.is_empty() {
+            if out.last().is_some_and(|l| !l.is_empty()) {
+                out.push(String::new());
+            }
+            statement_indent = None;
+            continue;
+        }
+        let line = line.get(indent..).unwrap_or_else(|| line.trim_start());
+        let normalized = match statement_indent {
+            Some(depth) => format!("{}  {}", " ".repeat(depth), line.trim_start()),
+            None => line.to_string(),
+        };
+        let depth = normalized.len() - normalized.trim_start().len();
+        statement_indent = match statement_indent {
+            _ if !normalized.ends_with('\\') => None,
+            Some(start) => Some(start),
+            None => Some(depth),
+        };
+        out.push(normalized);
+    }
+    while out.last().is_some_and(String::is_empty) {
+        out.pop();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_comments() {
+        let cleanup = BodyCleanup {
+            strip_comments: true,
+            ..BodyCleanup::default()
+        };
+        let body = "# build\ncargo build # release?\necho 'a # b' x#y\n  # done\n";
+        assert_eq!(cleanup.apply(body), "cargo build\necho 'a # b' x#y\n");
+        assert_eq!(cleanup.apply("echo $#"), "echo $#");
+    }
+
+    #[test]
+    fn test_normalize() {
+        let cleanup = BodyCleanup {
+            normalize: true,
+            ..BodyCleanup::default()
+        };
+        let body = "    docker build \\\n            -t app \\\n     .   \n\n\n    if true; then\n      echo ok\n    fi\n\n";
+        assert_eq!(
+            cleanup.apply(body),
+            "docker build \\\n  -t app \\\n  .\n\nif true; then\n  echo ok\nfi\n"
+        );
+        assert!(cleanup.apply("").is_empty());
+        assert!(BodyCleanup::default().is_noop());
+    }
+}
@@ -0,0 +1,162 @@
+//! Names offered by shell completion, cached in `completions.toml`.
+//!
+//! Every TAB press starts a new process, and parsing every project file there
+//! gets slow with hundreds of projects. The cache is rewritten whenever pacs
+//! saves or deletes a project. It records the modification time of the
+//! projects directory and its files, and is ignored once that changes, e.g.
+//! after editing a project by hand, or if it was written by another version.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{PacsError, Project, write_atomic};
+
+/// Format version of `completions.toml`, bumped on incompatible changes.
+const CACHE_VERSION: u32 = 1;
+
+/// Whether the completion cache can be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheState {
+    Fresh,
+    /// A project changed since it was written, or it has another version.
+    Outdated,
+    Missing,
+}
+
+/// The completion cache as shown by `pacs cache status`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheStatus {
+    pub path: PathBuf,
+    pub state: CacheState,
+    /// Projects and commands in a fresh cache, zero otherwise.
+    pub projects: usize,
+    pub commands: usize,
+}
+
+/// Completable names of one project.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectNames {
+    #[serde(default)]
+    pub commands: Vec<String>,
+    /// Sorted and without duplicates.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub environments: Vec<String>,
+}
+
+/// Completable names of all projects.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompletionCache {
+    #[serde(default)]
+    version: u32,
+    /// Latest modification of the projects directory in nanoseconds since
+    /// the Unix epoch when the cache was written.
+    #[serde(default)]
+    modified: u64,
+    #[serde(default)]
+    pub projects: BTreeMap<String, ProjectNames>,
+    /// Active project from `PACS_PROJECT` or config.toml, not stored.
+    #[serde(skip)]
+    pub active_project: Option<String>,
+    /// Projects switched to, most recent first, from config.toml. Not stored.
+    #[serde(skip)]
+    pub recent_projects: Vec<String>,
+}
+
+impl CompletionCache {
+    pub(crate) fn new<'a>(
+        projects: impl IntoIterator<Item = &'a Project>,
+        projects_dir: &Path,
+    ) -> Self {
+        let projects = projects
+            .into_iter()
+            .filter(|project| !project.archived)
+            .map(|project| {
+                let mut tags: Vec<String> = project
+                    .commands
+                    .iter()
+                    .map(|c| c.tag.clone())
+                    .filter(|t| !t.is_empty())
+                    .collect();
+                tags.sort();
+                tags.dedup();
+                let names = ProjectNames {
+                    commands: project.commands.iter().map(|c| c.name.clone()).collect(),
+                    tags,
+                    environments: project
+                        .environments
+                        .iter()
+                        .map(|e| e.name.clone())
+                        .collect(),
+                };
+                (project.name.clone(), names)
+            })
+            .collect();
+        Self {
+            version: CACHE_VERSION,
+            modified: newest_file(projects_dir),
+            projects,
+            active_project: None,
+            recent_projects: Vec::new(),
+        }
+    }
+
+    /// Reads the cache if it is fresh for `projects_dir`, or returns why not.
+    pub(crate) fn load(path: &Path, projects_dir: &Path) -> Result<Self, CacheState> {
+        let content = fs::read_to_string(path).map_err(|_| CacheState::Missing)?;
+        let cache: Self = toml::from_str(&content).map_err(|_| CacheState::Outdated)?;
+        if cache.version != CACHE_VERSION || cache.modified != newest_file(projects_dir) {
+            return Err(CacheState::Outdated);
+        }
+        Ok(cache)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), PacsError> {
+        write_atomic(path, &toml::to_string(self)?)
+    }
+
+    /// Returns all project names, the most recently switched to first.
+    #[must_use]
+    pub fn projects(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.projects.keys().cloned().collect();
+        names.sort_by_key(|name| {
+            self.recent_projects
+                .iter()
+                .position(|r| r.eq_ignore_ascii_case(name))
+                .unwrap_or(usize::MAX)
+        });
+        names
+    }
+
+    /// Returns the names of `project`, or of the active project if `None`.
+    #[must_use]
+    pub fn project(&self, project: Option<&str>) -> Option<&ProjectNames> {
+        let name = project.or(self.active_project.as_deref())?.to_lowercase();
+        self.projects
+            .iter()
+            .find(|(n, _)| n.to_lowercase() == name)
+            .map(|(_, names)| names)
+    }
+}
+
+/// Returns the latest modification time of `dir` and the files in it, in
+/// nanoseconds since the Unix epoch.
+fn newest_file(dir: &Path) -> u64 {
+    let files = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|e| e.path());
+    std::iter::once(dir.to_path_buf())
+        .chain(files)
+        .filter_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .max()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .and_then(|age| u64::try_from(age.as_nanos()).ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,125 @@
+//! Detection of command names that are claimed more than once.
+
+#[cfg(feature = "fs-store")]
+use std::collections::BTreeMap;
+
+use crate::CommandRef;
+#[cfg(feature = "fs-store")]
+use crate::Project;
+
+/// A command that answers to a conflicting name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Claim {
+    pub command: CommandRef,
+    /// True if the name is an alias of the command rather than its name.
+    pub alias: bool,
+}
+
+/// A name that more than one command answers to, within a project or
+/// across projects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameConflict {
+    pub name: String,
+    /// The commands answering to the name, in project and file order.
+    pub claims: Vec<Claim>,
+}
+
+impl NameConflict {
+    /// Returns the command the name resolves to in `project`. Lookups take
+    /// the first command in the project file that has the name or alias.
+    #[must_use]
+    pub fn resolve(&self, project: &str) -> Option<&CommandRef> {
+        self.claims
+            .iter()
+            .map(|c| &c.command)
+            .find(|c| c.project == project)
+    }
+
+    /// Returns true if the name is claimed more than once in `project`, so
+    /// that some of its commands cannot be reached by this name.
+    #[must_use]
+    pub fn is_shadowed_in(&self, project: &str) -> bool {
+        self.claims
+            .iter()
+            .filter(|c| c.command.project == project)
+            .count()
+            > 1
+    }
+}
+
+/// Returns all names claimed by more than one command, sorted by name.
+#[cfg(feature = "fs-store")]
+pub(crate) fn find_conflicts(projects: &[Project]) -> Vec<NameConflict> {
+    let mut claims: BTreeMap<&str, Vec<Claim>> = BTreeMap::new();
+
+    for project in projects {
+        for cmd in &project.commands {
+            let names =
+                std::iter::once((&cmd.name, false)).chain(cmd.aliases.iter().map(|a| (a, true)));
+            for (name, alias) in names {
+                let entry = claims.entry(name).or_default();
+                let command = CommandRef {
+                    project: project.name.clone(),
+                    name: cmd.name.clone(),
+                };
+                // A command listing its own name as alias is not a conflict
+                if alias && entry.iter().any(|c| c.command == command) {
+                    continue;
+                }
+                entry.push(Claim { command, alias });
+            }
+        }
+    }
+
+    claims
+        .into_iter()
+        .filter(|(_, claims)| claims.len() > 1)
+        .map(|(name, claims)| NameConflict {
+            name: name.to_string(),
+            claims,
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "fs-store"))]
+mod tests {
+    use super::*;
+    use crate::PacsCommand;
+
+    fn command(name: &str, aliases: &[&str]) -> PacsCommand {
+        PacsCommand {
+            name: name.into(),
+            command: "true".into(),
+            aliases: aliases.iter().map(|a| (*a).to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let projects = [
+            Project {
+                name: "api".into(),
+                commands: vec![command("build", &[]), command("test", &["t"])],
+                ..Project::default()
+            },
+            Project {
+                name: "web".into(),
+                commands: vec![command("compile", &["build"]), command("build", &[])],
+                ..Project::default()
+            },
+        ];
+
+        let conflicts = find_conflicts(&projects);
+        assert_eq!(conflicts.len(), 1);
+        let build = &conflicts[0];
+        assert_eq!(build.name, "build");
+        assert_eq!(build.claims.len(), 3);
+        assert!(build.claims[1].alias);
+
+        assert_eq!(build.resolve("api").unwrap().name, "build");
+        assert_eq!(build.resolve("web").unwrap().name, "compile");
+        assert!(build.is_shadowed_in("web"));
+        assert!(!build.is_shadowed_in("api"));
+    }
+}
@@ -0,0 +1,203 @@
+//! Detection of commands with identical or near-identical bodies.
+
+#[cfg(feature = "fs-store")]
+use crate::Project;
+
+/// A command in a project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandRef {
+    pub project: String,
+    pub name: String,
+}
+
+/// Commands whose bodies are the same, or similar enough to be merged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    /// The commands in the group, in project order.
+    pub commands: Vec<CommandRef>,
+    /// True if all bodies are equal after normalizing whitespace.
+    pub identical: bool,
+}
+
+/// Collapses all runs of whitespace, including newlines, into single spaces.
+#[must_use]
+pub fn normalize_body(body: &str) -> String {
+    body.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Returns the similarity of two strings between 0 and 1, based on their
+/// Levenshtein distance relative to the longer one.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    1.0 - row[b.len()] as f64 / longest as f64
+}
+
+/// Groups commands of all projects whose normalized bodies have at least the
+/// given similarity to the first command of the group.
+#[cfg(feature = "fs-store")]
+pub(crate) fn find_duplicates(projects: &[Project], threshold: f64) -> Vec<DuplicateGroup> {
+    let mut groups: Vec<(String, DuplicateGroup)> = Vec::new();
+
+    for project in projects {
+        for cmd in &project.commands {
+            let body = normalize_body(&cmd.command);
+            if body.is_empty() {
+                continue;
+            }
+            let command = CommandRef {
+                project: project.name.clone(),
+                name: cmd.name.clone(),
+            };
+
+            let group = groups.iter_mut().find(|(first, _)| {
+                // The length difference bounds the similarity from above
+                #[allow(clippy::cast_precision_loss)]
+                let bound = first.len().min(body.len()) as f64 / first.len().max(body.len()) as f64;
+                bound >= threshold && similarity(first, &body) >= threshold
+            });
+            match group {
+                Some((first, group)) => {
+                    group.identical &= *first == body;
+                    group.commands.push(command);
+                }
+                None => groups.push((
+                    body,
+                    DuplicateGroup {
+                        commands: vec![command],
+                        identical: true,
+                    },
+                )),
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(_, group)| group)
+        .filter(|group| group.commands.len() > 1)
+        .collect()
+}
+
+/// Returns the commands of all projects whose normalized body has at least
+/// the given similarity to `body`, the most similar first.
+#[cfg(feature = "fs-store")]
+pub(crate) fn find_similar(
+    projects: &[Project],
+    body: &str,
+    threshold: f64,
+) -> Vec<(CommandRef, f64)> {
+    let body = normalize_body(body);
+    if body.is_empty() {
+        return Vec::new();
+    }
+    let mut found: Vec<(CommandRef, f64)> = projects
+        .iter()
+        .flat_map(|project| project.commands.iter().map(move |cmd| (project, cmd)))
+        .filter_map(|(project, cmd)| {
+            let other = normalize_body(&cmd.command);
+            #[allow(clippy::cast_precision_loss)]
+            let bound = body.len().min(other.len()) as f64 / body.len().max(other.len()) as f64;
+            if bound < threshold {
+                return None;
+            }
+            let score = similarity(&body, &other);
+            let command = CommandRef {
+                project: project.name.clone(),
+                name: cmd.name.clone(),
+            };
+            (score >= threshold).then_some((command, score))
+        })
+        .collect();
+    found.sort_by(|a, b| b.1.total_cmp(&a.1));
+    found
+}
+
+#[cfg(all(test, feature = "fs-store"))]
+mod tests {
+
+    use super::*;
+    use crate::PacsCommand;
+
+    fn project(name: &str, commands: &[(&str, &str)]) -> Project {
+        Project {
+            name: name.into(),
+            commands: commands
+                .iter()
+                .map(|(name, command)| PacsCommand {
+                    name: (*name).into(),
+                    command: (*command).into(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Project::default()
+        }
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let projects = [
+            project(
+                "api",
+                &[
+                    ("build", "cargo build --release"),
+                    ("pods", "kubectl get pods"),
+                ],
+            ),
+            project(
+                "web",
+                &[
+                    ("build", "cargo   build\n  --release\n"),
+                    ("pods-wide", "kubectl get pods -o wide"),
+                    ("deploy", "make deploy"),
+                ],
+            ),
+        ];
+
+        let groups = find_duplicates(&projects, 1.0);
+        assert_eq!(groups.len(), 1);
+        assert!(groups[0].identical);
+        assert_eq!(groups[0].commands[1].project, "web");
+
+        let groups = find_duplicates(&projects, 0.6);
+        assert_eq!(groups.len(), 2);
+        assert!(!groups[1].identical);
+        assert_eq!(groups[1].commands[1].name, "pods-wide");
+
+        assert!((similarity("kitten", "sitting") - (1.0 - 3.0 / 7.0)).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_find_similar() {
+        let projects = [
+            project("api", &[("pods", "kubectl get pods")]),
+            project(
+                "web",
+                &[("pods", "kubectl  get pod"), ("build", "cargo build")],
+            ),
+        ];
+        let found = find_similar(&projects, "kubectl get pods\n", 0.9);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].0.project, "api");
+        assert!((found[0].1 - 1.0).abs() < f64::EPSILON);
+        assert!(find_similar(&projects, "make deploy", 0.9).is_empty());
+        assert!(find_similar(&projects, "  ", 0.0).is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+//! Line diffs between two versions of a command body.
+
+/// A line of a diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// A run of changed lines with surrounding context, as in a unified diff.
+/// Line numbers start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk<'a> {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine<'a>>,
+}
+
+impl Hunk<'_> {
+    /// Returns the `@@ -1,3 +1,4 @@` header of the hunk.
+    #[must_use]
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_len, self.new_start, self.new_len
+        )
+    }
+}
+
+/// Returns a shortest line diff between `old` and `new`, based on their
+/// longest common subsequence.
+#[must_use]
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // common[i][j] is the length of the common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(DiffLine::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// Groups the changes between `old` and `new` into hunks with up to
+/// `context` unchanged lines around them. Empty if nothing changed.
+#[must_use]
+pub fn unified_diff<'a>(old: &'a str, new: &'a str, context: usize) -> Vec<Hunk<'a>> {
+    let lines = diff_lines(old, new);
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, DiffLine::Same(_)))
+        .map(|(i, _)| i)
+        .collect();
+
+    // Merge changes whose context overlaps into ranges of `lines`
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(lines.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            // Line numbers of the first line of the hunk in both versions
+            let (mut old_start, mut new_start) = (1, 1);
+            for line in &lines[..start] {
+                match line {
+                    DiffLine::Same(_) => {
+                        old_start += 1;
+                        new_start += 1;
+                    }
+                    DiffLine::Removed(_) => old_start += 1,
+                    DiffLine::Added(_) => new_start += 1,
+                }
+            }
+            let hunk = &lines[start..end];
+            let old_len = hunk
+                .iter()
+                .filter(|l| !matches!(l, DiffLine::Added(_)))
+                .count();
+            let new_len = hunk
+                .iter()
+                .filter(|l| !matches!(l, DiffLine::Removed(_)))
+                .count();
+            Hunk {
+                // Like diff, an empty side starts at the line before it
+                old_start: if old_len == 0 {
+                    old_start - 1
+                } else {
+                    old_start
+                },
+                old_len,
+                new_start: if new_len == 0 {
+                    new_start - 1
+                } else {
+                    new_start
+                },
+                new_len,
+                lines: hunk.to_vec(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(
+            diff_lines("a\nb\nc", "a\nc\nd"),
+            [
+                DiffLine::Same("a"),
+                DiffLine::Removed("b"),
+                DiffLine::Same("c"),
+                DiffLine::Added("d"),
+            ]
+        );
+        assert!(unified_diff("a\nb", "a\nb\n", 3).is_empty());
+
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11";
+        let hunks = unified_diff(old, new, 2);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].header(), "@@ -1,5 +1,5 @@");
+        assert_eq!(hunks[1].header(), "@@ -9,2 +9,3 @@");
+        assert_eq!(hunks[1].lines.last(), Some(&DiffLine::Added("11")));
+
+        assert_eq!(
+            unified_diff("", "echo hi", 3)[0].header(),
+            "@@ -0,0 +1,1 @@"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! Export of environment values as a direnv `.envrc` block, and parsing of
+//! `.env` files.
+//!
+//! pacs only owns the lines between its start and end markers, so the rest of
+//! an existing `.envrc` is left untouched when the block is rewritten.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+#[cfg(feature = "fs-store")]
+use std::{fs, path::Path};
+
+#[cfg(feature = "fs-store")]
+use crate::PacsError;
+use crate::{secrets, shell_quote};
+
+const START_MARKER: &str = "# >>> pacs >>>";
+const END_MARKER: &str = "# <<< pacs <<<";
+
+/// Converts a placeholder key like `kube-context` into a variable name like `KUBE_CONTEXT`.
+#[must_use]
+pub fn variable_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Renders the `.envrc` block exporting the values of an environment.
+#[must_use]
+pub fn envrc_block(project: &str, environment: &str, values: &BTreeMap<String, String>) -> String {
+    let mut out = format!("{START_MARKER}\n# environment '{environment}' of project '{project}'\n");
+    for (key, value) in values {
+        // Secrets are resolved when direnv loads the file, never written to it
+        let value = secrets::shell_expression(value).unwrap_or_else(|| shell_quote(value));
+        let _ = writeln!(out, "export {}={value}", variable_name(key));
+    }
+    out.push_str(END_MARKER);
+    out.push('\n');
+    out
+}
+
+/// Parses `KEY=value` lines of a `.env` file. Blank lines, comments and an
+/// `export ` prefix are skipped, and values may be single or double quoted.
+#[must_use]
+pub fn parse_env_file(content: &str) -> BTreeMap<String, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = [('"', '"'), ('\'', '\'')]
+                .iter()
+                .find_map(|(open, close)| value.strip_prefix(*open)?.strip_suffix(*close))
+                .unwrap_or(value);
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Writes `block` to the file at `path`, replacing an earlier pacs block if present.
+#[cfg(feature = "fs-store")]
+pub(crate) fn write_block(path: &Path, block: &str) -> Result<(), PacsError> {
+    let existing = if path.exists() {
+        fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+
+    let content = match (existing.find(START_MARKER), existing.find(END_MARKER)) {
+        (Some(start), Some(end)) if start < end => {
+            let end = end + END_MARKER.len();
+            let end = end + usize::from(existing[end..].starts_with('\n'));
+            format!("{}{block}{}", &existing[..start], &existing[end..])
+        }
+        _ if existing.is_empty() => block.to_string(),
+        _ if existing.ends_with('\n') => format!("{existing}\n{block}"),
+        _ => format!("{existing}\n\n{block}"),
+    };
+
+    fs::write(path, content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "fs-store")]
+    #[test]
+    fn test_write_block() {
+        let path = std::env::temp_dir().join(format!("pacs_envrc_{}", std::process::id()));
+        fs::write(&path, "use nix\n").unwrap();
+
+        let values = BTreeMap::from([("kube-context".to_string(), "it's dev".to_string())]);
+        write_block(&path, &envrc_block("api", "dev", &values)).unwrap();
+        let values = BTreeMap::from([("kube-context".to_string(), "prod".to_string())]);
+        write_block(&path, &envrc_block("api", "prod", &values)).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "use nix\n\n# >>> pacs >>>\n# environment 'prod' of project 'api'\nexport KUBE_CONTEXT='prod'\n# <<< pacs <<<\n"
+        );
+        assert_eq!(variable_name("1st.key"), "_1ST_KEY");
+
+        let values = BTreeMap::from([("token".to_string(), "op://ops/api/token".to_string())]);
+        assert!(
+            envrc_block("api", "prod", &values)
+                .contains("export TOKEN=\"$(op read --no-newline 'op://ops/api/token')\"\n")
+        );
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let values = parse_env_file(
+            "# prod overrides\nexport NAMESPACE=prod\n\nTAG = \"v1.2 rc\"\nURL='http://x?a=b'\nbroken\n",
+        );
+        assert_eq!(values.len(), 3);
+        assert_eq!(values["NAMESPACE"], "prod");
+        assert_eq!(values["TAG"], "v1.2 rc");
+        assert_eq!(values["URL"], "http://x?a=b");
+    }
+}
@@ -0,0 +1,181 @@
+//! Expiry and review dates of commands.
+//!
+//! Dates are stored as `YYYY-MM-DD` strings in UTC and compared as days
+//! since the Unix epoch.
+
+use std::time::Duration;
+
+use crate::{PacsCommand, PacsError, history::unix_now};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Why a command needs attention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpiryStatus {
+    /// The `expires` date has passed.
+    Expired,
+    /// The `review_after` date has passed.
+    ReviewDue,
+}
+
+impl ExpiryStatus {
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Expired => "expired",
+            Self::ReviewDue => "review due",
+        }
+    }
+}
+
+impl PacsCommand {
+    /// Returns whether the command has expired or is due for review on `today`,
+    /// given in days since the Unix epoch. Invalid dates are ignored.
+    #[must_use]
+    pub fn expiry_status(&self, today: i64) -> Option<ExpiryStatus> {
+        let passed = |date: &Option<String>| {
+            date.as_deref()
+                .and_then(parse_date)
+                .is_some_and(|day| day <= today)
+        };
+        if passed(&self.expires) {
+            Some(ExpiryStatus::Expired)
+        } else if passed(&self.review_after) {
+            Some(ExpiryStatus::ReviewDue)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parses an interval like `48h`, `90d` or `12w`.
+#[must_use]
+pub fn parse_interval(s: &str) -> Option<Duration> {
+    let (i, unit) = s.char_indices().last()?;
+    let count: u64 = s[..i].parse().ok()?;
+    let hours = match unit {
+        'h' => count,
+        'd' => count.checked_mul(24)?,
+        'w' => count.checked_mul(24 * 7)?,
+        _ => return None,
+    };
+    Some(Duration::from_hours(hours))
+}
+
+/// Returns today in days since the Unix epoch.
+#[must_use]
+pub fn today() -> i64 {
+    i64::try_from(unix_now() / SECONDS_PER_DAY).unwrap_or_default()
+}
+
+/// Turns a `YYYY-MM-DD` date or an interval from today like `30d` into a date.
+pub fn resolve_date(s: &str) -> Result<String, PacsError> {
+    if parse_date(s).is_some() {
+        return Ok(s.to_string());
+    }
+    let interval = parse_interval(s).ok_or_else(|| PacsError::InvalidDate(s.to_string()))?;
+    let days = i64::try_from(interval.as_secs().div_ceil(SECONDS_PER_DAY))
+        .map_err(|_| PacsError::InvalidDate(s.to_string()))?;
+    Ok(format_date(today() + days))
+}
+
+/// Parses a `YYYY-MM-DD` date into days since the Unix epoch.
+#[must_use]
+pub fn parse_date(s: &str) -> Option<i64> {
+    let mut parts = s.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+
+    // Howard Hinnant's days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+/// Formats days since the Unix epoch as a `YYYY-MM-DD` date.
+#[must_use]
+pub fn format_date(days: i64) -> String {
+    // Howard Hinnant's civil_from_days
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2000-03-01"), Some(11_017));
+        assert_eq!(parse_date("2024-02-30"), None);
+        assert_eq!(parse_date("next week"), None);
+        for days in [0, 11_016, 11_017, 20_000, -1] {
+            assert_eq!(parse_date(&format_date(days)), Some(days));
+        }
+
+        assert_eq!(
+            parse_interval("12w"),
+            Some(Duration::from_hours(12 * 7 * 24))
+        );
+        assert_eq!(parse_interval("3m"), None);
+        assert_eq!(parse_interval("9é"), None);
+        assert_eq!(parse_interval("é"), None);
+        assert_eq!(resolve_date("0d").unwrap(), format_date(today()));
+        assert!(resolve_date("soon").is_err());
+    }
+
+    #[test]
+    fn test_expiry_status() {
+        let mut cmd = PacsCommand {
+            name: "hotfix".into(),
+            command: "kubectl rollout undo deployment/api".into(),
+            expires: Some("2024-06-01".into()),
+            review_after: Some("2024-03-01".into()),
+            ..Default::default()
+        };
+        let day = |s| parse_date(s).unwrap();
+
+        assert_eq!(cmd.expiry_status(day("2024-02-29")), None);
+        assert_eq!(
+            cmd.expiry_status(day("2024-03-01")),
+            Some(ExpiryStatus::ReviewDue)
+        );
+        assert_eq!(
+            cmd.expiry_status(day("2024-06-01")),
+            Some(ExpiryStatus::Expired)
+        );
+
+        cmd.expires = Some("invalid".into());
+        assert_eq!(
+            cmd.expiry_status(day("2025-01-01")),
+            Some(ExpiryStatus::ReviewDue)
+        );
+    }
+}
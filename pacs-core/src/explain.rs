@@ -0,0 +1,579 @@
+//! Annotating command bodies with what their programs, subcommands and flags
+//! do, from a built-in table of common tools.
+
+use crate::highlight::{TokenKind, tokenize_shell_line};
+
+/// A program pipes and command lists start, with its common subcommands and flags.
+struct Tool {
+    name: &'static str,
+    summary: &'static str,
+    subcommands: &'static [(&'static str, &'static str)],
+    flags: &'static [(&'static str, &'static str)],
+}
+
+const fn tool(
+    name: &'static str,
+    summary: &'static str,
+    subcommands: &'static [(&'static str, &'static str)],
+    flags: &'static [(&'static str, &'static str)],
+) -> Tool {
+    Tool {
+        name,
+        summary,
+        subcommands,
+        flags,
+    }
+}
+
+const TOOLS: &[Tool] = &[
+    tool(
+        "awk",
+        "pattern scanning and processing",
+        &[],
+        &[("-F", "field separator"), ("-v", "set a variable")],
+    ),
+    tool(
+        "cargo",
+        "Rust package manager",
+        &[
+            ("build", "compile the package"),
+            ("run", "build and run a binary"),
+            ("test", "run the tests"),
+            ("check", "check for errors without building"),
+            ("clippy", "run the linter"),
+            ("fmt", "format the code"),
+            ("install", "install a binary"),
+            ("publish", "upload the package to crates.io"),
+        ],
+        &[
+            ("--release", "optimized build"),
+            ("--workspace", "all packages of the workspace"),
+            ("--all-targets", "also tests, benches and examples"),
+            ("-p", "package to act on"),
+            ("--package", "package to act on"),
+            ("--features", "features to enable"),
+        ],
+    ),
+    tool("cat", "print files", &[], &[("-n", "number lines")]),
+    tool("cd", "change the working directory", &[], &[]),
+    tool(
+        "cp",
+        "copy files",
+        &[],
+        &[
+            ("-r", "copy directories recursively"),
+            ("-a", "keep attributes and copy recursively"),
+            ("-f", "overwrite without asking"),
+        ],
+    ),
+    tool(
+        "curl",
+        "transfer data from or to a URL",
+        &[],
+        &[
+            ("-X", "request method"),
+            ("-H", "extra header"),
+            ("-d", "request body"),
+            ("-s", "no progress output"),
+            ("-S", "show errors despite -s"),
+            ("-f", "fail on HTTP errors"),
+            ("-L", "follow redirects"),
+            ("-o", "write the output to a file"),
+            ("-O", "save under the remote file name"),
+            ("-u", "user and password"),
+            ("-k", "skip TLS verification"),
+            ("-v", "verbose"),
+            ("--data", "request body"),
+            ("--header", "extra header"),
+        ],
+    ),
+    tool(
+        "docker",
+        "container engine",
+        &[
+            ("build", "build an image"),
+            ("run", "run a container"),
+            ("exec", "run a command in a running container"),
+            ("ps", "list containers"),
+            ("logs", "show container logs"),
+            ("pull", "download an image"),
+            ("push", "upload an image"),
+            ("compose", "multi-container applications"),
+            ("stop", "stop containers"),
+            ("rm", "remove containers"),
+        ],
+        &[
+            ("-d", "run in the background"),
+            ("-it", "interactive with a terminal"),
+            ("-i", "keep stdin open"),
+            ("-t", "allocate a terminal, or image tag for build"),
+            ("-p", "publish a port"),
+            ("-v", "mount a volume"),
+            ("-e", "set an environment variable"),
+            ("-f", "follow, or file to use"),
+            ("--rm", "remove the container on exit"),
+            ("--name", "container name"),
+        ],
+    ),
+    tool(
+        "echo",
+        "print its arguments",
+        &[],
+        &[("-n", "no trailing newline"), ("-e", "interpret escapes")],
+    ),
+    tool(
+        "find",
+        "search for files",
+        &[],
+        &[
+            ("-name", "match file names"),
+            ("-type", "match file types"),
+            ("-exec", "run a command on each match"),
+            ("-delete", "delete the matches"),
+            ("-mtime", "match modification times in days"),
+            ("-maxdepth", "limit the directory depth"),
+        ],
+    ),
+    tool(
+        "git",
+        "version control",
+        &[
+            ("add", "stage changes"),
+            ("commit", "record staged changes"),
+            ("push", "upload commits"),
+            ("pull", "fetch and integrate remote commits"),
+            ("fetch", "download remote commits"),
+            ("checkout", "switch branches or restore files"),
+            ("switch", "switch branches"),
+            ("log", "show the history"),
+            ("diff", "show changes"),
+            ("status", "show the working tree status"),
+            ("rebase", "reapply commits on another base"),
+            ("merge", "join histories"),
+            ("reset", "move the branch head"),
+            ("stash", "set changes aside"),
+            ("clean", "remove untracked files"),
+        ],
+        &[
+            ("-a", "all"),
+            ("-m", "message"),
+            ("-b", "create a branch"),
+            ("-f", "force"),
+            ("--force", "force, overwriting remote history"),
+            ("--hard", "discard local changes"),
+            ("--oneline", "one line per commit"),
+            ("-d", "also directories, or delete"),
+        ],
+    ),
+    tool(
+        "grep",
+        "search text for patterns",
+        &[],
+        &[
+            ("-i", "ignore case"),
+            ("-v", "select non-matching lines"),
+            ("-r", "search directories recursively"),
+            ("-n", "print line numbers"),
+            ("-l", "print only file names"),
+            ("-c", "count matches"),
+            ("-E", "extended regular expressions"),
+            ("-o", "print only the matched parts"),
+            ("-w", "match whole words"),
+        ],
+    ),
+    tool(
+        "head",
+        "print the first lines",
+        &[],
+        &[("-n", "number of lines")],
+    ),
+    tool(
+        "jq",
+        "process JSON",
+        &[],
+        &[
+            ("-r", "raw strings without quotes"),
+            ("-c", "compact output"),
+            ("-s", "read all inputs into an array"),
+        ],
+    ),
+    tool(
+        "kill",
+        "send a signal to processes",
+        &[],
+        &[("-9", "force kill")],
+    ),
+    tool(
+        "kubectl",
+        "Kubernetes cluster manager",
+        &[
+            ("get", "list resources"),
+            ("describe", "show details of resources"),
+            ("apply", "create or update resources from files"),
+            ("delete", "delete resources"),
+            ("logs", "print container logs"),
+            ("exec", "run a command in a container"),
+            ("port-forward", "forward local ports to a pod"),
+            ("rollout", "manage rollouts"),
+            ("scale", "set the number of replicas"),
+            ("config", "edit kubeconfig"),
+        ],
+        &[
+            ("-n", "namespace"),
+            ("--namespace", "namespace"),
+            ("-f", "file, or follow logs"),
+            ("-o", "output format"),
+            ("-l", "label selector"),
+            ("-A", "all namespaces"),
+            ("-it", "interactive with a terminal"),
+            ("-c", "container"),
+            ("--context", "kubeconfig context"),
+        ],
+    ),
+    tool(
+        "ls",
+        "list directory contents",
+        &[],
+        &[
+            ("-l", "long format"),
+            ("-a", "include hidden files"),
+            ("-h", "human readable sizes"),
+            ("-t", "sort by modification time"),
+            ("-r", "reverse order"),
+            ("-R", "list subdirectories recursively"),
+        ],
+    ),
+    tool(
+        "make",
+        "run makefile targets",
+        &[],
+        &[
+            ("-j", "parallel jobs"),
+            ("-C", "change to a directory first"),
+            ("-f", "makefile to use"),
+        ],
+    ),
+    tool(
+        "mkdir",
+        "create directories",
+        &[],
+        &[("-p", "create parents, no error if existing")],
+    ),
+    tool(
+        "mv",
+        "move or rename files",
+        &[],
+        &[("-f", "overwrite without asking")],
+    ),
+    tool(
+        "npm",
+        "Node.js package manager",
+        &[
+            ("install", "install dependencies"),
+            ("ci", "clean install from the lockfile"),
+            ("run", "run a package script"),
+            ("test", "run the tests"),
+            ("start", "run the start script"),
+            ("publish", "upload the package"),
+        ],
+        &[("-g", "global"), ("-D", "as a dev dependency")],
+    ),
+    tool(
+        "ps",
+        "list processes",
+        &[],
+        &[
+            ("aux", "all processes with users"),
+            ("-e", "all processes"),
+            ("-f", "full format"),
+        ],
+    ),
+    tool(
+        "psql",
+        "PostgreSQL client",
+        &[],
+        &[
+            ("-h", "host"),
+            ("-p", "port"),
+            ("-U", "user"),
+            ("-d", "database"),
+            ("-c", "run a single command"),
+            ("-f", "run commands from a file"),
+        ],
+    ),
+    tool(
+        "rm",
+        "remove files",
+        &[],
+        &[
+            ("-r", "remove directories recursively"),
+            ("-f", "ignore missing files, never ask"),
+            ("-rf", "remove recursively without asking"),
+        ],
+    ),
+    tool(
+        "rsync",
+        "sync files, also remotely",
+        &[],
+        &[
+            ("-a", "archive mode, keeping attributes"),
+            ("-v", "verbose"),
+            ("-z", "compress during transfer"),
+            ("--delete", "delete files missing in the source"),
+            ("-n", "dry run"),
+        ],
+    ),
+    tool(
+        "scp",
+        "copy files over ssh",
+        &[],
+        &[("-r", "copy directories recursively"), ("-P", "port")],
+    ),
+    tool(
+        "sed",
+        "stream editor",
+        &[],
+        &[
+            ("-i", "edit files in place"),
+            ("-n", "print only explicitly printed lines"),
+            ("-e", "script to run"),
+            ("-E", "extended regular expressions"),
+        ],
+    ),
+    tool(
+        "sort",
+        "sort lines",
+        &[],
+        &[
+            ("-n", "numeric"),
+            ("-r", "reverse"),
+            ("-u", "drop duplicates"),
+            ("-k", "sort key"),
+            ("-h", "human readable numbers"),
+        ],
+    ),
+    tool(
+        "ssh",
+        "remote login",
+        &[],
+        &[
+            ("-i", "identity file"),
+            ("-p", "port"),
+            ("-L", "forward a local port"),
+            ("-N", "no remote command"),
+        ],
+    ),
+    tool(
+        "sudo",
+        "run as another user, root by default",
+        &[],
+        &[("-u", "user")],
+    ),
+    tool(
+        "tail",
+        "print the last lines",
+        &[],
+        &[("-n", "number of lines"), ("-f", "follow appended data")],
+    ),
+    tool(
+        "tar",
+        "archive files",
+        &[],
+        &[
+            ("-c", "create an archive"),
+            ("-x", "extract an archive"),
+            ("-z", "gzip compression"),
+            ("-v", "list processed files"),
+            ("-f", "archive file"),
+            ("-C", "change to a directory first"),
+        ],
+    ),
+    tool(
+        "uniq",
+        "filter repeated lines",
+        &[],
+        &[("-c", "prefix lines with counts")],
+    ),
+    tool(
+        "wc",
+        "count lines, words and bytes",
+        &[],
+        &[("-l", "lines"), ("-w", "words"), ("-c", "bytes")],
+    ),
+    tool(
+        "xargs",
+        "build commands from input",
+        &[],
+        &[
+            ("-n", "arguments per command"),
+            ("-I", "replace a string with the input"),
+            ("-0", "input separated by NUL"),
+            ("-P", "parallel processes"),
+        ],
+    ),
+];
+
+/// A program invocation of a command body with the parts pacs knows about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplainedSegment<'a> {
+    /// The `|`, `&&`, `||`, `;` or `&` joining it to the previous segment.
+    pub operator: Option<&'a str>,
+    /// The source of the invocation, trimmed.
+    pub text: &'a str,
+    /// The program, if there is one.
+    pub program: Option<&'a str>,
+    /// What the program does, if it is a known one.
+    pub summary: Option<&'static str>,
+    /// Known subcommands and flags with their meaning, in order.
+    pub notes: Vec<(String, &'static str)>,
+}
+
+/// Splits `body` into program invocations and annotates those of known
+/// tools. Comments and blank lines are skipped.
+#[must_use]
+pub fn explain_command(body: &str) -> Vec<ExplainedSegment<'_>> {
+    let mut segments = Vec::new();
+    for line in body.lines() {
+        let mut operator = None;
+        let mut start = None;
+        let mut end = 0;
+        let mut words: Vec<(TokenKind, &str)> = Vec::new();
+        let mut offset = 0;
+
+        for (kind, token) in tokenize_shell_line(line) {
+            let token_start = offset;
+            offset += token.len();
+            if kind == TokenKind::Comment {
+                break;
+            }
+            if kind == TokenKind::Operator && matches!(token, "|" | "||" | "&&" | ";" | "&") {
+                if let Some(start) = start {
+                    segments.push(explain_segment(operator, &line[start..end], &words));
+                }
+                operator = Some(token);
+                start = None;
+                words.clear();
+                continue;
+            }
+            if !token.trim().is_empty() {
+                start.get_or_insert(token_start);
+                end = offset;
+                words.push((kind, token));
+            }
+        }
+        if let Some(start) = start {
+            segments.push(explain_segment(operator, &line[start..end], &words));
+        }
+    }
+    segments
+}
+
+fn explain_segment<'a>(
+    operator: Option<&'a str>,
+    text: &'a str,
+    words: &[(TokenKind, &'a str)],
+) -> ExplainedSegment<'a> {
+    // Redirections like `> out.txt` are not part of the invocation
+    let mut args = Vec::new();
+    let mut redirect = false;
+    for &(kind, word) in words {
+        if kind == TokenKind::Operator {
+            redirect = true;
+        } else if !std::mem::take(&mut redirect) {
+            args.push(word);
+        }
+    }
+
+    // Skip leading `VAR=value` assignments
+    let mut args = args.into_iter().skip_while(|w| {
+        w.split_once('=')
+            .is_some_and(|(name, _)| !name.is_empty() && !name.starts_with('-'))
+    });
+    let program = args.next();
+    let tool = program.and_then(|p| {
+        let name = p.rsplit('/').next().unwrap_or(p);
+        TOOLS.iter().find(|t| t.name == name)
+    });
+
+    let mut notes = Vec::new();
+    if let Some(tool) = tool {
+        let mut first = true;
+        for arg in args {
+            if std::mem::take(&mut first)
+                && let Some((_, meaning)) = tool.subcommands.iter().find(|(s, _)| *s == arg)
+            {
+                notes.push((arg.to_string(), *meaning));
+                continue;
+            }
+            let flag = arg.split_once('=').map_or(arg, |(flag, _)| flag);
+            if let Some((_, meaning)) = tool.flags.iter().find(|(f, _)| *f == flag) {
+                notes.push((arg.to_string(), *meaning));
+            } else if !flag.starts_with("--") && flag.starts_with('-') && flag.len() > 2 {
+                // Combined short flags like `-la`
+                for c in flag.chars().skip(1) {
+                    let short = format!("-{c}");
+                    if let Some((_, meaning)) = tool.flags.iter().find(|(f, _)| *f == short) {
+                        notes.push((short, *meaning));
+                    }
+                }
+            }
+        }
+    }
+
+    ExplainedSegment {
+        operator,
+        text,
+        program,
+        summary: tool.map(|t| t.summary),
+        notes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_command() {
+        let segments = explain_command(
+            "# pods\nkubectl get pods -n {{ns}} | grep -iv Running > out.txt\nls -la && RUST_LOG=debug cargo test --release",
+        );
+        assert_eq!(segments.len(), 4);
+
+        let kubectl = &segments[0];
+        assert_eq!(kubectl.operator, None);
+        assert_eq!(kubectl.text, "kubectl get pods -n {{ns}}");
+        assert_eq!(kubectl.summary, Some("Kubernetes cluster manager"));
+        assert_eq!(
+            kubectl.notes,
+            [("get".into(), "list resources"), ("-n".into(), "namespace")]
+        );
+
+        let grep = &segments[1];
+        assert_eq!(grep.operator, Some("|"));
+        assert_eq!(grep.text, "grep -iv Running > out.txt");
+        assert_eq!(
+            grep.notes,
+            [
+                ("-i".into(), "ignore case"),
+                ("-v".into(), "select non-matching lines")
+            ]
+        );
+
+        assert_eq!(segments[2].notes.len(), 2);
+        assert_eq!(segments[3].operator, Some("&&"));
+        assert_eq!(segments[3].program, Some("cargo"));
+        assert_eq!(
+            segments[3].notes,
+            [
+                ("test".into(), "run the tests"),
+                ("--release".into(), "optimized build")
+            ]
+        );
+
+        let unknown = &explain_command("./deploy.sh --fast")[0];
+        assert_eq!(unknown.program, Some("./deploy.sh"));
+        assert_eq!(unknown.summary, None);
+        assert!(unknown.notes.is_empty());
+    }
+}
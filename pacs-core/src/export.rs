@@ -0,0 +1,444 @@
+//! Export of project commands to other file formats.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{
+    CommandView, PacsCommand, PacsError, Versioned, is_secret_reference, render_placeholders,
+    scan_placeholders, shell_quote, variable_name,
+};
+
+/// File format commands can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The same `[[commands]]` layout used by project files.
+    Toml,
+    Json,
+    Markdown,
+    /// A justfile with one recipe per command.
+    Just,
+    /// A Makefile with one phony target per command.
+    Make,
+    /// A GitHub Actions workflow with one job that runs the commands as steps.
+    Gha,
+}
+
+impl ExportFormat {
+    /// Infers the format from a file name or extension.
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.file_name()?.to_str()? {
+            "justfile" | "Justfile" | ".justfile" => return Some(Self::Just),
+            "Makefile" | "makefile" | "GNUmakefile" => return Some(Self::Make),
+            _ => {}
+        }
+        match path.extension()?.to_str()? {
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            "md" | "markdown" => Some(Self::Markdown),
+            "just" => Some(Self::Just),
+            "mk" => Some(Self::Make),
+            "yml" | "yaml" => Some(Self::Gha),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+            Self::Json => "json",
+            Self::Markdown => "md",
+            Self::Just => "just",
+            Self::Make => "mk",
+            Self::Gha => "yml",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TomlExport<'a> {
+    commands: &'a [PacsCommand],
+}
+
+/// Renders the commands of a project in the given format.
+///
+/// Placeholders are kept as they are. For just and make they become variables,
+/// which default to the given environment `values`. For GitHub Actions they
+/// become job environment variables set to the values, to secrets for secret
+/// references and to configuration variables for keys without a value.
+pub fn export_commands(
+    project: &str,
+    commands: &[PacsCommand],
+    values: &BTreeMap<String, String>,
+    format: ExportFormat,
+) -> Result<String, PacsError> {
+    match format {
+        ExportFormat::Toml => Ok(toml::to_string(&TomlExport { commands })?),
+        ExportFormat::Json => {
+            let commands: Vec<CommandView> = commands.iter().map(CommandView::from).collect();
+            let mut json = serde_json::to_string_pretty(&Versioned::new(commands))?;
+            json.push('\n');
+            Ok(json)
+        }
+        ExportFormat::Markdown => Ok(markdown(project, commands)),
+        ExportFormat::Just => Ok(justfile(project, commands, values)),
+        ExportFormat::Make => Ok(makefile(project, commands, values)),
+        ExportFormat::Gha => Ok(workflow(project, commands, values)),
+    }
+}
+
+fn markdown(project: &str, commands: &[PacsCommand]) -> String {
+    let mut tags: BTreeMap<&str, Vec<&PacsCommand>> = BTreeMap::new();
+    for cmd in commands {
+        tags.entry(cmd.tag.as_str()).or_default().push(cmd);
+    }
+
+    let mut out = format!("# {project}\n");
+    for (tag, commands) in tags {
+        let level = if tag.is_empty() {
+            "##"
+        } else {
+            let _ = write!(out, "\n## {tag}\n");
+            "###"
+        };
+        for cmd in commands {
+            let _ = write!(out, "\n{level} {}\n\n", cmd.name);
+            if let Some(cwd) = &cmd.cwd {
+                let _ = writeln!(out, "Working directory: `{cwd}`\n");
+            }
+            let _ = writeln!(out, "```sh\n{}\n```", cmd.command.trim_end());
+        }
+    }
+    out
+}
+
+/// Placeholder keys used by any of the commands that have an environment value.
+fn used_variables<'a>(
+    commands: &[PacsCommand],
+    values: &'a BTreeMap<String, String>,
+) -> BTreeMap<&'a str, &'a str> {
+    commands
+        .iter()
+        .flat_map(|c| scan_placeholders(&c.command))
+        .filter_map(|key| values.get_key_value(key))
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect()
+}
+
+/// Rewrites every `{{key}}` placeholder in `src` using `f`.
+fn map_placeholders(src: &str, f: impl Fn(&str) -> String) -> String {
+    let replacements: BTreeMap<String, String> = scan_placeholders(src)
+        .into_iter()
+        .map(|key| (key.to_string(), f(key)))
+        .collect();
+    render_placeholders(src, &replacements).0
+}
+
+fn justfile(project: &str, commands: &[PacsCommand], values: &BTreeMap<String, String>) -> String {
+    let mut out = format!("# Commands of project '{project}', exported by pacs\n");
+
+    let variables = used_variables(commands, values);
+    if !variables.is_empty() {
+        out.push('\n');
+    }
+    for (key, value) in &variables {
+        let value = if value.contains('\'') {
+            format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+        } else {
+            format!("'{value}'")
+        };
+        let _ = writeln!(out, "{key} := {value}");
+    }
+
+    for cmd in commands {
+        let body = cmd.command.trim_end();
+
+        // Placeholders without a value become recipe parameters
+        let mut params: Vec<&str> = Vec::new();
+        for key in scan_placeholders(body) {
+            if !variables.contains_key(key) && !params.contains(&key) {
+                params.push(key);
+            }
+        }
+
+        out.push('\n');
+        if !cmd.tag.is_empty() {
+            let _ = writeln!(out, "[group('{}')]", cmd.tag);
+        }
+        // Recipe names cannot contain slashes, so `db/migrate` becomes `db-migrate`
+        let mut header = cmd.name.replace('/', "-");
+        for param in &params {
+            let _ = write!(header, " {param}");
+        }
+        let _ = writeln!(out, "{header}:");
+
+        // Multi-line commands run as a script so that lines share one shell, like in pacs
+        if body.contains('\n') {
+            out.push_str("    #!/usr/bin/env sh\n");
+            if let Some(cwd) = &cmd.cwd {
+                let _ = writeln!(out, "    cd {}", shell_quote(cwd));
+            }
+            for line in body.lines() {
+                let _ = writeln!(out, "    {line}");
+            }
+        } else if let Some(cwd) = &cmd.cwd {
+            let _ = writeln!(out, "    cd {} && {body}", shell_quote(cwd));
+        } else {
+            let _ = writeln!(out, "    {body}");
+        }
+    }
+    out
+}
+
+fn makefile(project: &str, commands: &[PacsCommand], values: &BTreeMap<String, String>) -> String {
+    let mut out = format!("# Commands of project '{project}', exported by pacs\n");
+    out.push_str("\n# Run each recipe in a single shell, like pacs does\n.ONESHELL:\n");
+
+    let variables = used_variables(commands, values);
+    if !variables.is_empty() {
+        out.push('\n');
+    }
+    for (key, value) in &variables {
+        let value = value.replace('$', "$$").replace('#', "\\#");
+        let _ = writeln!(out, "{key} ?= {value}");
+    }
+
+    for cmd in commands {
+        let body = map_placeholders(&cmd.command.trim_end().replace('$', "$$"), |key| {
+            format!("$({key})")
+        });
+
+        out.push('\n');
+        if !cmd.tag.is_empty() {
+            let _ = writeln!(out, "# [{}]", cmd.tag);
+        }
+        let _ = writeln!(out, ".PHONY: {}\n{}:", cmd.name, cmd.name);
+        if let Some(cwd) = &cmd.cwd {
+            let _ = writeln!(out, "\tcd {}", shell_quote(&cwd.replace('$', "$$")));
+        }
+        for line in body.lines() {
+            let _ = writeln!(out, "\t{line}");
+        }
+    }
+    out
+}
+
+/// Quotes a value as a single-quoted YAML scalar.
+fn yaml_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn workflow(project: &str, commands: &[PacsCommand], values: &BTreeMap<String, String>) -> String {
+    // Commands that share a tag, as with `--tag release`, become a job named after it
+    let tag = commands.first().map_or("", |c| c.tag.as_str());
+    let job = if !tag.is_empty() && commands.iter().all(|c| c.tag == tag) {
+        tag
+    } else {
+        "commands"
+    };
+    let job: String = job
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let mut out = format!(
+        "# Commands of project '{project}', exported by pacs
+"
+    );
+    let _ = write!(
+        out,
+        "name: {}
+
+on:
+  workflow_dispatch:
+
+jobs:
+  {job}:
+    runs-on: ubuntu-latest
+",
+        yaml_quote(project)
+    );
+
+    let mut keys: Vec<&str> = Vec::new();
+    for cmd in commands {
+        for key in scan_placeholders(&cmd.command)
+            .into_iter()
+            .chain(cmd.cwd.iter().flat_map(|cwd| scan_placeholders(cwd)))
+        {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    if !keys.is_empty() {
+        out.push_str(
+            "    env:
+",
+        );
+    }
+    for key in keys {
+        let name = variable_name(key);
+        let value = match values.get(key) {
+            Some(value) if is_secret_reference(value) => format!("${{{{ secrets.{name} }}}}"),
+            Some(value) => yaml_quote(value),
+            None => format!("${{{{ vars.{name} }}}}"),
+        };
+        let _ = writeln!(out, "      {name}: {value}");
+    }
+
+    out.push_str(
+        "    steps:
+      - uses: actions/checkout@v4
+",
+    );
+    for cmd in commands {
+        let body = map_placeholders(cmd.command.trim_end(), |key| {
+            format!("${{{}}}", variable_name(key))
+        });
+        let _ = writeln!(out, "      - name: {}", yaml_quote(&cmd.name));
+        if let Some(cwd) = &cmd.cwd {
+            let cwd = map_placeholders(cwd, |key| format!("${{{{ env.{} }}}}", variable_name(key)));
+            let _ = writeln!(out, "        working-directory: {}", yaml_quote(&cwd));
+        }
+        out.push_str("        run: |\n");
+        for line in body.lines() {
+            if line.is_empty() {
+                out.push('\n');
+            } else {
+                let _ = writeln!(out, "          {line}");
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmd(name: &str, command: &str, tag: &str) -> PacsCommand {
+        PacsCommand {
+            name: name.into(),
+            command: command.into(),
+            tag: tag.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let commands = vec![
+            cmd("build", "cargo build", ""),
+            cmd("up", "docker up\n", "dev"),
+        ];
+
+        let values = BTreeMap::new();
+
+        let toml = export_commands("api", &commands, &values, ExportFormat::Toml).unwrap();
+        assert!(toml.contains("[[commands]]"));
+        assert!(toml.contains("name = \"up\""));
+
+        let json = export_commands("api", &commands, &values, ExportFormat::Json).unwrap();
+        assert!(json.contains("\"command\": \"docker up\""));
+        assert!(json.starts_with("{\n  \"version\": 1,"));
+
+        let md = export_commands("api", &commands, &values, ExportFormat::Markdown).unwrap();
+        assert!(md.starts_with("# api\n"));
+        assert!(md.contains("## build\n\n```sh\ncargo build\n```"));
+        assert!(md.contains("## dev\n\n### up\n"));
+
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out/api.md")),
+            Some(ExportFormat::Markdown)
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("Makefile")),
+            Some(ExportFormat::Make)
+        );
+        assert_eq!(ExportFormat::from_path(Path::new("api.txt")), None);
+    }
+
+    #[test]
+    fn test_export_just_and_make() {
+        let mut deploy = cmd("deploy", "kubectl --context {{ctx}} apply -n {{ns}}", "k8s");
+        deploy.cwd = Some("/srv/api".into());
+        let commands = vec![deploy, cmd("env", "echo $HOME\necho done", "")];
+        let values = BTreeMap::from([("ctx".to_string(), "dev".to_string())]);
+
+        let just = export_commands("api", &commands, &values, ExportFormat::Just).unwrap();
+        assert!(just.contains("ctx := 'dev'\n"));
+        assert!(just.contains(
+            "[group('k8s')]\ndeploy ns:\n    cd '/srv/api' && kubectl --context {{ctx}} apply -n {{ns}}\n"
+        ));
+        assert!(just.contains("env:\n    #!/usr/bin/env sh\n    echo $HOME\n    echo done\n"));
+
+        let make = export_commands("api", &commands, &values, ExportFormat::Make).unwrap();
+        assert!(make.contains("ctx ?= dev\n"));
+        assert!(make.contains(
+            ".PHONY: deploy\ndeploy:\n\tcd '/srv/api'\n\tkubectl --context $(ctx) apply -n $(ns)\n"
+        ));
+        assert!(make.contains("env:\n\techo $$HOME\n\techo done\n"));
+    }
+
+    #[test]
+    fn test_export_gha() {
+        let mut deploy = cmd(
+            "deploy",
+            "kubectl apply -n {{ns}} --token {{token}}",
+            "release",
+        );
+        deploy.cwd = Some("{{root}}/k8s".into());
+        let commands = vec![
+            cmd("build", "cargo build --release\n\nls", "release"),
+            deploy,
+        ];
+        let values = BTreeMap::from([
+            ("ns".to_string(), "it's-prod".to_string()),
+            ("token".to_string(), "op://ops/k8s/token".to_string()),
+        ]);
+
+        let gha = export_commands("api", &commands, &values, ExportFormat::Gha).unwrap();
+        assert_eq!(
+            gha,
+            "# Commands of project 'api', exported by pacs
+name: 'api'
+
+on:
+  workflow_dispatch:
+
+jobs:
+  release:
+    runs-on: ubuntu-latest
+    env:
+      NS: 'it''s-prod'
+      TOKEN: ${{ secrets.TOKEN }}
+      ROOT: ${{ vars.ROOT }}
+    steps:
+      - uses: actions/checkout@v4
+      - name: 'build'
+        run: |
+          cargo build --release
+
+          ls
+      - name: 'deploy'
+        working-directory: '${{ env.ROOT }}/k8s'
+        run: |
+          kubectl apply -n ${NS} --token ${TOKEN}
+"
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new(".github/workflows/release.yml")),
+            Some(ExportFormat::Gha)
+        );
+    }
+}
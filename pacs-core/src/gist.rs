@@ -0,0 +1,84 @@
+//! Publishing and pulling single projects via GitHub gists.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{PacsError, http};
+
+const GISTS_API: &str = "https://api.github.com/gists";
+
+#[derive(Deserialize)]
+pub(crate) struct Gist {
+    pub id: String,
+    pub html_url: String,
+    #[serde(default)]
+    files: BTreeMap<String, GistFile>,
+}
+
+#[derive(Deserialize)]
+struct GistFile {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    truncated: bool,
+    raw_url: String,
+}
+
+fn request<'a>(url: &'a str, token: Option<&str>) -> http::Request<'a> {
+    let request = http::Request::get(url)
+        .header("Accept: application/vnd.github+json")
+        .header("X-GitHub-Api-Version: 2022-11-28");
+    match token {
+        Some(token) => request.header(format!("Authorization: Bearer {token}")),
+        None => request,
+    }
+}
+
+/// Creates a secret gist with a single file, or updates the existing gist `id`.
+pub(crate) fn publish(
+    token: &str,
+    id: Option<&str>,
+    file_name: &str,
+    content: &str,
+    description: &str,
+) -> Result<Gist, PacsError> {
+    let body = serde_json::json!({
+        "description": description,
+        "public": false,
+        "files": { file_name: { "content": content } },
+    })
+    .to_string();
+
+    let response = match id {
+        Some(id) => {
+            let url = format!("{GISTS_API}/{id}");
+            http::send(&request(&url, Some(token)).body("PATCH", body))?
+        }
+        None => http::send(&request(GISTS_API, Some(token)).body("POST", body))?,
+    };
+    Ok(serde_json::from_str(&response)?)
+}
+
+/// Fetches a gist and returns the content of its first TOML file.
+pub(crate) fn fetch(token: Option<&str>, id: &str) -> Result<String, PacsError> {
+    let url = format!("{GISTS_API}/{id}");
+    let gist: Gist = serde_json::from_str(&http::send(&request(&url, token))?)?;
+
+    let file = gist
+        .files
+        .into_iter()
+        .find(|(name, _)| {
+            Path::new(name)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+        })
+        .map(|(_, file)| file)
+        .ok_or_else(|| PacsError::FetchFailed(format!("gist {id}: no .toml file found")))?;
+
+    match file.content {
+        Some(content) if !file.truncated => Ok(content),
+        _ => http::send(&request(&file.raw_url, token)),
+    }
+}
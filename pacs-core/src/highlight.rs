@@ -0,0 +1,180 @@
+//! Shell syntax tokenizer shared by the CLI and TUI for highlighting command bodies.
+
+/// Kind of a highlighted shell token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// The program name at the start of a pipeline or command list.
+    Command,
+    /// A `-f` or `--flag` argument.
+    Flag,
+    /// A single or double quoted string.
+    String,
+    /// A `$VAR` or `${VAR}` expansion.
+    Variable,
+    /// Pipes, redirections and command separators.
+    Operator,
+    /// A `#` comment running to the end of the line.
+    Comment,
+    /// Anything else, including whitespace.
+    Text,
+}
+
+/// Splits a single line of shell code into highlighted tokens.
+///
+/// Concatenating the token texts yields the input line.
+#[must_use]
+pub fn tokenize_shell_line(line: &str) -> Vec<(TokenKind, &str)> {
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let len = chars.len();
+    let byte = |i: usize| chars.get(i).map_or(line.len(), |&(b, _)| b);
+    let at = |i: usize| chars[i].1;
+    let is_operator = |c: char| matches!(c, '|' | '>' | '<' | '&' | ';');
+
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut expect_command = true; // true at start and after pipe/semicolon
+
+    while i < len {
+        let ch = at(i);
+        let start = i;
+
+        // Comments
+        if ch == '#' {
+            tokens.push((TokenKind::Comment, &line[byte(start)..]));
+            break;
+        }
+
+        // Strings (double quotes)
+        if ch == '"' {
+            i += 1;
+            while i < len && at(i) != '"' {
+                i += if at(i) == '\\' && i + 1 < len { 2 } else { 1 };
+            }
+            i = (i + 1).min(len); // include closing quote
+            tokens.push((TokenKind::String, &line[byte(start)..byte(i)]));
+            continue;
+        }
+
+        // Strings (single quotes)
+        if ch == '\'' {
+            i += 1;
+            while i < len && at(i) != '\'' {
+                i += 1;
+            }
+            i = (i + 1).min(len); // include closing quote
+            tokens.push((TokenKind::String, &line[byte(start)..byte(i)]));
+            continue;
+        }
+
+        // Variables ($VAR or ${VAR})
+        if ch == '$' {
+            i += 1;
+            if i < len && at(i) == '{' {
+                while i < len && at(i) != '}' {
+                    i += 1;
+                }
+                i = (i + 1).min(len); // include closing brace
+            } else {
+                while i < len && (at(i).is_alphanumeric() || at(i) == '_') {
+                    i += 1;
+                }
+            }
+            tokens.push((TokenKind::Variable, &line[byte(start)..byte(i)]));
+            continue;
+        }
+
+        // Operators (|, >, >>, <, <<, &&, ||, ;)
+        if is_operator(ch) {
+            i += 1;
+            if i < len && at(i) == ch {
+                i += 1;
+            }
+            tokens.push((TokenKind::Operator, &line[byte(start)..byte(i)]));
+            // After pipe or semicolon, expect a new command
+            if ch == '|' || ch == ';' {
+                expect_command = true;
+            }
+            continue;
+        }
+
+        let starts_word = i == 0 || at(i - 1).is_whitespace();
+
+        // Flags (--flag or -f)
+        if ch == '-' && starts_word {
+            i += 1;
+            if i < len && at(i) == '-' {
+                i += 1;
+            }
+            while i < len && (at(i).is_alphanumeric() || at(i) == '-' || at(i) == '_') {
+                i += 1;
+            }
+            if i > start + 1 {
+                tokens.push((TokenKind::Flag, &line[byte(start)..byte(i)]));
+                continue;
+            }
+            i = start; // reset, not a flag
+        }
+
+        // Whitespace
+        if ch.is_whitespace() {
+            while i < len && at(i).is_whitespace() {
+                i += 1;
+            }
+            tokens.push((TokenKind::Text, &line[byte(start)..byte(i)]));
+            continue;
+        }
+
+        // Regular text (collect until special character or whitespace)
+        i += 1;
+        while i < len {
+            let c = at(i);
+            if c.is_whitespace() || matches!(c, '#' | '"' | '\'' | '$') || is_operator(c) {
+                break;
+            }
+            i += 1;
+        }
+        let kind = if expect_command {
+            expect_command = false;
+            TokenKind::Command
+        } else {
+            TokenKind::Text
+        };
+        tokens.push((kind, &line[byte(start)..byte(i)]));
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_shell_line() {
+        use TokenKind::{Command, Flag, Operator, String, Text, Variable};
+
+        let tokens = tokenize_shell_line("cat - | grep -v \"a b\" $HOME");
+        assert_eq!(
+            tokens,
+            vec![
+                (Command, "cat"),
+                (Text, " "),
+                (Text, "-"),
+                (Text, " "),
+                (Operator, "|"),
+                (Text, " "),
+                (Command, "grep"),
+                (Text, " "),
+                (Flag, "-v"),
+                (Text, " "),
+                (String, "\"a b\""),
+                (Text, " "),
+                (Variable, "$HOME"),
+            ]
+        );
+
+        let line = "echo ünïcode # done";
+        let joined: std::string::String = tokenize_shell_line(line).iter().map(|t| t.1).collect();
+        assert_eq!(joined, line);
+    }
+}
@@ -0,0 +1,422 @@
+//! Run history stored in `history.toml`.
+//!
+//! Every execution appends a `[[runs]]` table to the file, so writes never
+//! need to parse or rewrite existing entries. Only clearing runs, enforcing
+//! retention limits and `pacs gc` rewrite it.
+
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(feature = "fs-store")]
+use std::{
+    fs::{self, OpenOptions},
+    io::Write as _,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::PacsCommand;
+#[cfg(feature = "fs-store")]
+use crate::{PacsError, parse_interval, write_atomic};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// A single recorded command execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Name of the command that was run.
+    pub command: String,
+    /// Project the command belongs to.
+    pub project: String,
+    /// Environment used to expand placeholders, if any.
+    #[serde(default)]
+    pub environment: Option<String>,
+    /// Exit code of the process, -1 if it was terminated by a signal.
+    pub exit_code: i32,
+    /// Start time in seconds since the Unix epoch.
+    pub started_at: u64,
+    /// Wall time of the run in milliseconds.
+    pub duration_ms: u64,
+}
+
+impl RunRecord {
+    #[must_use]
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+/// Runs that are not recorded and how long recorded runs are kept, set in
+/// the `[history]` table of config.toml.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Names or aliases of commands.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_commands: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_projects: Vec<String>,
+    /// Most recent runs kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_entries: Option<usize>,
+    /// Runs older than this interval, like `90d`, are dropped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<String>,
+    /// Size of history.toml above which the oldest runs are dropped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+    /// Limits for single projects, overriding the ones above.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub projects: BTreeMap<String, Retention>,
+}
+
+/// Retention limits of one project, set in a `[history.projects.<name>]` table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retention {
+    /// Most recent runs of the project kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_entries: Option<usize>,
+    /// Runs of the project older than this interval are dropped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<String>,
+}
+
+impl HistoryConfig {
+    /// Returns true if a run of `cmd`, or of the command named in `record`
+    /// if it no longer exists, must not be recorded.
+    #[must_use]
+    pub fn excludes(&self, record: &RunRecord, cmd: Option<&PacsCommand>) -> bool {
+        self.exclude_projects.contains(&record.project)
+            || self
+                .exclude_commands
+                .iter()
+                .any(|name| cmd.map_or(*name == record.command, |c| c.matches(name)))
+            || cmd.is_some_and(|c| self.exclude_tags.contains(&c.tag))
+    }
+
+    /// Returns true if any retention limit is set.
+    #[must_use]
+    pub fn limits_retention(&self) -> bool {
+        self.max_entries.is_some()
+            || self.max_age.is_some()
+            || self.max_bytes.is_some()
+            || !self.projects.is_empty()
+    }
+
+    /// Drops the runs in `records`, oldest first, that exceed the retention
+    /// limits at `now`.
+    #[cfg(feature = "fs-store")]
+    pub(crate) fn retain(
+        &self,
+        records: Vec<RunRecord>,
+        now: u64,
+    ) -> Result<Vec<RunRecord>, PacsError> {
+        let max_age = |s: &Option<String>| {
+            s.as_deref()
+                .map(|s| parse_interval(s).ok_or_else(|| PacsError::InvalidDate(s.to_string())))
+                .transpose()
+        };
+        let default_age = max_age(&self.max_age)?;
+        let mut ages = BTreeMap::new();
+        for (project, retention) in &self.projects {
+            ages.insert(
+                project.as_str(),
+                max_age(&retention.max_age)?.or(default_age),
+            );
+        }
+
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut kept = 0;
+        let mut bytes = 0;
+        let mut keep = vec![false; records.len()];
+        for (i, record) in records.iter().enumerate().rev() {
+            let age = ages
+                .get(record.project.as_str())
+                .copied()
+                .unwrap_or(default_age);
+            if age.is_some_and(|age| now.saturating_sub(record.started_at) > age.as_secs()) {
+                continue;
+            }
+            let count = counts.entry(record.project.as_str()).or_default();
+            let project_limit = self
+                .projects
+                .get(&record.project)
+                .and_then(|r| r.max_entries);
+            if project_limit.is_some_and(|max| *count >= max)
+                || self.max_entries.is_some_and(|max| kept >= max)
+            {
+                continue;
+            }
+            if let Some(max) = self.max_bytes {
+                bytes += entry(record)?.len() as u64 + 1;
+                if bytes > max {
+                    break;
+                }
+            }
+            *count += 1;
+            kept += 1;
+            keep[i] = true;
+        }
+        Ok(records
+            .into_iter()
+            .zip(keep)
+            .filter_map(|(record, keep)| keep.then_some(record))
+            .collect())
+    }
+}
+
+#[cfg(feature = "fs-store")]
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    runs: Vec<RunRecord>,
+}
+
+/// Appends a record to the history file.
+#[cfg(feature = "fs-store")]
+pub(crate) fn append(path: &Path, record: &RunRecord) -> Result<(), PacsError> {
+    let entry = entry(record)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{entry}")?;
+    Ok(())
+}
+
+/// Returns the `[[runs]]` table appended for `record`.
+#[cfg(feature = "fs-store")]
+fn entry(record: &RunRecord) -> Result<String, PacsError> {
+    Ok(toml::to_string(&HistoryFile {
+        runs: vec![record.clone()],
+    })?)
+}
+
+/// Reads all records from the history file, oldest first.
+#[cfg(feature = "fs-store")]
+pub(crate) fn load(path: &Path) -> Result<Vec<RunRecord>, PacsError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)?;
+    let file: HistoryFile = toml::from_str(&content)?;
+    Ok(file.runs)
+}
+
+/// Replaces the history file with `records`.
+#[cfg(feature = "fs-store")]
+pub(crate) fn save(path: &Path, records: Vec<RunRecord>) -> Result<(), PacsError> {
+    write_atomic(path, &toml::to_string(&HistoryFile { runs: records })?)
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+#[must_use]
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Run counts for a single command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandStats {
+    pub project: String,
+    pub command: String,
+    pub runs: usize,
+    pub successes: usize,
+    pub last_run: u64,
+}
+
+/// Aggregated statistics over the run history.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunStats {
+    /// Commands sorted by number of runs, most run first.
+    pub commands: Vec<CommandStats>,
+    /// Total runs per project.
+    pub projects: BTreeMap<String, usize>,
+    pub successes: usize,
+    pub failures: usize,
+    /// Runs per day, oldest first, ending with today.
+    pub daily: Vec<u64>,
+}
+
+impl RunStats {
+    /// Computes statistics from the given records, bucketing the last `days`
+    /// days of activity relative to `now`.
+    #[must_use]
+    pub fn from_records(records: &[RunRecord], now: u64, days: usize) -> Self {
+        let mut stats = Self {
+            daily: vec![0; days],
+            ..Self::default()
+        };
+        let mut commands: BTreeMap<(&str, &str), CommandStats> = BTreeMap::new();
+        let today = now / SECONDS_PER_DAY;
+
+        for record in records {
+            let entry = commands
+                .entry((&record.project, &record.command))
+                .or_insert_with(|| CommandStats {
+                    project: record.project.clone(),
+                    command: record.command.clone(),
+                    ..CommandStats::default()
+                });
+            entry.runs += 1;
+            entry.last_run = entry.last_run.max(record.started_at);
+
+            *stats.projects.entry(record.project.clone()).or_default() += 1;
+
+            if record.success() {
+                entry.successes += 1;
+                stats.successes += 1;
+            } else {
+                stats.failures += 1;
+            }
+
+            let age = today.saturating_sub(record.started_at / SECONDS_PER_DAY);
+            if let Ok(age) = usize::try_from(age)
+                && age < days
+            {
+                stats.daily[days - 1 - age] += 1;
+            }
+        }
+
+        stats.commands = commands.into_values().collect();
+        stats
+            .commands
+            .sort_by(|a, b| b.runs.cmp(&a.runs).then(b.last_run.cmp(&a.last_run)));
+        stats
+    }
+
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.successes + self.failures
+    }
+}
+
+/// Returns the start time of the latest run of each command, keyed by
+/// project and command name.
+#[must_use]
+pub fn last_runs(records: &[RunRecord]) -> BTreeMap<(String, String), u64> {
+    let mut last_runs = BTreeMap::new();
+    for record in records {
+        let last = last_runs
+            .entry((record.project.clone(), record.command.clone()))
+            .or_default();
+        *last = record.started_at.max(*last);
+    }
+    last_runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(command: &str, project: &str, exit_code: i32, started_at: u64) -> RunRecord {
+        RunRecord {
+            command: command.into(),
+            project: project.into(),
+            environment: None,
+            exit_code,
+            started_at,
+            duration_ms: 10,
+        }
+    }
+
+    #[cfg(feature = "fs-store")]
+    #[test]
+    fn test_append_and_load() {
+        let path = std::env::temp_dir().join(format!("pacs_history_{}.toml", std::process::id()));
+        fs::remove_file(&path).ok();
+
+        append(&path, &record("build", "api", 0, 100)).unwrap();
+        append(&path, &record("test", "api", 1, 200)).unwrap();
+
+        let records = load(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1], record("test", "api", 1, 200));
+        fs::remove_file(&path).ok();
+    }
+
+    #[cfg(feature = "fs-store")]
+    #[test]
+    fn test_retain() {
+        let day = SECONDS_PER_DAY;
+        let now = 100 * day;
+        let records = vec![
+            record("old", "api", 0, now - 40 * day),
+            record("build", "api", 0, now - 3 * day),
+            record("lint", "web", 0, now - 20 * day),
+            record("test", "api", 0, now - 2 * day),
+            record("deploy", "api", 0, now - day),
+        ];
+        let names = |config: &HistoryConfig| {
+            config
+                .retain(records.clone(), now)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.command)
+                .collect::<Vec<_>>()
+        };
+
+        let mut config = HistoryConfig {
+            max_age: Some("30d".into()),
+            ..HistoryConfig::default()
+        };
+        assert_eq!(names(&config), ["build", "lint", "test", "deploy"]);
+
+        config.projects.insert(
+            "web".into(),
+            Retention {
+                max_age: Some("1w".into()),
+                ..Retention::default()
+            },
+        );
+        config.projects.insert(
+            "api".into(),
+            Retention {
+                max_entries: Some(2),
+                ..Retention::default()
+            },
+        );
+        assert_eq!(names(&config), ["test", "deploy"]);
+
+        let size = entry(&records[4]).unwrap().len() as u64 + 1;
+        let config = HistoryConfig {
+            max_entries: Some(3),
+            max_bytes: Some(2 * size + 5),
+            ..HistoryConfig::default()
+        };
+        assert_eq!(names(&config), ["test", "deploy"]);
+
+        let config = HistoryConfig {
+            max_age: Some("soon".into()),
+            ..HistoryConfig::default()
+        };
+        assert!(config.retain(records.clone(), now).is_err());
+    }
+
+    #[test]
+    fn test_stats() {
+        let day = SECONDS_PER_DAY;
+        let now = 10 * day;
+        let records = vec![
+            record("build", "api", 0, now - 2 * day),
+            record("build", "api", 1, now - day),
+            record("build", "api", 0, now),
+            record("deploy", "web", 0, now),
+            record("old", "web", 0, 0),
+        ];
+
+        let stats = RunStats::from_records(&records, now, 3);
+        assert_eq!(stats.total(), 5);
+        assert_eq!(stats.failures, 1);
+        assert_eq!(stats.commands[0].command, "build");
+        assert_eq!(stats.commands[0].runs, 3);
+        assert_eq!(stats.commands[0].successes, 2);
+        assert_eq!(stats.projects["web"], 2);
+        assert_eq!(stats.daily, vec![1, 1, 2]);
+
+        let last_runs = last_runs(&records);
+        assert_eq!(last_runs[&("api".into(), "build".into())], now);
+        assert_eq!(last_runs[&("web".into(), "old".into())], 0);
+    }
+}
@@ -0,0 +1,231 @@
+//! Scripted hooks in `hooks/*.rhai`.
+//!
+//! A script may define `pre_run(ctx)`, `post_run(ctx)` and `on_switch(ctx)`,
+//! which are called with a map describing the event. `pre_run` and
+//! `on_switch` block the action by throwing or returning `false`:
+//!
+//! ```rhai
+//! fn pre_run(ctx) {
+//!     if ctx.environment == "prod" && ctx.command.tag == "deploy" && weekday() == 5 {
+//!         throw "no prod deploys on Fridays";
+//!     }
+//! }
+//! ```
+//!
+//! Scripts can call `now()`, `weekday()` (0 is Sunday) and `hour()`, all in UTC.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use rhai::{AST, Dynamic, Engine, EvalAltResult, Map, Scope};
+
+use crate::{PacsCommand, PacsError, history::unix_now};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Upper bound on the operations of a single hook call, so a runaway script
+/// cannot hang pacs.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    /// Before a command runs. Hooks can block the run.
+    PreRun,
+    /// After a command finished, with its exit code.
+    PostRun,
+    /// Before the active project or environment changes. Hooks can block the switch.
+    Switch,
+}
+
+impl HookEvent {
+    fn function(self) -> &'static str {
+        match self {
+            Self::PreRun => "pre_run",
+            Self::PostRun => "post_run",
+            Self::Switch => "on_switch",
+        }
+    }
+
+    fn can_block(self) -> bool {
+        matches!(self, Self::PreRun | Self::Switch)
+    }
+}
+
+/// Details passed to hook functions as `ctx`.
+#[derive(Debug, Clone, Default)]
+pub struct HookContext {
+    pub project: String,
+    pub environment: Option<String>,
+    /// Values of the environment, with secret references left unresolved.
+    pub values: BTreeMap<String, String>,
+    pub command: Option<PacsCommand>,
+    pub exit_code: Option<i32>,
+}
+
+impl HookContext {
+    fn to_map(&self, event: HookEvent) -> Map {
+        let mut map = Map::new();
+        map.insert("event".into(), event.function().into());
+        map.insert("project".into(), self.project.clone().into());
+        map.insert("environment".into(), optional(self.environment.clone()));
+        let values: Map = self
+            .values
+            .iter()
+            .map(|(k, v)| (k.into(), v.clone().into()))
+            .collect();
+        map.insert("values".into(), values.into());
+
+        let command = self.command.as_ref().map(|cmd| {
+            let mut command = Map::new();
+            command.insert("name".into(), cmd.name.clone().into());
+            command.insert("command".into(), cmd.command.clone().into());
+            command.insert("tag".into(), cmd.tag.clone().into());
+            command.insert("cwd".into(), optional(cmd.cwd.clone()));
+            command
+        });
+        map.insert("command".into(), optional(command));
+        map.insert("exit_code".into(), optional(self.exit_code.map(i64::from)));
+        map
+    }
+}
+
+fn optional<T: Into<Dynamic>>(value: Option<T>) -> Dynamic {
+    value.map_or(Dynamic::UNIT, Into::into)
+}
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    let now = || i64::try_from(unix_now()).unwrap_or_default();
+    engine.register_fn("now", now);
+    // 1970-01-01 was a Thursday
+    engine.register_fn("weekday", move || {
+        (now() / SECONDS_PER_DAY + 4).rem_euclid(7)
+    });
+    engine.register_fn("hour", move || now() % SECONDS_PER_DAY / 3600);
+    engine
+}
+
+/// Runs the hook function for `event` in every script in `dir`, in file name order.
+pub(crate) fn run(dir: &Path, event: HookEvent, ctx: &HookContext) -> Result<(), PacsError> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    let mut scripts: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("rhai"))
+        })
+        .collect();
+    if scripts.is_empty() {
+        return Ok(());
+    }
+    scripts.sort();
+
+    let engine = engine();
+    let ctx = ctx.to_map(event);
+    for path in scripts {
+        let script = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let source = fs::read_to_string(&path)?;
+        let ast = engine.compile(&source).map_err(|e| PacsError::HookFailed {
+            script: script.clone(),
+            message: e.to_string(),
+        })?;
+        call(&engine, &ast, event, ctx.clone()).map_err(|(blocked, message)| {
+            if blocked {
+                PacsError::HookRejected { script, message }
+            } else {
+                PacsError::HookFailed { script, message }
+            }
+        })?;
+    }
+    Ok(())
+}
+
+/// Calls the event's function if the script defines it. On error, returns
+/// whether the hook deliberately blocked the action, and why.
+fn call(engine: &Engine, ast: &AST, event: HookEvent, ctx: Map) -> Result<(), (bool, String)> {
+    let name = event.function();
+    if !ast.iter_functions().any(|f| f.name == name) {
+        return Ok(());
+    }
+
+    match engine.call_fn::<Dynamic>(&mut Scope::new(), ast, name, (ctx,)) {
+        Ok(result) if event.can_block() && result.as_bool() == Ok(false) => {
+            Err((true, format!("{name} returned false")))
+        }
+        Ok(_) => Ok(()),
+        Err(err) => {
+            let mut err = *err;
+            while let EvalAltResult::ErrorInFunctionCall(_, _, inner, _) = err {
+                err = *inner;
+            }
+            match err {
+                EvalAltResult::ErrorRuntime(value, _) => {
+                    Err((event.can_block(), value.to_string()))
+                }
+                other => Err((false, other.to_string())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(environment: &str) -> HookContext {
+        HookContext {
+            project: "api".into(),
+            environment: Some(environment.into()),
+            command: Some(PacsCommand {
+                name: "deploy".into(),
+                command: "make deploy".into(),
+                tag: "release".into(),
+                ..Default::default()
+            }),
+            ..HookContext::default()
+        }
+    }
+
+    #[test]
+    fn test_hooks_can_block() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("policy.rhai"),
+            r#"
+            fn pre_run(ctx) {
+                if ctx.environment == "prod" && ctx.command.tag == "release" {
+                    throw "no prod releases";
+                }
+            }
+            fn on_switch(ctx) { ctx.environment != "legacy" }
+            "#,
+        )
+        .unwrap();
+
+        assert!(run(dir.path(), HookEvent::PreRun, &ctx("dev")).is_ok());
+        assert!(run(dir.path(), HookEvent::PostRun, &ctx("prod")).is_ok());
+        let err = run(dir.path(), HookEvent::PreRun, &ctx("prod")).unwrap_err();
+        assert!(matches!(
+            err,
+            PacsError::HookRejected { ref message, .. } if message == "no prod releases"
+        ));
+        assert!(matches!(
+            run(dir.path(), HookEvent::Switch, &ctx("legacy")),
+            Err(PacsError::HookRejected { .. })
+        ));
+
+        fs::write(dir.path().join("broken.rhai"), "fn pre_run(ctx) {").unwrap();
+        assert!(matches!(
+            run(dir.path(), HookEvent::PreRun, &ctx("dev")),
+            Err(PacsError::HookFailed { .. })
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+//! Minimal HTTP client shelling out to `curl`.
+//!
+//! Requests are passed to curl as a config file on stdin, so credentials and
+//! bodies never show up in the process list.
+
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::process::{Command, Stdio};
+
+use crate::PacsError;
+
+/// An HTTP request to send with [`send`].
+pub(crate) struct Request<'a> {
+    pub method: &'a str,
+    pub url: &'a str,
+    pub headers: Vec<String>,
+    pub body: Option<String>,
+    /// Maximum time for the whole request in seconds.
+    pub timeout: Option<u64>,
+}
+
+impl<'a> Request<'a> {
+    pub fn get(url: &'a str) -> Self {
+        Self {
+            method: "GET",
+            url,
+            headers: Vec::new(),
+            body: None,
+            timeout: None,
+        }
+    }
+
+    #[must_use]
+    pub fn header(mut self, header: impl Into<String>) -> Self {
+        self.headers.push(header.into());
+        self
+    }
+
+    #[must_use]
+    pub fn timeout(mut self, seconds: u64) -> Self {
+        self.timeout = Some(seconds);
+        self
+    }
+
+    #[must_use]
+    pub fn body(mut self, method: &'a str, body: String) -> Self {
+        self.method = method;
+        self.body = Some(body);
+        self
+    }
+}
+
+/// Quotes a value for a curl config file.
+fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Sends a request and returns the response body. Fails on HTTP errors.
+pub(crate) fn send(request: &Request) -> Result<String, PacsError> {
+    let mut config = String::new();
+    let _ = writeln!(config, "url = {}", quote(request.url));
+    let _ = writeln!(config, "request = {}", quote(request.method));
+    for header in &request.headers {
+        let _ = writeln!(config, "header = {}", quote(header));
+    }
+    if let Some(body) = &request.body {
+        let _ = writeln!(config, "data-binary = {}", quote(body));
+    }
+    if let Some(timeout) = request.timeout {
+        let _ = writeln!(config, "max-time = {timeout}");
+    }
+
+    let mut child = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--location",
+            "--config",
+            "-",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(PacsError::FetchFailed(format!(
+            "{}: {}",
+            request.url,
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("plain"), "\"plain\"");
+        assert_eq!(
+            quote("{\"a\": \"b\\c\"}\nnext"),
+            r#""{\"a\": \"b\\c\"}\nnext""#
+        );
+    }
+}
@@ -0,0 +1,39 @@
+//! Guard against running Kubernetes commands against the wrong cluster.
+
+#[cfg(all(feature = "exec", feature = "fs-store"))]
+use std::process::Command;
+
+#[cfg(all(feature = "exec", feature = "fs-store"))]
+use crate::{PacsCommand, PacsError};
+
+/// Commands with this tag are checked against the environment's `kube_context`.
+pub const KUBE_TAG: &str = "k8s";
+
+/// Placeholder that resolves to the environment's `kube_context`.
+pub const KUBE_CONTEXT_PLACEHOLDER: &str = "kube_context";
+
+/// Verifies that kubectl's current context matches `expected` before running a
+/// `k8s` command. Commands that pass `--context` themselves are not checked.
+#[cfg(all(feature = "exec", feature = "fs-store"))]
+pub(crate) fn verify_context(cmd: &PacsCommand, expected: Option<&str>) -> Result<(), PacsError> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    if cmd.tag != KUBE_TAG || cmd.command.contains("--context") {
+        return Ok(());
+    }
+
+    let output = Command::new("kubectl")
+        .args(["config", "current-context"])
+        .output()?;
+    let current = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    if current == expected {
+        Ok(())
+    } else {
+        Err(PacsError::KubeContextMismatch {
+            expected: expected.to_string(),
+            current,
+        })
+    }
+}
@@ -4,14 +4,29 @@
 //!
 //! **Command Management:**
 //! - `add_command(cmd, project_name)` - Add a command to a project
+//! - `add_commands(cmds, project_name)` - Add several commands, all or none
 //! - `delete_command(name, project_name)` - Remove a command from a project
 //! - `list(project_name, environment)` - List all commands in a project
 //! - `run(name, project_name, environment)` - Execute a command
+//! - `danger_pattern(name, project_name, environment, values)` - Get the configured danger pattern a command matches
+//! - `check_run(cmd, project_name, environment)` - Check a resolved command before running it
 //! - `copy(name, project_name, environment)` - Get command text for clipboard
+//! - `on_this_machine(cmd)` - Apply the override for this host to a command
+//! - `set_command_dates_auto(name, expires, review_after)` - Mark a command as temporary
+//! - `expiring_commands()` - Get commands that have expired or are due for review
+//! - `find_duplicates(threshold)` - Group commands with identical or similar bodies
+//! - `find_conflicts()` - Get names claimed by more than one command or alias
+//! - `merge_commands(keep, others)` - Replace duplicates by aliases of one command
+//! - `similar_commands(body, threshold)` - Find commands with a body similar to a new one
+//! - `add_alias(project_name, name, alias)` - Give a command another name
 //!
 //! **Project Management:**
 //! - `init_project(name, path)` - Create a new project
+//! - `init_project_from_template(name, path, template)` - Create a project seeded from a template
+//! - `project_templates()` - Get the names of user and built-in project templates
+//! - `projects()` - Get all loaded projects, read-only
 //! - `delete_project(name)` - Remove a project and all its commands
+//! - `project_file(name)` / `replace_project(name, content)` - Edit a project file by hand, validated on save
 //! - `set_active_project(name)` - Set the active project
 //! - `get_active_project()` - Get the current active project name
 //!
@@ -19,7 +34,53 @@
 //! - `add_environment(project_name, env_name)` - Add an environment to a project
 //! - `remove_environment(project_name, env_name)` - Remove an environment
 //! - `set_active_environment(project_name, env_name)` - Set active environment for a project
+//! - `all_environments()` - Get the environments and active environment of every project
 //! - `edit_environment_values(project_name, env_name, values)` - Update environment values
+//! - `set_environment_kube_context(project_name, env_name, context)` - Guard `k8s` commands with a kubectl context
+//! - `direnv_export(project_name, env_name)` - Render environment values as an `.envrc` block
+//! - `write_direnv(project_name, path, hook)` - Write the `.envrc` block, optionally on every switch
+//!
+//! Environment values may be secret references like `op://vault/item/field`
+//! or `vault:secret/data/path#key`, which are resolved only when running or
+//! copying a command.
+//!
+//! **Export:**
+//! - `export(project_name, environment, tag, format)` - Render a project's commands as TOML, JSON, Markdown, a justfile, a Makefile or a GitHub Actions workflow
+//! - `export_selected(project_name, environment, names, format)` - Render only the named commands
+//!
+//! **Storage:**
+//! - `base_dir()` - Get the directory pacs stores its data in
+//! - `config_file()` / `projects_dir()` / `project_file(name)` - Locate the config and project files
+//!
+//! **Gists:**
+//! - `publish_project_gist(name)` - Push a project file to a secret gist
+//! - `pull_project_gist(id, force)` - Create a project from a gist, or replace one with force
+//!
+//! **Registries:**
+//! - `add_registry(source)` - Subscribe to a read-only remote command source
+//! - `remove_registry(name)` - Unsubscribe from a registry
+//! - `approve_registry_command(name, registry)` - Allow a registry command to run
+//! - `update_registries(max_age)` - Refetch registries whose cached copy is older than `max_age`
+//! - `registries()` - Get all subscribed registries with their cached commands
+//!
+//! **Templates:**
+//! - `templates()` / `search_templates(query)` - Browse the built-in command library
+//! - `add_template(template, name, project_name)` - Copy a template into a project
+//!
+//! **Highlighting:**
+//! - `tokenize_shell_line(line)` - Split a line of a command body into highlighted tokens
+//!
+//! **Hooks:**
+//! - `hook_context(command, project_name, environment)` - Describe a command run or switch for hooks
+//! - `run_hooks(event, ctx)` - Run the `pre_run`, `post_run` or `on_switch` functions in `hooks/*.rhai`
+//!
+//! **History:**
+//! - `history()` - Get all recorded runs, oldest first
+//! - `record_run(record)` - Append a run to the history, unless excluded in config.toml
+//! - `clear_history(project_name, before)` - Remove recorded runs
+//! - `webhook(record)` - Get the report of a run for its project's webhook
+//! - `unused_commands(project_name, window)` - Get commands not run within a time window
+//! - `archive_commands(commands)` - Move commands out of their projects into `archive/`
 //!
 //! ### Auto Functions (use active project)
 //!
@@ -28,15 +89,143 @@
 //! - `update_command_auto(name, command)` - Update a command in the active project
 //! - `rename_command_auto(old, new)` - Rename a command in the active project
 //! - `delete_command_auto(name)` - Delete a command from the active project
+//!
+//! ## Features
+//!
+//! - `search` - Fuzzy search over commands and templates
+//! - `exec` - Running commands in a shell and rhai hooks
+//! - `fs-store` - The `Pacs` store itself; with `exec` it also runs commands and hooks
+//!
+//! All are enabled by default. Without them, the crate only provides the data
+//! model, TOML parsing, placeholder rendering and exports, and compiles to
+//! `wasm32-unknown-unknown`, e.g. to view exported projects in a browser with
+//! `Versioned::from_json`.
 
 #![allow(clippy::missing_errors_doc)]
 #![allow(clippy::missing_panics_doc)]
 
-use fuzzy_matcher::FuzzyMatcher;
-use fuzzy_matcher::skim::SkimMatcherV2;
+mod aliases;
+#[cfg(feature = "fs-store")]
+mod bundle;
+mod cleanup;
+#[cfg(feature = "fs-store")]
+mod completion;
+mod conflicts;
+mod dedupe;
+mod diff;
+mod direnv;
+mod expiry;
+mod explain;
+mod export;
+#[cfg(feature = "fs-store")]
+mod gist;
+mod highlight;
+mod history;
+#[cfg(all(feature = "exec", feature = "fs-store"))]
+mod hooks;
+#[cfg(feature = "fs-store")]
+mod http;
+mod kube;
+mod paths;
+mod placeholders;
+mod quoting;
+#[cfg(feature = "fs-store")]
+mod registry;
+mod sandbox;
+#[cfg(all(feature = "search", feature = "fs-store"))]
+mod search;
+mod secrets;
+#[cfg(feature = "fs-store")]
+mod settings;
+#[cfg(feature = "fs-store")]
+mod suggest;
+#[cfg(feature = "fs-store")]
+mod sync;
+mod templates;
+mod views;
+#[cfg(feature = "fs-store")]
+mod webhook;
+
+pub use aliases::parse_aliases;
+#[cfg(feature = "fs-store")]
+pub use bundle::{Bundle, BundleConflict, BundleImport, Resolution};
+pub use cleanup::BodyCleanup;
+#[cfg(feature = "fs-store")]
+pub use completion::{CacheState, CacheStatus, CompletionCache, ProjectNames};
+pub use conflicts::{Claim, NameConflict};
+pub use dedupe::{CommandRef, DuplicateGroup, normalize_body, similarity};
+pub use diff::{DiffLine, Hunk, diff_lines, unified_diff};
+pub use direnv::{envrc_block, parse_env_file, variable_name};
+pub use expiry::{ExpiryStatus, format_date, parse_date, parse_interval, resolve_date, today};
+pub use explain::{ExplainedSegment, explain_command};
+pub use export::{ExportFormat, export_commands};
+pub use highlight::{TokenKind, tokenize_shell_line};
+pub use history::{
+    CommandStats, HistoryConfig, Retention, RunRecord, RunStats, last_runs, unix_now,
+};
+#[cfg(all(feature = "exec", feature = "fs-store"))]
+pub use hooks::{HookContext, HookEvent};
+pub use kube::{KUBE_CONTEXT_PLACEHOLDER, KUBE_TAG};
+pub use paths::{common_prefix, complete_dir, expand_home, project_dir};
+#[cfg(feature = "fs-store")]
+use placeholders::rename_placeholder;
+pub use placeholders::{Segment, placeholder_segments, render_placeholders};
+use placeholders::{interpolate_values, scan_placeholders};
+pub use quoting::{Shell, flatten_command};
+#[cfg(feature = "fs-store")]
+pub use registry::{Registry, RegistrySource};
+pub use sandbox::Sandbox;
+#[cfg(all(feature = "search", feature = "fs-store"))]
+pub use search::SearchMatch;
+pub use secrets::{REDACTED, is_secret_reference};
+#[cfg(feature = "fs-store")]
+pub use settings::{Setting, SettingSource};
+#[cfg(feature = "fs-store")]
+pub use suggest::SuggestConfig;
+#[cfg(feature = "fs-store")]
+pub use sync::SyncConflict;
+#[cfg(feature = "search")]
+pub use templates::search_templates;
+pub use templates::{ProjectTemplate, Template, templates};
+pub use views::{CommandView, ProjectView, RunView, VIEW_VERSION, Versioned};
+#[cfg(feature = "fs-store")]
+pub use webhook::Webhook;
+
 use serde::{Deserialize, Serialize, Serializer, ser::SerializeStruct as _};
-use std::{fs, path::PathBuf, process::Command};
+use std::collections::BTreeMap;
+#[cfg(any(feature = "exec", feature = "fs-store"))]
+use std::process::Command;
+#[cfg(all(feature = "exec", feature = "fs-store"))]
+use std::process::Stdio;
+#[cfg(all(feature = "exec", feature = "fs-store"))]
+use std::time::Instant;
+#[cfg(feature = "fs-store")]
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::{Duration, SystemTime},
+};
 use thiserror::Error;
+#[cfg(feature = "fs-store")]
+use tracing::{debug, trace};
+
+/// Number of recently run commands listed first unless `recent` is set in config.toml.
+pub const DEFAULT_RECENT: usize = 3;
+
+/// Number of projects remembered in `recent_projects`.
+#[cfg(feature = "fs-store")]
+const MAX_RECENT_PROJECTS: usize = 20;
+
+/// Placeholder that resolves to the project's path.
+pub const PROJECT_PATH_PLACEHOLDER: &str = "project_path";
+
+/// `cwd` of commands that ask for their working directory when they run.
+pub const ASK_CWD: &str = "ask";
+
+/// Key of the value that sets the working directory of commands with
+/// `cwd = "ask"`, filled like a placeholder.
+pub const CWD_KEY: &str = "cwd";
 
 /// Type alias for project names
 pub type ProjectName<'a> = &'a str;
@@ -55,24 +244,76 @@ pub enum PacsError {
     #[error("TOML serialization error: {0}")]
     TomlSer(#[from] toml::ser::Error),
 
+    #[error("JSON serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+
     #[error("Command not found: {0}")]
     CommandNotFound(String),
 
+    #[error("Invalid date: {0}, expected YYYY-MM-DD or an interval like 30d")]
+    InvalidDate(String),
+
+    #[error("Template not found: {0}")]
+    TemplateNotFound(String),
+
     #[error("Command already exists: {0}")]
     CommandExists(String),
 
+    #[error(
+        "Command '{name}' is in project '{project}', only commands of one project can be merged"
+    )]
+    MergeAcrossProjects { project: String, name: String },
+
     #[error("Command is marked as dangerous: {0}")]
     DangerousCommand(String),
 
+    #[error("Command was not confirmed: {0}")]
+    NotConfirmed(String),
+
+    #[error(
+        "Command '{name}' of registry '{registry}' is not approved, review it with pacs approve {name}"
+    )]
+    NotApproved { registry: String, name: String },
+
+    #[error(
+        "Command '{name}' of registry '{registry}' changed since it was approved, review it again with pacs approve {name}"
+    )]
+    ChangedSinceApproval { registry: String, name: String },
+
+    #[error("Body of command '{name}' does not match its checksum in registry '{registry}'")]
+    ChecksumMismatch { registry: String, name: String },
+
+    #[error("Command '{0}' is locked, unlock it or pass --force")]
+    CommandLocked(String),
+
+    #[error("Invalid danger pattern '{pattern}': {message}")]
+    InvalidDangerPattern { pattern: String, message: String },
+
+    #[error("pacs is in safe mode and runs nothing, unset safe_mode in config.toml or PACS_SAFE")]
+    SafeMode,
+
     #[error("Command execution failed with status: {0}")]
     CommandFailed(i32),
 
-    #[error("Unresolved placeholders: {0}")]
-    UnresolvedPlaceholders(String),
+    #[error("{hook} of environment '{environment}' failed: {message}")]
+    EnvironmentHookFailed {
+        hook: String,
+        environment: String,
+        message: String,
+    },
+
+    #[error("Command {command} is missing values for: {keys}")]
+    UnresolvedPlaceholders { command: String, keys: String },
+
+    #[error("Values of environment '{environment}' reference each other: {cycle}")]
+    ValueCycle { environment: String, cycle: String },
 
     #[error("Could not determine home directory")]
     HomeDirUnavailable,
 
+    #[error("No directory at {0}, pass --create to create it")]
+    DirectoryNotFound(String),
+
     #[error("Project already exists: {0}")]
     ProjectExists(String),
 
@@ -81,20 +322,119 @@ pub enum PacsError {
 
     #[error("No active project set")]
     NoActiveProject,
+
+    #[error("Registry already exists: {0}")]
+    RegistryExists(String),
+
+    #[error("Registry not found: {0}")]
+    RegistryNotFound(String),
+
+    #[error("Failed to fetch {0}")]
+    FetchFailed(String),
+
+    #[error(
+        "kubectl context is '{current}' but the environment expects '{expected}', run 'kubectl config use-context {expected}'"
+    )]
+    KubeContextMismatch { expected: String, current: String },
+
+    #[error("Failed to resolve secret {0}")]
+    SecretResolution(String),
+
+    #[error("Blocked by hook {script}: {message}")]
+    HookRejected { script: String, message: String },
+
+    #[error("Hook {script} failed: {message}")]
+    HookFailed { script: String, message: String },
+
+    #[error(
+        "pacs is in read-only mode, unset readonly in config.toml or PACS_READONLY to make changes"
+    )]
+    ReadOnly,
+
+    #[error("No GitHub token configured, set github_token in config.toml or GITHUB_TOKEN")]
+    GithubTokenMissing,
+
+    #[error("No suggest endpoint configured, add a [suggest] section with a url to config.toml")]
+    SuggestNotConfigured,
+
+    #[error("Sandboxing this command needs {0}, which is not available")]
+    SandboxUnavailable(String),
+
+    #[error("Project {0} is local only, unset local_only in its file to share it")]
+    LocalOnly(String),
+
+    #[error("Invalid project name: '{0}'")]
+    InvalidProjectName(String),
+
+    #[error("Invalid registry name: '{0}'")]
+    InvalidRegistryName(String),
+
+    #[error("Invalid registry URL '{0}', expected https://, http://, file://, ssh:// or git@")]
+    InvalidRegistryUrl(String),
+
+    #[error("Unsupported format version {found}, this pacs supports up to {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
 }
 
 /// A saved shell command that can be executed.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct PacsCommand {
     /// Unique identifier for this command within its project.
     pub name: String,
     /// The shell command to execute. Can contain `{{placeholder}}` values.
     pub command: String,
-    /// Working directory for execution. Uses current directory if None.
+    /// Working directory for execution. Uses current directory if None, and
+    /// asks at run time if `"ask"`.
     pub cwd: Option<String>,
     /// Optional tag for organization.
     #[serde(default)]
     pub tag: String,
+    /// Alternative names the command can be run by.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Date (`YYYY-MM-DD`) after which the command should be removed.
+    #[serde(default)]
+    pub expires: Option<String>,
+    /// Date (`YYYY-MM-DD`) after which the command should be reviewed.
+    #[serde(default)]
+    pub review_after: Option<String>,
+    /// Who created or last changed the command.
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Keys the environment must provide to run the command, in addition to
+    /// its placeholders.
+    #[serde(default)]
+    pub requires: Vec<String>,
+    /// Keeps the command on this machine: it is left out of gists, bundles and exports.
+    #[serde(default)]
+    pub local_only: bool,
+    /// Replacements for the body or working directory, keyed by hostname.
+    #[serde(default)]
+    pub overrides: BTreeMap<String, CommandOverride>,
+    /// Runs the command without confirmation even if it matches a danger pattern.
+    #[serde(default)]
+    pub safe: bool,
+    /// Always shows the resolved body and asks before running, even if
+    /// forced or marked `safe`.
+    #[serde(default)]
+    pub confirm: bool,
+    /// Refuses edits, renames and removal unless forced, for carefully
+    /// reviewed commands in shared libraries.
+    #[serde(default)]
+    pub locked: bool,
+    /// Restrictions applied when the command runs.
+    #[serde(default)]
+    pub sandbox: Option<Sandbox>,
+}
+
+/// Parts of a command that differ on one machine.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+pub struct CommandOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
 }
 
 impl Serialize for PacsCommand {
@@ -102,8 +442,15 @@ impl Serialize for PacsCommand {
     where
         S: Serializer,
     {
-        let mut s = serializer.serialize_struct("PacsCommand", 4)?;
+        let mut s = serializer.serialize_struct("PacsCommand", 15)?;
         s.serialize_field("name", &self.name)?;
+        for (key, value) in [("aliases", &self.aliases), ("requires", &self.requires)] {
+            if value.is_empty() {
+                s.skip_field(key)?;
+            } else {
+                s.serialize_field(key, value)?;
+            }
+        }
 
         // Append a newline so toml serializes this string as a multiline block
         let mut command = self.command.clone();
@@ -113,12 +460,178 @@ impl Serialize for PacsCommand {
 
         s.serialize_field("cwd", &self.cwd)?;
         s.serialize_field("tag", &self.tag)?;
+        for (key, value) in [
+            ("expires", &self.expires),
+            ("review_after", &self.review_after),
+            ("author", &self.author),
+        ] {
+            match value {
+                Some(value) => s.serialize_field(key, value)?,
+                None => s.skip_field(key)?,
+            }
+        }
+        for (key, value) in [
+            ("local_only", self.local_only),
+            ("safe", self.safe),
+            ("confirm", self.confirm),
+            ("locked", self.locked),
+        ] {
+            if value {
+                s.serialize_field(key, &true)?;
+            } else {
+                s.skip_field(key)?;
+            }
+        }
         s.serialize_field("command", &command)?;
+        match &self.sandbox {
+            Some(sandbox) => s.serialize_field("sandbox", sandbox)?,
+            None => s.skip_field("sandbox")?,
+        }
+        if self.overrides.is_empty() {
+            s.skip_field("overrides")?;
+        } else {
+            s.serialize_field("overrides", &self.overrides)?;
+        }
         s.end()
     }
 }
 
 impl PacsCommand {
+    /// Returns true if the command is called `name` or has it as an alias.
+    #[must_use]
+    pub fn matches(&self, name: &str) -> bool {
+        self.name == name || self.aliases.iter().any(|a| a == name)
+    }
+
+    /// Returns the groups of a namespaced name, outermost first: `db/` and
+    /// `db/migrate/` for `db/migrate/up`.
+    pub fn groups(&self) -> impl Iterator<Item = &str> {
+        self.name.match_indices('/').map(|(i, _)| &self.name[..=i])
+    }
+
+    /// Returns the name without its groups, `up` for `db/migrate/up`.
+    #[must_use]
+    pub fn leaf_name(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or_default()
+    }
+
+    /// Returns the command as it runs on the machine called `hostname`, with
+    /// its override for that machine applied.
+    #[must_use]
+    pub fn for_host(&self, hostname: &str) -> PacsCommand {
+        let mut cmd = self.clone();
+        if let Some(host) = self.overrides.get(hostname) {
+            if let Some(command) = &host.command {
+                cmd.command.clone_from(command);
+            }
+            if let Some(cwd) = &host.cwd {
+                cmd.cwd = Some(cwd.clone());
+            }
+        }
+        cmd
+    }
+
+    /// Returns the comment lines at the start of the body without their `#`,
+    /// which commands are described by. A shebang line is skipped.
+    #[must_use]
+    pub fn description(&self) -> Option<String> {
+        let lines: Vec<&str> = self
+            .command
+            .lines()
+            .map(str::trim)
+            .skip_while(|line| line.starts_with("#!"))
+            .map_while(|line| line.strip_prefix('#'))
+            .map(str::trim)
+            .collect();
+        let description = lines.join("\n").trim().to_string();
+        (!description.is_empty()).then_some(description)
+    }
+
+    /// Returns the distinct placeholder keys used in the command body, in order of appearance.
+    #[must_use]
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for key in scan_placeholders(&self.command) {
+            if !keys.iter().any(|k| k == key) {
+                keys.push(key.to_string());
+            }
+        }
+        keys
+    }
+
+    /// Returns the distinct placeholder keys used in the body and working directory.
+    #[must_use]
+    pub fn used_keys(&self) -> Vec<String> {
+        let mut keys = self.placeholders();
+        for key in self.cwd.iter().flat_map(|cwd| scan_placeholders(cwd)) {
+            if !keys.iter().any(|k| k == key) {
+                keys.push(key.to_string());
+            }
+        }
+        keys
+    }
+
+    /// Returns true if the command asks for its working directory when it
+    /// runs, with `cwd = "ask"`.
+    #[must_use]
+    pub fn asks_cwd(&self) -> bool {
+        self.cwd.as_deref() == Some(ASK_CWD)
+    }
+
+    /// Renames the key `old` to `new` in the placeholders of the body, the
+    /// working directory and host overrides, and in `requires`. Returns true
+    /// if anything changed.
+    #[cfg(feature = "fs-store")]
+    fn rename_key(&mut self, old: &str, new: &str) -> bool {
+        let overrides = self
+            .overrides
+            .values_mut()
+            .flat_map(|o| o.command.iter_mut().chain(o.cwd.iter_mut()));
+        let mut changed = false;
+        for text in std::iter::once(&mut self.command)
+            .chain(self.cwd.iter_mut())
+            .chain(overrides)
+        {
+            let renamed = rename_placeholder(text, old, new);
+            changed |= renamed != *text;
+            *text = renamed;
+        }
+        if self.requires.iter().any(|k| k == old) {
+            if self.requires.iter().any(|k| k == new) {
+                self.requires.retain(|k| k != old);
+            } else {
+                for key in self.requires.iter_mut().filter(|k| *k == old) {
+                    *key = new.to_string();
+                }
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// Returns the keys the command needs to run: the declared `requires`
+    /// followed by any other keys it uses.
+    #[must_use]
+    pub fn required_keys(&self) -> Vec<String> {
+        let mut keys = self.requires.clone();
+        for key in self.used_keys() {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    /// Builds the shell process that executes this command in its working
+    /// directory, within its sandbox if it has one.
+    #[cfg(feature = "exec")]
+    pub fn shell_command(&self) -> Result<Command, PacsError> {
+        self.sandbox
+            .clone()
+            .unwrap_or_default()
+            .shell_command(&self.command, self.cwd.as_deref())
+    }
+
     /// Finds a command by name in a slice.
     pub fn find_by_name<'a>(
         commands: &'a [PacsCommand],
@@ -126,7 +639,7 @@ impl PacsCommand {
     ) -> Result<&'a PacsCommand, PacsError> {
         commands
             .iter()
-            .find(|c| c.name == name)
+            .find(|c| c.matches(name))
             .ok_or_else(|| PacsError::CommandNotFound(name.to_string()))
     }
 
@@ -137,7 +650,7 @@ impl PacsCommand {
     ) -> Result<&'a mut PacsCommand, PacsError> {
         commands
             .iter_mut()
-            .find(|c| c.name == name)
+            .find(|c| c.matches(name))
             .ok_or_else(|| PacsError::CommandNotFound(name.to_string()))
     }
 }
@@ -150,10 +663,52 @@ pub struct Environment {
     /// Key-value pairs used to render placeholders like `{key}`.
     #[serde(default)]
     pub values: std::collections::BTreeMap<String, String>,
+    /// kubectl context that commands tagged `k8s` must run against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kube_context: Option<String>,
+    /// Shell command run after switching to this environment, e.g. to point
+    /// `gcloud` at the matching project. Placeholders are rendered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_activate: Option<String>,
+    /// Shell command run before switching away from this environment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_deactivate: Option<String>,
+}
+
+impl Environment {
+    /// Returns the values used to render placeholders, including `{{kube_context}}`.
+    /// Values may reference other values, e.g. `url = "https://{{host}}"`,
+    /// which are rendered recursively. Values on a cycle are left as they are.
+    #[must_use]
+    pub fn placeholder_values(&self) -> BTreeMap<String, String> {
+        self.interpolated().0
+    }
+
+    /// Returns an error if values reference each other in a cycle.
+    pub fn check_references(&self) -> Result<(), PacsError> {
+        match self.interpolated().1 {
+            Some(cycle) => Err(PacsError::ValueCycle {
+                environment: self.name.clone(),
+                cycle: cycle.join(" -> "),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn interpolated(&self) -> (BTreeMap<String, String>, Option<Vec<String>>) {
+        let mut values = self.values.clone();
+        if let Some(context) = &self.kube_context {
+            values
+                .entry(KUBE_CONTEXT_PLACEHOLDER.to_string())
+                .or_insert_with(|| context.clone());
+        }
+        // Secret references are resolved later, so they cannot be inlined here
+        interpolate_values(&values, is_secret_reference)
+    }
 }
 
 /// A collection of commands associated with a project.
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Project {
     /// Unique project identifier.
     pub name: String,
@@ -168,146 +723,853 @@ pub struct Project {
     /// The active environment name used to render placeholders for this project.
     #[serde(default)]
     pub active_environment: Option<String>,
+    /// Keeps the whole project on this machine: it is left out of bundles and
+    /// cannot be published.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub local_only: bool,
+    /// Hides a finished project from project listings, the TUI and completion.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    /// Color of the project name in listings and the TUI, like a tag color.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Shown before the project name in listings and the TUI.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Free-form markdown notes, such as conventions for using the commands.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// URL that receives a JSON report whenever a command of the project finishes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+    /// Shell command run after `pacs project switch` makes this the active
+    /// project, e.g. to bring up a VPN. Runs in the project's path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_switch: Option<String>,
+}
+
+/// Output of a hook command that ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookOutput {
+    pub exit_code: i32,
+    /// Standard output followed by standard error, with secrets masked.
+    pub output: String,
+}
+
+impl HookOutput {
+    #[must_use]
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+impl Project {
+    /// Returns a copy without the commands marked as local only and without
+    /// the webhook, whose URL usually carries a token.
+    #[must_use]
+    pub fn shareable(&self) -> Self {
+        Self {
+            webhook: None,
+            commands: self
+                .commands
+                .iter()
+                .filter(|c| !c.local_only)
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+/// A project file that was skipped because it could not be read or parsed.
+#[cfg(feature = "fs-store")]
+#[derive(Debug, Clone)]
+pub struct LoadWarning {
+    /// Project name derived from the file stem.
+    pub project: String,
+    /// Path of the file that failed to load.
+    pub path: PathBuf,
+    /// Reason the file could not be loaded.
+    pub message: String,
 }
 
 /// Configuration stored in config.toml
+#[cfg(feature = "fs-store")]
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Config {
     /// The currently active project name.
     #[serde(default)]
     pub active_project: Option<String>,
+    /// Projects switched to, most recent first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recent_projects: Vec<String>,
+    /// Subscribed read-only registries.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub registries: Vec<RegistrySource>,
+    /// GitHub token used to publish projects as gists.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github_token: Option<String>,
+    /// Gist ids of published or pulled projects, by project name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub gists: BTreeMap<String, String>,
+    /// `.envrc` files rewritten when the active environment of a project changes.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub direnv_hooks: BTreeMap<String, PathBuf>,
+    /// Name recorded as the author of added or changed commands. Defaults to
+    /// `git config user.name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Rejects all changes to projects, commands, environments and registries.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub readonly: bool,
+    /// Shows commands instead of running them, e.g. for demos.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub safe_mode: bool,
+    /// Regular expressions for command bodies that need a confirmation to run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub danger_patterns: Vec<String>,
+    /// Colors and icons of tags in listings, by tag name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, TagStyle>,
+    /// Number of recently run commands listed first, [`DEFAULT_RECENT`] if
+    /// unset and none for 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recent: Option<usize>,
+    /// Prints the exit code, duration, working directory and environment
+    /// after `pacs run`. On unless false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_summary: Option<bool>,
+    /// Endpoint used by `pacs suggest`. Nothing is sent anywhere without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggest: Option<SuggestConfig>,
+    /// Commands, tags and projects whose runs are not recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<HistoryConfig>,
+}
+
+/// How a tag is shown in listings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagStyle {
+    /// A color name like `red` or `bright-blue`, or a hex color like `#ff8800`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Shown before the tag name, e.g. a nerd font glyph.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
 }
 
 /// Main container managing projects and their commands.
+#[cfg(feature = "fs-store")]
 #[derive(Debug, Serialize, Deserialize, Default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Pacs {
     /// Registered projects with their own commands.
+    #[deprecated(
+        since = "0.1.4",
+        note = "use `Pacs::projects()`; changes go through the command and project methods"
+    )]
     pub projects: Vec<Project>,
     #[serde(skip)]
     base_dir: PathBuf,
+    #[serde(skip)]
+    load_warnings: Vec<LoadWarning>,
+    #[serde(skip)]
+    config_error: Option<String>,
+    #[serde(skip)]
+    registries: Vec<Registry>,
+    #[serde(skip)]
+    secrets: secrets::SecretCache,
+    #[serde(skip)]
+    readonly: bool,
+    #[serde(skip)]
+    safe_mode: bool,
+    #[serde(skip)]
+    reveal_secrets: bool,
+    #[serde(skip)]
+    ignore_locks: bool,
+    #[serde(skip)]
+    loaded_at: Option<SystemTime>,
+    #[serde(skip)]
+    session: Session,
+    #[serde(skip)]
+    hostname: OnceLock<Option<String>>,
+}
+
+/// Selections that only apply to the current terminal session.
+#[cfg(feature = "fs-store")]
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    /// Project from `PACS_PROJECT`.
+    pub project: Option<String>,
+    /// Environment of the active project from `PACS_ENV`.
+    pub environment: Option<String>,
+}
+
+#[cfg(feature = "fs-store")]
+impl Session {
+    fn from_env() -> Self {
+        let var = |key| std::env::var(key).ok().filter(|v: &String| !v.is_empty());
+        Self {
+            project: var("PACS_PROJECT"),
+            environment: var("PACS_ENV"),
+        }
+    }
+}
+
+/// The environments of one project, as listed by [`Pacs::all_environments`].
+#[cfg(feature = "fs-store")]
+#[derive(Debug, Clone, Copy)]
+pub struct ProjectEnvironments<'a> {
+    pub project: &'a str,
+    pub environments: &'a [Environment],
+    /// Active environment, taking `PACS_ENV` into account for the active project.
+    pub active: Option<&'a str>,
 }
 
+// The deprecated `projects` field stays the storage until it becomes private
+#[cfg(feature = "fs-store")]
+#[allow(deprecated)]
 impl Pacs {
-    /// Initializes Pacs home directory at ~/.pacs/
+    /// Initializes Pacs home directory at `$PACS_HOME`, or ~/.pacs/ if unset.
     pub fn init_home() -> Result<Self, PacsError> {
+        Self::init_at(Self::home()?)
+    }
+
+    /// Returns `$PACS_HOME`, or ~/.pacs/ if unset.
+    fn home() -> Result<PathBuf, PacsError> {
+        if let Some(home) = std::env::var_os("PACS_HOME").filter(|v| !v.is_empty()) {
+            return Ok(PathBuf::from(home));
+        }
         let mut base = dirs::home_dir().ok_or(PacsError::HomeDirUnavailable)?;
         base.push(".pacs");
-        Self::init_at(base)
+        Ok(base)
+    }
+
+    /// Returns the names for shell completion from `completions.toml` in the
+    /// home directory, loading all projects only if the cache is outdated.
+    pub fn completions() -> Result<CompletionCache, PacsError> {
+        let base = Self::home()?;
+        let config: Option<Config> = fs::read_to_string(base.join("config.toml"))
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok());
+        let cached = CompletionCache::load(&base.join("completions.toml"), &base.join("projects"));
+        let mut cache = if let Ok(cache) = cached {
+            cache
+        } else {
+            let pacs = Self::init_at(base)?;
+            pacs.refresh_completions(None);
+            CompletionCache::new(pacs.projects(), &pacs.projects_dir())
+        };
+        let (active, recent) = config
+            .map(|c| (c.active_project, c.recent_projects))
+            .unwrap_or_default();
+        cache.active_project = Session::from_env().project.or(active);
+        cache.recent_projects = recent;
+        Ok(cache)
+    }
+
+    /// Rewrites `completions.toml`, with `saved` in place of the loaded
+    /// project of the same name.
+    fn refresh_completions(&self, saved: Option<&Project>) {
+        let projects = self
+            .projects
+            .iter()
+            .filter(|p| saved.is_none_or(|s| s.name != p.name))
+            .chain(saved);
+        let path = self.completions_file();
+        if let Err(err) = CompletionCache::new(projects, &self.projects_dir()).save(&path) {
+            debug!("failed to write {}: {err}", path.display());
+        }
+    }
+
+    /// Rewrites `completions.toml` from the loaded projects.
+    pub fn rebuild_completions(&self) -> Result<(), PacsError> {
+        CompletionCache::new(&self.projects, &self.projects_dir()).save(&self.completions_file())
+    }
+
+    /// Returns whether `completions.toml` is up to date.
+    #[must_use]
+    pub fn completions_status(&self) -> CacheStatus {
+        let path = self.completions_file();
+        let (state, projects, commands) = match CompletionCache::load(&path, &self.projects_dir()) {
+            Ok(cache) => (
+                CacheState::Fresh,
+                cache.projects.len(),
+                cache.projects.values().map(|p| p.commands.len()).sum(),
+            ),
+            Err(state) => (state, 0, 0),
+        };
+        CacheStatus {
+            path,
+            state,
+            projects,
+            commands,
+        }
+    }
+
+    fn completions_file(&self) -> PathBuf {
+        self.base_dir.join("completions.toml")
     }
 
     /// Initializes Pacs at a custom base path.
     pub fn init_at(base: PathBuf) -> Result<Self, PacsError> {
         let projects_dir = base.join("projects");
 
-        if !base.exists() {
+        if !projects_dir.exists() {
             fs::create_dir_all(&projects_dir)?;
         }
 
-        let projects = Self::load_projects(&projects_dir)?;
+        let (projects, load_warnings) = Self::load_projects(&projects_dir)?;
 
-        Ok(Self {
+        let mut pacs = Self {
             projects,
             base_dir: base,
-        })
+            load_warnings,
+            config_error: None,
+            registries: Vec::new(),
+            secrets: secrets::SecretCache::default(),
+            readonly: false,
+            safe_mode: false,
+            reveal_secrets: false,
+            ignore_locks: false,
+            loaded_at: None,
+            session: Session::from_env(),
+            hostname: OnceLock::new(),
+        };
+        pacs.loaded_at = pacs.last_modified();
+        // A broken config.toml must not lock out `pacs open --config`, which fixes it
+        let config = pacs.load_config().unwrap_or_else(|e| {
+            pacs.config_error = Some(e.to_string());
+            Config::default()
+        });
+        let registries_dir = pacs.registries_dir();
+        pacs.readonly = config.readonly || env_flag("PACS_READONLY");
+        pacs.safe_mode = config.safe_mode || env_flag("PACS_SAFE");
+        pacs.registries = config
+            .registries
+            .into_iter()
+            .map(|source| registry::load_cached(&registries_dir, source))
+            .collect();
+
+        Ok(pacs)
     }
 
-    /// Loads the config from config.toml.
-    fn load_config(&self) -> Result<Config, PacsError> {
-        let path = self.base_dir.join("config.toml");
-        if path.exists() {
-            let content = fs::read_to_string(&path)?;
-            if content.trim().is_empty() {
-                Ok(Config::default())
-            } else {
-                Ok(toml::from_str(&content)?)
-            }
-        } else {
-            Ok(Config::default())
-        }
+    /// Returns the tag colors and icons from config.toml.
+    #[must_use]
+    pub fn tag_styles(&self) -> BTreeMap<String, TagStyle> {
+        self.load_config().map(|c| c.tags).unwrap_or_default()
     }
 
-    /// Saves the config to config.toml.
-    fn save_config(&self, config: &Config) -> Result<(), PacsError> {
-        fs::write(
-            self.base_dir.join("config.toml"),
-            toml::to_string_pretty(config)?,
-        )?;
-        Ok(())
+    /// Returns true if changes are rejected, set by `readonly = true` in
+    /// config.toml or `PACS_READONLY=1`.
+    #[must_use]
+    pub fn is_readonly(&self) -> bool {
+        self.readonly
     }
 
-    /// Sets the active project by name.
-    pub fn set_active_project(&self, name: ProjectName) -> Result<(), PacsError> {
-        self.get_project(name)?;
-        let mut config = self.load_config()?;
-        config.active_project = Some(name.to_string());
-        self.save_config(&config)?;
-        Ok(())
+    /// Returns true if commands are never run, set by `safe_mode = true` in
+    /// config.toml, `PACS_SAFE=1` or [`Pacs::set_safe_mode`].
+    #[must_use]
+    pub fn is_safe_mode(&self) -> bool {
+        self.safe_mode
     }
 
-    /// Clears the active project.
-    pub fn clear_active_project(&self) -> Result<(), PacsError> {
-        let mut config = self.load_config()?;
-        config.active_project = None;
-        self.save_config(&config)?;
-        Ok(())
+    /// Turns on safe mode for this instance, e.g. for `pacs --safe`.
+    pub fn set_safe_mode(&mut self) {
+        self.safe_mode = true;
     }
 
-    /// Returns the name of the active project.
-    pub fn get_active_project_name(&self) -> Result<String, PacsError> {
-        let config = self.load_config()?;
-        let name = config.active_project.ok_or(PacsError::NoActiveProject)?;
-        self.get_project(&name)?;
-        Ok(name)
+    /// Shows secret values instead of their references and masks, e.g. for
+    /// `pacs --reveal`.
+    pub fn set_reveal_secrets(&mut self) {
+        self.reveal_secrets = true;
     }
 
-    /// Returns a reference to the active project.
-    pub fn get_active_project(&self) -> Result<&Project, PacsError> {
-        let config = self.load_config()?;
-        let name = config.active_project.ok_or(PacsError::NoActiveProject)?;
-        self.get_project(&name)
+    /// Allows changing locked commands, e.g. for `pacs rm --force`.
+    pub fn set_ignore_locks(&mut self) {
+        self.ignore_locks = true;
     }
 
-    /// Returns a reference to the specified project, or the active project if none specified.
-    pub fn get_project_or_active(&self, name: Option<ProjectName>) -> Result<&Project, PacsError> {
-        match name {
-            Some(n) => self.get_project(n),
-            None => self.get_active_project(),
+    /// Masks the secret values resolved by this instance in `text`, such as
+    /// the output of a command, unless secrets are revealed.
+    #[must_use]
+    pub fn redact(&self, text: &str) -> String {
+        if self.reveal_secrets {
+            return text.to_string();
         }
+        self.secrets.redact(text)
     }
 
-    /// Returns a mutable reference to the active project.
-    pub fn get_active_project_mut(&mut self) -> Result<&mut Project, PacsError> {
-        let name = self.get_active_project_name()?;
-        self.get_project_mut(&name)
-    }
+    /// Returns the settings pacs uses with the source of each value: the
+    /// defaults, config.toml, environment variables or flags. Settings that
+    /// are unset and have no default are left out.
+    pub fn effective_settings(&self) -> Result<Vec<Setting>, PacsError> {
+        use SettingSource::{ConfigFile, Env, Flag, Git};
 
-    /// Returns a mutable reference to the specified project, or the active project if none specified.
-    pub fn get_project_or_active_mut(
-        &mut self,
-        name: Option<ProjectName>,
-    ) -> Result<&mut Project, PacsError> {
-        match name {
-            Some(n) => self.get_project_mut(n),
-            None => self.get_active_project_mut(),
+        let config = self.load_config()?;
+        let mut table = toml::Table::try_from(&config)?;
+        for key in [
+            "active_project",
+            "author",
+            "readonly",
+            "safe_mode",
+            "recent",
+            "run_summary",
+        ] {
+            table.remove(key);
         }
-    }
+        let mask = |value: String| {
+            if self.reveal_secrets {
+                value
+            } else {
+                REDACTED.to_string()
+            }
+        };
 
-    /// Creates a new project with the given name and optional path.
-    pub fn init_project(
+        let home = SettingSource::first(&[(
+            std::env::var_os("PACS_HOME").is_some_and(|v| !v.is_empty()),
+            Env("PACS_HOME"),
+        )]);
+        let mut settings = vec![Setting::new(
+            "home",
+            self.base_dir.display().to_string(),
+            home,
+        )];
+        if let Some(project) = &self.session.project {
+            settings.push(Setting::new(
+                "active_project",
+                project.as_str(),
+                Env("PACS_PROJECT"),
+            ));
+        } else if let Some(project) = config.active_project {
+            settings.push(Setting::new("active_project", project, ConfigFile));
+        }
+        if let Some(environment) = &self.session.environment {
+            settings.push(Setting::new(
+                "active_environment",
+                environment.as_str(),
+                Env("PACS_ENV"),
+            ));
+        }
+        if let Some(author) = config.author {
+            settings.push(Setting::new("author", author, ConfigFile));
+        } else if let Some(author) = self.author() {
+            settings.push(Setting::new("author", author, Git));
+        }
+
+        let readonly = SettingSource::first(&[
+            (config.readonly, ConfigFile),
+            (self.readonly, Env("PACS_READONLY")),
+        ]);
+        settings.push(Setting::new("readonly", self.readonly, readonly));
+        let safe_mode = SettingSource::first(&[
+            (config.safe_mode, ConfigFile),
+            (env_flag("PACS_SAFE"), Env("PACS_SAFE")),
+            (self.safe_mode, Flag("--safe")),
+        ]);
+        settings.push(Setting::new("safe_mode", self.safe_mode, safe_mode));
+        let reveal = SettingSource::first(&[(self.reveal_secrets, Flag("--reveal"))]);
+        settings.push(Setting::new("reveal", self.reveal_secrets, reveal));
+        let recent = SettingSource::first(&[(config.recent.is_some(), ConfigFile)]);
+        let limit = config.recent.unwrap_or(DEFAULT_RECENT);
+        settings.push(Setting::new(
+            "recent",
+            i64::try_from(limit).unwrap_or(i64::MAX),
+            recent,
+        ));
+        let run_summary = SettingSource::first(&[(config.run_summary.is_some(), ConfigFile)]);
+        settings.push(Setting::new(
+            "run_summary",
+            config.run_summary.unwrap_or(true),
+            run_summary,
+        ));
+
+        table.remove("github_token");
+        if let Some(token) = config.github_token {
+            settings.push(Setting::new("github_token", mask(token), ConfigFile));
+        } else if let Some(token) = Self::github_token(&Config::default()) {
+            settings.push(Setting::new(
+                "github_token",
+                mask(token),
+                Env("GITHUB_TOKEN"),
+            ));
+        }
+        if let Some(toml::Value::Table(suggest)) = table.get_mut("suggest")
+            && let Some(key) = suggest.get_mut("api_key")
+        {
+            *key = mask(key.as_str().unwrap_or_default().to_string()).into();
+        }
+        for (key, value) in table {
+            settings.push(Setting::new(&key, value, ConfigFile));
+        }
+        Ok(settings)
+    }
+
+    /// Returns true unless `run_summary = false` in config.toml.
+    #[must_use]
+    pub fn shows_run_summary(&self) -> bool {
+        self.load_config()
+            .ok()
+            .and_then(|c| c.run_summary)
+            .unwrap_or(true)
+    }
+
+    /// Returns the author recorded on changed commands, from config.toml or
+    /// `git config user.name`.
+    fn author(&self) -> Option<String> {
+        if let Some(author) = self.load_config().ok().and_then(|c| c.author) {
+            return Some(author);
+        }
+        let output = Command::new("git")
+            .args(["config", "user.name"])
+            .output()
+            .ok()?;
+        let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !name.is_empty()).then_some(name)
+    }
+
+    /// Returns the name of this machine, which picks the command overrides.
+    fn hostname(&self) -> Option<&str> {
+        self.hostname
+            .get_or_init(|| {
+                let output = Command::new("hostname").output().ok();
+                let name = output
+                    .filter(|o| o.status.success())
+                    .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+                    .or_else(|| std::env::var("HOSTNAME").ok())?;
+                (!name.is_empty()).then_some(name)
+            })
+            .as_deref()
+    }
+
+    /// Returns `cmd` with the override for this machine applied.
+    #[must_use]
+    pub fn on_this_machine(&self, cmd: &PacsCommand) -> PacsCommand {
+        match self.hostname() {
+            Some(hostname) => cmd.for_host(hostname),
+            None => cmd.clone(),
+        }
+    }
+
+    fn ensure_writable(&self) -> Result<(), PacsError> {
+        if self.readonly {
+            return Err(PacsError::ReadOnly);
+        }
+        Ok(())
+    }
+
+    /// Refuses to change a locked command of a project, or of the active
+    /// project if `None`, unless locks are ignored. Unknown commands pass, so
+    /// that callers report them as usual.
+    pub fn ensure_unlocked(
+        &self,
+        project_name: Option<ProjectName>,
+        name: &str,
+    ) -> Result<(), PacsError> {
+        if self.ignore_locks {
+            return Ok(());
+        }
+        let project = self.get_project_or_active(project_name)?;
+        match PacsCommand::find_by_name(&project.commands, name) {
+            Ok(cmd) if cmd.locked => Err(PacsError::CommandLocked(cmd.name.clone())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns all loaded projects.
+    #[must_use]
+    pub fn projects(&self) -> &[Project] {
+        &self.projects
+    }
+
+    /// Returns the project files that were skipped while loading.
+    #[must_use]
+    pub fn load_warnings(&self) -> &[LoadWarning] {
+        &self.load_warnings
+    }
+
+    /// Returns why config.toml could not be loaded, in which case the
+    /// defaults are used until it is fixed.
+    #[must_use]
+    pub fn config_error(&self) -> Option<&str> {
+        self.config_error.as_deref()
+    }
+
+    /// Loads the config from config.toml.
+    fn load_config(&self) -> Result<Config, PacsError> {
+        let path = self.config_file();
+        if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            debug!("loaded {}", path.display());
+            if content.trim().is_empty() {
+                Ok(Config::default())
+            } else {
+                Ok(toml::from_str(&content)?)
+            }
+        } else {
+            Ok(Config::default())
+        }
+    }
+
+    /// Saves the config to config.toml.
+    fn save_config(&self, config: &Config) -> Result<(), PacsError> {
+        write_atomic(&self.config_file(), &toml::to_string_pretty(config)?)
+    }
+
+    /// Takes an exclusive lock on the pacs directory, held until the returned
+    /// file is dropped. Guards read-modify-write cycles against other processes.
+    fn lock(&self) -> Result<fs::File, PacsError> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.base_dir.join(".lock"))?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    /// Loads, changes and saves the config while holding the lock.
+    fn update_config<T>(
+        &self,
+        f: impl FnOnce(&mut Config) -> Result<T, PacsError>,
+    ) -> Result<T, PacsError> {
+        let _lock = self.lock()?;
+        let mut config = self.load_config()?;
+        let result = f(&mut config)?;
+        self.save_config(&config)?;
+        Ok(result)
+    }
+
+    /// Returns the latest modification time of the config and project files.
+    fn last_modified(&self) -> Option<SystemTime> {
+        let projects_dir = self.projects_dir();
+        let files = fs::read_dir(&projects_dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|e| e.path());
+        [self.config_file(), projects_dir]
+            .into_iter()
+            .chain(files)
+            .filter_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+            .max()
+    }
+
+    /// Reloads the projects if the config or a project file changed since
+    /// they were loaded, e.g. by another pacs process. Returns true if reloaded.
+    pub fn reload_if_changed(&mut self) -> Result<bool, PacsError> {
+        let modified = self.last_modified();
+        if modified == self.loaded_at {
+            return Ok(false);
+        }
+        let (projects, load_warnings) = Self::load_projects(&self.projects_dir())?;
+        self.projects = projects;
+        self.load_warnings = load_warnings;
+        self.loaded_at = modified;
+        Ok(true)
+    }
+
+    /// Sets the active project by name.
+    pub fn set_active_project(&self, name: ProjectName) -> Result<(), PacsError> {
+        #[cfg(feature = "exec")]
+        self.run_hooks(
+            HookEvent::Switch,
+            &self.hook_context(None, Some(name), None)?,
+        )?;
+        self.update_config(|config| {
+            // Projects activated before switches were remembered
+            if let Some(previous) = config.active_project.take() {
+                remember_project(&mut config.recent_projects, &previous);
+            }
+            remember_project(&mut config.recent_projects, name);
+            config.active_project = Some(name.to_string());
+            Ok(())
+        })
+    }
+
+    /// Returns the project that was active before the active one, for
+    /// `pacs project switch -`.
+    pub fn previous_project(&self) -> Result<Option<String>, PacsError> {
+        let config = self.load_config()?;
+        let active = config.active_project.unwrap_or_default();
+        Ok(config
+            .recent_projects
+            .iter()
+            .filter(|name| !name.eq_ignore_ascii_case(&active))
+            .find_map(|name| self.get_project(name).ok())
+            .map(|project| project.name.clone()))
+    }
+
+    /// Returns all projects, the most recently switched to first and the
+    /// others in their usual order after them.
+    pub fn projects_by_recency(&self) -> Result<Vec<&Project>, PacsError> {
+        let recent = self.load_config()?.recent_projects;
+        let mut projects: Vec<&Project> = self.projects.iter().collect();
+        projects.sort_by_key(|project| {
+            recent
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(&project.name))
+                .unwrap_or(usize::MAX)
+        });
+        Ok(projects)
+    }
+
+    /// Sets the active project by name and, if it changed, runs the
+    /// project's `on_switch` command unless `run_hook` is false or in safe
+    /// mode. Returns the output of the command if it ran.
+    #[cfg(feature = "exec")]
+    pub fn switch_project(
+        &self,
+        name: ProjectName,
+        run_hook: bool,
+    ) -> Result<Option<HookOutput>, PacsError> {
+        let project = self.get_project(name)?;
+        let previous = self.load_config()?.active_project;
+        self.set_active_project(&project.name)?;
+
+        let changed = previous.is_none_or(|p| !p.eq_ignore_ascii_case(&project.name));
+        let Some(body) = project.on_switch.as_deref().filter(|_| changed && run_hook) else {
+            return Ok(None);
+        };
+        if self.safe_mode {
+            debug!(project = %project.name, "on_switch skipped in safe mode");
+            return Ok(None);
+        }
+        let cwd = project.path.as_deref().filter(|p| Path::new(p).is_dir());
+        let environment = self.active_environment_of(project);
+        let output = self
+            .hook_command(project, environment, body, cwd)?
+            .stdin(Stdio::null())
+            .output()?;
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        Ok(Some(HookOutput {
+            exit_code: output.status.code().unwrap_or(-1),
+            output: self.redact(text.trim_end()),
+        }))
+    }
+
+    /// Clears the active project.
+    pub fn clear_active_project(&self) -> Result<(), PacsError> {
+        self.update_config(|config| {
+            config.active_project = None;
+            Ok(())
+        })
+    }
+
+    /// Returns the name of the active project, which `PACS_PROJECT` overrides.
+    pub fn get_active_project_name(&self) -> Result<String, PacsError> {
+        Ok(self.get_active_project()?.name.clone())
+    }
+
+    /// Returns a reference to the active project, which `PACS_PROJECT` overrides.
+    pub fn get_active_project(&self) -> Result<&Project, PacsError> {
+        let name = match &self.session.project {
+            Some(name) => name.clone(),
+            None => self
+                .load_config()?
+                .active_project
+                .ok_or(PacsError::NoActiveProject)?,
+        };
+        self.get_project(&name)
+    }
+
+    /// Returns the project and environment selected for this terminal session
+    /// by `PACS_PROJECT` and `PACS_ENV`, which take precedence over the
+    /// persisted active project and environment.
+    #[must_use]
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Makes `name` the active project of this instance, e.g. for
+    /// `pacs --project`. `PACS_ENV` is dropped if it was meant for another
+    /// project.
+    pub fn scope_to_project(&mut self, name: ProjectName) -> Result<(), PacsError> {
+        let name = self.get_project(name)?.name.clone();
+        if self.get_active_project_name().ok().as_ref() != Some(&name) {
+            self.session.environment = None;
+        }
+        self.session.project = Some(name);
+        Ok(())
+    }
+
+    /// Returns a reference to the specified project, or the active project if none specified.
+    pub fn get_project_or_active(&self, name: Option<ProjectName>) -> Result<&Project, PacsError> {
+        match name {
+            Some(n) => self.get_project(n),
+            None => self.get_active_project(),
+        }
+    }
+
+    /// Returns a mutable reference to the active project.
+    pub fn get_active_project_mut(&mut self) -> Result<&mut Project, PacsError> {
+        let name = self.get_active_project_name()?;
+        self.get_project_mut(&name)
+    }
+
+    /// Returns a mutable reference to the specified project, or the active project if none specified.
+    pub fn get_project_or_active_mut(
+        &mut self,
+        name: Option<ProjectName>,
+    ) -> Result<&mut Project, PacsError> {
+        match name {
+            Some(n) => self.get_project_mut(n),
+            None => self.get_active_project_mut(),
+        }
+    }
+
+    /// Creates a new project with the given name and optional path.
+    pub fn init_project(
+        &mut self,
+        name: ProjectName,
+        path: Option<String>,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        if let Ok(existing) = self.get_project(name) {
+            return Err(PacsError::ProjectExists(existing.name.clone()));
+        }
+
+        let project = Project {
+            name: name.to_string(),
+            path,
+            ..Project::default()
+        };
+
+        self.save_project(&project)?;
+        self.projects.push(project);
+        Ok(())
+    }
+
+    /// Creates a new project with the commands and environments of a template.
+    ///
+    /// `template` is a path to a TOML file, or the name of a template in
+    /// `templates/` or of a built-in one (`rust`, `node`, `python`).
+    pub fn init_project_from_template(
         &mut self,
         name: ProjectName,
         path: Option<String>,
+        template: &str,
     ) -> Result<(), PacsError> {
-        if self.projects.iter().any(|p| p.name == name) {
-            return Err(PacsError::ProjectExists(name.to_string()));
+        self.ensure_writable()?;
+        if let Ok(existing) = self.get_project(name) {
+            return Err(PacsError::ProjectExists(existing.name.clone()));
         }
+        let template = templates::load_project_template(&self.templates_dir(), template)?;
 
         let project = Project {
             name: name.to_string(),
             path,
-            commands: Vec::new(),
-            environments: Vec::new(),
-            active_environment: None,
+            commands: template.commands,
+            environments: template.environments,
+            active_environment: template.active_environment,
+            local_only: false,
+            archived: false,
+            color: None,
+            icon: None,
+            notes: None,
+            webhook: None,
+            on_switch: None,
         };
 
         self.save_project(&project)?;
@@ -315,8 +1577,19 @@ impl Pacs {
         Ok(())
     }
 
+    /// Returns the names of user templates in `templates/`, followed by the built-in ones.
+    #[must_use]
+    pub fn project_templates(&self) -> Vec<String> {
+        templates::project_template_names(&self.templates_dir())
+    }
+
+    fn templates_dir(&self) -> PathBuf {
+        self.base_dir.join("templates")
+    }
+
     /// Removes a project and its associated file.
     pub fn delete_project(&mut self, name: ProjectName) -> Result<(), PacsError> {
+        self.ensure_writable()?;
         let idx = self
             .projects
             .iter()
@@ -329,126 +1602,564 @@ impl Pacs {
         if path.exists() {
             fs::remove_file(path)?;
         }
+        self.refresh_completions(None);
 
         // Clear active project config if it was the deleted one
-        let config = self.load_config()?;
-        if config
-            .active_project
-            .is_some_and(|a| a.to_lowercase() == name.to_lowercase())
-        {
-            self.clear_active_project()?;
-        }
-
-        Ok(())
+        self.update_config(|config| {
+            if config
+                .active_project
+                .as_ref()
+                .is_some_and(|a| a.to_lowercase() == name.to_lowercase())
+            {
+                config.active_project = None;
+            }
+            config
+                .recent_projects
+                .retain(|p| p.to_lowercase() != name.to_lowercase());
+            Ok(())
+        })
     }
 
     /// Adds a command to the specified project, or the active project if none specified.
     /// Returns an error if a command with the same name already exists in the project.
     pub fn add_command(
         &mut self,
-        cmd: PacsCommand,
+        mut cmd: PacsCommand,
         project_name: Option<ProjectName>,
     ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        if cmd.author.is_none() {
+            cmd.author = self.author();
+        }
         let project = self.get_project_or_active_mut(project_name)?;
         let project_name = project.name.clone();
 
-        if project.commands.iter().any(|c| c.name == cmd.name) {
-            return Err(PacsError::CommandExists(cmd.name));
-        }
+        let names: Vec<&str> = std::iter::once(&cmd.name)
+            .chain(&cmd.aliases)
+            .map(String::as_str)
+            .collect();
+        check_names_available(project, &names, None)?;
 
         project.commands.push(cmd);
         self.save_project_by_name(&project_name)?;
         Ok(())
     }
 
-    /// Removes a command by name from the specified project, or the active project if none specified.
-    pub fn delete_command(
+    /// Adds several commands at once to the specified project, or the active
+    /// project if none specified. Nothing is added if any name is taken, also
+    /// within `commands`, or any date is invalid. Dates may be intervals like
+    /// `30d`, as in `pacs add`.
+    pub fn add_commands(
         &mut self,
-        command_name: &str,
+        commands: Vec<PacsCommand>,
         project_name: Option<ProjectName>,
     ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        let author = self.author();
         let project = self.get_project_or_active_mut(project_name)?;
         let project_name = project.name.clone();
 
-        let before = project.commands.len();
-        project.commands.retain(|c| c.name != command_name);
-
-        if project.commands.len() == before {
-            return Err(PacsError::CommandNotFound(command_name.to_string()));
+        let mut staged = project.clone();
+        for mut cmd in commands {
+            let names: Vec<&str> = std::iter::once(&cmd.name)
+                .chain(&cmd.aliases)
+                .map(String::as_str)
+                .collect();
+            check_names_available(&staged, &names, None)?;
+            cmd.expires = cmd.expires.as_deref().map(resolve_date).transpose()?;
+            cmd.review_after = cmd.review_after.as_deref().map(resolve_date).transpose()?;
+            if cmd.author.is_none() {
+                cmd.author.clone_from(&author);
+            }
+            staged.commands.push(cmd);
         }
 
+        *project = staged;
         self.save_project_by_name(&project_name)?;
         Ok(())
     }
 
-    /// Updates a command's content in the active project.
-    pub fn update_command_auto(
+    /// Adds commands to the specified project, or the active project if none specified.
+    /// Commands whose name is already taken are skipped and their names returned.
+    pub fn import_commands(
         &mut self,
-        name: &str,
-        new_command: String,
-    ) -> Result<String, PacsError> {
-        let project = self.get_active_project_mut()?;
+        commands: Vec<PacsCommand>,
+        project_name: Option<ProjectName>,
+    ) -> Result<Vec<String>, PacsError> {
+        self.ensure_writable()?;
+        let author = self.author();
+        let project = self.get_project_or_active_mut(project_name)?;
         let project_name = project.name.clone();
 
-        let cmd = find_command_mut(project, name)?;
-
-        let old_command = cmd.command.clone();
-        cmd.command = new_command;
+        let mut skipped = Vec::new();
+        for mut cmd in commands {
+            let names: Vec<&str> = std::iter::once(&cmd.name)
+                .chain(&cmd.aliases)
+                .map(String::as_str)
+                .collect();
+            if check_names_available(project, &names, None).is_err() {
+                skipped.push(cmd.name);
+                continue;
+            }
+            if cmd.author.is_none() {
+                cmd.author.clone_from(&author);
+            }
+            project.commands.push(cmd);
+        }
 
         self.save_project_by_name(&project_name)?;
-        Ok(old_command)
+        Ok(skipped)
     }
 
-    pub fn rename_command_auto(&mut self, old_name: &str, new_name: &str) -> Result<(), PacsError> {
-        let project = self.get_active_project_mut()?;
-        let project_name = project.name.clone();
-
-        if project.commands.iter().any(|c| c.name == new_name) {
-            return Err(PacsError::CommandExists(new_name.to_string()));
-        }
-
-        if !project.commands.iter().any(|c| c.name == old_name) {
-            return Err(PacsError::CommandNotFound(old_name.to_string()));
+    /// Returns all projects as a bundle, to import them on another machine.
+    /// Projects and commands marked as local only are left out.
+    #[must_use]
+    pub fn bundle(&self) -> Bundle {
+        Bundle {
+            projects: self
+                .projects
+                .iter()
+                .filter(|p| !p.local_only)
+                .map(Project::shareable)
+                .collect(),
         }
+    }
 
-        let cmd = find_command_mut(project, old_name)?;
-        cmd.name = new_name.to_string();
-
-        self.save_project_by_name(&project_name)?;
-        Ok(())
+    /// Returns the commands of `bundle` that clash with different commands of existing projects.
+    #[must_use]
+    pub fn bundle_conflicts(&self, bundle: &Bundle) -> Vec<BundleConflict> {
+        bundle
+            .projects
+            .iter()
+            .filter_map(|theirs| {
+                let mine = self.get_project(&theirs.name).ok()?;
+                Some(bundle::project_conflicts(mine, theirs))
+            })
+            .flatten()
+            .collect()
     }
 
-    pub fn get_command_auto(&self, name: &str) -> Result<&PacsCommand, PacsError> {
-        let project = self.get_active_project()?;
+    /// Imports the projects of a bundle. Missing projects are created. Existing
+    /// ones are replaced if `replace` is set, and merged otherwise, resolving
+    /// conflicts by `resolutions` keyed by project and command name.
+    pub fn import_bundle(
+        &mut self,
+        bundle: Bundle,
+        replace: bool,
+        resolutions: &BTreeMap<(String, String), Resolution>,
+    ) -> Result<BundleImport, PacsError> {
+        self.ensure_writable()?;
+        let mut summary = BundleImport::default();
+        for mut theirs in bundle.projects {
+            let name = theirs.name.clone();
+            if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+                return Err(PacsError::InvalidProjectName(name));
+            }
 
-        if let Some(cmd) = project.commands.iter().find(|c| c.name == name) {
-            return Ok(cmd);
+            let Ok(mine) = self.get_project_mut(&name) else {
+                validate_project(&theirs)?;
+                self.save_project(&theirs)?;
+                self.projects.push(theirs);
+                summary.created.push(name);
+                continue;
+            };
+            if replace {
+                // Local only commands never left this machine, so they are kept
+                let kept: Vec<PacsCommand> = mine
+                    .commands
+                    .iter()
+                    .filter(|c| c.local_only && !theirs.commands.iter().any(|t| t.name == c.name))
+                    .cloned()
+                    .collect();
+                theirs.commands.extend(kept);
+                validate_project(&theirs)?;
+                *mine = Project {
+                    name: mine.name.clone(),
+                    local_only: mine.local_only,
+                    webhook: mine.webhook.clone(),
+                    ..theirs
+                };
+            } else {
+                let project_resolutions = resolutions
+                    .iter()
+                    .filter(|((project, _), _)| project.eq_ignore_ascii_case(&name))
+                    .map(|((_, command), resolution)| (command.clone(), resolution.clone()))
+                    .collect();
+                summary.commands += bundle::merge_project(mine, theirs, &project_resolutions)?;
+                validate_project(mine)?;
+            }
+            let name = mine.name.clone();
+            self.save_project_by_name(&name)?;
+            summary.updated.push(name);
         }
+        Ok(summary)
+    }
 
-        Err(PacsError::CommandNotFound(name.to_string()))
+    /// Copies a built-in template into the specified project, or the active project if none specified.
+    /// The command is named `name`, or after the template. Returns the added command.
+    pub fn add_template(
+        &mut self,
+        template: &str,
+        name: Option<&str>,
+        project_name: Option<ProjectName>,
+    ) -> Result<PacsCommand, PacsError> {
+        let cmd = templates()
+            .into_iter()
+            .find(|t| t.name == template)
+            .ok_or_else(|| PacsError::TemplateNotFound(template.to_string()))?
+            .to_command(name);
+        self.add_command(cmd.clone(), project_name)?;
+        Ok(cmd)
     }
 
-    pub fn delete_command_auto(&mut self, name: &str) -> Result<(), PacsError> {
-        let project = self.get_active_project_mut()?;
-        let project_name = project.name.clone();
+    /// Groups the commands of all projects whose bodies, with whitespace
+    /// normalized, have at least the given similarity (0 to 1).
+    #[must_use]
+    pub fn find_duplicates(&self, threshold: f64) -> Vec<DuplicateGroup> {
+        dedupe::find_duplicates(&self.projects, threshold)
+    }
 
-        let before = project.commands.len();
-        project.commands.retain(|c| c.name != name);
-        if project.commands.len() == before {
-            return Err(PacsError::CommandNotFound(name.to_string()));
-        }
+    /// Returns the commands of all projects whose body, with whitespace
+    /// normalized, has at least the given similarity (0 to 1) to `body`, the
+    /// most similar first.
+    #[must_use]
+    pub fn similar_commands(&self, body: &str, threshold: f64) -> Vec<(CommandRef, f64)> {
+        dedupe::find_similar(&self.projects, body, threshold)
+    }
+
+    /// Adds `alias` as another name of a command.
+    pub fn add_alias(
+        &mut self,
+        project_name: ProjectName,
+        name: &str,
+        alias: &str,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        self.ensure_unlocked(Some(project_name), name)?;
+        let project = self.get_project_mut(project_name)?;
+        check_names_available(project, &[alias], None)?;
+        find_command_mut(project, name)?
+            .aliases
+            .push(alias.to_string());
+        let project_name = project.name.clone();
+        self.save_project_by_name(&project_name)
+    }
+
+    /// Returns the names that more than one command answers to, by name or
+    /// alias, within a project or across projects.
+    #[must_use]
+    pub fn find_conflicts(&self) -> Vec<NameConflict> {
+        conflicts::find_conflicts(&self.projects)
+    }
+
+    /// Deletes the `others` commands and adds their names and aliases as
+    /// aliases of `keep`, skipping names that are already taken in its project.
+    /// All commands must be in the project of `keep`, so that their names keep
+    /// resolving where they did. Nothing is changed if any of them fails.
+    pub fn merge_commands(
+        &mut self,
+        keep: &CommandRef,
+        others: &[CommandRef],
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        if let Some(other) = others
+            .iter()
+            .find(|other| !other.project.eq_ignore_ascii_case(&keep.project))
+        {
+            return Err(PacsError::MergeAcrossProjects {
+                project: other.project.clone(),
+                name: other.name.clone(),
+            });
+        }
+        for command in std::iter::once(keep).chain(others) {
+            self.ensure_unlocked(Some(&command.project), &command.name)?;
+        }
+
+        let mut project = self.get_project(&keep.project)?.clone();
+        find_command_mut(&mut project, &keep.name)?;
+        let mut names = Vec::new();
+        for other in others.iter().filter(|other| other.name != keep.name) {
+            let index = project
+                .commands
+                .iter()
+                .position(|c| c.name == other.name)
+                .ok_or_else(|| PacsError::CommandNotFound(other.name.clone()))?;
+            let removed = project.commands.remove(index);
+            names.push(removed.name);
+            names.extend(removed.aliases);
+        }
+        for name in names {
+            if check_names_available(&project, &[&name], None).is_ok() {
+                find_command_mut(&mut project, &keep.name)?
+                    .aliases
+                    .push(name);
+            }
+        }
+
+        self.save_project(&project)?;
+        *self.get_project_mut(&keep.project)? = project;
+        Ok(())
+    }
+
+    /// Removes a command by name from the specified project, or the active project if none specified.
+    pub fn delete_command(
+        &mut self,
+        command_name: &str,
+        project_name: Option<ProjectName>,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        self.ensure_unlocked(project_name, command_name)?;
+        let project = self.get_project_or_active_mut(project_name)?;
+        let project_name = project.name.clone();
+
+        let before = project.commands.len();
+        project.commands.retain(|c| c.name != command_name);
+
+        if project.commands.len() == before {
+            return Err(PacsError::CommandNotFound(command_name.to_string()));
+        }
+
+        self.save_project_by_name(&project_name)?;
+        Ok(())
+    }
+
+    /// Updates a command's content in the active project.
+    pub fn update_command_auto(
+        &mut self,
+        name: &str,
+        new_command: String,
+    ) -> Result<String, PacsError> {
+        self.ensure_writable()?;
+        self.ensure_unlocked(None, name)?;
+        let author = self.author();
+        let project = self.get_active_project_mut()?;
+        let project_name = project.name.clone();
+
+        let cmd = find_command_mut(project, name)?;
+
+        let old_command = cmd.command.clone();
+        if old_command != new_command {
+            cmd.command = new_command;
+            cmd.author = author;
+        }
+
+        self.save_project_by_name(&project_name)?;
+        Ok(old_command)
+    }
+
+    pub fn rename_command_auto(&mut self, old_name: &str, new_name: &str) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        self.ensure_unlocked(None, old_name)?;
+        let author = self.author();
+        let project = self.get_active_project_mut()?;
+        let project_name = project.name.clone();
+
+        let current = PacsCommand::find_by_name(&project.commands, old_name)?
+            .name
+            .clone();
+        check_names_available(project, &[new_name], Some(&current))?;
+
+        let cmd = find_command_mut(project, old_name)?;
+        cmd.name = new_name.to_string();
+        cmd.aliases.retain(|a| a != new_name);
+        cmd.author = author;
+
+        self.save_project_by_name(&project_name)?;
+        Ok(())
+    }
+
+    pub fn get_command_auto(&self, name: &str) -> Result<&PacsCommand, PacsError> {
+        let project = self.get_active_project()?;
+
+        if let Some(cmd) = project.commands.iter().find(|c| c.matches(name)) {
+            return Ok(cmd);
+        }
+
+        Err(PacsError::CommandNotFound(name.to_string()))
+    }
+
+    pub fn delete_command_auto(&mut self, name: &str) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        self.ensure_unlocked(None, name)?;
+        let project = self.get_active_project_mut()?;
+        let project_name = project.name.clone();
+
+        let before = project.commands.len();
+        project.commands.retain(|c| c.name != name);
+        if project.commands.len() == before {
+            return Err(PacsError::CommandNotFound(name.to_string()));
+        }
 
         self.save_project_by_name(&project_name)?;
         Ok(())
     }
 
+    /// Sets the expiry and review dates of a command in the active project.
+    /// Dates are `YYYY-MM-DD` or an interval from today like `30d`. `None`
+    /// keeps a date and an empty string removes it.
+    pub fn set_command_dates_auto(
+        &mut self,
+        name: &str,
+        expires: Option<&str>,
+        review_after: Option<&str>,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        self.ensure_unlocked(None, name)?;
+        let resolve = |date: Option<&str>| {
+            date.map(|d| (!d.is_empty()).then(|| resolve_date(d)).transpose())
+                .transpose()
+        };
+        let expires = resolve(expires)?;
+        let review_after = resolve(review_after)?;
+        if expires.is_none() && review_after.is_none() {
+            return Ok(());
+        }
+
+        let author = self.author();
+        let project = self.get_active_project_mut()?;
+        let project_name = project.name.clone();
+
+        let cmd = find_command_mut(project, name)?;
+        if let Some(expires) = expires {
+            cmd.expires = expires;
+        }
+        if let Some(review_after) = review_after {
+            cmd.review_after = review_after;
+        }
+        cmd.author = author;
+
+        self.save_project_by_name(&project_name)
+    }
+
+    /// Marks a command of the active project as local only, or shares it again.
+    pub fn set_command_local_only_auto(
+        &mut self,
+        name: &str,
+        local_only: bool,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        self.ensure_unlocked(None, name)?;
+        let project = self.get_active_project_mut()?;
+        let project_name = project.name.clone();
+        find_command_mut(project, name)?.local_only = local_only;
+        self.save_project_by_name(&project_name)
+    }
+
+    /// Makes a command of the active project ask for confirmation before every
+    /// run, or stops asking.
+    pub fn set_command_confirm_auto(&mut self, name: &str, confirm: bool) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        self.ensure_unlocked(None, name)?;
+        let project = self.get_active_project_mut()?;
+        let project_name = project.name.clone();
+        find_command_mut(project, name)?.confirm = confirm;
+        self.save_project_by_name(&project_name)
+    }
+
+    /// Marks a command of the active project as safe to run without the
+    /// confirmation danger patterns ask for.
+    pub fn set_command_safe_auto(&mut self, name: &str, safe: bool) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        self.ensure_unlocked(None, name)?;
+        let project = self.get_active_project_mut()?;
+        let project_name = project.name.clone();
+        find_command_mut(project, name)?.safe = safe;
+        self.save_project_by_name(&project_name)
+    }
+
+    /// Locks a command of the active project against edits, renames and
+    /// removal, or unlocks it.
+    pub fn set_command_locked_auto(&mut self, name: &str, locked: bool) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        let project = self.get_active_project_mut()?;
+        let project_name = project.name.clone();
+        find_command_mut(project, name)?.locked = locked;
+        self.save_project_by_name(&project_name)
+    }
+
+    /// Marks a project as local only, or shares it again.
+    pub fn set_project_local_only(
+        &mut self,
+        name: ProjectName,
+        local_only: bool,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        self.get_project_mut(name)?.local_only = local_only;
+        self.save_project_by_name(name)
+    }
+
+    /// Archives or restores a project. Archiving the active project clears it.
+    pub fn set_project_archived(
+        &mut self,
+        name: ProjectName,
+        archived: bool,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        let project = self.get_project_mut(name)?;
+        project.archived = archived;
+        let name = project.name.clone();
+        self.save_project_by_name(&name)?;
+        if archived && self.load_config()?.active_project.as_deref() == Some(name.as_str()) {
+            self.clear_active_project()?;
+        }
+        Ok(())
+    }
+
+    /// Sets the color and icon of a project. `None` keeps a value and an
+    /// empty string removes it.
+    pub fn set_project_style(
+        &mut self,
+        name: ProjectName,
+        color: Option<&str>,
+        icon: Option<&str>,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        let project = self.get_project_mut(name)?;
+        for (field, value) in [(&mut project.color, color), (&mut project.icon, icon)] {
+            if let Some(value) = value {
+                *field = Some(value.to_string()).filter(|v| !v.is_empty());
+            }
+        }
+        self.save_project_by_name(name)
+    }
+
+    /// Sets the notes of a project. Blank notes remove them.
+    pub fn set_project_notes(&mut self, name: ProjectName, notes: &str) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        self.get_project_mut(name)?.notes =
+            Some(notes.trim_end().to_string()).filter(|n| !n.trim().is_empty());
+        self.save_project_by_name(name)
+    }
+
+    /// Returns the commands of all projects that have expired or are due for review.
+    #[must_use]
+    pub fn expiring_commands(&self) -> Vec<(CommandRef, ExpiryStatus)> {
+        let today = today();
+        self.projects
+            .iter()
+            .flat_map(|p| p.commands.iter().map(move |c| (p, c)))
+            .filter_map(|(project, cmd)| {
+                let status = cmd.expiry_status(today)?;
+                let command = CommandRef {
+                    project: project.name.clone(),
+                    name: cmd.name.clone(),
+                };
+                Some((command, status))
+            })
+            .collect()
+    }
+
     pub fn tag_command_auto(&mut self, name: &str, tag: String) -> Result<String, PacsError> {
+        self.ensure_writable()?;
+        self.ensure_unlocked(None, name)?;
+        let author = self.author();
         let project = self.get_active_project_mut()?;
         let project_name = project.name.clone();
 
         let cmd = find_command_mut(project, name)?;
 
         let old_tag = std::mem::replace(&mut cmd.tag, tag);
+        cmd.author = author;
 
         self.save_project_by_name(&project_name)?;
         Ok(old_tag)
@@ -461,12 +2172,18 @@ impl Pacs {
     ) -> Result<Vec<PacsCommand>, PacsError> {
         let project = self.get_project_or_active(project_name)?;
         let project_name = &project.name;
-        let environment = environment.or(project.active_environment.as_deref());
+        let environment = environment.or(self.active_environment_of(project));
 
         let mut cmds: Vec<PacsCommand> = Vec::with_capacity(project.commands.len());
 
         for c in &project.commands {
-            let pc = self.expand_command_with_environment(c, project_name, environment)?;
+            let pc = self.expand_command_with_environment(
+                c,
+                project_name,
+                environment,
+                &BTreeMap::new(),
+                false,
+            )?;
             cmds.push(pc);
         }
 
@@ -476,115 +2193,776 @@ impl Pacs {
 
     /// Resolves a command with environment, returning an expanded command ready to execute.
     /// Requires an active project if `project_name` is not specified.
+    ///
+    /// Secret references in environment values are shown as they are, unless
+    /// secrets are revealed with [`Pacs::set_reveal_secrets`].
     pub fn resolve_command(
         &self,
         name: &str,
         project_name: Option<ProjectName>,
         environment: Option<EnvironmentName>,
     ) -> Result<PacsCommand, PacsError> {
-        let project = self.get_project_or_active(project_name)?;
-        let project_name = &project.name;
-        let environment = environment.or(project.active_environment.as_deref());
-
-        let cmd = project
-            .commands
-            .iter()
-            .find(|c| c.name == name)
-            .ok_or_else(|| PacsError::CommandNotFound(name.to_string()))?;
-
-        self.expand_command_with_environment(cmd, project_name, environment)
+        self.resolve_command_with_values(name, project_name, environment, &BTreeMap::new())
     }
 
-    pub fn run(
+    /// Resolves a command like [`Pacs::resolve_command`], with `values` taking
+    /// precedence over the environment's as in [`Pacs::run_with_values`].
+    pub fn resolve_command_with_values(
         &self,
         name: &str,
         project_name: Option<ProjectName>,
         environment: Option<EnvironmentName>,
-    ) -> Result<(), PacsError> {
-        let command = self.resolve_command(name, project_name, environment)?;
-        Self::execute(&command)
+        values: &BTreeMap<String, String>,
+    ) -> Result<PacsCommand, PacsError> {
+        self.resolve_command_with_secrets(
+            name,
+            project_name,
+            environment,
+            values,
+            self.reveal_secrets,
+        )
     }
 
-    pub fn copy(
+    fn resolve_command_with_secrets(
         &self,
         name: &str,
         project_name: Option<ProjectName>,
         environment: Option<EnvironmentName>,
+        values: &BTreeMap<String, String>,
+        resolve_secrets: bool,
     ) -> Result<PacsCommand, PacsError> {
-        self.resolve_command(name, project_name, environment)
-    }
+        let project = self.get_project_or_active(project_name)?;
+        let project_name = &project.name;
+        check_environment(project, environment)?;
+        let environment = environment.or(self.active_environment_of(project));
 
-    fn load_projects(projects_dir: &std::path::Path) -> Result<Vec<Project>, PacsError> {
-        let mut projects = Vec::new();
+        let cmd = self.find_runnable(project, name)?;
 
-        if !projects_dir.exists() {
-            return Ok(projects);
-        }
+        self.expand_command_with_environment(
+            &self.on_this_machine(cmd),
+            project_name,
+            environment,
+            values,
+            resolve_secrets,
+        )
+    }
 
-        for entry in fs::read_dir(projects_dir)? {
-            let path = entry?.path();
-            if path.extension().and_then(|s| s.to_str()) != Some("toml") {
-                continue;
-            }
+    /// Finds a command of `project`, or else an approved command of a
+    /// subscribed registry.
+    fn find_runnable<'a>(
+        &'a self,
+        project: &'a Project,
+        name: &str,
+    ) -> Result<&'a PacsCommand, PacsError> {
+        if let Some(cmd) = project.commands.iter().find(|c| c.matches(name)) {
+            return Ok(cmd);
+        }
+        let (registry, cmd) = self.registry_command(name, None)?;
+        registry.verify(cmd)?;
+        if registry.is_approved(cmd) {
+            return Ok(cmd);
+        }
+        let (name, changed) = (
+            cmd.name.clone(),
+            registry.approved_version(&cmd.name).is_some(),
+        );
+        let registry = registry.source.name.clone();
+        if changed {
+            Err(PacsError::ChangedSinceApproval { registry, name })
+        } else {
+            Err(PacsError::NotApproved { registry, name })
+        }
+    }
 
-            let mut proj: Project = toml::from_str(&fs::read_to_string(&path)?)?;
-            if proj.name.is_empty()
-                && let Some(stem) = path.file_stem().and_then(|s| s.to_str())
-            {
-                proj.name = stem.to_string();
-            }
-            projects.push(proj);
+    /// Returns the first pattern of `danger_patterns` in config.toml that the
+    /// resolved body of a command matches, unless the command is marked `safe`.
+    #[cfg(feature = "exec")]
+    pub fn danger_pattern(
+        &self,
+        name: &str,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+        values: &BTreeMap<String, String>,
+    ) -> Result<Option<String>, PacsError> {
+        if self.load_config()?.danger_patterns.is_empty() {
+            return Ok(None);
         }
+        let cmd =
+            self.resolve_command_with_secrets(name, project_name, environment, values, false)?;
+        self.matching_danger_pattern(&cmd)
+    }
 
-        Ok(projects)
+    /// Checks a resolved command right before it runs, like `pacs run` does.
+    /// Fails if a `k8s` command would run against another cluster than the
+    /// environment's `kube_context`, and returns the danger pattern the
+    /// command matches, which is to be confirmed before running it.
+    #[cfg(feature = "exec")]
+    pub fn check_run(
+        &self,
+        cmd: &PacsCommand,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+    ) -> Result<Option<String>, PacsError> {
+        self.verify_kube_context(cmd, project_name, environment)?;
+        self.matching_danger_pattern(cmd)
     }
 
-    fn get_project_mut(&mut self, name: ProjectName) -> Result<&mut Project, PacsError> {
-        self.projects
-            .iter_mut()
-            .find(|p| p.name.to_lowercase() == name.to_lowercase())
-            .ok_or_else(|| PacsError::ProjectNotFound(name.to_string()))
+    /// Verifies kubectl's current context for a `k8s` command against the
+    /// `kube_context` of the environment, or of the active one.
+    #[cfg(feature = "exec")]
+    fn verify_kube_context(
+        &self,
+        cmd: &PacsCommand,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+    ) -> Result<(), PacsError> {
+        let project = self.get_project_or_active(project_name)?;
+        let kube_context = environment
+            .or(self.active_environment_of(project))
+            .and_then(|name| project.environments.iter().find(|e| e.name == name))
+            .and_then(|e| e.kube_context.as_deref());
+        kube::verify_context(cmd, kube_context)
     }
 
-    fn get_project(&self, name: ProjectName) -> Result<&Project, PacsError> {
-        self.projects
-            .iter()
-            .find(|p| p.name.to_lowercase() == name.to_lowercase())
-            .ok_or_else(|| PacsError::ProjectNotFound(name.to_string()))
+    /// Returns the first pattern of `danger_patterns` that a resolved body
+    /// matches, unless the command is marked `safe`.
+    #[cfg(feature = "exec")]
+    fn matching_danger_pattern(&self, cmd: &PacsCommand) -> Result<Option<String>, PacsError> {
+        if cmd.safe {
+            return Ok(None);
+        }
+        for pattern in self.load_config()?.danger_patterns {
+            let regex =
+                regex::Regex::new(&pattern).map_err(|e| PacsError::InvalidDangerPattern {
+                    pattern: pattern.clone(),
+                    message: e.to_string(),
+                })?;
+            if regex.is_match(&cmd.command) {
+                return Ok(Some(pattern));
+            }
+        }
+        Ok(None)
     }
 
-    fn project_path(&self, name: ProjectName) -> PathBuf {
-        self.base_dir.join("projects").join(format!("{name}.toml"))
+    /// Runs a command and records the run in the history.
+    #[cfg(feature = "exec")]
+    pub fn run(
+        &self,
+        name: &str,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+    ) -> Result<(), PacsError> {
+        self.run_with_values(name, project_name, environment, &BTreeMap::new())
     }
 
-    fn save_project(&self, project: &Project) -> Result<(), PacsError> {
-        let mut sorted = project.commands.clone();
-        sorted.sort_by(|a, b| a.name.cmp(&b.name));
-        let temp = Project {
-            name: project.name.clone(),
-            path: project.path.clone(),
-            commands: sorted,
-            environments: project.environments.clone(),
-            active_environment: project.active_environment.clone(),
-        };
-        fs::write(
-            self.project_path(&project.name),
-            toml::to_string_pretty(&temp)?,
-        )?;
-        Ok(())
+    /// Runs a command like [`Pacs::run`], with `values` taking precedence over
+    /// the environment's. Keys match placeholders as they are or as variable
+    /// names, so `NAMESPACE` fills `{{namespace}}`. The active environment is
+    /// never changed.
+    #[cfg(feature = "exec")]
+    pub fn run_with_values(
+        &self,
+        name: &str,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+        values: &BTreeMap<String, String>,
+    ) -> Result<(), PacsError> {
+        self.run_command(name, project_name, environment, values, false)
+            .map(drop)
     }
 
-    pub fn save_project_by_name(&self, name: ProjectName) -> Result<(), PacsError> {
-        let project = self.get_project(name)?;
-        self.save_project(project)
+    /// Runs a command like [`Pacs::run_with_values`], but returns its stdout
+    /// instead of printing it. Stderr and stdin stay attached to the terminal.
+    #[cfg(feature = "exec")]
+    pub fn run_capturing_output(
+        &self,
+        name: &str,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+        values: &BTreeMap<String, String>,
+    ) -> Result<Vec<u8>, PacsError> {
+        self.run_command(name, project_name, environment, values, true)
+            .map(Option::unwrap_or_default)
     }
 
-    /// Adds a new empty environment to a project.
-    pub fn add_environment(
-        &mut self,
+    #[cfg(feature = "exec")]
+    fn run_command(
+        &self,
+        name: &str,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+        values: &BTreeMap<String, String>,
+        capture: bool,
+    ) -> Result<Option<Vec<u8>>, PacsError> {
+        if self.safe_mode {
+            return Err(PacsError::SafeMode);
+        }
+        let project = self.get_project_or_active(project_name)?;
+        check_environment(project, environment)?;
+        let environment = environment.or(self.active_environment_of(project));
+
+        let cmd = &self.on_this_machine(self.find_runnable(project, name)?);
+        let missing = self.missing_keys(cmd, Some(&project.name), environment, values)?;
+        if !missing.is_empty() {
+            return Err(PacsError::UnresolvedPlaceholders {
+                command: cmd.name.clone(),
+                keys: missing.join(", "),
+            });
+        }
+
+        let command = self.resolve_command_with_secrets(
+            name,
+            Some(&project.name),
+            environment,
+            values,
+            true,
+        )?;
+        if command.asks_cwd() {
+            return Err(PacsError::UnresolvedPlaceholders {
+                command: command.name,
+                keys: CWD_KEY.to_string(),
+            });
+        }
+
+        self.verify_kube_context(&command, Some(&project.name), environment)?;
+
+        let mut ctx = self.hook_context(Some(name), Some(&project.name), environment)?;
+        self.run_hooks(HookEvent::PreRun, &ctx)?;
+
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            // Log secret references rather than their values
+            let shown = self.resolve_command_with_secrets(
+                name,
+                Some(&project.name),
+                environment,
+                values,
+                false,
+            )?;
+            debug!(cwd = ?shown.cwd, "spawning sh -c {:?}", shown.command);
+        }
+
+        let started_at = unix_now();
+        let timer = Instant::now();
+        let result = Self::execute(&command, capture);
+
+        let exit_code = match &result {
+            Ok(_) => 0,
+            Err(PacsError::CommandFailed(code)) => *code,
+            Err(_) => return result,
+        };
+
+        // Like the history, post-run hooks must not turn a finished run into an error
+        ctx.exit_code = Some(exit_code);
+        self.run_hooks(HookEvent::PostRun, &ctx).ok();
+
+        let record = RunRecord {
+            command: command.name,
+            project: project.name.clone(),
+            environment: environment.map(str::to_string),
+            exit_code,
+            started_at,
+            duration_ms: u64::try_from(timer.elapsed().as_millis()).unwrap_or(u64::MAX),
+        };
+        self.record_run(&record).ok();
+        if let Some(webhook) = self.webhook(&record)
+            && let Err(e) = webhook.send()
+        {
+            tracing::warn!("webhook of project {} failed: {e}", project.name);
+        }
+
+        result
+    }
+
+    /// Renders the commands of a project, or only those tagged `tag`, in the
+    /// given format.
+    ///
+    /// Values of the environment (or the project's active environment) become
+    /// variable defaults in justfiles and Makefiles and job variables in
+    /// workflows. Bodies are cleaned up by `cleanup` on the way out.
+    pub fn export(
+        &self,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+        tag: Option<&str>,
+        format: ExportFormat,
+        cleanup: BodyCleanup,
+    ) -> Result<String, PacsError> {
+        let keep = |c: &PacsCommand| tag.is_none_or(|tag| c.tag == tag);
+        let (output, _) = self.export_where(project_name, environment, keep, format, cleanup)?;
+        Ok(output)
+    }
+
+    /// Renders the commands of a project named in `names`, like
+    /// [`Pacs::export`]. Returns the output and the number of commands in it,
+    /// which leaves out local only commands.
+    pub fn export_selected(
+        &self,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+        names: &[String],
+        format: ExportFormat,
+        cleanup: BodyCleanup,
+    ) -> Result<(String, usize), PacsError> {
+        let keep = |c: &PacsCommand| names.contains(&c.name);
+        self.export_where(project_name, environment, keep, format, cleanup)
+    }
+
+    fn export_where(
+        &self,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+        keep: impl Fn(&PacsCommand) -> bool,
+        format: ExportFormat,
+        cleanup: BodyCleanup,
+    ) -> Result<(String, usize), PacsError> {
+        let project = self.get_project_or_active(project_name)?;
+        let environment = environment.or(self.active_environment_of(project));
+        let values = environment
+            .and_then(|name| project.environments.iter().find(|e| e.name == name))
+            .map(Environment::placeholder_values)
+            .unwrap_or_default();
+        let mut commands = project.shareable().commands;
+        commands.retain(keep);
+        if !cleanup.is_noop() {
+            for cmd in &mut commands {
+                cmd.command = cleanup.apply(&cmd.command);
+            }
+        }
+        let output = export_commands(&project.name, &commands, &values, format)?;
+        Ok((output, commands.len()))
+    }
+
+    /// Returns the context passed to hooks: the project, the environment (or
+    /// the project's active one) with its unresolved values, and optionally a
+    /// command with its placeholders expanded.
+    #[cfg(feature = "exec")]
+    pub fn hook_context(
+        &self,
+        command: Option<&str>,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+    ) -> Result<HookContext, PacsError> {
+        let project = self.get_project_or_active(project_name)?;
+        let environment = environment.or(self.active_environment_of(project));
+        let values = environment
+            .and_then(|name| project.environments.iter().find(|e| e.name == name))
+            .map(Environment::placeholder_values)
+            .unwrap_or_default();
+        let command = command
+            .map(|name| {
+                let values = BTreeMap::new();
+                self.resolve_command_with_secrets(
+                    name,
+                    Some(&project.name),
+                    environment,
+                    &values,
+                    false,
+                )
+            })
+            .transpose()?;
+
+        Ok(HookContext {
+            project: project.name.clone(),
+            environment: environment.map(str::to_string),
+            values,
+            command,
+            exit_code: None,
+        })
+    }
+
+    /// Runs the hook function for `event` in every script in `hooks/`.
+    #[cfg(feature = "exec")]
+    pub fn run_hooks(&self, event: HookEvent, ctx: &HookContext) -> Result<(), PacsError> {
+        hooks::run(&self.base_dir.join("hooks"), event, ctx)
+    }
+
+    /// Appends a run to the history, unless `[history]` in config.toml
+    /// excludes its command, tag or project.
+    pub fn record_run(&self, record: &RunRecord) -> Result<(), PacsError> {
+        let config = self.load_config()?.history.unwrap_or_default();
+        let cmd = self
+            .projects()
+            .iter()
+            .find(|p| p.name == record.project)
+            .and_then(|p| p.commands.iter().find(|c| c.name == record.command));
+        if config.excludes(record, cmd) {
+            return Ok(());
+        }
+        let path = self.base_dir.join("history.toml");
+        history::append(&path, record)?;
+        if config.limits_retention() {
+            let runs = history::load(&path)?;
+            let total = runs.len();
+            let kept = config.retain(runs, history::unix_now())?;
+            if kept.len() < total {
+                history::save(&path, kept)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the retention limits of config.toml to the history and
+    /// rewrites it compactly. Returns the number of runs removed.
+    pub fn gc(&self) -> Result<usize, PacsError> {
+        let config = self.load_config()?.history.unwrap_or_default();
+        let path = self.base_dir.join("history.toml");
+        if !path.exists() {
+            return Ok(0);
+        }
+        let runs = history::load(&path)?;
+        let total = runs.len();
+        let kept = config.retain(runs, history::unix_now())?;
+        let removed = total - kept.len();
+        history::save(&path, kept)?;
+        Ok(removed)
+    }
+
+    /// Returns the report of a finished run for the webhook of its project,
+    /// if the project has one.
+    #[must_use]
+    pub fn webhook(&self, record: &RunRecord) -> Option<Webhook> {
+        let project = self.projects().iter().find(|p| p.name == record.project)?;
+        Webhook::new(project.webhook.as_deref()?, record).ok()
+    }
+
+    /// Removes the recorded runs of `project`, or of all projects, that
+    /// started before `before` in seconds since the Unix epoch, or all of them.
+    /// Returns the number of runs removed.
+    pub fn clear_history(
+        &self,
+        project: Option<ProjectName>,
+        before: Option<u64>,
+    ) -> Result<usize, PacsError> {
+        let path = self.base_dir.join("history.toml");
+        let runs = history::load(&path)?;
+        let total = runs.len();
+        let kept: Vec<RunRecord> = runs
+            .into_iter()
+            .filter(|r| {
+                project.is_some_and(|p| r.project != p) || before.is_some_and(|b| r.started_at >= b)
+            })
+            .collect();
+        let removed = total - kept.len();
+        if removed > 0 {
+            history::save(&path, kept)?;
+        }
+        Ok(removed)
+    }
+
+    /// Returns all recorded runs, oldest first.
+    pub fn history(&self) -> Result<Vec<RunRecord>, PacsError> {
+        history::load(&self.base_dir.join("history.toml"))
+    }
+
+    /// Returns the last distinct commands run in a project (or the active
+    /// one), most recent first, as many as `recent` in config.toml allows.
+    pub fn recent_commands(
+        &self,
+        project_name: Option<ProjectName>,
+    ) -> Result<Vec<&PacsCommand>, PacsError> {
+        let project = self.get_project_or_active(project_name)?;
+        let limit = self.load_config()?.recent.unwrap_or(DEFAULT_RECENT);
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut recent: Vec<&PacsCommand> = Vec::new();
+        for record in self.history()?.iter().rev() {
+            if recent.len() == limit {
+                break;
+            }
+            if record.project != project.name || recent.iter().any(|c| c.name == record.command) {
+                continue;
+            }
+            if let Some(cmd) = project.commands.iter().find(|c| c.name == record.command) {
+                recent.push(cmd);
+            }
+        }
+        Ok(recent)
+    }
+
+    /// Returns the commands of a project (or of all projects) that have not
+    /// been run within `window`, with the time of their last run if any.
+    pub fn unused_commands(
+        &self,
+        project_name: Option<ProjectName>,
+        window: Duration,
+    ) -> Result<Vec<(CommandRef, Option<u64>)>, PacsError> {
+        if let Some(name) = project_name {
+            self.get_project(name)?;
+        }
+        let last_runs = last_runs(&self.history()?);
+        let cutoff = unix_now().saturating_sub(window.as_secs());
+
+        let mut unused = Vec::new();
+        for project in &self.projects {
+            if project_name.is_some_and(|name| name != project.name) {
+                continue;
+            }
+            for cmd in &project.commands {
+                let last_run = last_runs
+                    .get(&(project.name.clone(), cmd.name.clone()))
+                    .copied();
+                if last_run.is_none_or(|t| t < cutoff) {
+                    let command = CommandRef {
+                        project: project.name.clone(),
+                        name: cmd.name.clone(),
+                    };
+                    unused.push((command, last_run));
+                }
+            }
+        }
+        Ok(unused)
+    }
+
+    /// Removes commands from their projects and appends them to
+    /// `archive/<project>.toml`, which uses the project file format so
+    /// commands can be restored by copying them back.
+    pub fn archive_commands(&mut self, commands: &[CommandRef]) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        let archive_dir = self.base_dir.join("archive");
+        fs::create_dir_all(&archive_dir)?;
+
+        let mut projects: Vec<&str> = commands.iter().map(|c| c.project.as_str()).collect();
+        projects.sort_unstable();
+        projects.dedup();
+
+        for project_name in projects {
+            let path = archive_dir.join(format!("{project_name}.toml"));
+            let mut archive = if path.exists() {
+                toml::from_str(&fs::read_to_string(&path)?)?
+            } else {
+                Project {
+                    name: project_name.to_string(),
+                    ..Project::default()
+                }
+            };
+
+            let project = self.get_project_mut(project_name)?;
+            let (archived, kept): (Vec<PacsCommand>, Vec<PacsCommand>) =
+                std::mem::take(&mut project.commands)
+                    .into_iter()
+                    .partition(|cmd| {
+                        commands
+                            .iter()
+                            .any(|c| c.project == project_name && c.name == cmd.name)
+                    });
+            project.commands = kept;
+            archive.commands.extend(archived);
+
+            fs::write(&path, serialize_project(&archive)?)?;
+            self.save_project_by_name(project_name)?;
+        }
+        Ok(())
+    }
+
+    pub fn copy(
+        &self,
+        name: &str,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+    ) -> Result<PacsCommand, PacsError> {
+        self.resolve_command_with_secrets(name, project_name, environment, &BTreeMap::new(), true)
+    }
+
+    /// Loads all project files. Files that cannot be read or parsed are skipped
+    /// and reported as warnings instead of failing the whole load.
+    fn load_projects(
+        projects_dir: &std::path::Path,
+    ) -> Result<(Vec<Project>, Vec<LoadWarning>), PacsError> {
+        let mut projects = Vec::new();
+        let mut warnings = Vec::new();
+
+        if !projects_dir.exists() {
+            return Ok((projects, warnings));
+        }
+
+        for entry in fs::read_dir(projects_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("toml") {
+                continue;
+            }
+
+            let stem = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string();
+
+            let parsed = fs::read_to_string(&path)
+                .map_err(PacsError::from)
+                .and_then(|content| toml::from_str::<Project>(&content).map_err(PacsError::from));
+
+            match parsed {
+                Ok(mut proj) => {
+                    if proj.name.is_empty() {
+                        proj.name = stem;
+                    }
+                    debug!("loaded project {} from {}", proj.name, path.display());
+                    projects.push(proj);
+                }
+                Err(e) => warnings.push(LoadWarning {
+                    project: stem,
+                    path,
+                    message: e.to_string(),
+                }),
+            }
+        }
+
+        Ok((projects, warnings))
+    }
+
+    /// Returns the project files that failed to load because of git conflict
+    /// markers, parsed into both sides.
+    pub fn sync_conflicts(&self) -> Result<Vec<SyncConflict>, PacsError> {
+        let mut conflicts = Vec::new();
+        for warning in &self.load_warnings {
+            let content = fs::read_to_string(&warning.path)?;
+            let Some((ours, theirs)) = sync::conflict_sides(&content) else {
+                continue;
+            };
+            let parse = |side: &str| -> Result<Project, PacsError> {
+                let mut project: Project = toml::from_str(side)?;
+                if project.name.is_empty() {
+                    project.name.clone_from(&warning.project);
+                }
+                Ok(project)
+            };
+            let (ours, theirs) = (parse(&ours)?, parse(&theirs)?);
+            conflicts.push(SyncConflict {
+                project: warning.project.clone(),
+                path: warning.path.clone(),
+                conflicts: bundle::project_conflicts(&ours, &theirs),
+                ours,
+                theirs,
+            });
+        }
+        Ok(conflicts)
+    }
+
+    /// Merges both sides of a conflicted project file and saves the result.
+    /// Commands and environments of both sides are kept, and commands that
+    /// differ are resolved by `resolutions` keyed by name, keeping ours by default.
+    pub fn resolve_sync_conflict(
+        &mut self,
+        conflict: SyncConflict,
+        resolutions: &BTreeMap<String, Resolution>,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        let SyncConflict {
+            path,
+            ours: mut merged,
+            theirs,
+            ..
+        } = conflict;
+        bundle::merge_project(&mut merged, theirs, resolutions)?;
+        validate_project(&merged)?;
+        write_atomic(&path, &serialize_project(&merged)?)?;
+
+        self.load_warnings.retain(|w| w.path != path);
+        self.projects.retain(|p| p.name != merged.name);
+        self.projects.push(merged);
+        Ok(())
+    }
+
+    fn get_project_mut(&mut self, name: ProjectName) -> Result<&mut Project, PacsError> {
+        self.projects
+            .iter_mut()
+            .find(|p| p.name.to_lowercase() == name.to_lowercase())
+            .ok_or_else(|| PacsError::ProjectNotFound(name.to_string()))
+    }
+
+    fn get_project(&self, name: ProjectName) -> Result<&Project, PacsError> {
+        self.projects
+            .iter()
+            .find(|p| p.name.to_lowercase() == name.to_lowercase())
+            .ok_or_else(|| PacsError::ProjectNotFound(name.to_string()))
+    }
+
+    fn project_path(&self, name: ProjectName) -> PathBuf {
+        self.projects_dir().join(format!("{name}.toml"))
+    }
+
+    fn save_project(&self, project: &Project) -> Result<(), PacsError> {
+        write_atomic(
+            &self.project_path(&project.name),
+            &serialize_project(project)?,
+        )?;
+        self.refresh_completions(Some(project));
+        Ok(())
+    }
+
+    pub fn save_project_by_name(&self, name: ProjectName) -> Result<(), PacsError> {
+        let project = self.get_project(name)?;
+        self.save_project(project)
+    }
+
+    /// Returns the file a project is stored in.
+    pub fn project_file(&self, name: ProjectName) -> Result<PathBuf, PacsError> {
+        let project = self.get_project(name)?;
+        Ok(self.project_path(&project.name))
+    }
+
+    /// Returns the directory pacs stores its data in.
+    #[must_use]
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// Returns the path of config.toml.
+    #[must_use]
+    pub fn config_file(&self) -> PathBuf {
+        self.base_dir.join("config.toml")
+    }
+
+    /// Returns the directory holding one file per project.
+    #[must_use]
+    pub fn projects_dir(&self) -> PathBuf {
+        self.base_dir.join("projects")
+    }
+
+    /// Replaces a project by the edited content of its file. The content is
+    /// validated and saved in the usual format, keeping the project's name.
+    /// Locked commands must be kept unchanged unless locks are ignored.
+    pub fn replace_project(&mut self, name: ProjectName, content: &str) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        let mut edited: Project = toml::from_str(content)?;
+        let ignore_locks = self.ignore_locks;
+        let project = self.get_project_mut(name)?;
+        edited.name.clone_from(&project.name);
+        validate_project(&edited)?;
+        if !ignore_locks {
+            for cmd in project.commands.iter().filter(|c| c.locked) {
+                let kept = edited
+                    .commands
+                    .iter()
+                    .find(|c| c.name == cmd.name)
+                    .is_some_and(|c| toml::to_string(c).ok() == toml::to_string(cmd).ok());
+                if !kept {
+                    return Err(PacsError::CommandLocked(cmd.name.clone()));
+                }
+            }
+        }
+        *project = edited;
+        self.save_project_by_name(name)
+    }
+
+    /// Replaces config.toml by edited content. Allowed in read-only mode, so
+    /// that it can be turned off.
+    pub fn replace_config(&self, content: &str) -> Result<(), PacsError> {
+        let edited: Config = toml::from_str(content)?;
+        // The current config is not loaded, as it may be what is being fixed
+        let _lock = self.lock()?;
+        self.save_config(&edited)
+    }
+
+    /// Adds a new empty environment to a project.
+    pub fn add_environment(
+        &mut self,
         project_name: ProjectName,
         environment_name: EnvironmentName,
     ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
         let project = self.get_project_mut(project_name)?;
         if project
             .environments
@@ -598,6 +2976,9 @@ impl Pacs {
         project.environments.push(Environment {
             name: environment_name.to_string(),
             values: std::collections::BTreeMap::new(),
+            kube_context: None,
+            on_activate: None,
+            on_deactivate: None,
         });
 
         self.save_project_by_name(project_name)
@@ -609,6 +2990,7 @@ impl Pacs {
         project_name: ProjectName,
         environment_name: EnvironmentName,
     ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
         let project = self.get_project_mut(project_name)?;
         if let Some(idx) = project
             .environments
@@ -629,6 +3011,46 @@ impl Pacs {
         self.save_project_by_name(project_name)
     }
 
+    /// Copies an environment with its values, kube context and hooks from one
+    /// project to another. An environment of the same name in the target
+    /// project is only replaced with `overwrite`.
+    pub fn copy_environment(
+        &mut self,
+        environment_name: EnvironmentName,
+        from: ProjectName,
+        to: ProjectName,
+        overwrite: bool,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        let environment = self
+            .get_project(from)?
+            .environments
+            .iter()
+            .find(|e| e.name == environment_name)
+            .cloned()
+            .ok_or_else(|| {
+                PacsError::ProjectNotFound(format!(
+                    "Environment '{environment_name}' not found in project '{from}'"
+                ))
+            })?;
+        let project = self.get_project_mut(to)?;
+        match project
+            .environments
+            .iter_mut()
+            .find(|e| e.name == environment_name)
+        {
+            Some(existing) if overwrite => *existing = environment,
+            Some(_) => {
+                return Err(PacsError::ProjectExists(format!(
+                    "Environment '{environment_name}' already exists in project '{to}'"
+                )));
+            }
+            None => project.environments.push(environment),
+        }
+
+        self.save_project_by_name(to)
+    }
+
     /// Replaces all key/value pairs in a project's environment.
     pub fn edit_environment_values(
         &mut self,
@@ -636,6 +3058,7 @@ impl Pacs {
         environment_name: EnvironmentName,
         values: std::collections::BTreeMap<String, String>,
     ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
         let project = self.get_project_mut(project_name)?;
         let env = project
             .environments
@@ -646,49 +3069,294 @@ impl Pacs {
                     "Environment '{environment_name}' not found in project '{project_name}'"
                 ))
             })?;
-        env.values = values;
+        let previous = std::mem::replace(&mut env.values, values);
+        if let Err(err) = env.check_references() {
+            env.values = previous;
+            return Err(err);
+        }
 
         self.save_project_by_name(project_name)
     }
 
-    /// Sets the active environment for a project.
-    pub fn set_active_environment(
+    /// Sets or clears the kubectl context of an environment.
+    pub fn set_environment_kube_context(
         &mut self,
         project_name: ProjectName,
         environment_name: EnvironmentName,
+        kube_context: Option<String>,
     ) -> Result<(), PacsError> {
-        {
-            let project = self.get_project_mut(project_name)?;
-            if !project
-                .environments
-                .iter()
-                .any(|e| e.name == environment_name)
-            {
-                return Err(PacsError::ProjectNotFound(format!(
+        self.ensure_writable()?;
+        let project = self.get_project_mut(project_name)?;
+        let env = project
+            .environments
+            .iter_mut()
+            .find(|e| e.name == environment_name)
+            .ok_or_else(|| {
+                PacsError::ProjectNotFound(format!(
                     "Environment '{environment_name}' not found in project '{project_name}'"
-                )));
-            }
-            project.active_environment = Some(environment_name.to_string());
-        }
+                ))
+            })?;
+        env.kube_context = kube_context.filter(|c| !c.is_empty());
+
         self.save_project_by_name(project_name)
     }
 
-    /// Deactivates the active environment for a project.
-    pub fn deactivate_environment(&mut self, project_name: ProjectName) -> Result<(), PacsError> {
-        {
-            let project = self.get_project_mut(project_name)?;
-            project.active_environment = None;
-        }
+    /// Sets or clears the commands run when switching to and away from an
+    /// environment.
+    pub fn set_environment_hooks(
+        &mut self,
+        project_name: ProjectName,
+        environment_name: EnvironmentName,
+        on_activate: Option<String>,
+        on_deactivate: Option<String>,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        let project = self.get_project_mut(project_name)?;
+        let env = project
+            .environments
+            .iter_mut()
+            .find(|e| e.name == environment_name)
+            .ok_or_else(|| {
+                PacsError::ProjectNotFound(format!(
+                    "Environment '{environment_name}' not found in project '{project_name}'"
+                ))
+            })?;
+        env.on_activate = on_activate.filter(|c| !c.is_empty());
+        env.on_deactivate = on_deactivate.filter(|c| !c.is_empty());
+
         self.save_project_by_name(project_name)
     }
 
-    /// Returns the currently active environment name for a project, if any.
-    pub fn get_active_environment(
-        &self,
-        project_name: Option<ProjectName>,
-    ) -> Result<Option<String>, PacsError> {
-        let project = self.get_project_or_active(project_name)?;
-        Ok(project.active_environment.clone())
+    /// Sets the active environment for a project.
+    pub fn set_active_environment(
+        &mut self,
+        project_name: ProjectName,
+        environment_name: EnvironmentName,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        if !self
+            .get_project(project_name)?
+            .environments
+            .iter()
+            .any(|e| e.name == environment_name)
+        {
+            return Err(PacsError::ProjectNotFound(format!(
+                "Environment '{environment_name}' not found in project '{project_name}'"
+            )));
+        }
+        #[cfg(feature = "exec")]
+        self.run_hooks(
+            HookEvent::Switch,
+            &self.hook_context(None, Some(project_name), Some(environment_name))?,
+        )?;
+
+        #[cfg(feature = "exec")]
+        let previous = self.get_project(project_name)?.active_environment.clone();
+        #[cfg(feature = "exec")]
+        let changed = previous.as_deref() != Some(environment_name);
+        #[cfg(feature = "exec")]
+        if changed && let Some(previous) = &previous {
+            self.run_environment_hook(project_name, previous, false)?;
+        }
+
+        self.update_active_environment(project_name, Some(environment_name.to_string()))?;
+
+        #[cfg(feature = "exec")]
+        if changed {
+            self.run_environment_hook(project_name, environment_name, true)?;
+        }
+
+        let project = self.get_project(project_name)?.name.clone();
+        if let Some(path) = self.load_config()?.direnv_hooks.get(&project) {
+            direnv::write_block(path, &self.direnv_export(Some(&project), None)?)?;
+        }
+        Ok(())
+    }
+
+    /// Renders the values of an environment (or the active one) as an `.envrc` block.
+    pub fn direnv_export(
+        &self,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+    ) -> Result<String, PacsError> {
+        let project = self.get_project_or_active(project_name)?;
+        let name = environment
+            .or(self.active_environment_of(project))
+            .ok_or_else(|| {
+                PacsError::ProjectNotFound(format!(
+                    "No active environment in project '{}'",
+                    project.name
+                ))
+            })?;
+        let env = project
+            .environments
+            .iter()
+            .find(|e| e.name == name)
+            .ok_or_else(|| {
+                PacsError::ProjectNotFound(format!(
+                    "Environment '{name}' not found in project '{}'",
+                    project.name
+                ))
+            })?;
+        Ok(envrc_block(
+            &project.name,
+            &env.name,
+            &env.placeholder_values(),
+        ))
+    }
+
+    /// Writes the active environment of a project into the pacs block of an `.envrc` file.
+    /// With `hook`, the block is rewritten whenever the active environment changes.
+    pub fn write_direnv(
+        &self,
+        project_name: Option<ProjectName>,
+        path: PathBuf,
+        hook: bool,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        let project = self.get_project_or_active(project_name)?.name.clone();
+        direnv::write_block(&path, &self.direnv_export(Some(&project), None)?)?;
+
+        self.update_config(|config| {
+            if hook {
+                config.direnv_hooks.insert(project, path);
+            } else {
+                config.direnv_hooks.remove(&project);
+            }
+            Ok(())
+        })
+    }
+
+    /// Deactivates the active environment for a project.
+    pub fn deactivate_environment(&mut self, project_name: ProjectName) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        #[cfg(feature = "exec")]
+        if let Some(previous) = self.get_project(project_name)?.active_environment.clone() {
+            self.run_environment_hook(project_name, &previous, false)?;
+        }
+        self.update_active_environment(project_name, None)
+    }
+
+    /// Runs the `on_activate` or `on_deactivate` command of an environment
+    /// with its values, unless in safe mode.
+    #[cfg(feature = "exec")]
+    fn run_environment_hook(
+        &self,
+        project_name: ProjectName,
+        environment_name: EnvironmentName,
+        activate: bool,
+    ) -> Result<(), PacsError> {
+        let project = self.get_project(project_name)?;
+        let Some(env) = project
+            .environments
+            .iter()
+            .find(|e| e.name == environment_name)
+        else {
+            return Ok(());
+        };
+        let (hook, body) = if activate {
+            ("on_activate", &env.on_activate)
+        } else {
+            ("on_deactivate", &env.on_deactivate)
+        };
+        let Some(body) = body else {
+            return Ok(());
+        };
+        if self.safe_mode {
+            debug!(environment = %env.name, hook, "skipped in safe mode");
+            return Ok(());
+        }
+
+        debug!(environment = %env.name, hook, "running environment hook");
+        let output = self
+            .hook_command(project, Some(&env.name), body, None)?
+            .output()?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        Err(PacsError::EnvironmentHookFailed {
+            hook: hook.to_string(),
+            environment: env.name.clone(),
+            message: if stderr.is_empty() {
+                format!("exited with {}", output.status.code().unwrap_or(-1))
+            } else {
+                self.redact(&stderr)
+            },
+        })
+    }
+
+    /// Builds the shell process for a hook command of `project`, with the
+    /// values of `environment` rendered.
+    #[cfg(feature = "exec")]
+    fn hook_command(
+        &self,
+        project: &Project,
+        environment: Option<EnvironmentName>,
+        body: &str,
+        cwd: Option<&str>,
+    ) -> Result<Command, PacsError> {
+        let used = scan_placeholders(body);
+        let mut values = known_values(project, environment, &[], &BTreeMap::new());
+        retain_used(&mut values, |key| used.contains(&key));
+        let (body, _) = render_placeholders(body, &self.resolve_secrets(values)?);
+        Sandbox::default().shell_command(&body, cwd)
+    }
+
+    /// Changes the active environment while holding the lock, on top of the
+    /// project file as it is on disk so changes by other processes are kept.
+    fn update_active_environment(
+        &mut self,
+        project_name: ProjectName,
+        environment: Option<String>,
+    ) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        let _lock = self.lock()?;
+        let path = self.project_path(project_name);
+        let project = self.get_project_mut(project_name)?;
+        if let Ok(content) = fs::read_to_string(path) {
+            let name = project.name.clone();
+            *project = toml::from_str(&content)?;
+            project.name = name;
+        }
+        project.active_environment = environment;
+        self.save_project_by_name(project_name)
+    }
+
+    /// Returns the currently active environment name for a project, if any.
+    pub fn get_active_environment(
+        &self,
+        project_name: Option<ProjectName>,
+    ) -> Result<Option<String>, PacsError> {
+        let project = self.get_project_or_active(project_name)?;
+        Ok(self.active_environment_of(project).map(str::to_string))
+    }
+
+    /// Returns the active environment of a project. For the active project,
+    /// `PACS_ENV` takes precedence over the persisted selection.
+    fn active_environment_of<'a>(&'a self, project: &'a Project) -> Option<&'a str> {
+        if let Some(environment) = &self.session.environment
+            && self
+                .get_active_project_name()
+                .is_ok_and(|name| name == project.name)
+        {
+            return Some(environment);
+        }
+        project.active_environment.as_deref()
+    }
+
+    /// Returns the environments of every project with the active one, including
+    /// projects without environments.
+    #[must_use]
+    pub fn all_environments(&self) -> Vec<ProjectEnvironments<'_>> {
+        self.projects
+            .iter()
+            .map(|project| ProjectEnvironments {
+                project: &project.name,
+                environments: &project.environments,
+                active: self.active_environment_of(project),
+            })
+            .collect()
     }
 
     /// Returns the environments for a project.
@@ -700,86 +3368,271 @@ impl Pacs {
         Ok(&project.environments)
     }
 
+    /// Returns the placeholder values of an environment (or the active one)
+    /// with secret references resolved.
+    pub fn resolved_environment_values(
+        &self,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+    ) -> Result<BTreeMap<String, String>, PacsError> {
+        let project = self.get_project_or_active(project_name)?;
+        let environment = environment.or(self.active_environment_of(project));
+        let Some(env) =
+            environment.and_then(|name| project.environments.iter().find(|e| e.name == name))
+        else {
+            return Ok(BTreeMap::new());
+        };
+        self.resolve_secrets(env.placeholder_values())
+    }
+
+    /// Resolves the secret references among `values` and inlines them into
+    /// the values that reference them, e.g. `url = "https://{{token}}@host"`.
+    fn resolve_secrets(
+        &self,
+        values: BTreeMap<String, String>,
+    ) -> Result<BTreeMap<String, String>, PacsError> {
+        let secrets = values
+            .iter()
+            .filter(|(_, value)| is_secret_reference(value))
+            .map(|(key, value)| Ok((key.clone(), self.secrets.resolve(value)?)))
+            .collect::<Result<BTreeMap<_, _>, PacsError>>()?;
+        Ok(values
+            .into_iter()
+            .map(|(key, value)| match secrets.get(&key) {
+                Some(secret) => (key, secret.clone()),
+                None => (key, render_placeholders(&value, &secrets).0),
+            })
+            .collect())
+    }
+
     fn expand_command_with_environment(
         &self,
         cmd: &PacsCommand,
         project_name: ProjectName,
         environment: Option<EnvironmentName>,
+        values: &BTreeMap<String, String>,
+        resolve_secrets: bool,
     ) -> Result<PacsCommand, PacsError> {
         let project = self.get_project(project_name)?;
-
-        let env_values = environment
-            .and_then(|name| project.environments.iter().find(|e| e.name == name))
-            .map(|e| &e.values);
-
-        if env_values.is_none() {
+        let env = environment.and_then(|name| project.environments.iter().find(|e| e.name == name));
+        if let Some(env) = env {
+            env.check_references()?;
+        }
+        let mut used = cmd.used_keys();
+        if cmd.asks_cwd() {
+            used.push(CWD_KEY.to_string());
+        }
+        let mut env_values = known_values(project, environment, &used, values);
+        for key in &used {
+            let source = if values.contains_key(key) || values.contains_key(&variable_name(key)) {
+                "values"
+            } else if env_values.contains_key(key) {
+                environment.unwrap_or(project_name)
+            } else {
+                "nowhere"
+            };
+            trace!(command = %cmd.name, key, source, "resolved placeholder");
+        }
+        if env_values.is_empty() {
             return Ok(cmd.clone());
         }
 
-        let mut unresolved = false;
-        let mut output = String::with_capacity(cmd.command.len());
+        // Only resolve the secrets this command actually uses
+        if resolve_secrets {
+            retain_used(&mut env_values, |key| used.iter().any(|k| k == key));
+            env_values = self.resolve_secrets(env_values)?;
+        }
 
-        let mut cursor = 0;
-        let src = &cmd.command;
+        let mut expanded = cmd.clone();
+        if cmd.asks_cwd() {
+            if let Some(cwd) = env_values.get(CWD_KEY) {
+                expanded.cwd = Some(expand_home(cwd));
+            }
+        } else if let Some(cwd) = &cmd.cwd {
+            let (cwd, unresolved) = render_placeholders(cwd, &env_values);
+            if unresolved.is_empty() {
+                expanded.cwd = Some(cwd);
+            }
+        }
+        let (output, unresolved) = render_placeholders(&cmd.command, &env_values);
+        if unresolved.is_empty() {
+            expanded.command = output;
+        } else {
+            debug!(command = %cmd.name, ?unresolved, "left placeholders unrendered");
+        }
+        Ok(expanded)
+    }
 
-        while let Some(open) = src[cursor..].find("{{").map(|i| cursor + i) {
-            output.push_str(&src[cursor..open]);
+    /// Returns the required keys of `cmd` that neither the environment (or
+    /// the project's active one) nor `values` provide.
+    pub fn missing_keys(
+        &self,
+        cmd: &PacsCommand,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+        values: &BTreeMap<String, String>,
+    ) -> Result<Vec<String>, PacsError> {
+        let project = self.get_project_or_active(project_name)?;
+        let environment = environment.or(self.active_environment_of(project));
+        let required = cmd.required_keys();
+        let known = known_values(project, environment, &required, values);
+        Ok(required
+            .into_iter()
+            .filter(|key| !known.contains_key(key))
+            .collect())
+    }
 
-            let key_start = open + 2;
-            let Some(close) = src[key_start..].find("}}").map(|i| key_start + i) else {
-                output.push_str(&src[open..]);
-                cursor = src.len();
-                break;
-            };
+    /// Returns the required keys of `cmd` with their values in the
+    /// environment (or the project's active one), or `None` if it has none.
+    /// Secret references are not resolved.
+    pub fn command_keys(
+        &self,
+        cmd: &PacsCommand,
+        project_name: Option<ProjectName>,
+        environment: Option<EnvironmentName>,
+    ) -> Result<Vec<(String, Option<String>)>, PacsError> {
+        let project = self.get_project_or_active(project_name)?;
+        let environment = environment.or(self.active_environment_of(project));
+        let required = cmd.required_keys();
+        let mut known = known_values(project, environment, &required, &BTreeMap::new());
+        Ok(required
+            .into_iter()
+            .map(|key| {
+                let value = known.remove(&key);
+                (key, value)
+            })
+            .collect())
+    }
 
-            let key = &src[key_start..close];
+    /// Returns the commands of a project (or the active one) that use or
+    /// require `key`.
+    pub fn commands_using_key(
+        &self,
+        project_name: Option<ProjectName>,
+        key: &str,
+    ) -> Result<Vec<&PacsCommand>, PacsError> {
+        let project = self.get_project_or_active(project_name)?;
+        Ok(project
+            .commands
+            .iter()
+            .filter(|cmd| cmd.required_keys().iter().any(|k| k == key))
+            .collect())
+    }
 
-            if let Some(value) = env_values
-                .and_then(|vals: &std::collections::BTreeMap<String, String>| vals.get(key))
+    /// Renames the key `old` to `new` across a project (or the active one):
+    /// in every environment, in placeholders of commands, environment values
+    /// and hooks, and in `requires`. Returns the project file before and
+    /// after; with `dry_run` nothing is saved.
+    pub fn rename_key(
+        &mut self,
+        project_name: Option<ProjectName>,
+        old: &str,
+        new: &str,
+        dry_run: bool,
+    ) -> Result<(String, String), PacsError> {
+        if !dry_run {
+            self.ensure_writable()?;
+        }
+        let project = self.get_project_or_active(project_name)?;
+        let before = serialize_project(project)?;
+        let author = self.author();
+
+        let mut renamed = project.clone();
+        for env in &mut renamed.environments {
+            if let Some(value) = env.values.remove(old) {
+                if env.values.contains_key(new) {
+                    return Err(PacsError::ProjectExists(format!(
+                        "Key '{new}' already exists in environment '{}'",
+                        env.name
+                    )));
+                }
+                env.values.insert(new.to_string(), value);
+            }
+            for text in env
+                .values
+                .values_mut()
+                .chain(env.on_activate.iter_mut())
+                .chain(env.on_deactivate.iter_mut())
             {
-                output.push_str(value);
-            } else {
-                unresolved = true;
-                output.push_str("{{");
-                output.push_str(key);
-                output.push_str("}}");
+                *text = rename_placeholder(text, old, new);
             }
-
-            cursor = close + 2;
+        }
+        for cmd in &mut renamed.commands {
+            if !cmd.rename_key(old, new) {
+                continue;
+            }
+            if cmd.locked && !self.ignore_locks {
+                return Err(PacsError::CommandLocked(cmd.name.clone()));
+            }
+            cmd.author.clone_from(&author);
         }
 
-        output.push_str(&src[cursor..]);
+        let after = serialize_project(&renamed)?;
+        if !dry_run && after != before {
+            let name = renamed.name.clone();
+            *self.get_project_mut(&name)? = renamed;
+            self.save_project_by_name(&name)?;
+        }
+        Ok((before, after))
+    }
 
-        if unresolved {
-            return Ok(cmd.clone());
+    /// Returns the commands of a project that use keys missing from their
+    /// `requires`, with those keys. With `fix`, the keys are added and the
+    /// project is saved.
+    pub fn lint_required_keys(
+        &mut self,
+        project_name: Option<ProjectName>,
+        fix: bool,
+    ) -> Result<Vec<(String, Vec<String>)>, PacsError> {
+        if fix {
+            self.ensure_writable()?;
+        }
+        let project = self.get_project_or_active(project_name)?.name.clone();
+        let author = self.author();
+
+        let mut undeclared = Vec::new();
+        for cmd in &mut self.get_project_mut(&project)?.commands {
+            let keys: Vec<String> = cmd
+                .used_keys()
+                .into_iter()
+                .filter(|key| !cmd.requires.contains(key))
+                .collect();
+            if keys.is_empty() {
+                continue;
+            }
+            if fix {
+                cmd.requires.extend(keys.iter().cloned());
+                cmd.author.clone_from(&author);
+            }
+            undeclared.push((cmd.name.clone(), keys));
         }
 
-        Ok(PacsCommand {
-            name: cmd.name.clone(),
-            command: output,
-            cwd: cmd.cwd.clone(),
-            tag: cmd.tag.clone(),
-        })
+        if fix && !undeclared.is_empty() {
+            self.save_project_by_name(&project)?;
+        }
+        Ok(undeclared)
     }
 
-    fn execute(cmd: &PacsCommand) -> Result<(), PacsError> {
+    /// Runs `cmd` in a shell and returns its stdout if `capture` is set.
+    #[cfg(feature = "exec")]
+    fn execute(cmd: &PacsCommand, capture: bool) -> Result<Option<Vec<u8>>, PacsError> {
         if cmd.command.trim().is_empty() {
             return Err(PacsError::CommandNotFound(cmd.name.clone()));
         }
 
-        let cwd = cmd
-            .cwd
-            .as_ref()
-            .map_or_else(|| std::env::current_dir().unwrap(), PathBuf::from);
-
-        let status = Command::new("sh")
-            .arg("-c")
-            .arg(&cmd.command)
-            .current_dir(cwd)
-            .status()?;
+        let mut process = cmd.shell_command()?;
+        let (status, stdout) = if capture {
+            let output = process
+                .stdin(Stdio::inherit())
+                .stderr(Stdio::inherit())
+                .output()?;
+            (output.status, Some(output.stdout))
+        } else {
+            (process.status()?, None)
+        };
 
         if status.success() {
-            Ok(())
+            Ok(stdout)
         } else {
             Err(PacsError::CommandFailed(status.code().unwrap_or(-1)))
         }
@@ -844,77 +3697,454 @@ impl Pacs {
             .collect()
     }
 
-    /// Fuzzy search commands by name or content. Returns matches sorted by relevance.
+    /// Returns the keys the named commands of the active or specified project
+    /// require, in order of appearance. Unknown names are ignored.
     #[must_use]
-    pub fn search(&self, query: &str) -> Vec<&PacsCommand> {
-        let matcher = SkimMatcherV2::default();
+    pub fn suggest_placeholders(
+        &self,
+        project_name: Option<ProjectName>,
+        names: &[&str],
+    ) -> Vec<String> {
+        let Ok(project) = self.get_project_or_active(project_name) else {
+            return Vec::new();
+        };
 
-        let mut results: Vec<(&PacsCommand, i64)> = self
-            .projects
+        let mut keys: Vec<String> = Vec::new();
+        for cmd in project
+            .commands
             .iter()
-            .flat_map(|p| p.commands.iter())
-            .filter_map(|cmd| {
-                let score = matcher
-                    .fuzzy_match(&cmd.name, query)
-                    .unwrap_or(0)
-                    .max(matcher.fuzzy_match(&cmd.command, query).unwrap_or(0));
-
-                if score > 0 { Some((cmd, score)) } else { None }
-            })
-            .collect();
+            .filter(|c| names.iter().any(|name| c.matches(name)))
+        {
+            for key in cmd.required_keys() {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+        keys
+    }
 
-        // Sort descending by score
-        results.sort_by_key(|&(_, score)| -score);
+    fn github_token(config: &Config) -> Option<String> {
+        config
+            .github_token
+            .clone()
+            .or_else(|| std::env::var("GITHUB_TOKEN").ok())
+            .filter(|t| !t.is_empty())
+    }
 
-        results.into_iter().map(|(cmd, _)| cmd).collect()
+    /// Drafts a command body for `description` with the endpoint configured in
+    /// `[suggest]`. The draft is returned as is, never run.
+    pub fn suggest_command(
+        &self,
+        description: &str,
+        project: Option<ProjectName>,
+    ) -> Result<String, PacsError> {
+        let config = self.load_config()?;
+        let suggest = config.suggest.ok_or(PacsError::SuggestNotConfigured)?;
+        let project = match project {
+            Some(name) => self.get_project(name)?,
+            None => self.get_active_project()?,
+        };
+        let context = match &project.path {
+            Some(path) => format!("It runs in the directory {path}."),
+            None => String::new(),
+        };
+        suggest::draft(&suggest, description, &context)
     }
-}
 
-fn find_command_mut<'a>(
-    project: &'a mut Project,
-    name: &str,
-) -> Result<&'a mut PacsCommand, PacsError> {
-    project
-        .commands
-        .iter_mut()
-        .find(|c| c.name == name)
-        .ok_or_else(|| PacsError::CommandNotFound(name.to_string()))
-}
+    /// Explains a command body with the endpoint configured in `[suggest]`.
+    pub fn explain_with_endpoint(&self, command: &str) -> Result<String, PacsError> {
+        let config = self.load_config()?;
+        let suggest = config.suggest.ok_or(PacsError::SuggestNotConfigured)?;
+        suggest::explain(&suggest, command)
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::sync::atomic::{AtomicUsize, Ordering};
+    /// Publishes a project file to a secret gist, updating the gist of earlier
+    /// publishes. Returns the gist URL.
+    pub fn publish_project_gist(&self, name: ProjectName) -> Result<String, PacsError> {
+        let project = self.get_project(name)?;
+        if project.local_only {
+            return Err(PacsError::LocalOnly(project.name.clone()));
+        }
+        let config = self.load_config()?;
+        let token = Self::github_token(&config).ok_or(PacsError::GithubTokenMissing)?;
+
+        let gist = gist::publish(
+            &token,
+            config.gists.get(&project.name).map(String::as_str),
+            &format!("{}.toml", project.name),
+            &serialize_project(&project.shareable())?,
+            &format!("pacs project {}", project.name),
+        )?;
 
-    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        self.update_config(|config| {
+            config.gists.insert(project.name.clone(), gist.id);
+            Ok(())
+        })?;
+        Ok(gist.html_url)
+    }
 
-    fn temp_pacs() -> Pacs {
-        let id = COUNTER.fetch_add(1, Ordering::SeqCst);
-        let dir = std::env::temp_dir().join(format!("pacs_test_{}_{}", std::process::id(), id));
-        if dir.exists() {
-            fs::remove_dir_all(&dir).ok();
-        }
-        Pacs::init_at(dir).unwrap()
+    /// Creates a project from the TOML file of a gist. A local project of the
+    /// same name, in any case, is only replaced with `force`. Returns the
+    /// project name.
+    pub fn pull_project_gist(&mut self, id: &str, force: bool) -> Result<String, PacsError> {
+        self.ensure_writable()?;
+        let config = self.load_config()?;
+        let content = gist::fetch(Self::github_token(&config).as_deref(), id)?;
+        self.add_pulled_project(id, &content, force)
     }
 
-    #[test]
-    fn test_project() {
-        let mut pacs = temp_pacs();
-        pacs.init_project("test", None).unwrap();
-        pacs.set_active_project("test").unwrap();
-        pacs.add_command(
-            PacsCommand {
-                name: "hello".into(),
-                command: "echo hello".into(),
-                cwd: None,
-                tag: "".into(),
-            },
-            Some("test"),
-        )
-        .unwrap();
+    /// Adds a project pulled from gist `id`, see [`Pacs::pull_project_gist`].
+    fn add_pulled_project(
+        &mut self,
+        id: &str,
+        content: &str,
+        force: bool,
+    ) -> Result<String, PacsError> {
+        let mut project: Project = toml::from_str(content)?;
+        if project.name.is_empty()
+            || project.name.starts_with('.')
+            || project.name.contains(['/', '\\'])
+        {
+            return Err(PacsError::FetchFailed(format!(
+                "gist {id}: invalid project name '{}'",
+                project.name
+            )));
+        }
+        if let Ok(existing) = self.get_project(&project.name) {
+            if !force {
+                return Err(PacsError::ProjectExists(existing.name.clone()));
+            }
+            // Keep the local spelling, so that the same file is replaced
+            project.name.clone_from(&existing.name);
+        }
+        let name = project.name.clone();
 
-        let commands = pacs.list(Some("test"), None).unwrap();
-        assert_eq!(commands.len(), 1);
+        self.save_project(&project)?;
+        match self.projects.iter_mut().find(|p| p.name == name) {
+            Some(existing) => *existing = project,
+            None => self.projects.push(project),
+        }
+
+        self.update_config(|config| {
+            config.gists.insert(name.clone(), id.to_string());
+            Ok(())
+        })?;
+        Ok(name)
+    }
+
+    fn registries_dir(&self) -> PathBuf {
+        self.base_dir.join("registries")
+    }
+
+    /// Returns all subscribed registries with their cached commands.
+    #[must_use]
+    pub fn registries(&self) -> &[Registry] {
+        &self.registries
+    }
+
+    /// Subscribes to a registry and fetches it. Returns the number of commands it provides.
+    pub fn add_registry(&mut self, source: RegistrySource) -> Result<usize, PacsError> {
+        self.ensure_writable()?;
+        let exists = |config: &Config| config.registries.iter().any(|r| r.name == source.name);
+        if exists(&self.load_config()?) {
+            return Err(PacsError::RegistryExists(source.name));
+        }
+
+        let registry = registry::fetch(&self.registries_dir(), source.clone())?;
+        let count = registry.commands.len();
+
+        self.update_config(|config| {
+            if exists(config) {
+                return Err(PacsError::RegistryExists(source.name.clone()));
+            }
+            config.registries.push(source.clone());
+            Ok(())
+        })?;
+        self.registries.push(registry);
+        Ok(count)
+    }
+
+    /// Approves the current version of a registry command to run. Searches
+    /// all registries in the order they were added unless `registry` is given.
+    /// Returns the registry's name.
+    pub fn approve_registry_command(
+        &mut self,
+        name: &str,
+        registry: Option<&str>,
+    ) -> Result<String, PacsError> {
+        self.ensure_writable()?;
+        let found = self.registry_command(name, registry)?.0.source.name.clone();
+        let dir = self.registries_dir();
+        self.registries
+            .iter_mut()
+            .filter(|r| r.source.name == found)
+            .try_for_each(|r| r.approve(&dir, name))?;
+        Ok(found)
+    }
+
+    /// Returns the registry providing a command, and the command.
+    pub fn registry_command(
+        &self,
+        name: &str,
+        registry: Option<&str>,
+    ) -> Result<(&Registry, &PacsCommand), PacsError> {
+        if let Some(registry) = registry
+            && !self.registries.iter().any(|r| r.source.name == registry)
+        {
+            return Err(PacsError::RegistryNotFound(registry.to_string()));
+        }
+        self.registries
+            .iter()
+            .filter(|r| registry.is_none_or(|n| r.source.name == n))
+            .find_map(|r| r.commands.iter().find(|c| c.matches(name)).map(|c| (r, c)))
+            .ok_or_else(|| PacsError::CommandNotFound(name.to_string()))
+    }
+
+    /// Unsubscribes from a registry and removes its cached copy.
+    pub fn remove_registry(&mut self, name: &str) -> Result<(), PacsError> {
+        self.ensure_writable()?;
+        self.update_config(|config| {
+            let before = config.registries.len();
+            config.registries.retain(|r| r.name != name);
+            if config.registries.len() == before {
+                return Err(PacsError::RegistryNotFound(name.to_string()));
+            }
+            Ok(())
+        })?;
+        registry::remove_cached(&self.registries_dir(), name)?;
+        self.registries.retain(|r| r.source.name != name);
+        Ok(())
+    }
+
+    /// Refetches registries whose cached copy is older than `max_age`, or all
+    /// registries if `max_age` is `None`. A registry that fails to fetch keeps
+    /// its cached commands; the failures are returned by registry name.
+    pub fn update_registries(&mut self, max_age: Option<Duration>) -> Vec<(String, PacsError)> {
+        let dir = self.registries_dir();
+        let mut errors = Vec::new();
+        for registry in &mut self.registries {
+            if max_age.is_some_and(|max_age| !registry.is_stale(max_age)) {
+                continue;
+            }
+            match registry::fetch(&dir, registry.source.clone()) {
+                Ok(fetched) => *registry = fetched,
+                Err(e) => errors.push((registry.source.name.clone(), e)),
+            }
+        }
+        errors
+    }
+
+    /// Fuzzy search commands by name or content. Returns matches sorted by relevance.
+    ///
+    /// Commands of subscribed registries are searched as well.
+    #[cfg(feature = "search")]
+    #[must_use]
+    pub fn search(&self, query: &str) -> Vec<SearchMatch<'_>> {
+        search::search_commands(&self.projects, &self.registries, query)
+    }
+}
+
+/// Serializes a project file with its commands sorted by name.
+#[cfg(feature = "fs-store")]
+fn serialize_project(project: &Project) -> Result<String, PacsError> {
+    let mut sorted = project.commands.clone();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    let temp = Project {
+        commands: sorted,
+        ..project.clone()
+    };
+    Ok(toml::to_string_pretty(&temp)?)
+}
+
+/// Quotes a string for use as a single shell word.
+#[must_use]
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Fails if an explicitly requested environment does not exist in the project.
+#[cfg(feature = "fs-store")]
+fn check_environment(
+    project: &Project,
+    environment: Option<EnvironmentName>,
+) -> Result<(), PacsError> {
+    match environment {
+        Some(name) if !project.environments.iter().any(|e| e.name == name) => {
+            Err(PacsError::ProjectNotFound(format!(
+                "Environment '{name}' not found in project '{}'",
+                project.name
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns true if an environment variable is set to something other than
+/// empty, `0` or `false`.
+#[cfg(feature = "fs-store")]
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|v| !matches!(v.as_str(), "" | "0" | "false"))
+}
+
+/// Checks that the names and aliases of a project's commands and the names of
+/// its environments are unique, and that its active environment exists.
+#[cfg(feature = "fs-store")]
+fn validate_project(project: &Project) -> Result<(), PacsError> {
+    let mut names = Vec::new();
+    for cmd in &project.commands {
+        let mut own: Vec<&str> = std::iter::once(&cmd.name)
+            .chain(&cmd.aliases)
+            .map(String::as_str)
+            .collect();
+        own.sort_unstable();
+        own.dedup();
+        for name in own {
+            if names.contains(&name) {
+                return Err(PacsError::CommandExists(name.to_string()));
+            }
+            names.push(name);
+        }
+    }
+
+    for (i, env) in project.environments.iter().enumerate() {
+        if project.environments[..i].iter().any(|e| e.name == env.name) {
+            return Err(PacsError::ProjectExists(format!(
+                "Environment '{}' already exists in project '{}'",
+                env.name, project.name
+            )));
+        }
+    }
+    check_environment(project, project.active_environment.as_deref())
+}
+
+/// Writes a file via a temporary file and a rename, so that other processes
+/// never read a partially written file.
+#[cfg(feature = "fs-store")]
+fn write_atomic(path: &std::path::Path, content: &str) -> Result<(), PacsError> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)?;
+    debug!("saved {}", path.display());
+    Ok(())
+}
+
+/// Fails if any of `names` is the name or an alias of a command in `project`,
+/// other than the command named `except`. Every operation that gives a
+/// command a name or alias goes through this check.
+#[cfg(feature = "fs-store")]
+fn check_names_available(
+    project: &Project,
+    names: &[&str],
+    except: Option<&str>,
+) -> Result<(), PacsError> {
+    let taken = names.iter().find(|name| {
+        project
+            .commands
+            .iter()
+            .any(|c| Some(c.name.as_str()) != except && c.matches(name))
+    });
+    match taken {
+        Some(name) => Err(PacsError::CommandExists((*name).to_string())),
+        None => Ok(()),
+    }
+}
+
+/// Returns the values known for `keys` in a project: the environment's values,
+/// `{{project_path}}` and `values`, which match a key as-is or as variable name.
+#[cfg(feature = "fs-store")]
+fn known_values(
+    project: &Project,
+    environment: Option<EnvironmentName>,
+    keys: &[String],
+    values: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut known = environment
+        .and_then(|name| project.environments.iter().find(|e| e.name == name))
+        .map(Environment::placeholder_values)
+        .unwrap_or_default();
+    if let Some(path) = &project.path {
+        known
+            .entry(PROJECT_PATH_PLACEHOLDER.to_string())
+            .or_insert_with(|| path.clone());
+    }
+    for key in keys {
+        let value = values.get(key).or_else(|| values.get(&variable_name(key)));
+        if let Some(value) = value {
+            known.insert(key.clone(), value.clone());
+        }
+    }
+    known
+}
+
+/// Keeps the values of the keys for which `used` holds, and the secret
+/// references those values use, which are resolved only after interpolation.
+#[cfg(feature = "fs-store")]
+fn retain_used(values: &mut BTreeMap<String, String>, used: impl Fn(&str) -> bool) {
+    let referenced: Vec<String> = values
+        .iter()
+        .filter(|(key, _)| used(key))
+        .flat_map(|(_, value)| scan_placeholders(value))
+        .map(str::to_string)
+        .collect();
+    values
+        .retain(|key, value| used(key) || (referenced.contains(key) && is_secret_reference(value)));
+}
+
+/// Moves `name` to the front of the recently switched to projects.
+#[cfg(feature = "fs-store")]
+fn remember_project(recent: &mut Vec<String>, name: &str) {
+    recent.retain(|p| !p.eq_ignore_ascii_case(name));
+    recent.insert(0, name.to_string());
+    recent.truncate(MAX_RECENT_PROJECTS);
+}
+
+#[cfg(feature = "fs-store")]
+fn find_command_mut<'a>(
+    project: &'a mut Project,
+    name: &str,
+) -> Result<&'a mut PacsCommand, PacsError> {
+    project
+        .commands
+        .iter_mut()
+        .find(|c| c.matches(name))
+        .ok_or_else(|| PacsError::CommandNotFound(name.to_string()))
+}
+
+// The store tests exercise running and searching as well
+#[cfg(all(test, feature = "exec", feature = "fs-store", feature = "search"))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    fn temp_pacs() -> Pacs {
+        let id = COUNTER.fetch_add(1, Ordering::SeqCst);
+        let dir = std::env::temp_dir().join(format!("pacs_test_{}_{}", std::process::id(), id));
+        if dir.exists() {
+            fs::remove_dir_all(&dir).ok();
+        }
+        Pacs::init_at(dir).unwrap()
+    }
+
+    #[test]
+    fn test_project() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("test", None).unwrap();
+        pacs.set_active_project("test").unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "hello".into(),
+                command: "echo hello".into(),
+                ..Default::default()
+            },
+            Some("test"),
+        )
+        .unwrap();
+
+        let commands = pacs.list(Some("test"), None).unwrap();
+        assert_eq!(commands.len(), 1);
         assert_eq!(commands[0].name, "hello");
 
         pacs.delete_command("hello", Some("test")).unwrap();
@@ -922,7 +4152,7 @@ mod tests {
         assert!(cmds.is_empty());
 
         pacs.delete_project("test").unwrap();
-        assert!(!pacs.projects.iter().any(|p| p.name == "test"));
+        assert!(!pacs.projects().iter().any(|p| p.name == "test"));
     }
 
     #[test]
@@ -934,8 +4164,7 @@ mod tests {
             PacsCommand {
                 name: "build".into(),
                 command: "cargo build".into(),
-                cwd: None,
-                tag: "".into(),
+                ..Default::default()
             },
             Some("test"),
         )
@@ -946,8 +4175,7 @@ mod tests {
             PacsCommand {
                 name: "build".into(),
                 command: "cargo build --release".into(),
-                cwd: None,
-                tag: "".into(),
+                ..Default::default()
             },
             Some("test"),
         );
@@ -964,8 +4192,7 @@ mod tests {
             PacsCommand {
                 name: "deploy".into(),
                 command: "echo deploy".into(),
-                cwd: None,
-                tag: "".into(),
+                ..Default::default()
             },
             Some("myproject"),
         )
@@ -975,8 +4202,7 @@ mod tests {
             PacsCommand {
                 name: "deploy".into(),
                 command: "echo project deploy".into(),
-                cwd: None,
-                tag: "".into(),
+                ..Default::default()
             },
             Some("myproject"),
         );
@@ -993,8 +4219,7 @@ mod tests {
             PacsCommand {
                 name: "test".into(),
                 command: "cargo test".into(),
-                cwd: None,
-                tag: "".into(),
+                ..Default::default()
             },
             Some("proj1"),
         )
@@ -1005,8 +4230,7 @@ mod tests {
             PacsCommand {
                 name: "test".into(),
                 command: "cargo test --all".into(),
-                cwd: None,
-                tag: "".into(),
+                ..Default::default()
             },
             Some("proj1"),
         );
@@ -1025,8 +4249,7 @@ mod tests {
             PacsCommand {
                 name: "run".into(),
                 command: "echo proj1".into(),
-                cwd: None,
-                tag: "".into(),
+                ..Default::default()
             },
             Some("proj1"),
         )
@@ -1037,301 +4260,1771 @@ mod tests {
             PacsCommand {
                 name: "run".into(),
                 command: "echo proj2".into(),
-                cwd: None,
-                tag: "".into(),
+                ..Default::default()
             },
             Some("proj2"),
         )
         .unwrap();
 
-        // Verify both exist
-        let cmds1 = pacs.list(Some("proj1"), None).unwrap();
-        let cmds2 = pacs.list(Some("proj2"), None).unwrap();
-        assert_eq!(cmds1.len(), 1);
-        assert_eq!(cmds2.len(), 1);
-        assert_eq!(cmds1[0].command, "echo proj1");
-        assert_eq!(cmds2[0].command, "echo proj2");
+        // Verify both exist
+        let cmds1 = pacs.list(Some("proj1"), None).unwrap();
+        let cmds2 = pacs.list(Some("proj2"), None).unwrap();
+        assert_eq!(cmds1.len(), 1);
+        assert_eq!(cmds2.len(), 1);
+        assert_eq!(cmds1[0].command, "echo proj1");
+        assert_eq!(cmds2[0].command, "echo proj2");
+    }
+
+    #[test]
+    fn test_delete_command_auto() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("active_proj", None).unwrap();
+        pacs.set_active_project("active_proj").unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "cmd1".into(),
+                command: "echo 1".into(),
+                ..Default::default()
+            },
+            Some("active_proj"),
+        )
+        .unwrap();
+
+        // Add command to another project
+        pacs.init_project("other_proj", None).unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "cmd2".into(),
+                command: "echo 2".into(),
+                ..Default::default()
+            },
+            Some("other_proj"),
+        )
+        .unwrap();
+
+        // delete_command_auto should find cmd1 in active project
+        pacs.delete_command_auto("cmd1").unwrap();
+        assert!(pacs.list(Some("active_proj"), None).unwrap().is_empty());
+
+        // delete_command_auto should NOT find cmd2 (in other project, not active)
+        assert!(pacs.delete_command_auto("cmd2").is_err());
+
+        // Deleting non-existent command should fail
+        assert!(pacs.delete_command_auto("cmd3").is_err());
+    }
+
+    #[test]
+    fn test_get_command_auto() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("proj", None).unwrap();
+        pacs.set_active_project("proj").unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "proj-cmd".into(),
+                command: "echo project".into(),
+                ..Default::default()
+            },
+            Some("proj"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            pacs.get_command_auto("proj-cmd").unwrap().command,
+            "echo project"
+        );
+        assert!(matches!(
+            pacs.get_command_auto("nope"),
+            Err(PacsError::CommandNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_update_command_auto() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("test", None).unwrap();
+        pacs.set_active_project("test").unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "cmd".into(),
+                command: "old".into(),
+                ..Default::default()
+            },
+            Some("test"),
+        )
+        .unwrap();
+
+        let old = pacs.update_command_auto("cmd", "new".into()).unwrap();
+        assert_eq!(old, "old");
+        assert_eq!(pacs.get_command_auto("cmd").unwrap().command, "new");
+    }
+
+    #[test]
+    fn test_rename_command_auto() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("test", None).unwrap();
+        pacs.set_active_project("test").unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "old-name".into(),
+                command: "echo test".into(),
+                ..Default::default()
+            },
+            Some("test"),
+        )
+        .unwrap();
+
+        pacs.rename_command_auto("old-name", "new-name").unwrap();
+        assert!(matches!(
+            pacs.get_command_auto("old-name"),
+            Err(PacsError::CommandNotFound(_))
+        ));
+        assert_eq!(
+            pacs.get_command_auto("new-name").unwrap().command,
+            "echo test"
+        );
+    }
+
+    #[test]
+    fn test_rename_to_existing_fails() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("test", None).unwrap();
+        pacs.set_active_project("test").unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "a".into(),
+                command: "".into(),
+                ..Default::default()
+            },
+            Some("test"),
+        )
+        .unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "b".into(),
+                command: "".into(),
+                ..Default::default()
+            },
+            Some("test"),
+        )
+        .unwrap();
+
+        let result = pacs.rename_command_auto("a", "b");
+        assert!(matches!(result, Err(PacsError::CommandExists(_))));
+    }
+
+    #[test]
+    fn test_run_auto() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("test", None).unwrap();
+        pacs.set_active_project("test").unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "echo-test".into(),
+                command: "echo hello".into(),
+                ..Default::default()
+            },
+            Some("test"),
+        )
+        .unwrap();
+
+        // Test would run the command, but we can't easily test output
+        // pacs.run("echo-test", None, None).unwrap();
+        assert!(matches!(
+            pacs.run("nonexistent", None, None),
+            Err(PacsError::CommandNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_run_records_history() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("test", None).unwrap();
+        pacs.set_active_project("test").unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "ok".into(),
+                command: "echo token".into(),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "fail".into(),
+                command: "exit 3".into(),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+
+        pacs.run("ok", None, None).unwrap();
+        assert!(matches!(
+            pacs.run("fail", None, None),
+            Err(PacsError::CommandFailed(3))
+        ));
+        let output = pacs.run_capturing_output("ok", None, None, &BTreeMap::new());
+        assert_eq!(output.unwrap(), b"token\n");
+
+        let history = pacs.history().unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].command, "ok");
+        assert_eq!(history[0].exit_code, 0);
+        assert_eq!(history[1].project, "test");
+        assert_eq!(history[1].exit_code, 3);
+
+        let recent = |pacs: &Pacs| -> Vec<String> {
+            let commands = pacs.recent_commands(Some("test")).unwrap();
+            commands.iter().map(|c| c.name.clone()).collect()
+        };
+        assert_eq!(recent(&pacs), ["ok", "fail"]);
+        fs::write(pacs.config_file(), "recent = 1\n").unwrap();
+        assert_eq!(recent(&pacs), ["ok"]);
+        fs::write(pacs.config_file(), "recent = 0\n").unwrap();
+        assert!(recent(&pacs).is_empty());
+    }
+
+    #[test]
+    fn test_history_privacy() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        let login = "name = 'login'\ncommand = 'login'\ntag = 'auth'\naliases = ['l']";
+        pacs.add_command(toml::from_str(login).unwrap(), Some("api"))
+            .unwrap();
+        fs::write(
+            pacs.config_file(),
+            "[history]\nexclude_tags = ['auth']\nexclude_commands = ['token']\n",
+        )
+        .unwrap();
+
+        let record = |command: &str, started_at| RunRecord {
+            command: command.into(),
+            project: "api".into(),
+            environment: None,
+            exit_code: 0,
+            started_at,
+            duration_ms: 0,
+        };
+        for (command, started_at) in [("login", 1), ("token", 2), ("build", 3), ("test", 4)] {
+            pacs.record_run(&record(command, started_at)).unwrap();
+        }
+        pacs.record_run(&RunRecord {
+            project: "web".into(),
+            ..record("build", 5)
+        })
+        .unwrap();
+        let commands = |pacs: &Pacs| -> Vec<String> {
+            pacs.history()
+                .unwrap()
+                .into_iter()
+                .map(|r| r.command)
+                .collect()
+        };
+        assert_eq!(commands(&pacs), ["build", "test", "build"]);
+
+        assert_eq!(pacs.clear_history(Some("api"), Some(4)).unwrap(), 1);
+        assert_eq!(commands(&pacs), ["test", "build"]);
+        assert_eq!(pacs.clear_history(None, None).unwrap(), 2);
+        assert!(pacs.history().unwrap().is_empty());
+        pacs.record_run(&record("build", 6)).unwrap();
+        assert_eq!(commands(&pacs), ["build"]);
+    }
+
+    #[test]
+    fn test_effective_settings() {
+        let mut pacs = temp_pacs();
+        fs::write(
+            pacs.config_file(),
+            "recent = 7\nrun_summary = false\ngithub_token = 'ghp_x'\n",
+        )
+        .unwrap();
+        pacs.set_safe_mode();
+        assert!(!pacs.shows_run_summary());
+        let settings = pacs.effective_settings().unwrap();
+        let setting = |key: &str| {
+            let s = settings.iter().find(|s| s.key == key).unwrap();
+            (s.value.as_str(), s.source)
+        };
+        assert_eq!(setting("recent"), ("7", SettingSource::ConfigFile));
+        assert_eq!(setting("run_summary"), ("false", SettingSource::ConfigFile));
+        assert_eq!(
+            setting("safe_mode"),
+            ("true", SettingSource::Flag("--safe"))
+        );
+        assert_eq!(setting("reveal"), ("false", SettingSource::Default));
+        assert_eq!(setting("github_token").0, format!("\"{REDACTED}\""));
+    }
+
+    #[test]
+    fn test_completion_cache() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        let build = "name = 'build'\ncommand = 'cargo build'\ntag = 'rust'";
+        pacs.add_command(toml::from_str(build).unwrap(), Some("api"))
+            .unwrap();
+
+        let path = pacs.base_dir().join("completions.toml");
+        let mut cache = CompletionCache::load(&path, &pacs.projects_dir()).unwrap();
+        assert_eq!(cache.projects(), ["api"]);
+        assert!(cache.project(None).is_none());
+        cache.active_project = Some("API".into());
+        let names = cache.project(None).unwrap();
+        assert_eq!(names.commands, ["build"]);
+        assert_eq!(names.tags, ["rust"]);
+
+        pacs.delete_project("api").unwrap();
+        let cache = CompletionCache::load(&path, &pacs.projects_dir()).unwrap();
+        assert!(cache.projects.is_empty());
+
+        // A project file edited by hand outdates the cache
+        fs::write(pacs.projects_dir().join("web.toml"), "name = 'web'\n").unwrap();
+        assert_eq!(pacs.completions_status().state, CacheState::Outdated);
+        pacs.rebuild_completions().unwrap();
+        assert_eq!(pacs.completions_status().state, CacheState::Fresh);
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, content.replace("version = 1", "version = 0")).unwrap();
+        assert_eq!(pacs.completions_status().state, CacheState::Outdated);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(pacs.completions_status().state, CacheState::Missing);
+    }
+
+    #[test]
+    fn test_history_retention() {
+        let pacs = temp_pacs();
+        let now = unix_now();
+        let record = |command: &str, started_at| RunRecord {
+            command: command.into(),
+            project: "api".into(),
+            environment: None,
+            exit_code: 0,
+            started_at,
+            duration_ms: 0,
+        };
+        for (i, command) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            pacs.record_run(&record(command, now - 100 + i as u64))
+                .unwrap();
+        }
+        fs::write(
+            pacs.config_file(),
+            "[history]
+max_entries = 3
+",
+        )
+        .unwrap();
+        pacs.record_run(&record("e", now)).unwrap();
+        let commands = |pacs: &Pacs| -> Vec<String> {
+            pacs.history()
+                .unwrap()
+                .into_iter()
+                .map(|r| r.command)
+                .collect()
+        };
+        assert_eq!(commands(&pacs), ["c", "d", "e"]);
+
+        fs::write(
+            pacs.config_file(),
+            "[history.projects.api]
+max_entries = 1
+",
+        )
+        .unwrap();
+        assert_eq!(pacs.gc().unwrap(), 2);
+        assert_eq!(commands(&pacs), ["e"]);
+        assert_eq!(pacs.gc().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_active_project() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("p1", None).unwrap();
+        pacs.init_project("p2", None).unwrap();
+
+        assert!(pacs.get_active_project_name().is_err());
+
+        pacs.set_active_project("p1").unwrap();
+        assert_eq!(pacs.get_active_project_name().unwrap(), String::from("p1"));
+
+        pacs.clear_active_project().unwrap();
+        assert!(pacs.get_active_project_name().is_err());
+    }
+
+    #[test]
+    fn test_recent_projects() {
+        let mut pacs = temp_pacs();
+        for name in ["p1", "p2", "p3", "p4"] {
+            pacs.init_project(name, None).unwrap();
+        }
+        assert_eq!(pacs.previous_project().unwrap(), None);
+
+        pacs.set_active_project("p3").unwrap();
+        pacs.set_active_project("p1").unwrap();
+        pacs.set_active_project("p2").unwrap();
+        pacs.set_active_project("p1").unwrap();
+        assert_eq!(pacs.previous_project().unwrap().as_deref(), Some("p2"));
+        let names: Vec<&str> = pacs
+            .projects_by_recency()
+            .unwrap()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, ["p1", "p2", "p3", "p4"]);
+
+        pacs.delete_project("p2").unwrap();
+        assert_eq!(pacs.previous_project().unwrap().as_deref(), Some("p3"));
+        assert_eq!(pacs.load_config().unwrap().recent_projects, ["p1", "p3"]);
+    }
+
+    #[test]
+    fn test_archived_projects() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("old", None).unwrap();
+        pacs.init_project("new", None).unwrap();
+        pacs.set_active_project("old").unwrap();
+
+        pacs.set_project_archived("old", true).unwrap();
+        assert!(pacs.get_active_project_name().is_err());
+        let cache = CompletionCache::new(pacs.projects(), &pacs.projects_dir());
+        assert_eq!(cache.projects(), ["new"]);
+
+        pacs.set_project_archived("old", false).unwrap();
+        let reloaded = Pacs::init_at(pacs.base_dir().to_path_buf()).unwrap();
+        assert!(reloaded.projects().iter().all(|p| !p.archived));
+    }
+
+    #[test]
+    fn test_project_style() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("prod", None).unwrap();
+        pacs.set_project_style("prod", Some("red"), Some("!"))
+            .unwrap();
+        pacs.set_project_style("prod", None, Some("")).unwrap();
+
+        let reloaded = Pacs::init_at(pacs.base_dir().to_path_buf()).unwrap();
+        let project = reloaded.get_project("prod").unwrap();
+        assert_eq!(project.color.as_deref(), Some("red"));
+        assert_eq!(project.icon, None);
+    }
+
+    #[test]
+    fn test_list_by_tag() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("test", None).unwrap();
+        pacs.set_active_project("test").unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "tag1".into(),
+                command: "".into(),
+                tag: "dev".into(),
+                ..Default::default()
+            },
+            Some("test"),
+        )
+        .unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "tag2".into(),
+                command: "".into(),
+                tag: "prod".into(),
+                ..Default::default()
+            },
+            Some("test"),
+        )
+        .unwrap();
+
+        let all = pacs.list(Some("test"), None).unwrap();
+        let dev = all
+            .into_iter()
+            .filter(|c| c.tag == "dev")
+            .collect::<Vec<_>>();
+        assert_eq!(dev.len(), 1);
+        assert_eq!(dev[0].name, "tag1");
+    }
+
+    #[test]
+    fn test_add_command_active_project_fallback() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("test", None).unwrap();
+        pacs.set_active_project("test").unwrap();
+
+        // Add command without specifying project (uses active project)
+        pacs.add_command(
+            PacsCommand {
+                name: "fallback-cmd".into(),
+                command: "echo fallback".into(),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+
+        // Verify command was added to active project
+        let commands = pacs.list(None, None).unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].name, "fallback-cmd");
+
+        // Also works with explicit project name
+        pacs.add_command(
+            PacsCommand {
+                name: "explicit-cmd".into(),
+                command: "echo explicit".into(),
+                ..Default::default()
+            },
+            Some("test"),
+        )
+        .unwrap();
+
+        let commands = pacs.list(Some("test"), None).unwrap();
+        assert_eq!(commands.len(), 2);
+    }
+
+    #[test]
+    fn test_add_commands_all_or_none() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("test", None).unwrap();
+        let cmd = |toml: &str| -> PacsCommand { toml::from_str(toml).unwrap() };
+
+        let batch = vec![
+            cmd("name = 'build'\ncommand = 'cargo build'"),
+            cmd("name = 'test'\ncommand = 'cargo test'\naliases = ['build']"),
+        ];
+        assert!(matches!(
+            pacs.add_commands(batch, Some("test")),
+            Err(PacsError::CommandExists(name)) if name == "build"
+        ));
+        let batch = vec![
+            cmd("name = 'build'\ncommand = 'cargo build'"),
+            cmd("name = 'old'\ncommand = 'make'\nexpires = 'soon'"),
+        ];
+        assert!(matches!(
+            pacs.add_commands(batch, Some("test")),
+            Err(PacsError::InvalidDate(_))
+        ));
+        assert!(pacs.list(Some("test"), None).unwrap().is_empty());
+
+        let batch = vec![
+            cmd("name = 'build'\ncommand = 'cargo build'"),
+            cmd("name = 'test'\ncommand = 'cargo test'\nreview_after = '2030-01-01'"),
+        ];
+        pacs.add_commands(batch, Some("test")).unwrap();
+        let commands = pacs.list(Some("test"), None).unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[1].review_after.as_deref(), Some("2030-01-01"));
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let cmd = PacsCommand {
+            name: "get-pods".into(),
+            command: "kubectl --context {{ctx}} -n {{ns}} get pods {{ctx}} {{open".into(),
+            ..Default::default()
+        };
+        assert_eq!(cmd.placeholders(), vec!["ctx", "ns"]);
+
+        let values = std::collections::BTreeMap::from([("ctx".to_string(), "dev".to_string())]);
+        let (output, unresolved) = render_placeholders(&cmd.command, &values);
+        assert_eq!(
+            output,
+            "kubectl --context dev -n {{ns}} get pods dev {{open"
+        );
+        assert_eq!(unresolved, vec!["ns"]);
+    }
+
+    #[test]
+    fn test_description() {
+        let cmd = |body: &str| PacsCommand {
+            command: body.into(),
+            ..toml::from_str("name = 'deploy'\ncommand = ''").unwrap()
+        };
+        assert_eq!(
+            cmd("#!/bin/sh\n# Build and push\n#   the image\ndocker push # not this\n")
+                .description()
+                .as_deref(),
+            Some("Build and push\nthe image")
+        );
+        assert_eq!(cmd("docker push\n# done").description(), None);
+        assert_eq!(cmd("#\n").description(), None);
+    }
+
+    #[test]
+    fn test_broken_project_file_is_skipped() {
+        let id = COUNTER.fetch_add(1, Ordering::SeqCst);
+        let dir = std::env::temp_dir().join(format!("pacs_test_{}_{}", std::process::id(), id));
+        if dir.exists() {
+            fs::remove_dir_all(&dir).ok();
+        }
+        let mut pacs = Pacs::init_at(dir.clone()).unwrap();
+        pacs.init_project("good", None).unwrap();
+        fs::write(dir.join("projects").join("broken.toml"), "name = [").unwrap();
+
+        let pacs = Pacs::init_at(dir).unwrap();
+        assert_eq!(pacs.projects().len(), 1);
+        assert_eq!(pacs.projects()[0].name, "good");
+        assert_eq!(pacs.load_warnings().len(), 1);
+        assert_eq!(pacs.load_warnings()[0].project, "broken");
+    }
+
+    #[test]
+    fn test_delete_command_active_project_fallback() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("test", None).unwrap();
+        pacs.set_active_project("test").unwrap();
+
+        // Add a command
+        pacs.add_command(
+            PacsCommand {
+                name: "to-delete".into(),
+                command: "echo delete me".into(),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+
+        // Delete without specifying project (uses active project)
+        pacs.delete_command("to-delete", None).unwrap();
+
+        let commands = pacs.list(None, None).unwrap();
+        assert!(commands.is_empty());
+    }
+
+    #[test]
+    fn test_project_names_ignore_case() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("foo", None).unwrap();
+
+        assert!(matches!(
+            pacs.init_project("Foo", None),
+            Err(PacsError::ProjectExists(name)) if name == "foo"
+        ));
+        assert!(matches!(
+            pacs.init_project_from_template("FOO", None, "rust"),
+            Err(PacsError::ProjectExists(name)) if name == "foo"
+        ));
+        assert_eq!(pacs.projects().len(), 1);
+        assert!(pacs.get_project("foo").unwrap().commands.is_empty());
+    }
+
+    #[test]
+    fn test_pulled_project_replaces_only_with_force() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("foo", None).unwrap();
+        let content = "name = 'Foo'\n\n[[commands]]\nname = 'build'\ncommand = 'make'\n";
+
+        assert!(matches!(
+            pacs.add_pulled_project("abc", content, false),
+            Err(PacsError::ProjectExists(name)) if name == "foo"
+        ));
+        assert!(pacs.get_project("foo").unwrap().commands.is_empty());
+
+        assert_eq!(
+            pacs.add_pulled_project("abc", content, true).unwrap(),
+            "foo"
+        );
+        assert_eq!(pacs.projects().len(), 1);
+        assert_eq!(pacs.get_project("foo").unwrap().commands.len(), 1);
+        let files = fs::read_dir(pacs.projects_dir()).unwrap().count();
+        assert_eq!(files, 1);
+    }
+
+    #[test]
+    fn test_registry_add_and_remove() {
+        let mut pacs = temp_pacs();
+        let file = pacs.base_dir.join("ops.toml");
+        fs::write(
+            &file,
+            "[[commands]]\nname = \"pods\"\ncommand = \"kubectl get pods\"\n",
+        )
+        .unwrap();
+        let source = RegistrySource {
+            name: "ops".into(),
+            url: format!("file://{}", file.display()),
+        };
+
+        assert_eq!(pacs.add_registry(source.clone()).unwrap(), 1);
+        assert!(matches!(
+            pacs.add_registry(source),
+            Err(PacsError::RegistryExists(_))
+        ));
+        let found = &pacs.search("pods")[0];
+        assert_eq!(found.command.name, "pods");
+        assert_eq!(found.scope, "ops");
+        assert!(found.registry);
+
+        // Cached copy survives a reload
+        let reloaded = Pacs::init_at(pacs.base_dir().to_path_buf()).unwrap();
+        assert_eq!(reloaded.registries()[0].commands.len(), 1);
+
+        pacs.remove_registry("ops").unwrap();
+        assert!(pacs.registries().is_empty());
+        assert!(pacs.search("pods").is_empty());
+    }
+
+    #[test]
+    fn test_registry_approval() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        pacs.set_active_project("api").unwrap();
+        let file = pacs.base_dir.join("ops.toml");
+        fs::write(
+            &file,
+            "[[commands]]\nname = \"pods\"\ncommand = \"kubectl get pods\"\n",
+        )
+        .unwrap();
+        pacs.add_registry(RegistrySource {
+            name: "ops".into(),
+            url: format!("file://{}", file.display()),
+        })
+        .unwrap();
+        let unapproved = |pacs: &Pacs| {
+            matches!(
+                pacs.resolve_command("pods", None, None),
+                Err(PacsError::NotApproved { registry, .. }) if registry == "ops"
+            )
+        };
+        assert!(unapproved(&pacs));
+
+        assert_eq!(pacs.approve_registry_command("pods", None).unwrap(), "ops");
+        let reloaded = Pacs::init_at(pacs.base_dir().to_path_buf()).unwrap();
+        assert_eq!(
+            reloaded
+                .resolve_command("pods", None, None)
+                .unwrap()
+                .command,
+            "kubectl get pods"
+        );
+
+        // A changed command needs another approval
+        fs::write(
+            &file,
+            "[[commands]]\nname = \"pods\"\ncommand = \"kubectl delete pods\"\n",
+        )
+        .unwrap();
+        assert!(pacs.update_registries(None).is_empty());
+        assert!(matches!(
+            pacs.resolve_command("pods", None, None),
+            Err(PacsError::ChangedSinceApproval { .. })
+        ));
+        let changed: Vec<_> = pacs.registries()[0].changed_since_approval().collect();
+        assert_eq!(changed[0].command, "kubectl delete pods");
+        assert!(matches!(
+            pacs.approve_registry_command("pods", Some("infra")),
+            Err(PacsError::RegistryNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_merge_duplicates_into_aliases() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        pacs.init_project("web", None).unwrap();
+        for (project, name) in [("api", "build"), ("api", "compile"), ("web", "build")] {
+            pacs.add_command(
+                PacsCommand {
+                    name: name.into(),
+                    command: "cargo build".into(),
+                    ..Default::default()
+                },
+                Some(project),
+            )
+            .unwrap();
+        }
+
+        let groups = pacs.find_duplicates(1.0);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].commands.len(), 3);
+
+        let keep = groups[0].commands[0].clone();
+        assert!(matches!(
+            pacs.merge_commands(&keep, &groups[0].commands),
+            Err(PacsError::MergeAcrossProjects { .. })
+        ));
+        assert_eq!(pacs.get_project("api").unwrap().commands.len(), 2);
+
+        pacs.merge_commands(&keep, &groups[0].commands[..2])
+            .unwrap();
+        assert_eq!(pacs.get_project("web").unwrap().commands.len(), 1);
+        let reloaded = Pacs::init_at(pacs.base_dir().to_path_buf()).unwrap();
+        assert_eq!(reloaded.get_project("api").unwrap().commands.len(), 1);
+
+        let api = pacs.get_project("api").unwrap();
+        assert_eq!(api.commands[0].aliases, ["compile"]);
+        assert_eq!(
+            PacsCommand::find_by_name(&api.commands, "compile")
+                .unwrap()
+                .name,
+            "build"
+        );
+
+        let similar = pacs.similar_commands("cargo  build\n", 0.9);
+        assert_eq!(similar[0].0, keep);
+        pacs.add_alias("api", "build", "b").unwrap();
+        assert_eq!(
+            pacs.get_project("api").unwrap().commands[0].aliases,
+            ["compile", "b"]
+        );
+        assert!(matches!(
+            pacs.add_alias("api", "build", "compile"),
+            Err(PacsError::CommandExists(_))
+        ));
+    }
+
+    #[test]
+    fn test_names_and_aliases_share_one_namespace() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        pacs.set_active_project("api").unwrap();
+        let command = |name: &str, aliases: &[&str]| PacsCommand {
+            name: name.into(),
+            command: "true".into(),
+            aliases: aliases.iter().map(|a| (*a).to_string()).collect(),
+            ..Default::default()
+        };
+        pacs.add_command(command("build", &["b"]), None).unwrap();
+
+        assert!(matches!(
+            pacs.add_command(command("bundle", &["b"]), None),
+            Err(PacsError::CommandExists(name)) if name == "b"
+        ));
+        pacs.add_command(command("test", &[]), None).unwrap();
+        assert!(matches!(
+            pacs.rename_command_auto("test", "b"),
+            Err(PacsError::CommandExists(_))
+        ));
+
+        pacs.rename_command_auto("build", "b").unwrap();
+        let cmd = pacs.get_command_auto("b").unwrap();
+        assert_eq!(cmd.name, "b");
+        assert!(cmd.aliases.is_empty());
+    }
+
+    #[test]
+    fn test_unused_commands_are_archived() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        for name in ["build", "deploy"] {
+            pacs.add_command(
+                PacsCommand {
+                    name: name.into(),
+                    command: "true".into(),
+                    ..Default::default()
+                },
+                Some("api"),
+            )
+            .unwrap();
+        }
+        pacs.run("build", Some("api"), None).unwrap();
+
+        let unused = pacs
+            .unused_commands(None, Duration::from_hours(24))
+            .unwrap();
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].0.name, "deploy");
+        assert_eq!(unused[0].1, None);
+
+        let commands: Vec<CommandRef> = unused.into_iter().map(|(c, _)| c).collect();
+        pacs.archive_commands(&commands).unwrap();
+        assert_eq!(pacs.get_project("api").unwrap().commands.len(), 1);
+
+        let archive = fs::read_to_string(pacs.base_dir.join("archive/api.toml")).unwrap();
+        let archive: Project = toml::from_str(&archive).unwrap();
+        assert_eq!(archive.commands[0].name, "deploy");
+    }
+
+    #[test]
+    fn test_broken_config_falls_back_to_defaults() {
+        let pacs = temp_pacs();
+        let config = pacs.base_dir.join("config.toml");
+        fs::write(&config, "readonly = tu\n").unwrap();
+
+        let pacs = Pacs::init_at(pacs.base_dir.clone()).unwrap();
+        assert!(pacs.config_error().is_some());
+        assert!(!pacs.is_readonly());
+        assert!(pacs.replace_config("readonly = true\n").is_ok());
+
+        let pacs = Pacs::init_at(pacs.base_dir.clone()).unwrap();
+        assert_eq!(pacs.config_error(), None);
+        assert!(pacs.is_readonly());
+    }
+
+    #[test]
+    fn test_readonly_rejects_changes() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        fs::write(pacs.base_dir.join("config.toml"), "readonly = true\n").unwrap();
+
+        let mut pacs = Pacs::init_at(pacs.base_dir.clone()).unwrap();
+        assert!(pacs.is_readonly());
+        assert!(matches!(
+            pacs.init_project("web", None),
+            Err(PacsError::ReadOnly)
+        ));
+        assert!(matches!(
+            pacs.add_environment("api", "dev"),
+            Err(PacsError::ReadOnly)
+        ));
+        assert!(pacs.get_project("api").unwrap().environments.is_empty());
+        pacs.set_active_project("api").unwrap();
+    }
+
+    #[test]
+    fn test_readonly_keeps_active_environment() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        pacs.add_environment("api", "dev").unwrap();
+        pacs.add_environment("api", "prod").unwrap();
+        pacs.set_active_environment("api", "dev").unwrap();
+        let config = pacs.base_dir.join("config.toml");
+        let content = fs::read_to_string(&config).unwrap_or_default();
+        fs::write(&config, format!("readonly = true\n{content}")).unwrap();
+
+        let mut pacs = Pacs::init_at(pacs.base_dir.clone()).unwrap();
+        assert!(matches!(
+            pacs.set_active_environment("api", "prod"),
+            Err(PacsError::ReadOnly)
+        ));
+        assert!(matches!(
+            pacs.deactivate_environment("api"),
+            Err(PacsError::ReadOnly)
+        ));
+        let reloaded = Pacs::init_at(pacs.base_dir.clone()).unwrap();
+        assert_eq!(
+            reloaded.get_active_environment(Some("api")).unwrap(),
+            Some("dev".to_string())
+        );
+    }
+
+    #[test]
+    fn test_safe_mode_runs_nothing() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        pacs.set_active_project("api").unwrap();
+        let marker = pacs.base_dir.join("ran");
+        pacs.add_command(
+            PacsCommand {
+                name: "touch".into(),
+                command: format!("touch {}", marker.display()),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+        fs::write(pacs.base_dir.join("config.toml"), "safe_mode = true\n").unwrap();
+
+        let pacs = Pacs::init_at(pacs.base_dir.clone()).unwrap();
+        assert!(pacs.is_safe_mode());
+        assert!(matches!(
+            pacs.run("touch", None, None),
+            Err(PacsError::SafeMode)
+        ));
+        assert!(!marker.exists());
+        assert!(pacs.history().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_author_is_recorded() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        fs::write(pacs.base_dir.join("config.toml"), "author = \"ada\"\n").unwrap();
+        pacs.set_active_project("api").unwrap();
+        pacs.add_command(
+            PacsCommand {
+                name: "build".into(),
+                command: "make".into(),
+                author: Some("grace".into()),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            pacs.get_command_auto("build").unwrap().author.as_deref(),
+            Some("grace")
+        );
+
+        pacs.update_command_auto("build", "make all".into())
+            .unwrap();
+        assert_eq!(
+            pacs.get_command_auto("build").unwrap().author.as_deref(),
+            Some("ada")
+        );
+    }
+
+    #[test]
+    fn test_switches_from_other_processes() {
+        let mut cli = temp_pacs();
+        cli.init_project("api", None).unwrap();
+        cli.add_environment("api", "dev").unwrap();
+        let mut tui = Pacs::init_at(cli.base_dir.clone()).unwrap();
+        assert!(!tui.reload_if_changed().unwrap());
+
+        cli.add_command(
+            PacsCommand {
+                name: "build".into(),
+                command: "make".into(),
+                ..Default::default()
+            },
+            Some("api"),
+        )
+        .unwrap();
+        tui.set_active_environment("api", "dev").unwrap();
+        assert_eq!(tui.get_project("api").unwrap().commands.len(), 1);
+
+        assert!(cli.reload_if_changed().unwrap());
+        assert_eq!(
+            cli.get_active_environment(Some("api")).unwrap().as_deref(),
+            Some("dev")
+        );
+    }
+
+    #[cfg(feature = "exec")]
+    #[test]
+    fn test_environment_hooks() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        let log = pacs.base_dir().join("switches.log");
+        for env in ["dev", "prod"] {
+            pacs.add_environment("api", env).unwrap();
+            pacs.edit_environment_values(
+                "api",
+                env,
+                BTreeMap::from([("ns".to_string(), format!("{env}-ns"))]),
+            )
+            .unwrap();
+            pacs.set_environment_hooks(
+                "api",
+                env,
+                Some(format!("echo on {{{{ns}}}} >> {}", log.display())),
+                Some(format!("echo off {{{{ns}}}} >> {}", log.display())),
+            )
+            .unwrap();
+        }
+
+        pacs.set_active_environment("api", "dev").unwrap();
+        pacs.set_active_environment("api", "prod").unwrap();
+        pacs.set_active_environment("api", "prod").unwrap();
+        pacs.deactivate_environment("api").unwrap();
+        assert_eq!(
+            fs::read_to_string(&log).unwrap(),
+            "on dev-ns\noff dev-ns\non prod-ns\noff prod-ns\n"
+        );
+
+        pacs.set_environment_hooks("api", "dev", Some("echo denied >&2; exit 3".into()), None)
+            .unwrap();
+        let err = pacs.set_active_environment("api", "dev").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "on_activate of environment 'dev' failed: denied"
+        );
+    }
+
+    #[test]
+    fn test_copy_environment() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        pacs.init_project("web", None).unwrap();
+        pacs.add_environment("api", "dev").unwrap();
+        let values = BTreeMap::from([("host".to_string(), "localhost".to_string())]);
+        pacs.edit_environment_values("api", "dev", values.clone())
+            .unwrap();
+
+        pacs.copy_environment("dev", "api", "web", false).unwrap();
+        let web = pacs.get_project("web").unwrap();
+        assert_eq!(web.environments[0].values, values);
+        assert_eq!(web.active_environment, None);
+
+        pacs.edit_environment_values("api", "dev", BTreeMap::new())
+            .unwrap();
+        let err = pacs
+            .copy_environment("dev", "api", "web", false)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Project already exists: Environment 'dev' already exists in project 'web'"
+        );
+        pacs.copy_environment("dev", "api", "web", true).unwrap();
+        assert!(
+            pacs.get_project("web").unwrap().environments[0]
+                .values
+                .is_empty()
+        );
+        assert!(pacs.copy_environment("prod", "api", "web", false).is_err());
+    }
+
+    #[test]
+    fn test_key_usage() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", Some("/srv/api".into())).unwrap();
+        pacs.set_active_project("api").unwrap();
+        pacs.add_environment("api", "dev").unwrap();
+        pacs.edit_environment_values(
+            "api",
+            "dev",
+            BTreeMap::from([("host".to_string(), "localhost".to_string())]),
+        )
+        .unwrap();
+        for body in [
+            "name = 'ping'\ncommand = 'curl {{host}}/{{path}}'",
+            "name = 'build'\ncommand = 'make'\ncwd = '{{project_path}}'",
+            "name = 'deploy'\ncommand = 'deploy'\nrequires = ['host']",
+        ] {
+            let cmd: PacsCommand = toml::from_str(body).unwrap();
+            pacs.add_command(cmd, Some("api")).unwrap();
+        }
+
+        let users = pacs.commands_using_key(Some("api"), "host").unwrap();
+        let names: Vec<&str> = users.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["ping", "deploy"]);
+        assert!(
+            pacs.commands_using_key(Some("api"), "port")
+                .unwrap()
+                .is_empty()
+        );
+
+        let ping = pacs.get_command_auto("ping").unwrap();
+        assert_eq!(
+            pacs.command_keys(ping, Some("api"), Some("dev")).unwrap(),
+            [
+                ("host".to_string(), Some("localhost".to_string())),
+                ("path".to_string(), None)
+            ]
+        );
+        let build = pacs.get_command_auto("build").unwrap();
+        assert_eq!(
+            pacs.command_keys(build, Some("api"), None).unwrap(),
+            [("project_path".to_string(), Some("/srv/api".to_string()))]
+        );
+    }
+
+    #[test]
+    fn test_rename_key() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        pacs.add_environment("api", "dev").unwrap();
+        pacs.edit_environment_values(
+            "api",
+            "dev",
+            BTreeMap::from([
+                ("host".to_string(), "localhost".to_string()),
+                ("url".to_string(), "https://{{host}}".to_string()),
+            ]),
+        )
+        .unwrap();
+        for body in [
+            "name = 'ping'\ncommand = 'ping {{host}}'\nrequires = ['host']",
+            "name = 'build'\ncommand = 'make {{hostname}}'\nlocked = true",
+        ] {
+            let cmd: PacsCommand = toml::from_str(body).unwrap();
+            pacs.add_command(cmd, Some("api")).unwrap();
+        }
+
+        let (before, after) = pacs
+            .rename_key(Some("api"), "host", "api_host", true)
+            .unwrap();
+        assert!(after.contains("ping {{api_host}}") && !after.contains("{{host}}"));
+        assert_eq!(
+            before,
+            serialize_project(pacs.get_project("api").unwrap()).unwrap()
+        );
+
+        pacs.rename_key(Some("api"), "host", "api_host", false)
+            .unwrap();
+        let project = pacs.get_project("api").unwrap();
+        let values = &project.environments[0].values;
+        assert_eq!(values["api_host"], "localhost");
+        assert_eq!(values["url"], "https://{{api_host}}");
+        assert_eq!(project.commands[0].command, "ping {{api_host}}");
+        assert_eq!(project.commands[0].requires, ["api_host"]);
+        assert_eq!(project.commands[1].command, "make {{hostname}}");
+
+        // Locked commands and existing keys are left alone
+        let err = pacs
+            .rename_key(Some("api"), "hostname", "name", false)
+            .unwrap_err();
+        assert!(matches!(err, PacsError::CommandLocked(name) if name == "build"));
+        let err = pacs
+            .rename_key(Some("api"), "url", "api_host", false)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Project already exists: Key 'api_host' already exists in environment 'dev'"
+        );
     }
 
     #[test]
-    fn test_delete_command_auto() {
+    fn test_run_with_values_keeps_active_environment() {
         let mut pacs = temp_pacs();
-        pacs.init_project("active_proj", None).unwrap();
-        pacs.set_active_project("active_proj").unwrap();
-        pacs.add_command(
-            PacsCommand {
-                name: "cmd1".into(),
-                command: "echo 1".into(),
-                cwd: None,
-                tag: "".into(),
-            },
-            Some("active_proj"),
+        pacs.init_project("api", None).unwrap();
+        pacs.add_environment("api", "dev").unwrap();
+        pacs.edit_environment_values(
+            "api",
+            "dev",
+            BTreeMap::from([("ns".to_string(), "dev".to_string())]),
         )
         .unwrap();
-
-        // Add command to another project
-        pacs.init_project("other_proj", None).unwrap();
+        pacs.set_active_environment("api", "dev").unwrap();
         pacs.add_command(
             PacsCommand {
-                name: "cmd2".into(),
-                command: "echo 2".into(),
-                cwd: None,
-                tag: "".into(),
+                name: "check".into(),
+                command: "test {{ns}}-{{tag}} = prod-v2".into(),
+                ..Default::default()
             },
-            Some("other_proj"),
+            Some("api"),
         )
         .unwrap();
 
-        // delete_command_auto should find cmd1 in active project
-        pacs.delete_command_auto("cmd1").unwrap();
-        assert!(pacs.list(Some("active_proj"), None).unwrap().is_empty());
-
-        // delete_command_auto should NOT find cmd2 (in other project, not active)
-        assert!(pacs.delete_command_auto("cmd2").is_err());
-
-        // Deleting non-existent command should fail
-        assert!(pacs.delete_command_auto("cmd3").is_err());
+        let values = BTreeMap::from([
+            ("NS".to_string(), "prod".to_string()),
+            ("tag".to_string(), "v2".to_string()),
+        ]);
+        pacs.run_with_values("check", Some("api"), None, &values)
+            .unwrap();
+        assert!(pacs.run("check", Some("api"), None).is_err());
+        assert!(
+            pacs.run_with_values("check", Some("api"), Some("prod"), &values)
+                .is_err()
+        );
+        assert_eq!(
+            pacs.get_active_environment(Some("api")).unwrap().as_deref(),
+            Some("dev")
+        );
     }
 
     #[test]
-    fn test_get_command_auto() {
+    fn test_expands_cwd_and_environment_values() {
         let mut pacs = temp_pacs();
-        pacs.init_project("proj", None).unwrap();
-        pacs.set_active_project("proj").unwrap();
+        pacs.init_project("api", Some("/srv/api".into())).unwrap();
+        pacs.add_environment("api", "dev").unwrap();
+        pacs.edit_environment_values(
+            "api",
+            "dev",
+            BTreeMap::from([
+                ("host".to_string(), "dev.example.com".to_string()),
+                ("base".to_string(), "https://{{host}}".to_string()),
+                ("url".to_string(), "{{base}}/v1".to_string()),
+                ("dir".to_string(), "deploy/dev".to_string()),
+            ]),
+        )
+        .unwrap();
         pacs.add_command(
             PacsCommand {
-                name: "proj-cmd".into(),
-                command: "echo project".into(),
-                cwd: None,
-                tag: "".into(),
+                name: "ping".into(),
+                command: "curl {{url}}".into(),
+                cwd: Some("{{project_path}}/{{dir}}".into()),
+                ..Default::default()
             },
-            Some("proj"),
+            Some("api"),
         )
         .unwrap();
 
+        let cmd = pacs
+            .resolve_command("ping", Some("api"), Some("dev"))
+            .unwrap();
+        assert_eq!(cmd.command, "curl https://dev.example.com/v1");
+        assert_eq!(cmd.cwd.as_deref(), Some("/srv/api/deploy/dev"));
+
+        // Without an environment, only the built-in placeholder is known
+        let cmd = pacs.resolve_command("ping", Some("api"), None).unwrap();
+        assert_eq!(cmd.cwd.as_deref(), Some("{{project_path}}/{{dir}}"));
+
+        let cyclic = BTreeMap::from([
+            ("host".to_string(), "{{url}}".to_string()),
+            ("url".to_string(), "https://{{host}}".to_string()),
+        ]);
+        let err = pacs
+            .edit_environment_values("api", "dev", cyclic.clone())
+            .unwrap_err();
         assert_eq!(
-            pacs.get_command_auto("proj-cmd").unwrap().command,
-            "echo project"
+            err.to_string(),
+            "Values of environment 'dev' reference each other: host -> url -> host"
         );
+        // A cycle edited by hand is reported when a command is resolved
+        pacs.get_project_mut("api").unwrap().environments[0].values = cyclic;
         assert!(matches!(
-            pacs.get_command_auto("nope"),
-            Err(PacsError::CommandNotFound(_))
+            pacs.resolve_command("ping", Some("api"), Some("dev")),
+            Err(PacsError::ValueCycle { .. })
         ));
     }
 
     #[test]
-    fn test_update_command_auto() {
+    fn test_ask_cwd() {
         let mut pacs = temp_pacs();
-        pacs.init_project("test", None).unwrap();
-        pacs.set_active_project("test").unwrap();
+        pacs.init_project("api", None).unwrap();
         pacs.add_command(
             PacsCommand {
-                name: "cmd".into(),
-                command: "old".into(),
-                cwd: None,
-                tag: "".into(),
+                name: "scaffold".into(),
+                command: "true".into(),
+                cwd: Some(ASK_CWD.into()),
+                ..Default::default()
             },
-            Some("test"),
+            Some("api"),
         )
         .unwrap();
 
-        let old = pacs.update_command_auto("cmd", "new".into()).unwrap();
-        assert_eq!(old, "old");
-        assert_eq!(pacs.get_command_auto("cmd").unwrap().command, "new");
+        let cmd = pacs.resolve_command("scaffold", Some("api"), None).unwrap();
+        assert!(cmd.asks_cwd());
+        assert!(matches!(
+            pacs.run("scaffold", Some("api"), None),
+            Err(PacsError::UnresolvedPlaceholders { keys, .. }) if keys == CWD_KEY
+        ));
+
+        let dir = std::env::temp_dir().display().to_string();
+        let values = BTreeMap::from([(CWD_KEY.to_string(), dir.clone())]);
+        let cmd = pacs
+            .resolve_command_with_values("scaffold", Some("api"), None, &values)
+            .unwrap();
+        assert_eq!(cmd.cwd, Some(dir));
+        pacs.run_with_values("scaffold", Some("api"), None, &values)
+            .unwrap();
+
+        let values = BTreeMap::from([(CWD_KEY.to_string(), "~/src".to_string())]);
+        let cmd = pacs
+            .resolve_command_with_values("scaffold", Some("api"), None, &values)
+            .unwrap();
+        assert_eq!(cmd.cwd, Some(expand_home("~/src")));
     }
 
     #[test]
-    fn test_rename_command_auto() {
+    fn test_required_keys() {
         let mut pacs = temp_pacs();
-        pacs.init_project("test", None).unwrap();
-        pacs.set_active_project("test").unwrap();
+        pacs.init_project("api", None).unwrap();
+        pacs.add_environment("api", "dev").unwrap();
+        pacs.edit_environment_values(
+            "api",
+            "dev",
+            BTreeMap::from([("ns".to_string(), "dev".to_string())]),
+        )
+        .unwrap();
         pacs.add_command(
             PacsCommand {
-                name: "old-name".into(),
-                command: "echo test".into(),
-                cwd: None,
-                tag: "".into(),
+                name: "deploy".into(),
+                command: "echo {{ns}} {{image}}".into(),
+                requires: vec!["PROFILE".into()],
+                ..Default::default()
             },
-            Some("test"),
+            Some("api"),
         )
         .unwrap();
 
-        pacs.rename_command_auto("old-name", "new-name").unwrap();
-        assert!(matches!(
-            pacs.get_command_auto("old-name"),
-            Err(PacsError::CommandNotFound(_))
-        ));
+        let cmd = pacs.resolve_command("deploy", Some("api"), None).unwrap();
+        assert_eq!(cmd.required_keys(), ["PROFILE", "ns", "image"]);
         assert_eq!(
-            pacs.get_command_auto("new-name").unwrap().command,
-            "echo test"
+            pacs.suggest_placeholders(Some("api"), &["deploy", "unknown"]),
+            ["PROFILE", "ns", "image"]
+        );
+        let missing = pacs
+            .missing_keys(&cmd, Some("api"), Some("dev"), &BTreeMap::new())
+            .unwrap();
+        assert_eq!(missing, ["PROFILE", "image"]);
+
+        let err = pacs.run("deploy", Some("api"), Some("dev")).unwrap_err();
+        assert!(
+            matches!(err, PacsError::UnresolvedPlaceholders { ref keys, .. } if keys == "PROFILE, image")
+        );
+        let values = BTreeMap::from([
+            ("PROFILE".to_string(), "ci".to_string()),
+            ("image".to_string(), "api:1".to_string()),
+        ]);
+        pacs.run_with_values("deploy", Some("api"), Some("dev"), &values)
+            .unwrap();
+
+        let undeclared = pacs.lint_required_keys(Some("api"), true).unwrap();
+        assert_eq!(
+            undeclared,
+            [(
+                "deploy".to_string(),
+                vec!["ns".to_string(), "image".to_string()]
+            )]
+        );
+        assert!(
+            pacs.lint_required_keys(Some("api"), false)
+                .unwrap()
+                .is_empty()
         );
     }
 
     #[test]
-    fn test_rename_to_existing_fails() {
+    fn test_storage_paths() {
         let mut pacs = temp_pacs();
-        pacs.init_project("test", None).unwrap();
-        pacs.set_active_project("test").unwrap();
-        pacs.add_command(
-            PacsCommand {
-                name: "a".into(),
-                command: "".into(),
-                cwd: None,
-                tag: "".into(),
-            },
-            Some("test"),
-        )
-        .unwrap();
-        pacs.add_command(
-            PacsCommand {
-                name: "b".into(),
-                command: "".into(),
-                cwd: None,
-                tag: "".into(),
-            },
-            Some("test"),
-        )
-        .unwrap();
+        pacs.init_project("api", None).unwrap();
+        let base = pacs.base_dir().to_path_buf();
 
-        let result = pacs.rename_command_auto("a", "b");
-        assert!(matches!(result, Err(PacsError::CommandExists(_))));
+        assert_eq!(pacs.config_file(), base.join("config.toml"));
+        assert_eq!(
+            pacs.project_file("API").unwrap(),
+            base.join("projects/api.toml")
+        );
+        assert!(pacs.project_file("api").unwrap().exists());
+        assert!(pacs.project_file("web").is_err());
     }
 
     #[test]
-    fn test_run_auto() {
+    fn test_replace_project() {
         let mut pacs = temp_pacs();
-        pacs.init_project("test", None).unwrap();
-        pacs.set_active_project("test").unwrap();
+        pacs.init_project("api", None).unwrap();
+        let file = pacs.project_file("api").unwrap();
+
+        let edited = r#"
+            name = "renamed"
+            active_environment = "dev"
+
+            [[commands]]
+            name = "test"
+            aliases = ["t"]
+            command = "cargo test"
+
+            [[environments]]
+            name = "dev"
+        "#;
+        pacs.replace_project("api", edited).unwrap();
+        let saved = fs::read_to_string(&file).unwrap();
+        assert!(saved.starts_with("name = \"api\""));
+        assert_eq!(pacs.get_project("api").unwrap().commands[0].aliases, ["t"]);
+
+        let duplicate = r#"
+            name = "api"
+
+            [[commands]]
+            name = "test"
+            command = "cargo test"
+
+            [[commands]]
+            name = "t"
+            aliases = ["test"]
+            command = "cargo t"
+        "#;
+        assert!(matches!(
+            pacs.replace_project("api", duplicate),
+            Err(PacsError::CommandExists(name)) if name == "test"
+        ));
+        assert!(
+            pacs.replace_project("api", "name = \"api\"\nactive_environment = \"prod\"")
+                .is_err()
+        );
+        assert!(pacs.replace_project("api", "commands = 1").is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), saved);
+    }
+
+    #[test]
+    fn test_locked_commands() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        pacs.set_active_project("api").unwrap();
         pacs.add_command(
             PacsCommand {
-                name: "echo-test".into(),
-                command: "echo hello".into(),
-                cwd: None,
-                tag: "".into(),
+                name: "deploy".into(),
+                command: "make deploy".into(),
+                ..Default::default()
             },
-            Some("test"),
+            None,
         )
         .unwrap();
+        pacs.set_command_locked_auto("deploy", true).unwrap();
+        let file = pacs.project_file("api").unwrap();
+        assert!(fs::read_to_string(&file).unwrap().contains("locked = true"));
 
-        // Test would run the command, but we can't easily test output
-        // pacs.run("echo-test", None, None).unwrap();
-        assert!(matches!(
-            pacs.run("nonexistent", None, None),
-            Err(PacsError::CommandNotFound(_))
+        let locked = |error: Option<PacsError>| matches!(error, Some(PacsError::CommandLocked(name)) if name == "deploy");
+        assert!(locked(
+            pacs.update_command_auto("deploy", "make".into()).err()
         ));
+        assert!(locked(pacs.rename_command_auto("deploy", "ship").err()));
+        assert!(locked(pacs.tag_command_auto("deploy", "ops".into()).err()));
+        assert!(locked(pacs.delete_command_auto("deploy").err()));
+        assert!(locked(pacs.replace_project("api", "name = \"api\"").err()));
+
+        let mut forced = Pacs::init_at(pacs.base_dir.clone()).unwrap();
+        forced.set_ignore_locks();
+        forced.rename_command_auto("deploy", "ship").unwrap();
+
+        let mut pacs = Pacs::init_at(pacs.base_dir.clone()).unwrap();
+        pacs.set_command_locked_auto("ship", false).unwrap();
+        pacs.delete_command_auto("ship").unwrap();
     }
 
     #[test]
-    fn test_active_project() {
+    fn test_command_groups() {
+        let mut cmd: PacsCommand = toml::from_str("name = 'db/migrate/up'\ncommand = 'x'").unwrap();
+        assert_eq!(cmd.groups().collect::<Vec<_>>(), ["db/", "db/migrate/"]);
+        assert_eq!(cmd.leaf_name(), "up");
+        cmd.name = "build".into();
+        assert_eq!(cmd.groups().count(), 0);
+        assert_eq!(cmd.leaf_name(), "build");
+    }
+
+    #[test]
+    fn test_command_overrides() {
         let mut pacs = temp_pacs();
-        pacs.init_project("p1", None).unwrap();
-        pacs.init_project("p2", None).unwrap();
+        fs::write(
+            pacs.projects_dir().join("api.toml"),
+            r#"name = "api"
 
-        assert!(pacs.get_active_project_name().is_err());
+[[commands]]
+name = "serve"
+command = "/usr/bin/python3 -m http.server"
 
-        pacs.set_active_project("p1").unwrap();
-        assert_eq!(pacs.get_active_project_name().unwrap(), String::from("p1"));
+[commands.overrides.laptop]
+command = "/opt/homebrew/bin/python3 -m http.server"
+cwd = "~/www"
+"#,
+        )
+        .unwrap();
+        pacs.reload_if_changed().unwrap();
 
-        pacs.clear_active_project().unwrap();
-        assert!(pacs.get_active_project_name().is_err());
+        pacs.hostname.set(Some("laptop".into())).unwrap();
+        let cmd = pacs.resolve_command("serve", Some("api"), None).unwrap();
+        assert_eq!(cmd.command, "/opt/homebrew/bin/python3 -m http.server");
+        assert_eq!(cmd.cwd.as_deref(), Some("~/www"));
+
+        let other = pacs.get_project("api").unwrap().commands[0].for_host("desktop");
+        assert_eq!(other.command, "/usr/bin/python3 -m http.server");
+        assert_eq!(other.cwd, None);
+
+        pacs.save_project_by_name("api").unwrap();
+        let reloaded = Pacs::init_at(pacs.base_dir().to_path_buf()).unwrap();
+        let cmd = &reloaded.get_project("api").unwrap().commands[0];
+        assert_eq!(cmd.overrides["laptop"].cwd.as_deref(), Some("~/www"));
     }
 
     #[test]
-    fn test_list_by_tag() {
+    fn test_project_notes() {
         let mut pacs = temp_pacs();
-        pacs.init_project("test", None).unwrap();
-        pacs.set_active_project("test").unwrap();
-        pacs.add_command(
-            PacsCommand {
-                name: "tag1".into(),
-                command: "".into(),
-                cwd: None,
-                tag: "dev".into(),
-            },
-            Some("test"),
-        )
-        .unwrap();
-        pacs.add_command(
-            PacsCommand {
-                name: "tag2".into(),
-                command: "".into(),
-                cwd: None,
-                tag: "prod".into(),
-            },
-            Some("test"),
-        )
-        .unwrap();
+        pacs.init_project("api", None).unwrap();
+        pacs.set_project_notes("api", "# Deploy\n\nAlways run `migrate` first.\n\n")
+            .unwrap();
 
-        let all = pacs.list(Some("test"), None).unwrap();
-        let dev = all
-            .into_iter()
-            .filter(|c| c.tag == "dev")
-            .collect::<Vec<_>>();
-        assert_eq!(dev.len(), 1);
-        assert_eq!(dev[0].name, "tag1");
+        let reloaded = Pacs::init_at(pacs.base_dir().to_path_buf()).unwrap();
+        assert_eq!(
+            reloaded.get_project("api").unwrap().notes.as_deref(),
+            Some("# Deploy\n\nAlways run `migrate` first.")
+        );
+
+        pacs.set_project_notes("api", "  \n").unwrap();
+        assert_eq!(pacs.get_project("api").unwrap().notes, None);
+        let saved = fs::read_to_string(pacs.project_file("api").unwrap()).unwrap();
+        assert!(!saved.contains("notes"));
     }
 
     #[test]
-    fn test_add_command_active_project_fallback() {
+    fn test_local_only_stays_local() {
         let mut pacs = temp_pacs();
-        pacs.init_project("test", None).unwrap();
-        pacs.set_active_project("test").unwrap();
+        pacs.init_project("api", None).unwrap();
+        pacs.init_project("scratch", None).unwrap();
+        pacs.set_active_project("api").unwrap();
+        for (name, body) in [("build", "cargo build"), ("token", "echo secret")] {
+            let cmd = PacsCommand {
+                name: name.into(),
+                command: body.into(),
+                ..Default::default()
+            };
+            pacs.add_command(cmd, None).unwrap();
+        }
+        pacs.set_command_local_only_auto("token", true).unwrap();
+        pacs.set_project_local_only("scratch", true).unwrap();
 
-        // Add command without specifying project (uses active project)
-        pacs.add_command(
-            PacsCommand {
-                name: "fallback-cmd".into(),
-                command: "echo fallback".into(),
-                cwd: None,
-                tag: "".into(),
-            },
-            None,
-        )
-        .unwrap();
+        let saved = fs::read_to_string(pacs.project_file("api").unwrap()).unwrap();
+        assert!(saved.contains("local_only = true"));
+        let reloaded = Pacs::init_at(pacs.base_dir().to_path_buf()).unwrap();
+        assert!(reloaded.get_project("scratch").unwrap().local_only);
 
-        // Verify command was added to active project
-        let commands = pacs.list(None, None).unwrap();
-        assert_eq!(commands.len(), 1);
-        assert_eq!(commands[0].name, "fallback-cmd");
+        let bundle = pacs.bundle();
+        assert_eq!(bundle.projects.len(), 1);
+        let names: Vec<&str> = bundle.projects[0]
+            .commands
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["build"]);
+        let export = pacs
+            .export(None, None, None, ExportFormat::Toml, BodyCleanup::default())
+            .unwrap();
+        assert!(!export.contains("secret"));
+        let names = ["build".to_string(), "token".to_string()];
+        let (export, count) = pacs
+            .export_selected(
+                None,
+                None,
+                &names[1..],
+                ExportFormat::Toml,
+                BodyCleanup::default(),
+            )
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(!export.contains("secret"));
+        let (_, count) = pacs
+            .export_selected(
+                None,
+                None,
+                &names,
+                ExportFormat::Toml,
+                BodyCleanup::default(),
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(matches!(
+            pacs.publish_project_gist("scratch"),
+            Err(PacsError::LocalOnly(_))
+        ));
 
-        // Also works with explicit project name
-        pacs.add_command(
-            PacsCommand {
-                name: "explicit-cmd".into(),
-                command: "echo explicit".into(),
-                cwd: None,
-                tag: "".into(),
-            },
-            Some("test"),
+        // Replacing from a bundle keeps the commands that never left
+        pacs.import_bundle(bundle, true, &BTreeMap::new()).unwrap();
+        assert!(pacs.get_command_auto("token").unwrap().local_only);
+    }
+
+    #[test]
+    fn test_resolve_sync_conflict() {
+        let mut pacs = temp_pacs();
+        let file = pacs.projects_dir().join("api.toml");
+        fs::write(
+            &file,
+            r#"name = "api"
+
+<<<<<<< HEAD
+[[commands]]
+name = "build"
+command = "cargo build"
+
+[[commands]]
+name = "mine"
+command = "echo mine"
+=======
+[[commands]]
+name = "build"
+command = "cargo build --release"
+
+[[commands]]
+name = "theirs"
+command = "echo theirs"
+>>>>>>> origin/main
+"#,
         )
         .unwrap();
+        pacs.reload_if_changed().unwrap();
+        assert!(pacs.get_project("api").is_err());
 
-        let commands = pacs.list(Some("test"), None).unwrap();
-        assert_eq!(commands.len(), 2);
+        let mut conflicts = pacs.sync_conflicts().unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].conflicts[0].theirs.command,
+            "cargo build --release"
+        );
+
+        let rename = BTreeMap::from([("build".into(), Resolution::Rename("release".into()))]);
+        pacs.resolve_sync_conflict(conflicts.remove(0), &rename)
+            .unwrap();
+        assert!(pacs.load_warnings().is_empty());
+        let reloaded = Pacs::init_at(pacs.base_dir().to_path_buf()).unwrap();
+        let names: Vec<&str> = reloaded
+            .get_project("api")
+            .unwrap()
+            .commands
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["build", "mine", "release", "theirs"]);
     }
 
     #[test]
-    fn test_delete_command_active_project_fallback() {
+    fn test_session_overrides_active_selection() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        pacs.init_project("web", None).unwrap();
+        pacs.add_environment("web", "staging").unwrap();
+        pacs.set_active_project("api").unwrap();
+
+        pacs.session = Session {
+            project: Some("web".into()),
+            environment: Some("staging".into()),
+        };
+        assert_eq!(pacs.get_active_project_name().unwrap(), "web");
+        assert_eq!(
+            pacs.get_active_environment(None).unwrap().as_deref(),
+            Some("staging")
+        );
+        assert_eq!(pacs.get_active_environment(Some("api")).unwrap(), None);
+
+        let all = pacs.all_environments();
+        assert_eq!(all.len(), 2);
+        assert!(all[0].project == "api" && all[0].environments.is_empty());
+        assert_eq!(all[1].environments[0].name, "staging");
+        assert_eq!(all[1].active, Some("staging"));
+
+        pacs.session = Session::default();
+        assert_eq!(pacs.get_active_project_name().unwrap(), "api");
+        assert_eq!(pacs.get_project("web").unwrap().active_environment, None);
+        assert_eq!(pacs.all_environments()[1].active, None);
+    }
+
+    #[test]
+    fn test_scope_to_project() {
+        let mut pacs = temp_pacs();
+        pacs.init_project("api", None).unwrap();
+        pacs.init_project("Web", None).unwrap();
+        pacs.add_environment("web", "staging").unwrap();
+        pacs.set_active_project("api").unwrap();
+        pacs.session.environment = Some("staging".into());
+
+        pacs.scope_to_project("web").unwrap();
+        assert_eq!(pacs.get_active_project_name().unwrap(), "Web");
+        assert_eq!(pacs.session().environment, None);
+        assert_eq!(
+            pacs.load_config().unwrap().active_project.as_deref(),
+            Some("api")
+        );
+
+        pacs.session.environment = Some("staging".into());
+        pacs.scope_to_project("Web").unwrap();
+        assert_eq!(pacs.session().environment.as_deref(), Some("staging"));
+        assert!(matches!(
+            pacs.scope_to_project("missing"),
+            Err(PacsError::ProjectNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_kube_context_placeholder() {
         let mut pacs = temp_pacs();
         pacs.init_project("test", None).unwrap();
         pacs.set_active_project("test").unwrap();
-
-        // Add a command
+        pacs.add_environment("test", "prod").unwrap();
+        pacs.set_environment_kube_context("test", "prod", Some("prod-cluster".into()))
+            .unwrap();
         pacs.add_command(
             PacsCommand {
-                name: "to-delete".into(),
-                command: "echo delete me".into(),
-                cwd: None,
-                tag: "".into(),
+                name: "pods".into(),
+                command: "kubectl --context {{kube_context}} get pods".into(),
+                tag: KUBE_TAG.into(),
+                ..Default::default()
             },
             None,
         )
         .unwrap();
 
-        // Delete without specifying project (uses active project)
-        pacs.delete_command("to-delete", None).unwrap();
+        let cmd = pacs.resolve_command("pods", None, Some("prod")).unwrap();
+        assert_eq!(cmd.command, "kubectl --context prod-cluster get pods");
+        assert!(kube::verify_context(&cmd, Some("prod-cluster")).is_ok());
+    }
 
-        let commands = pacs.list(None, None).unwrap();
-        assert!(commands.is_empty());
+    #[test]
+    fn test_check_run_reports_danger_pattern() {
+        let pacs = temp_pacs();
+        fs::write(
+            pacs.base_dir.join("config.toml"),
+            "danger_patterns = ['rm -rf']\n",
+        )
+        .unwrap();
+        let mut pacs = Pacs::init_at(pacs.base_dir.clone()).unwrap();
+        pacs.init_project("test", None).unwrap();
+        pacs.set_active_project("test").unwrap();
+
+        let mut cmd: PacsCommand =
+            toml::from_str("name = 'clean'\ncommand = 'rm -rf out'").unwrap();
+        assert_eq!(
+            pacs.check_run(&cmd, None, None).unwrap().as_deref(),
+            Some("rm -rf")
+        );
+        cmd.safe = true;
+        assert_eq!(pacs.check_run(&cmd, None, None).unwrap(), None);
     }
 }
@@ -0,0 +1,132 @@
+//! Directories entered at run time for commands with `cwd = "ask"`, and
+//! project paths.
+
+use std::fs;
+use std::path::{MAIN_SEPARATOR, Path};
+
+use crate::PacsError;
+
+/// Replaces a leading `~` by the home directory.
+#[must_use]
+pub fn expand_home(path: &str) -> String {
+    let home = std::env::var("HOME").ok().filter(|h| !h.is_empty());
+    match (path.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(MAIN_SEPARATOR) => {
+            format!("{home}{rest}")
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// Turns `path` into an absolute path to an existing directory, expanding
+/// `~` and resolving relative paths against the current directory. With
+/// `create`, a missing directory is created.
+pub fn project_dir(path: &str, create: bool) -> Result<String, PacsError> {
+    let path = Path::new(&expand_home(path)).to_path_buf();
+    if create && !path.exists() {
+        fs::create_dir_all(&path)?;
+    }
+    if !path.is_dir() {
+        return Err(PacsError::DirectoryNotFound(path.display().to_string()));
+    }
+    Ok(path.canonicalize()?.display().to_string())
+}
+
+/// Returns the directories that `partial` can be completed to, sorted and
+/// with a trailing separator. Hidden directories are only offered once
+/// `partial` names them with a leading dot.
+#[must_use]
+pub fn complete_dir(partial: &str) -> Vec<String> {
+    let (parent, prefix) = match partial.rfind(MAIN_SEPARATOR) {
+        Some(i) => partial.split_at(i + 1),
+        None => ("", partial),
+    };
+    let dir = if parent.is_empty() {
+        ".".to_string()
+    } else {
+        expand_home(parent)
+    };
+    let mut found: Vec<String> = fs::read_dir(Path::new(&dir))
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|name| {
+            name.starts_with(prefix) && (prefix.starts_with('.') || !name.starts_with('.'))
+        })
+        .map(|name| format!("{parent}{name}{MAIN_SEPARATOR}"))
+        .collect();
+    found.sort();
+    found
+}
+
+/// Returns the longest prefix shared by all `candidates`.
+#[must_use]
+pub fn common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+    let mut len = first.len();
+    for candidate in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(candidate.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8())
+            .min(len);
+    }
+    first[..len].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["src", "scripts", ".git"] {
+            fs::create_dir(dir.path().join(name)).unwrap();
+        }
+        fs::write(dir.path().join("setup.sh"), "").unwrap();
+        let base = format!("{}/", dir.path().display());
+
+        let found = complete_dir(&format!("{base}s"));
+        assert_eq!(found, [format!("{base}scripts/"), format!("{base}src/")]);
+        assert_eq!(common_prefix(&found), format!("{base}s"));
+        assert_eq!(complete_dir(&format!("{base}.g")), [format!("{base}.git/")]);
+        assert!(complete_dir(&format!("{base}x")).is_empty());
+    }
+
+    #[test]
+    fn test_project_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let missing = root.join("api/src");
+        let missing = missing.to_str().unwrap();
+
+        assert!(matches!(
+            project_dir(missing, false),
+            Err(PacsError::DirectoryNotFound(_))
+        ));
+        assert_eq!(project_dir(missing, true).unwrap(), missing);
+        let dotted = format!("{}/api/./src/..", root.display());
+        assert_eq!(
+            project_dir(&dotted, false).unwrap(),
+            root.join("api").display().to_string()
+        );
+        fs::write(root.join("file"), "").unwrap();
+        assert!(project_dir(root.join("file").to_str().unwrap(), true).is_err());
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = std::env::var("HOME").unwrap();
+        assert_eq!(expand_home("~/code"), format!("{home}/code"));
+        assert_eq!(expand_home("~"), home);
+        assert_eq!(expand_home("~other/code"), "~other/code");
+        assert_eq!(expand_home("/srv"), "/srv");
+    }
+}
@@ -0,0 +1,300 @@
+//! Parser for `{{key}}` placeholders in command bodies, working directories
+//! and environment values.
+//!
+//! The syntax, exhaustively:
+//!
+//! - `{{` opens a placeholder and the next `}}` closes it. The key is
+//!   everything in between, verbatim: it is not trimmed and may be empty or
+//!   contain spaces, newlines, single braces and any unicode.
+//! - A `{{` without a later `}}` is plain text, as is everything after it.
+//! - A `}}` outside of a placeholder is plain text.
+//! - Placeholders do not nest. In `{{{a}}}` the key is `{a`, followed by the
+//!   text `}`.
+//! - There is no escaping.
+//!
+//! Environment values may reference other values, which are rendered first.
+
+use std::collections::BTreeMap;
+
+/// A piece of a string with placeholders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment<'a> {
+    /// Plain text, never empty.
+    Text(&'a str),
+    /// The key of a `{{key}}` placeholder.
+    Placeholder(&'a str),
+}
+
+/// Splits `src` into text and placeholders. Two text segments never follow
+/// each other, and joining the segments with placeholders written as
+/// `{{key}}` gives back `src`.
+pub fn placeholder_segments(src: &str) -> impl Iterator<Item = Segment<'_>> {
+    let mut rest = src;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let placeholder = rest.find("{{").and_then(|open| {
+            let close = open + 2 + rest[open + 2..].find("}}")?;
+            Some((open, close))
+        });
+        let segment = match placeholder {
+            Some((0, close)) => {
+                let key = &rest[2..close];
+                rest = &rest[close + 2..];
+                Segment::Placeholder(key)
+            }
+            Some((open, _)) => {
+                let (text, tail) = rest.split_at(open);
+                rest = tail;
+                Segment::Text(text)
+            }
+            None => Segment::Text(std::mem::take(&mut rest)),
+        };
+        Some(segment)
+    })
+}
+
+/// Returns the keys of all `{{key}}` placeholders in `src`, including duplicates.
+pub(crate) fn scan_placeholders(src: &str) -> Vec<&str> {
+    placeholder_segments(src)
+        .filter_map(|segment| match segment {
+            Segment::Placeholder(key) => Some(key),
+            Segment::Text(_) => None,
+        })
+        .collect()
+}
+
+/// Replaces `{{key}}` placeholders in `src` with the given values.
+///
+/// Returns the rendered string together with the keys that had no value.
+/// Unresolved placeholders are left untouched in the output.
+#[must_use]
+pub fn render_placeholders(src: &str, values: &BTreeMap<String, String>) -> (String, Vec<String>) {
+    let mut unresolved = Vec::new();
+    let mut output = String::with_capacity(src.len());
+
+    for segment in placeholder_segments(src) {
+        match segment {
+            Segment::Text(text) => output.push_str(text),
+            Segment::Placeholder(key) => {
+                if let Some(value) = values.get(key) {
+                    output.push_str(value);
+                } else {
+                    unresolved.push(key.to_string());
+                    output.push_str("{{");
+                    output.push_str(key);
+                    output.push_str("}}");
+                }
+            }
+        }
+    }
+
+    (output, unresolved)
+}
+
+/// Replaces the key of every `{{old}}` placeholder in `src` by `new`.
+#[cfg(feature = "fs-store")]
+#[must_use]
+pub(crate) fn rename_placeholder(src: &str, old: &str, new: &str) -> String {
+    placeholder_segments(src)
+        .map(|segment| match segment {
+            Segment::Placeholder(key) if key == old => format!("{{{{{new}}}}}"),
+            Segment::Placeholder(key) => format!("{{{{{key}}}}}"),
+            Segment::Text(text) => text.to_string(),
+        })
+        .collect()
+}
+
+/// Renders values that reference other values, e.g. `url = "https://{{host}}"`,
+/// following references recursively. Values for which `opaque` holds are not
+/// inlined, and references to unknown keys are kept.
+///
+/// Values on a reference cycle, and values referencing them, are left as they
+/// are. The first cycle found is returned as well, e.g. `["a", "b", "a"]`.
+pub(crate) fn interpolate_values(
+    values: &BTreeMap<String, String>,
+    opaque: impl Fn(&str) -> bool,
+) -> (BTreeMap<String, String>, Option<Vec<String>>) {
+    let mut rendered = BTreeMap::new();
+    let mut cycle = None;
+    for key in values.keys() {
+        if let Err(found) = interpolate(key, values, &opaque, &mut rendered, &mut Vec::new()) {
+            cycle.get_or_insert(found);
+        }
+    }
+    for (key, value) in values {
+        if opaque(value) || !rendered.contains_key(key) {
+            rendered.insert(key.clone(), value.clone());
+        }
+    }
+    (rendered, cycle)
+}
+
+/// Renders `key` into `rendered` after the values it references, or returns
+/// the cycle it is on.
+fn interpolate<'a>(
+    key: &'a str,
+    values: &'a BTreeMap<String, String>,
+    opaque: &impl Fn(&str) -> bool,
+    rendered: &mut BTreeMap<String, String>,
+    stack: &mut Vec<&'a str>,
+) -> Result<(), Vec<String>> {
+    if rendered.contains_key(key) || opaque(&values[key]) {
+        return Ok(());
+    }
+    if let Some(start) = stack.iter().position(|k| *k == key) {
+        let mut cycle: Vec<String> = stack[start..].iter().map(ToString::to_string).collect();
+        cycle.push(key.to_string());
+        return Err(cycle);
+    }
+    stack.push(key);
+    for reference in scan_placeholders(&values[key]) {
+        if let Some((reference, _)) = values.get_key_value(reference) {
+            interpolate(reference, values, opaque, rendered, stack)?;
+        }
+    }
+    stack.pop();
+    let value = render_placeholders(&values[key], rendered).0;
+    rendered.insert(key.to_string(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn join(segments: &[Segment]) -> String {
+        segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => (*text).to_string(),
+                Segment::Placeholder(key) => format!("{{{{{key}}}}}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_placeholder_spec() {
+        use Segment::{Placeholder as P, Text as T};
+
+        let cases: &[(&str, &[Segment])] = &[
+            ("", &[]),
+            ("plain", &[T("plain")]),
+            ("{{a}}", &[P("a")]),
+            ("x {{a}} y", &[T("x "), P("a"), T(" y")]),
+            ("{{a}}{{b}}", &[P("a"), P("b")]),
+            ("{{}}", &[P("")]),
+            ("{{ a b }}", &[P(" a b ")]),
+            ("{{a\nb}}", &[P("a\nb")]),
+            ("{{clé}}🦀", &[P("clé"), T("🦀")]),
+            ("{{a", &[T("{{a")]),
+            ("x {{a}} {{b", &[T("x "), P("a"), T(" {{b")]),
+            ("a}}", &[T("a}}")]),
+            ("{a}", &[T("{a}")]),
+            ("{{{a}}}", &[P("{a"), T("}")]),
+            ("{{a}b}}", &[P("a}b")]),
+            ("{{{{a}}}}", &[P("{{a"), T("}}")]),
+            ("{{a}}}}", &[P("a"), T("}}")]),
+        ];
+        for (src, expected) in cases {
+            let segments: Vec<Segment> = placeholder_segments(src).collect();
+            assert_eq!(&segments, expected, "segments of {src:?}");
+        }
+
+        let values = BTreeMap::from([("a".to_string(), "1".to_string())]);
+        assert_eq!(
+            render_placeholders("{{a}}-{{b}}-{{a", &values),
+            ("1-{{b}}-{{a".to_string(), vec!["b".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_placeholder_properties() {
+        const ALPHABET: &[char] = &['{', '}', 'a', 'b', ' ', '\n', 'é', '🦀'];
+        let mut rng = fastrand::Rng::with_seed(7);
+
+        for _ in 0..20_000 {
+            let src: String = (0..rng.usize(0..24))
+                .map(|_| ALPHABET[rng.usize(..ALPHABET.len())])
+                .collect();
+            let segments: Vec<Segment> = placeholder_segments(&src).collect();
+
+            assert_eq!(join(&segments), src);
+            assert!(!segments.contains(&Segment::Text("")));
+            assert!(
+                !segments
+                    .windows(2)
+                    .any(|w| matches!(w, [Segment::Text(_), Segment::Text(_)])),
+                "adjacent text segments in {src:?}"
+            );
+
+            let keys = scan_placeholders(&src);
+            assert_eq!(
+                render_placeholders(&src, &BTreeMap::new()),
+                (src.clone(), keys.iter().map(|k| (*k).to_string()).collect())
+            );
+
+            // Values without braces are inserted verbatim and resolve everything
+            let values: BTreeMap<String, String> = keys
+                .iter()
+                .map(|k| ((*k).to_string(), "V".to_string()))
+                .collect();
+            let expected: String = segments
+                .iter()
+                .map(|segment| match segment {
+                    Segment::Text(text) => text,
+                    Segment::Placeholder(_) => "V",
+                })
+                .collect();
+            assert_eq!(render_placeholders(&src, &values), (expected, Vec::new()));
+        }
+    }
+
+    #[test]
+    fn test_interpolate_values() {
+        let values: BTreeMap<String, String> = [
+            ("host", "example.com"),
+            ("port", "8080"),
+            ("base", "https://{{host}}:{{port}}"),
+            ("url", "{{base}}/api?token={{token}}&{{other}}"),
+            ("token", "secret:api"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let (rendered, cycle) = interpolate_values(&values, |v| v.starts_with("secret:"));
+        assert_eq!(cycle, None);
+        assert_eq!(rendered["base"], "https://example.com:8080");
+        // Secret references are inlined once resolved, before the command runs
+        assert_eq!(
+            rendered["url"],
+            "https://example.com:8080/api?token={{token}}&{{other}}"
+        );
+        assert_eq!(rendered["token"], "secret:api");
+
+        let mut cyclic = values.clone();
+        cyclic.insert("host".into(), "{{url}}".into());
+        let (rendered, cycle) = interpolate_values(&cyclic, |v| v.starts_with("secret:"));
+        assert_eq!(cycle.unwrap(), ["base", "host", "url", "base"]);
+        assert_eq!(rendered["base"], "https://{{host}}:{{port}}");
+        assert_eq!(rendered["port"], "8080");
+
+        let (_, cycle) = interpolate_values(
+            &BTreeMap::from([("a".to_string(), "{{a}}".to_string())]),
+            |_| false,
+        );
+        assert_eq!(cycle.unwrap(), ["a", "a"]);
+    }
+
+    #[cfg(feature = "fs-store")]
+    #[test]
+    fn test_rename_placeholder() {
+        assert_eq!(
+            rename_placeholder("{{host}}:{{port}} {{hostname}} {{host", "host", "api_host"),
+            "{{api_host}}:{{port}} {{hostname}} {{host"
+        );
+        assert_eq!(rename_placeholder("{{{host}}}", "host", "x"), "{{{host}}}");
+    }
+}
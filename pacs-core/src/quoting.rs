@@ -0,0 +1,169 @@
+//! Quoting of words and command bodies for interactive shells, e.g. for
+//! `pacs copy --escape`, and joining bodies into one line for `--oneline`.
+//!
+//! Command bodies are POSIX shell scripts. A single line is pasted as it is,
+//! longer bodies become one line that runs them in the target shell.
+
+use crate::shell_quote;
+
+/// A shell that text is quoted for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+impl Shell {
+    /// Quotes `s` as a single literal word.
+    #[must_use]
+    pub fn quote(self, s: &str) -> String {
+        match self {
+            Self::Bash | Self::Zsh => shell_quote(s),
+            Self::Fish => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'")),
+            Self::PowerShell => format!("'{}'", s.replace('\'', "''")),
+        }
+    }
+
+    /// Turns a command body into a single line to paste into this shell.
+    ///
+    /// bash and zsh `eval` an ANSI-C quoted string, fish hands the body to
+    /// `sh -c`, and PowerShell gets the lines joined by `;` as in
+    /// [`flatten_command`].
+    #[must_use]
+    pub fn escape_command(self, body: &str) -> String {
+        let body = body.trim_end_matches(['\r', '\n']);
+        if !body.contains('\n') {
+            return body.to_string();
+        }
+        match self {
+            Self::Bash | Self::Zsh => format!("eval {}", ansi_c_quote(body)),
+            Self::Fish => format!("sh -c {}", self.quote(body)),
+            Self::PowerShell => join_lines(body, "; "),
+        }
+    }
+}
+
+/// Quotes `s` as `$'...'`, which bash and zsh expand escapes in.
+fn ansi_c_quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 3);
+    out.push_str("$'");
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\'' => out.push_str("\\'"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('\'');
+    out
+}
+
+/// Joins the lines of a command body with `&&`, so that it stops at the first
+/// failing line like the body does when run by `pacs run`. Backslash
+/// continuations are joined, and blank and comment lines dropped. Returns
+/// `None` for bodies with a heredoc, which needs its own lines.
+#[must_use]
+pub fn flatten_command(body: &str) -> Option<String> {
+    let heredoc = body
+        .match_indices("<<")
+        .any(|(i, _)| !body[i + 2..].starts_with('<'));
+    if heredoc {
+        return None;
+    }
+    Some(join_lines(body, " && "))
+}
+
+/// Words after which the next line continues the statement.
+const OPENING_WORDS: [&str; 5] = ["then", "do", "else", "{", "in"];
+/// Words that start a line continuing a compound statement.
+const CLOSING_WORDS: [&str; 7] = ["then", "do", "done", "fi", "else", "elif", "esac"];
+
+/// Joins the lines of a body with `separator`, or with a space or `;` where
+/// the lines belong to one statement.
+fn join_lines(body: &str, separator: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut joins_next = false;
+    for line in body.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (line, continued) = match line.strip_suffix('\\') {
+            Some(rest) => (rest.trim_end(), true),
+            None => (line, false),
+        };
+        if !out.is_empty() {
+            let last_word = out.split_whitespace().next_back().unwrap_or_default();
+            let first_word = line.split_whitespace().next().unwrap_or_default();
+            if joins_next
+                || out.ends_with(['|', '&', ';', '('])
+                || OPENING_WORDS.contains(&last_word)
+            {
+                out.push(' ');
+            } else if CLOSING_WORDS.contains(&first_word) || line.starts_with('}') {
+                out.push_str("; ");
+            } else {
+                out.push_str(separator);
+            }
+        }
+        out.push_str(line);
+        joins_next = continued;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_command() {
+        let body =
+            "# build and push\ndocker build \\\n  -t app .\n\ndocker push app ||\n  echo failed\n";
+        assert_eq!(
+            flatten_command(body).as_deref(),
+            Some("docker build -t app . && docker push app || echo failed")
+        );
+        let body = "for f in *.log; do\n  gzip \"$f\"\ndone\nif [ -d out ]; then\n  ls out\nfi";
+        assert_eq!(
+            flatten_command(body).as_deref(),
+            Some("for f in *.log; do gzip \"$f\"; done && if [ -d out ]; then ls out; fi")
+        );
+        assert_eq!(flatten_command("cat <<EOF\nhi\nEOF"), None);
+        assert!(flatten_command("grep x <<< \"$y\"\necho").is_some());
+    }
+
+    #[test]
+    fn test_quote() {
+        let word = r"it's C:\tmp";
+        assert_eq!(Shell::Bash.quote(word), r"'it'\''s C:\tmp'");
+        assert_eq!(Shell::Fish.quote(word), r"'it\'s C:\\tmp'");
+        assert_eq!(Shell::PowerShell.quote(word), r"'it''s C:\tmp'");
+    }
+
+    #[test]
+    fn test_escape_command() {
+        let line = "cargo build --release\n";
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            assert_eq!(shell.escape_command(line), "cargo build --release");
+        }
+
+        let body = "# deploy\ndocker build \\\n  -t 'app' .\necho done\n";
+        assert_eq!(
+            Shell::Zsh.escape_command(body),
+            r"eval $'# deploy\ndocker build \\\n  -t \'app\' .\necho done'"
+        );
+        assert_eq!(
+            Shell::Fish.escape_command(body),
+            "sh -c '# deploy\ndocker build \\\\\n  -t \\'app\\' .\necho done'"
+        );
+        assert_eq!(
+            Shell::PowerShell.escape_command(body),
+            "docker build -t 'app' .; echo done"
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! Read-only command registries fetched from remote sources.
+//!
+//! A registry is a TOML file with the same `[[commands]]` layout as a project
+//! file. It is either served over HTTP(S) or stored as `pacs.toml` at the root
+//! of a git repository. Fetched files are cached in `registries/<name>.toml`.
+//!
+//! Registry commands only run once approved. The approved version of each
+//! command is kept in `registries/approved/<name>.toml`, so a command changed
+//! by its registry needs another review. Entries may carry the SHA-256 of
+//! their body as `sha256`, which is checked before they are approved or run.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{PacsCommand, PacsError, http, write_atomic};
+
+/// File read from the root of git registries.
+const GIT_REGISTRY_FILE: &str = "pacs.toml";
+
+/// URL prefixes a registry may be fetched from.
+const URL_SCHEMES: [&str; 5] = ["https://", "http://", "file://", "ssh://", "git@"];
+
+/// A registry subscription stored in config.toml.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistrySource {
+    pub name: String,
+    /// HTTP(S) URL of a TOML file or URL of a git repository.
+    pub url: String,
+}
+
+impl RegistrySource {
+    /// Derives a registry name from the last segment of a URL.
+    #[must_use]
+    pub fn name_from_url(url: &str) -> String {
+        let segment = url
+            .trim_end_matches('/')
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or(url);
+        let name = segment
+            .strip_suffix(".git")
+            .or_else(|| segment.strip_suffix(".toml"))
+            .unwrap_or(segment);
+        name.to_string()
+    }
+
+    /// Checks that the name is usable as a file name in the registries
+    /// directory and that the URL has a known scheme, so that it cannot be
+    /// taken for an option of git.
+    pub(crate) fn validate(&self) -> Result<(), PacsError> {
+        check_name(&self.name)?;
+        if !URL_SCHEMES
+            .iter()
+            .any(|scheme| self.url.starts_with(scheme))
+        {
+            return Err(PacsError::InvalidRegistryUrl(self.url.clone()));
+        }
+        Ok(())
+    }
+
+    fn is_git(&self) -> bool {
+        Path::new(&self.url)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("git"))
+            || self.url.starts_with("git@")
+            || self.url.starts_with("ssh://")
+    }
+
+    /// Downloads the registry file and returns its content.
+    fn fetch(&self, scratch: &Path) -> Result<String, PacsError> {
+        self.validate()?;
+        if self.is_git() {
+            let checkout = scratch.join(format!(".{}.git", self.name));
+            if checkout.exists() {
+                fs::remove_dir_all(&checkout)?;
+            }
+            let output = Command::new("git")
+                .args(["clone", "--quiet", "--depth", "1", "--", &self.url])
+                .arg(&checkout)
+                .output()?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(PacsError::FetchFailed(format!(
+                    "{}: {}",
+                    self.url,
+                    stderr.trim()
+                )));
+            }
+            let content = fs::read_to_string(checkout.join(GIT_REGISTRY_FILE));
+            fs::remove_dir_all(&checkout)?;
+            Ok(content?)
+        } else {
+            http::send(&http::Request::get(&self.url))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RegistryFile {
+    #[serde(default)]
+    commands: Vec<RegistryEntry>,
+}
+
+#[derive(Deserialize)]
+struct RegistryEntry {
+    #[serde(flatten)]
+    command: PacsCommand,
+    /// Hex SHA-256 of the body.
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+/// A fetched registry with its commands.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    pub source: RegistrySource,
+    pub commands: Vec<PacsCommand>,
+    /// When the cached copy was last fetched.
+    pub fetched_at: Option<SystemTime>,
+    /// Checksums of command bodies by command name.
+    checksums: BTreeMap<String, String>,
+    /// Approved commands by name, as they were when approved.
+    approved: BTreeMap<String, PacsCommand>,
+}
+
+impl Registry {
+    /// Returns true if the cached copy is missing or older than `max_age`.
+    #[must_use]
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.fetched_at
+            .and_then(|t| t.elapsed().ok())
+            .is_none_or(|age| age >= max_age)
+    }
+
+    /// Returns true if `cmd` was approved exactly as it is now.
+    #[must_use]
+    pub fn is_approved(&self, cmd: &PacsCommand) -> bool {
+        self.approved
+            .get(&cmd.name)
+            .is_some_and(|approved| toml::to_string(approved).ok() == toml::to_string(cmd).ok())
+    }
+
+    /// Returns the version of a command that was approved, if any.
+    #[must_use]
+    pub fn approved_version(&self, name: &str) -> Option<&PacsCommand> {
+        self.approved.get(name)
+    }
+
+    /// Returns the approved commands that the registry changed since.
+    pub fn changed_since_approval(&self) -> impl Iterator<Item = &PacsCommand> {
+        self.commands
+            .iter()
+            .filter(|c| self.approved.contains_key(&c.name) && !self.is_approved(c))
+    }
+
+    /// Checks the body of `cmd` against its checksum, if the registry has one.
+    pub fn verify(&self, cmd: &PacsCommand) -> Result<(), PacsError> {
+        match self.checksums.get(&cmd.name) {
+            Some(checksum) if !sha256_hex(&cmd.command).eq_ignore_ascii_case(checksum) => {
+                Err(PacsError::ChecksumMismatch {
+                    registry: self.source.name.clone(),
+                    name: cmd.name.clone(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Approves the current version of a command and saves the approvals.
+    pub(crate) fn approve(&mut self, dir: &Path, name: &str) -> Result<(), PacsError> {
+        self.source.validate()?;
+        let cmd = PacsCommand::find_by_name(&self.commands, name)?;
+        self.verify(cmd)?;
+        self.approved.insert(cmd.name.clone(), cmd.clone());
+        let path = approvals_path(dir, &self.source.name);
+        fs::create_dir_all(dir.join("approved"))?;
+        write_atomic(&path, &toml::to_string(&self.approved)?)
+    }
+}
+
+/// Checks that a registry name is a plain file name.
+fn check_name(name: &str) -> Result<(), PacsError> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(PacsError::InvalidRegistryName(name.to_string()));
+    }
+    Ok(())
+}
+
+fn cache_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.toml"))
+}
+
+fn approvals_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join("approved").join(format!("{name}.toml"))
+}
+
+/// Loads the approved commands of a registry. Missing or broken files approve nothing.
+fn load_approvals(dir: &Path, name: &str) -> BTreeMap<String, PacsCommand> {
+    fs::read_to_string(approvals_path(dir, name))
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Returns the hex SHA-256 of `text`, as written by `sha256sum`.
+fn sha256_hex(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+/// Parses a registry file into its commands and their checksums.
+fn parse(content: &str) -> Result<(Vec<PacsCommand>, BTreeMap<String, String>), PacsError> {
+    let entries = toml::from_str::<RegistryFile>(content)?.commands;
+    let checksums = entries
+        .iter()
+        .filter_map(|e| Some((e.command.name.clone(), e.sha256.clone()?)))
+        .collect();
+    let commands = entries.into_iter().map(|e| e.command).collect();
+    Ok((commands, checksums))
+}
+
+/// Loads the cached copy of a registry. Missing or broken caches yield no commands.
+pub(crate) fn load_cached(dir: &Path, source: RegistrySource) -> Registry {
+    let path = cache_path(dir, &source.name);
+    let (commands, checksums) = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| parse(&content).ok())
+        .unwrap_or_default();
+    let fetched_at = fs::metadata(&path).and_then(|m| m.modified()).ok();
+    let approved = load_approvals(dir, &source.name);
+    Registry {
+        source,
+        commands,
+        fetched_at,
+        checksums,
+        approved,
+    }
+}
+
+/// Fetches a registry, validates it and updates its cached copy.
+pub(crate) fn fetch(dir: &Path, source: RegistrySource) -> Result<Registry, PacsError> {
+    fs::create_dir_all(dir)?;
+    let content = source.fetch(dir)?;
+    let (commands, checksums) = parse(&content)?;
+    fs::write(cache_path(dir, &source.name), content)?;
+    let approved = load_approvals(dir, &source.name);
+    Ok(Registry {
+        source,
+        commands,
+        fetched_at: Some(SystemTime::now()),
+        checksums,
+        approved,
+    })
+}
+
+/// Removes the cached copy and the approvals of a registry.
+pub(crate) fn remove_cached(dir: &Path, name: &str) -> Result<(), PacsError> {
+    check_name(name)?;
+    for path in [cache_path(dir, name), approvals_path(dir, name)] {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_name_from_url() {
+        assert_eq!(
+            RegistrySource::name_from_url("https://example.com/platform/ops.toml"),
+            "ops"
+        );
+        assert_eq!(
+            RegistrySource::name_from_url("git@github.com:acme/k8s-commands.git"),
+            "k8s-commands"
+        );
+        assert_eq!(
+            RegistrySource::name_from_url("https://github.com/acme/blessed/"),
+            "blessed"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let source = |name: &str, url: &str| RegistrySource {
+            name: name.into(),
+            url: url.into(),
+        };
+        assert!(
+            source("ops", "https://example.com/ops.toml")
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            source("ops", "git@github.com:acme/ops.git")
+                .validate()
+                .is_ok()
+        );
+        assert!(matches!(
+            source("ops", "--upload-pack=touch /tmp/x;.git").validate(),
+            Err(PacsError::InvalidRegistryUrl(_))
+        ));
+        assert!(matches!(
+            source("ops", "/srv/ops.git").validate(),
+            Err(PacsError::InvalidRegistryUrl(_))
+        ));
+        for name in ["../ops", ".ops", "", "a\\b"] {
+            assert!(matches!(
+                source(name, "https://example.com/ops.toml").validate(),
+                Err(PacsError::InvalidRegistryName(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_checksums() {
+        let checksum = "56a79f3b115448072387c2480044bfa2cf8f90e4f5fddd8c943b4e051b81f80b";
+        assert_eq!(sha256_hex("echo hi"), checksum);
+
+        let content = format!(
+            "[[commands]]\nname = \"hi\"\ncommand = \"echo hi\"\nsha256 = \"{checksum}\"\n\n\
+             [[commands]]\nname = \"bye\"\ncommand = \"echo bye\"\nsha256 = \"{checksum}\"\n\n\
+             [[commands]]\nname = \"plain\"\ncommand = \"echo plain\"\n"
+        );
+        let (commands, checksums) = parse(&content).unwrap();
+        let registry = Registry {
+            source: RegistrySource {
+                name: "ops".into(),
+                url: String::new(),
+            },
+            commands,
+            fetched_at: None,
+            checksums,
+            approved: BTreeMap::new(),
+        };
+        assert!(registry.verify(&registry.commands[0]).is_ok());
+        assert!(matches!(
+            registry.verify(&registry.commands[1]),
+            Err(PacsError::ChecksumMismatch { name, .. }) if name == "bye"
+        ));
+        assert!(registry.verify(&registry.commands[2]).is_ok());
+    }
+}
@@ -0,0 +1,152 @@
+//! Restrictions for commands from less trusted sources, such as shared
+//! registries.
+//!
+//! Network and filesystem restrictions are enforced by `bwrap` on Linux and
+//! `sandbox-exec` on macOS. A command asking for a restriction that cannot be
+//! enforced on this machine is not run.
+
+#[cfg(feature = "exec")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "exec")]
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "exec")]
+use crate::PacsError;
+
+/// Environment variables kept by `clear_env`.
+#[cfg(feature = "exec")]
+const KEPT_VARIABLES: [&str; 5] = ["PATH", "HOME", "USER", "LANG", "TERM"];
+
+/// Restrictions applied whenever a command runs, set in its
+/// `[commands.sandbox]` table.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+pub struct Sandbox {
+    /// Runs with an empty environment apart from `PATH`, `HOME`, `USER`,
+    /// `LANG` and `TERM`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub clear_env: bool,
+    /// Cuts off network access.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_network: bool,
+    /// Allows writing only below the working directory and to a private `/tmp`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cwd_only: bool,
+}
+
+impl Sandbox {
+    /// Builds the shell process running `body` in `cwd` with these restrictions.
+    #[cfg(feature = "exec")]
+    pub(crate) fn shell_command(
+        &self,
+        body: &str,
+        cwd: Option<&str>,
+    ) -> Result<Command, PacsError> {
+        let mut process = if self.no_network || self.cwd_only {
+            let dir = match cwd {
+                Some(cwd) => PathBuf::from(cwd),
+                None => std::env::current_dir()?,
+            };
+            self.confine(&dir.canonicalize()?)?
+        } else {
+            Command::new("sh")
+        };
+        process.arg("-c").arg(body);
+        if let Some(cwd) = cwd {
+            process.current_dir(cwd);
+        }
+        if self.clear_env {
+            process.env_clear().envs(
+                std::env::vars_os().filter(|(key, _)| KEPT_VARIABLES.iter().any(|k| key == k)),
+            );
+        }
+        Ok(process)
+    }
+
+    /// Returns a `bwrap` process that starts `sh` in the sandbox.
+    #[cfg(all(feature = "exec", target_os = "linux"))]
+    fn confine(&self, dir: &Path) -> Result<Command, PacsError> {
+        let mut process = Command::new(find_tool("bwrap")?);
+        process.arg("--die-with-parent");
+        if self.cwd_only {
+            process.args(["--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc"]);
+            process
+                .args(["--tmpfs", "/tmp", "--bind"])
+                .arg(dir)
+                .arg(dir);
+        } else {
+            process.args(["--bind", "/", "/", "--dev", "/dev", "--proc", "/proc"]);
+        }
+        if self.no_network {
+            process.arg("--unshare-net");
+        }
+        process.arg("sh");
+        Ok(process)
+    }
+
+    /// Returns a `sandbox-exec` process that starts `sh` in the sandbox.
+    #[cfg(all(feature = "exec", target_os = "macos"))]
+    fn confine(&self, dir: &Path) -> Result<Command, PacsError> {
+        let mut profile = String::from("(version 1)(allow default)");
+        if self.no_network {
+            profile.push_str("(deny network*)");
+        }
+        if self.cwd_only {
+            profile.push_str(&format!(
+                "(deny file-write*)(allow file-write* (subpath {dir:?}) \
+                 (subpath \"/private/tmp\") (subpath \"/private/var/folders\") \
+                 (literal \"/dev/null\"))"
+            ));
+        }
+        let mut process = Command::new(find_tool("sandbox-exec")?);
+        process.arg("-p").arg(profile).arg("sh");
+        Ok(process)
+    }
+
+    #[cfg(all(feature = "exec", not(any(target_os = "linux", target_os = "macos"))))]
+    #[allow(clippy::unused_self)]
+    fn confine(&self, _dir: &Path) -> Result<Command, PacsError> {
+        Err(PacsError::SandboxUnavailable(
+            "bwrap or sandbox-exec".into(),
+        ))
+    }
+}
+
+/// Finds an executable on `PATH`.
+#[cfg(all(feature = "exec", any(target_os = "linux", target_os = "macos")))]
+fn find_tool(name: &str) -> Result<PathBuf, PacsError> {
+    std::env::var_os("PATH")
+        .iter()
+        .flat_map(std::env::split_paths)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+        .ok_or_else(|| PacsError::SandboxUnavailable(name.to_string()))
+}
+
+#[cfg(all(test, feature = "exec"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clear_env() {
+        let sandbox = Sandbox {
+            clear_env: true,
+            ..Sandbox::default()
+        };
+        // Cargo sets CARGO_PKG_NAME for test binaries
+        let output = sandbox
+            .shell_command("echo ${CARGO_PKG_NAME:-unset} ${PATH:+path}", None)
+            .unwrap()
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "unset path\n");
+    }
+
+    #[test]
+    fn test_sandbox_toml() {
+        let sandbox: Sandbox = toml::from_str("no_network = true").unwrap();
+        assert!(sandbox.no_network && !sandbox.clear_env && !sandbox.cwd_only);
+        assert_eq!(toml::to_string(&sandbox).unwrap(), "no_network = true\n");
+    }
+}
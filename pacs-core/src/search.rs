@@ -0,0 +1,138 @@
+//! Fuzzy search over the commands of all projects and registries.
+
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+
+use crate::{PacsCommand, Project, Registry};
+
+/// A command matching a search query.
+#[derive(Debug, Clone)]
+pub struct SearchMatch<'a> {
+    pub command: &'a PacsCommand,
+    /// Name of the project or registry the command belongs to.
+    pub scope: &'a str,
+    /// True if the command comes from a registry.
+    pub registry: bool,
+    pub score: i64,
+    /// True if the score is close to that of a contiguous match of the whole
+    /// query, so the command is very likely the one searched for.
+    pub confident: bool,
+    /// Char indices of the matched characters in the command name.
+    pub name_indices: Vec<usize>,
+    /// The trimmed line of the body that matches best, or its first line.
+    pub preview: &'a str,
+    /// Char indices of the matched characters in `preview`.
+    pub preview_indices: Vec<usize>,
+}
+
+/// Returns the commands matching `query` by name or body, best match first.
+pub(crate) fn search_commands<'a>(
+    projects: &'a [Project],
+    registries: &'a [Registry],
+    query: &str,
+) -> Vec<SearchMatch<'a>> {
+    let matcher = SkimMatcherV2::default();
+    let best_score = matcher.fuzzy_match(query, query).unwrap_or(0);
+
+    let commands = projects
+        .iter()
+        .flat_map(|p| p.commands.iter().map(move |c| (c, p.name.as_str(), false)))
+        .chain(registries.iter().flat_map(|r| {
+            r.commands
+                .iter()
+                .map(move |c| (c, r.source.name.as_str(), true))
+        }));
+
+    let mut results: Vec<SearchMatch> = commands
+        .filter_map(|(command, scope, registry)| {
+            let name = matcher.fuzzy_indices(&command.name, query);
+            let name_score = name.as_ref().map_or(0, |(score, _)| *score);
+            let score = name_score.max(matcher.fuzzy_match(&command.command, query).unwrap_or(0));
+            if score <= 0 {
+                return None;
+            }
+
+            // A match may span lines, then no single line is highlighted
+            let best_line = command
+                .command
+                .lines()
+                .map(str::trim)
+                .filter_map(|line| {
+                    matcher
+                        .fuzzy_indices(line, query)
+                        .map(|(score, indices)| (line, score, indices))
+                })
+                .max_by_key(|(_, score, _)| *score);
+            let (preview, line_score, preview_indices) = match best_line {
+                Some((line, score, indices)) => (line, score, indices),
+                None => (
+                    command
+                        .command
+                        .lines()
+                        .map(str::trim)
+                        .find(|line| !line.is_empty())
+                        .unwrap_or_default(),
+                    0,
+                    Vec::new(),
+                ),
+            };
+
+            Some(SearchMatch {
+                command,
+                scope,
+                registry,
+                score,
+                confident: name_score.max(line_score) * 10 >= best_score * 9,
+                name_indices: name.map(|(_, indices)| indices).unwrap_or_default(),
+                preview,
+                preview_indices,
+            })
+        })
+        .collect();
+
+    results.sort_by_key(|m| -m.score);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn command(name: &str, body: &str) -> PacsCommand {
+        PacsCommand {
+            name: name.into(),
+            command: body.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search_commands() {
+        let projects = [Project {
+            name: "api".into(),
+            commands: vec![
+                command("deploy", "cd infra\nkubectl apply -f deploy.yaml\n"),
+                command("build", "cargo build"),
+            ],
+            ..Project::default()
+        }];
+
+        let matches = search_commands(&projects, &[], "kubectl");
+        assert_eq!(matches.len(), 1);
+        let found = &matches[0];
+        assert_eq!(found.command.name, "deploy");
+        assert_eq!(found.scope, "api");
+        assert!(!found.registry);
+        assert!(found.name_indices.is_empty());
+        assert_eq!(found.preview, "kubectl apply -f deploy.yaml");
+        assert_eq!(found.preview_indices, (0..7).collect::<Vec<_>>());
+        assert!(found.confident);
+
+        let matches = search_commands(&projects, &[], "build");
+        assert_eq!(matches[0].command.name, "build");
+        assert_eq!(matches[0].name_indices, (0..5).collect::<Vec<_>>());
+        assert!(!search_commands(&projects, &[], "cbd")[0].confident);
+        assert!(search_commands(&projects, &[], "zzz").is_empty());
+    }
+}
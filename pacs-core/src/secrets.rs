@@ -0,0 +1,212 @@
+//! Secret references in environment values.
+//!
+//! Values like `op://vault/item/field` or `vault:secret/data/path#key` are
+//! stored as references and only resolved when a command is run or copied.
+//! Resolved values are cached in memory for the lifetime of the
+//! [`crate::Pacs`] instance and never written to disk, and masked in text
+//! shown to the user.
+
+#[cfg(feature = "fs-store")]
+use std::{collections::HashMap, process::Command, sync::Mutex};
+
+use crate::shell_quote;
+#[cfg(feature = "fs-store")]
+use crate::{PacsError, http};
+
+/// Shown in place of resolved secret values.
+pub const REDACTED: &str = "••••••";
+
+/// Resolved values shorter than this are not masked, as they would match
+/// too much unrelated text.
+#[cfg(feature = "fs-store")]
+const MIN_REDACTED_LEN: usize = 4;
+
+/// Prefix of 1Password secret references, resolved with `op read`.
+const ONEPASSWORD_PREFIX: &str = "op://";
+
+/// Prefix of Vault references, `vault:<path>#<key>`, resolved via the
+/// HTTP API using `VAULT_ADDR` and `VAULT_TOKEN`.
+const VAULT_PREFIX: &str = "vault:";
+
+/// Returns true if the value is a reference to a secret rather than a plain value.
+#[must_use]
+pub fn is_secret_reference(value: &str) -> bool {
+    value.starts_with(ONEPASSWORD_PREFIX) || parse_vault_reference(value).is_some()
+}
+
+/// Splits a `vault:<path>#<key>` reference into path and key.
+fn parse_vault_reference(value: &str) -> Option<(&str, &str)> {
+    let (path, key) = value.strip_prefix(VAULT_PREFIX)?.rsplit_once('#')?;
+    let path = path.trim_matches('/');
+    (!path.is_empty() && !key.is_empty()).then_some((path, key))
+}
+
+/// Returns a shell command substitution that resolves a secret reference at runtime.
+pub(crate) fn shell_expression(value: &str) -> Option<String> {
+    if value.starts_with(ONEPASSWORD_PREFIX) {
+        return Some(format!(
+            "\"$(op read --no-newline {})\"",
+            shell_quote(value)
+        ));
+    }
+    // `vault kv get` takes KV v2 paths without the `data/` segment after the mount
+    let (path, key) = parse_vault_reference(value)?;
+    let path = match path.split_once("/data/") {
+        Some((mount, rest)) => format!("{mount}/{rest}"),
+        None => path.to_string(),
+    };
+    Some(format!(
+        "\"$(vault kv get -field={} {})\"",
+        shell_quote(key),
+        shell_quote(&path)
+    ))
+}
+
+/// Resolves secret references, remembering each resolved value.
+#[cfg(feature = "fs-store")]
+#[derive(Debug, Default)]
+pub(crate) struct SecretCache(Mutex<HashMap<String, String>>);
+
+#[cfg(feature = "fs-store")]
+impl SecretCache {
+    /// Returns plain values as they are and resolves secret references.
+    pub fn resolve(&self, value: &str) -> Result<String, PacsError> {
+        if !is_secret_reference(value) {
+            return Ok(value.to_string());
+        }
+
+        if let Some(resolved) = self.0.lock().ok().and_then(|c| c.get(value).cloned()) {
+            return Ok(resolved);
+        }
+
+        let resolved = match parse_vault_reference(value) {
+            Some((path, key)) => read_vault(value, path, key)?,
+            None => read_onepassword(value)?,
+        };
+        if let Ok(mut cache) = self.0.lock() {
+            cache.insert(value.to_string(), resolved.clone());
+        }
+        Ok(resolved)
+    }
+
+    /// Replaces every secret value resolved so far in `text` with [`REDACTED`].
+    pub fn redact(&self, text: &str) -> String {
+        let Ok(cache) = self.0.lock() else {
+            return text.to_string();
+        };
+        // Longest first, so a secret containing another is masked as a whole
+        let mut values: Vec<&str> = cache
+            .values()
+            .map(|v| v.trim())
+            .filter(|v| v.len() >= MIN_REDACTED_LEN)
+            .collect();
+        values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+        values.into_iter().fold(text.to_string(), |text, value| {
+            text.replace(value, REDACTED)
+        })
+    }
+}
+
+#[cfg(feature = "fs-store")]
+fn read_onepassword(reference: &str) -> Result<String, PacsError> {
+    let output = Command::new("op")
+        .args(["read", "--no-newline", reference])
+        .output()
+        .map_err(|e| PacsError::SecretResolution(format!("{reference}: op: {e}")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(PacsError::SecretResolution(format!(
+            "{reference}: {}",
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(feature = "fs-store")]
+fn read_vault(reference: &str, path: &str, key: &str) -> Result<String, PacsError> {
+    let error = |message: &str| PacsError::SecretResolution(format!("{reference}: {message}"));
+
+    let addr = std::env::var("VAULT_ADDR").map_err(|_| error("VAULT_ADDR is not set"))?;
+    let token = std::env::var("VAULT_TOKEN")
+        .ok()
+        .or_else(|| {
+            let path = dirs::home_dir()?.join(".vault-token");
+            std::fs::read_to_string(path).ok()
+        })
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| error("VAULT_TOKEN is not set"))?;
+
+    let url = format!("{}/v1/{path}", addr.trim_end_matches('/'));
+    let mut request = http::Request::get(&url).header(format!("X-Vault-Token: {token}"));
+    if let Ok(namespace) = std::env::var("VAULT_NAMESPACE") {
+        request = request.header(format!("X-Vault-Namespace: {namespace}"));
+    }
+    let response = http::send(&request)?;
+
+    vault_field(&response, key).ok_or_else(|| error(&format!("key '{key}' not found")))
+}
+
+/// Extracts a field from a Vault read response. KV v2 nests the secret in
+/// `data.data`, KV v1 and other engines return it in `data`.
+#[cfg(feature = "fs-store")]
+fn vault_field(response: &str, key: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(response).ok()?;
+    let data = &json["data"];
+    let value = data["data"].get(key).or_else(|| data.get(key))?;
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(all(test, feature = "fs-store"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_values_are_not_resolved() {
+        let cache = SecretCache::default();
+        assert_eq!(cache.resolve("dev-cluster").unwrap(), "dev-cluster");
+        assert!(is_secret_reference("op://vault/db/password"));
+        assert!(!is_secret_reference("https://op.example.com"));
+    }
+
+    #[test]
+    fn test_vault_references() {
+        assert_eq!(
+            parse_vault_reference("vault:secret/data/api/db#password"),
+            Some(("secret/data/api/db", "password"))
+        );
+        assert_eq!(parse_vault_reference("vault:secret/data/api/db"), None);
+        assert!(is_secret_reference("vault:kv/app#token"));
+
+        let v2 = r#"{"data":{"data":{"password":"hunter2"},"metadata":{}}}"#;
+        assert_eq!(vault_field(v2, "password").as_deref(), Some("hunter2"));
+        let v1 = r#"{"data":{"port":5432}}"#;
+        assert_eq!(vault_field(v1, "port").as_deref(), Some("5432"));
+        assert_eq!(vault_field(v1, "missing"), None);
+
+        assert_eq!(
+            shell_expression("vault:secret/data/api/db#password").as_deref(),
+            Some("\"$(vault kv get -field='password' 'secret/api/db')\"")
+        );
+    }
+
+    #[test]
+    fn test_redact() {
+        let cache = SecretCache::default();
+        assert_eq!(cache.redact("token hunter2"), "token hunter2");
+
+        let mut values = cache.0.lock().unwrap();
+        values.insert("op://v/db/password".into(), "hunter2\n".into());
+        values.insert("op://v/db/user".into(), "hunter".into());
+        values.insert("op://v/db/port".into(), "42".into());
+        drop(values);
+        assert_eq!(
+            cache.redact("psql -U hunter -W hunter2 -p 42"),
+            "psql -U •••••• -W •••••• -p 42"
+        );
+    }
+}
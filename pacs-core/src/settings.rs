@@ -0,0 +1,46 @@
+//! Settings with the place their effective value comes from, for
+//! `pacs config show --effective`.
+
+/// Where the effective value of a setting comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingSource {
+    Default,
+    ConfigFile,
+    /// An environment variable.
+    Env(&'static str),
+    /// A command line flag.
+    Flag(&'static str),
+    /// `git config`.
+    Git,
+}
+
+impl SettingSource {
+    /// Returns the first source whose condition holds, or the default.
+    #[must_use]
+    pub(crate) fn first(candidates: &[(bool, SettingSource)]) -> Self {
+        candidates
+            .iter()
+            .find(|(holds, _)| *holds)
+            .map_or(Self::Default, |(_, source)| *source)
+    }
+}
+
+/// A setting and its effective value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    /// Key as in config.toml, e.g. `safe_mode`.
+    pub key: String,
+    /// The value as TOML, with secrets masked unless revealed.
+    pub value: String,
+    pub source: SettingSource,
+}
+
+impl Setting {
+    pub fn new(key: &str, value: impl Into<toml::Value>, source: SettingSource) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.into().to_string(),
+            source,
+        }
+    }
+}
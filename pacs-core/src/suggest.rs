@@ -0,0 +1,122 @@
+//! Drafting and explaining commands with the chat completions endpoint
+//! configured in `[suggest]`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{PacsError, http};
+
+const SYSTEM_PROMPT: &str = "You write shell commands. Reply with only the command \
+    body, without explanation or markdown. Use {{name}} placeholders for values the \
+    user has to fill in.";
+
+const EXPLAIN_PROMPT: &str = "Explain what the following shell command does, going \
+    through each program, pipe and flag. Be brief and reply in plain text.";
+
+/// The endpoint `pacs suggest` drafts commands with.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuggestConfig {
+    /// URL of a chat completions endpoint, e.g.
+    /// `https://api.openai.com/v1/chat/completions`.
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Sent as bearer token. Defaults to `PACS_SUGGEST_KEY`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Completion {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    content: String,
+}
+
+/// Asks the endpoint for a command doing what `description` says.
+pub(crate) fn draft(
+    config: &SuggestConfig,
+    description: &str,
+    context: &str,
+) -> Result<String, PacsError> {
+    let system = format!("{SYSTEM_PROMPT} {context}");
+    let reply = complete(config, system.trim_end(), description)?;
+    Ok(strip_fences(&reply))
+}
+
+/// Asks the endpoint to explain a command body.
+pub(crate) fn explain(config: &SuggestConfig, command: &str) -> Result<String, PacsError> {
+    Ok(complete(config, EXPLAIN_PROMPT, command)?
+        .trim()
+        .to_string())
+}
+
+/// Sends a system and a user message and returns the first reply.
+fn complete(config: &SuggestConfig, system: &str, user: &str) -> Result<String, PacsError> {
+    let mut body = serde_json::json!({
+        "messages": [
+            { "role": "system", "content": system },
+            { "role": "user", "content": user },
+        ],
+    });
+    if let Some(model) = &config.model {
+        body["model"] = model.clone().into();
+    }
+
+    let mut request = http::Request::get(&config.url)
+        .header("Content-Type: application/json")
+        .body("POST", body.to_string());
+    let key = config
+        .api_key
+        .clone()
+        .or_else(|| std::env::var("PACS_SUGGEST_KEY").ok())
+        .filter(|k| !k.is_empty());
+    if let Some(key) = key {
+        request = request.header(format!("Authorization: Bearer {key}"));
+    }
+
+    let completion: Completion = serde_json::from_str(&http::send(&request)?)?;
+    Ok(completion
+        .choices
+        .into_iter()
+        .next()
+        .map(|c| c.message.content)
+        .unwrap_or_default())
+}
+
+/// Removes a markdown code fence around the reply, which models add despite
+/// being asked not to.
+fn strip_fences(reply: &str) -> String {
+    let reply = reply.trim();
+    let Some(inner) = reply
+        .strip_prefix("```")
+        .and_then(|r| r.strip_suffix("```"))
+    else {
+        return format!("{reply}\n");
+    };
+    // Drop the language of the fence, e.g. ```bash
+    let inner = inner.split_once('\n').map_or("", |(_, body)| body);
+    format!("{}\n", inner.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_fences() {
+        assert_eq!(strip_fences("ls -la\n"), "ls -la\n");
+        assert_eq!(
+            strip_fences("```bash\nkubectl get pods -n {{ns}}\n```"),
+            "kubectl get pods -n {{ns}}\n"
+        );
+        assert_eq!(strip_fences("```\ncd app\nmake\n```\n"), "cd app\nmake\n");
+    }
+}
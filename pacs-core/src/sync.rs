@@ -0,0 +1,105 @@
+//! Resolution of git merge conflicts in project files, for a `~/.pacs` that
+//! is synced with git.
+
+use std::path::PathBuf;
+
+use crate::{BundleConflict, Project};
+
+/// A project file with git conflict markers, parsed into both sides.
+#[derive(Debug)]
+pub struct SyncConflict {
+    /// Project name taken from the file name.
+    pub project: String,
+    pub path: PathBuf,
+    pub ours: Project,
+    pub theirs: Project,
+    /// Commands of `theirs` that differ from a command of `ours`.
+    pub conflicts: Vec<BundleConflict>,
+}
+
+/// Splits content with git conflict markers into our and their version.
+/// Returns `None` if there are no complete conflict blocks. The base section
+/// of diff3-style conflicts is dropped.
+pub(crate) fn conflict_sides(content: &str) -> Option<(String, String)> {
+    #[derive(PartialEq)]
+    enum Section {
+        Both,
+        Ours,
+        Base,
+        Theirs,
+    }
+
+    let mut ours = String::new();
+    let mut theirs = String::new();
+    let mut section = Section::Both;
+    let mut found = false;
+
+    for line in content.split_inclusive('\n') {
+        let marker = |m: &str| {
+            line.strip_prefix(m)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\r', '\n']))
+        };
+        section = match section {
+            Section::Both if marker("<<<<<<<") => Section::Ours,
+            Section::Ours if marker("|||||||") => Section::Base,
+            Section::Ours | Section::Base if marker("=======") => Section::Theirs,
+            Section::Theirs if marker(">>>>>>>") => {
+                found = true;
+                Section::Both
+            }
+            section => {
+                match section {
+                    Section::Both => {
+                        ours.push_str(line);
+                        theirs.push_str(line);
+                    }
+                    Section::Ours => ours.push_str(line),
+                    Section::Base => {}
+                    Section::Theirs => theirs.push_str(line),
+                }
+                section
+            }
+        };
+    }
+    (found && section == Section::Both).then_some((ours, theirs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflict_sides() {
+        let content = "\
+name = \"api\"
+<<<<<<< HEAD
+[[commands]]
+name = \"build\"
+command = \"cargo build\"
+||||||| base
+[[commands]]
+name = \"old\"
+command = \"make\"
+=======
+[[commands]]
+name = \"build\"
+command = \"cargo build --release\"
+>>>>>>> origin/main
+
+[[environments]]
+name = \"dev\"
+";
+        let (ours, theirs) = conflict_sides(content).unwrap();
+        assert_eq!(
+            ours,
+            "name = \"api\"\n[[commands]]\nname = \"build\"\ncommand = \"cargo build\"\n\n[[environments]]\nname = \"dev\"\n"
+        );
+        assert!(theirs.contains("cargo build --release\"\n\n[[environments]]"));
+        assert!(!theirs.contains("make"));
+
+        assert!(conflict_sides("name = \"api\"\n").is_none());
+        assert!(conflict_sides("<<<<<<< HEAD\na\n=======\nb\n").is_none());
+        // A line of equal signs inside a multiline string is only a marker within a conflict
+        assert!(conflict_sides("command = \"\"\"\n=======\n\"\"\"\n").is_none());
+    }
+}
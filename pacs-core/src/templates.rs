@@ -0,0 +1,223 @@
+//! Built-in library of common commands that can be copied into a project,
+//! and project templates that seed a new project with commands and
+//! environments.
+
+#[cfg(feature = "fs-store")]
+use std::{fs, path::Path};
+
+#[cfg(feature = "search")]
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+#[cfg(feature = "fs-store")]
+use crate::PacsError;
+use crate::{Environment, PacsCommand};
+
+const TEMPLATES: &str = include_str!("templates/commands.toml");
+
+/// Built-in project templates by name. User templates in `templates/` take
+/// precedence over these.
+#[cfg(feature = "fs-store")]
+const PROJECT_TEMPLATES: &[(&str, &str)] = &[
+    ("node", include_str!("templates/node.toml")),
+    ("python", include_str!("templates/python.toml")),
+    ("rust", include_str!("templates/rust.toml")),
+];
+
+/// A built-in command with placeholders ready to fill.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Template {
+    pub name: String,
+    pub description: String,
+    pub command: String,
+    #[serde(default)]
+    pub tag: String,
+}
+
+impl Template {
+    /// Returns the template as a command, named `name` or after the template.
+    #[must_use]
+    pub fn to_command(&self, name: Option<&str>) -> PacsCommand {
+        PacsCommand {
+            name: name.unwrap_or(&self.name).to_string(),
+            command: self.command.clone(),
+            cwd: None,
+            tag: self.tag.clone(),
+            aliases: Vec::new(),
+            expires: None,
+            review_after: None,
+            author: None,
+            requires: Vec::new(),
+            local_only: false,
+            overrides: BTreeMap::new(),
+            safe: false,
+            confirm: false,
+            locked: false,
+            sandbox: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TemplateFile {
+    templates: Vec<Template>,
+}
+
+/// Returns all built-in templates, grouped by tag.
+#[must_use]
+pub fn templates() -> Vec<Template> {
+    toml::from_str::<TemplateFile>(TEMPLATES)
+        .map(|file| file.templates)
+        .unwrap_or_default()
+}
+
+/// Returns the templates matching `query` by name, tag, description or
+/// command, best match first.
+#[cfg(feature = "search")]
+#[must_use]
+pub fn search_templates(query: &str) -> Vec<Template> {
+    let matcher = SkimMatcherV2::default();
+
+    let mut results: Vec<(Template, i64)> = templates()
+        .into_iter()
+        .filter_map(|template| {
+            let score = [
+                &template.name,
+                &template.tag,
+                &template.description,
+                &template.command,
+            ]
+            .into_iter()
+            .filter_map(|field| matcher.fuzzy_match(field, query))
+            .max()
+            .unwrap_or(0);
+
+            (score > 0).then_some((template, score))
+        })
+        .collect();
+
+    results.sort_by_key(|&(_, score)| -score);
+    results.into_iter().map(|(template, _)| template).collect()
+}
+
+/// Commands and environments that seed a new project. Uses the format of
+/// project files, without a name.
+#[derive(Debug, Default, Deserialize)]
+pub struct ProjectTemplate {
+    #[serde(default)]
+    pub commands: Vec<PacsCommand>,
+    #[serde(default)]
+    pub environments: Vec<Environment>,
+    #[serde(default)]
+    pub active_environment: Option<String>,
+}
+
+/// Returns the names of the user templates in `dir` followed by the built-in ones.
+#[cfg(feature = "fs-store")]
+pub(crate) fn project_template_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+        })
+        .filter_map(|p| Some(p.file_stem()?.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    for (name, _) in PROJECT_TEMPLATES {
+        if !names.iter().any(|n| n == name) {
+            names.push((*name).to_string());
+        }
+    }
+    names
+}
+
+/// Loads a project template given as a path to a file, the name of a user
+/// template in `dir`, or the name of a built-in template.
+#[cfg(feature = "fs-store")]
+pub(crate) fn load_project_template(
+    dir: &Path,
+    template: &str,
+) -> Result<ProjectTemplate, PacsError> {
+    let file = Path::new(template);
+    let user = dir.join(format!("{template}.toml"));
+    let is_file = file
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+        && file.is_file();
+    let content = if is_file {
+        fs::read_to_string(file)?
+    } else if user.is_file() {
+        fs::read_to_string(user)?
+    } else {
+        PROJECT_TEMPLATES
+            .iter()
+            .find(|(name, _)| *name == template)
+            .map(|(_, content)| (*content).to_string())
+            .ok_or_else(|| PacsError::TemplateNotFound(template.to_string()))?
+    };
+    Ok(toml::from_str(&content)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates() {
+        let templates = templates();
+        assert!(templates.len() > 20);
+
+        let mut names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(
+            names.len(),
+            templates.len(),
+            "template names must be unique"
+        );
+
+        #[cfg(feature = "search")]
+        assert_eq!(search_templates("port-forward")[0].name, "k8s-port-forward");
+        let cmd = templates[0].to_command(Some("build"));
+        assert_eq!(cmd.name, "build");
+        assert_eq!(cmd.placeholders(), ["image", "version"]);
+    }
+
+    #[cfg(feature = "fs-store")]
+    #[test]
+    fn test_project_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, _) in PROJECT_TEMPLATES {
+            let template = load_project_template(dir.path(), name).unwrap();
+            assert!(!template.commands.is_empty());
+            assert!(
+                template
+                    .environments
+                    .iter()
+                    .any(|e| Some(&e.name) == template.active_environment.as_ref())
+            );
+        }
+
+        fs::write(
+            dir.path().join("rust.toml"),
+            "[[commands]]\nname = \"hello\"\ncommand = \"echo hello\"\n",
+        )
+        .unwrap();
+        let template = load_project_template(dir.path(), "rust").unwrap();
+        assert_eq!(template.commands[0].name, "hello");
+        assert_eq!(
+            project_template_names(dir.path()),
+            ["rust", "node", "python"]
+        );
+        assert!(matches!(
+            load_project_template(dir.path(), "cobol"),
+            Err(PacsError::TemplateNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,197 @@
+//! Serializable views of commands, projects and runs for integrations.
+//!
+//! The views are independent of the storage format: fields are only removed
+//! or change meaning together with [`VIEW_VERSION`].
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{PacsCommand, PacsError, Project, RunRecord};
+
+/// Version of the view schema, included in JSON output as `version`.
+pub const VIEW_VERSION: u32 = 1;
+
+/// Views tagged with the schema version they follow.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub version: u32,
+    pub data: T,
+}
+
+impl<T> Versioned<T> {
+    /// Tags `data` with the current [`VIEW_VERSION`].
+    pub fn new(data: T) -> Self {
+        Self {
+            version: VIEW_VERSION,
+            data,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Versioned<T> {
+    /// Parses JSON output of pacs, such as `pacs export -f json`. Fails for
+    /// versions newer than [`VIEW_VERSION`].
+    pub fn from_json(json: &str) -> Result<Self, PacsError> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        let Version { version } = serde_json::from_str(json)?;
+        if version > VIEW_VERSION {
+            return Err(PacsError::UnsupportedVersion {
+                found: version,
+                supported: VIEW_VERSION,
+            });
+        }
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// A command as seen by integrations.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandView {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub tag: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Placeholder keys the command needs values for.
+    #[serde(default)]
+    pub requires: Vec<String>,
+    #[serde(default)]
+    pub expires: Option<String>,
+    #[serde(default)]
+    pub review_after: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub local_only: bool,
+    /// True if the command asks for confirmation before every run.
+    #[serde(default)]
+    pub confirm: bool,
+    /// True if the command is protected from edits.
+    #[serde(default)]
+    pub locked: bool,
+}
+
+impl From<&PacsCommand> for CommandView {
+    fn from(cmd: &PacsCommand) -> Self {
+        Self {
+            name: cmd.name.clone(),
+            command: cmd.command.trim_end().to_string(),
+            cwd: cmd.cwd.clone(),
+            tag: cmd.tag.clone(),
+            aliases: cmd.aliases.clone(),
+            requires: cmd.required_keys(),
+            expires: cmd.expires.clone(),
+            review_after: cmd.review_after.clone(),
+            author: cmd.author.clone(),
+            local_only: cmd.local_only,
+            confirm: cmd.confirm,
+            locked: cmd.locked,
+        }
+    }
+}
+
+/// A project as seen by integrations. Environment values are left out, as
+/// they may hold secret references.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectView {
+    pub name: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Names of the environments.
+    #[serde(default)]
+    pub environments: Vec<String>,
+    #[serde(default)]
+    pub active_environment: Option<String>,
+    #[serde(default)]
+    pub commands: Vec<CommandView>,
+}
+
+impl From<&Project> for ProjectView {
+    fn from(project: &Project) -> Self {
+        Self {
+            name: project.name.clone(),
+            path: project.path.clone(),
+            environments: project
+                .environments
+                .iter()
+                .map(|e| e.name.clone())
+                .collect(),
+            active_environment: project.active_environment.clone(),
+            commands: project.commands.iter().map(CommandView::from).collect(),
+        }
+    }
+}
+
+/// A recorded run as seen by integrations.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunView {
+    pub command: String,
+    pub project: String,
+    #[serde(default)]
+    pub environment: Option<String>,
+    pub exit_code: i32,
+    /// Start time in seconds since the Unix epoch.
+    pub started_at: u64,
+    pub duration_ms: u64,
+}
+
+impl From<&RunRecord> for RunView {
+    fn from(record: &RunRecord) -> Self {
+        Self {
+            command: record.command.clone(),
+            project: record.project.clone(),
+            environment: record.environment.clone(),
+            exit_code: record.exit_code,
+            started_at: record.started_at,
+            duration_ms: record.duration_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_views() {
+        let project: Project = toml::from_str(
+            r#"
+            name = "api"
+            active_environment = "dev"
+
+            [[commands]]
+            name = "deploy"
+            command = "kubectl apply -n {{namespace}}\n"
+            aliases = ["d"]
+
+            [[environments]]
+            name = "dev"
+            values = { namespace = "secret-ns" }
+            "#,
+        )
+        .unwrap();
+
+        let view = ProjectView::from(&project);
+        assert_eq!(view.environments, ["dev"]);
+        assert_eq!(view.commands[0].command, "kubectl apply -n {{namespace}}");
+        assert_eq!(view.commands[0].requires, ["namespace"]);
+
+        let json = serde_json::to_string(&Versioned::new(&view)).unwrap();
+        assert!(json.starts_with(r#"{"version":1,"data":{"name":"api""#));
+        assert!(!json.contains("secret-ns"));
+        let parsed = Versioned::<ProjectView>::from_json(&json).unwrap();
+        assert_eq!(parsed.data, view);
+
+        let newer = json.replacen("\"version\":1", "\"version\":2", 1);
+        assert!(matches!(
+            Versioned::<ProjectView>::from_json(&newer),
+            Err(PacsError::UnsupportedVersion { found: 2, .. })
+        ));
+    }
+}
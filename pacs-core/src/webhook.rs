@@ -0,0 +1,85 @@
+//! Run reports posted to a project's webhook when a command finishes.
+
+use serde::Serialize;
+
+use crate::{PacsError, RunRecord, RunView, Versioned, http};
+
+/// Seconds to wait for the webhook before giving up.
+const WEBHOOK_TIMEOUT: u64 = 10;
+
+/// JSON posted to webhooks: the run as in `--json` output, plus a `text`
+/// summary for chat services like Slack.
+#[derive(Serialize)]
+struct Payload {
+    #[serde(flatten)]
+    run: Versioned<RunView>,
+    text: String,
+}
+
+/// A finished run ready to be posted to a webhook.
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    url: String,
+    payload: String,
+}
+
+impl Webhook {
+    pub(crate) fn new(url: &str, record: &RunRecord) -> Result<Self, PacsError> {
+        let environment = record
+            .environment
+            .as_ref()
+            .map(|env| format!(" in {env}"))
+            .unwrap_or_default();
+        #[allow(clippy::cast_precision_loss)]
+        let seconds = record.duration_ms as f64 / 1000.0;
+        let text = format!(
+            "{}/{}{environment} exited with {} after {seconds:.1}s",
+            record.project, record.command, record.exit_code
+        );
+        let payload = Payload {
+            run: Versioned::new(RunView::from(record)),
+            text,
+        };
+        Ok(Self {
+            url: url.to_string(),
+            payload: serde_json::to_string(&payload)?,
+        })
+    }
+
+    /// Posts the run report, waiting at most [`WEBHOOK_TIMEOUT`] seconds.
+    pub fn send(&self) -> Result<(), PacsError> {
+        let request = http::Request::get(&self.url)
+            .header("Content-Type: application/json")
+            .timeout(WEBHOOK_TIMEOUT)
+            .body("POST", self.payload.clone());
+        http::send(&request).map(drop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        let record = RunRecord {
+            command: "deploy".into(),
+            project: "api".into(),
+            environment: Some("prod".into()),
+            exit_code: 1,
+            started_at: 1_700_000_000,
+            duration_ms: 12_345,
+        };
+        let webhook = Webhook::new("https://hooks.example.com/x", &record).unwrap();
+        let payload: serde_json::Value = serde_json::from_str(&webhook.payload).unwrap();
+        assert_eq!(payload["version"], 1);
+        assert_eq!(payload["data"]["command"], "deploy");
+        assert_eq!(payload["data"]["environment"], "prod");
+        assert_eq!(payload["data"]["exit_code"], 1);
+        assert_eq!(payload["data"]["duration_ms"], 12_345);
+        assert_eq!(
+            payload["text"],
+            "api/deploy in prod exited with 1 after 12.3s"
+        );
+    }
+}
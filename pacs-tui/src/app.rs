@@ -1,10 +1,18 @@
 use crate::{
     client::PacsClient,
-    commands::{COMMANDS_LIST, Commands, CommandsPanel, CommandsState, CopyButtonState},
-    help,
+    commands::{
+        COMMANDS_DETAIL, COMMANDS_LIST, Commands, CommandsPanel, CommandsState, CopyButtonState,
+    },
+    components::selectable_text::Selections,
+    export::{self, ExportState},
+    help, notes,
+    output::{OutputPane, OutputState},
+    prompt::{self, PromptState},
     sidebar::{
         ENVIRONMENTS, Environments, EnvironmentsState, PROJECTS, Projects, ProjectsState, Sidebar,
+        project_span,
     },
+    stats, warnings,
 };
 use anyhow::Result;
 use ratatui::{
@@ -23,25 +31,52 @@ pub const GLOBAL: WidgetId = WidgetId("Global");
 /// Focus ring order for Tab navigation
 const FOCUS_RING: [WidgetId; 2] = [PROJECTS, COMMANDS_LIST];
 
+/// Backdrop shared by all overlays, closing the open overlay when clicked outside of it
+pub const BACKDROP: WidgetId = WidgetId("backdrop");
+
+/// Popup drawn on top of the main view. Only one can be open at a time.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Overlay {
+    Help,
+    Warnings,
+    Stats,
+    Notes,
+}
+
 #[derive(Default)]
 pub struct AppState {
     pub should_quit: bool,
-    pub help_open: bool,
+    pub overlay: Option<Overlay>,
     pub area: Rect,
 }
 
-pub fn setup_world(world: &mut World) -> Result<()> {
+pub fn setup_world(world: &mut World, safe_mode: bool, reveal_secrets: bool) -> Result<()> {
+    let mut client = PacsClient::new()?;
+    if safe_mode {
+        client.set_safe_mode();
+    }
+    if reveal_secrets {
+        client.set_reveal_secrets();
+    }
+    setup_world_with_client(world, client);
+    Ok(())
+}
+
+/// Sets up the world around the given client instead of the one in the home directory.
+pub fn setup_world_with_client(world: &mut World, client: PacsClient) {
     world.insert(Theme::default());
     world.insert(AppState::default());
     world.insert(Focus::new(PROJECTS));
-    let client = PacsClient::new()?;
     world.insert(ProjectsState::new(&client));
     world.insert(EnvironmentsState::new(&client));
     world.insert(CommandsState::new());
     world.insert(CopyButtonState::default());
+    world.insert(Selections::default());
+    world.insert(OutputState::default());
+    world.insert(PromptState::default());
+    world.insert(ExportState::default());
     world.insert(client);
 
-    global_keybindings(world);
     Projects::setup_keybindings(world);
     Projects::setup_pointer(world);
 
@@ -51,7 +86,14 @@ pub fn setup_world(world: &mut World) -> Result<()> {
     Commands::setup_keybindings(world);
     Commands::setup_pointer(world);
 
-    Ok(())
+    OutputPane::setup_keybindings(world);
+    OutputPane::setup_pointer(world);
+
+    prompt::setup_keybindings(world);
+    export::setup_keybindings(world);
+
+    // Bound last so widget bindings (e.g. Ctrl-C to copy a selection) take precedence
+    global_keybindings(world);
 }
 
 fn global_keybindings(world: &mut World) {
@@ -65,13 +107,17 @@ fn global_keybindings(world: &mut World) {
         help::toggle(world);
     });
 
+    kb.bind(GLOBAL, 's', "Stats", |world| {
+        stats::toggle(world);
+    });
+
     kb.bind(GLOBAL, KeyCode::Tab, "Next Focus", |world| {
         let focus = world.get_mut::<Focus>();
         if let Some(current) = focus.id {
-            let current = if current == ENVIRONMENTS {
-                PROJECTS
-            } else {
-                current
+            let current = match current {
+                ENVIRONMENTS => PROJECTS,
+                COMMANDS_DETAIL => COMMANDS_LIST,
+                _ => current,
             };
             if let Some(idx) = FOCUS_RING.iter().position(|&id| id == current) {
                 let next = (idx + 1) % FOCUS_RING.len();
@@ -87,8 +133,20 @@ pub fn render(frame: &mut Frame, world: &mut World) {
 
     render_main(world, frame, area);
 
-    if world.get::<AppState>().help_open {
-        help::render(world, frame, area);
+    if world.get::<PromptState>().is_open() {
+        prompt::render(world, frame, area);
+    }
+
+    if world.get::<ExportState>().is_open() {
+        export::render(world, frame, area);
+    }
+
+    match world.get::<AppState>().overlay {
+        Some(Overlay::Help) => help::render(world, frame, area),
+        Some(Overlay::Warnings) => warnings::render(world, frame, area),
+        Some(Overlay::Stats) => stats::render(world, frame, area),
+        Some(Overlay::Notes) => notes::render(world, frame, area),
+        None => {}
     }
 }
 
@@ -117,10 +175,21 @@ fn render_title(world: &mut World, frame: &mut ratatui::Frame, area: Rect) {
         Span::styled("Project Aware Command Storage", theme.text_muted),
     ]);
 
-    let right = Line::from(vec![Span::styled("? help ", theme.text_muted)]);
-
-    let [left_area, right_area] =
-        Layout::horizontal([Constraint::Min(0), Constraint::Length(8)]).areas(area);
+    // The active project, to see at a glance where commands run
+    let client = world.get::<PacsClient>();
+    let mut right = Vec::new();
+    if let Some(project) = client.active_project() {
+        right.push(project_span(client, &project, theme.text_accent));
+        right.push(Span::raw("  "));
+    }
+    right.push(Span::styled("? help ", theme.text_muted));
+    let right = Line::from(right);
+
+    let [left_area, right_area] = Layout::horizontal([
+        Constraint::Min(0),
+        Constraint::Length(u16::try_from(right.width()).unwrap_or(u16::MAX)),
+    ])
+    .areas(area);
 
     frame.render_widget(Paragraph::new(left), left_area);
     frame.render_widget(Paragraph::new(right), right_area);
@@ -1,8 +1,12 @@
 use std::collections::BTreeMap;
+use std::path::Path;
 
 use anyhow::Context;
 use anyhow::Result;
-use pacs_core::{Pacs, PacsCommand};
+use pacs_core::{
+    BodyCleanup, ExportFormat, HookEvent, LoadWarning, Pacs, PacsCommand, RunRecord, RunStats,
+    TagStyle, Webhook, unix_now,
+};
 
 pub struct PacsClient {
     pacs: Pacs,
@@ -11,11 +15,72 @@ pub struct PacsClient {
 impl PacsClient {
     pub fn new() -> Result<Self> {
         let pacs = Pacs::init_home().context("Failed to initialize pacs")?;
-        Ok(Self { pacs })
+        Ok(Self::from_pacs(pacs))
     }
 
+    /// Wraps an already initialized `Pacs`, e.g. one seeded for tests.
+    pub fn from_pacs(pacs: Pacs) -> Self {
+        Self { pacs }
+    }
+
+    /// Shows commands instead of running them.
+    pub fn set_safe_mode(&mut self) {
+        self.pacs.set_safe_mode();
+    }
+
+    pub fn is_safe_mode(&self) -> bool {
+        self.pacs.is_safe_mode()
+    }
+
+    /// Shows secret values instead of masking them.
+    pub fn set_reveal_secrets(&mut self) {
+        self.pacs.set_reveal_secrets();
+    }
+
+    /// Masks the secret values resolved so far in `text`.
+    pub fn redact(&self, text: &str) -> String {
+        self.pacs.redact(text)
+    }
+
+    /// Returns a command of the active project resolved for display, with
+    /// secret references left as they are.
+    pub fn resolved_command(&self, name: &str) -> Option<PacsCommand> {
+        self.pacs.resolve_command(name, None, None).ok()
+    }
+
+    /// Picks up projects and environments changed by another pacs process.
+    pub fn reload_if_changed(&mut self) {
+        self.pacs.reload_if_changed().ok();
+    }
+
+    /// Returns all project names apart from archived ones, followed by
+    /// projects whose files failed to load.
     pub fn list_projects(&self) -> Vec<String> {
-        self.pacs.projects.iter().map(|p| p.name.clone()).collect()
+        self.pacs
+            .projects()
+            .iter()
+            .filter(|p| !p.archived)
+            .map(|p| p.name.clone())
+            .chain(self.pacs.load_warnings().iter().map(|w| w.project.clone()))
+            .collect()
+    }
+
+    /// Returns the number of commands in a project, or `None` if it failed to load.
+    pub fn command_count(&self, project: &str) -> Option<usize> {
+        self.pacs
+            .projects()
+            .iter()
+            .find(|p| p.name == project)
+            .map(|p| p.commands.len())
+    }
+
+    pub fn load_warnings(&self) -> &[LoadWarning] {
+        self.pacs.load_warnings()
+    }
+
+    /// Returns why config.toml could not be loaded, if it could not.
+    pub fn config_error(&self) -> Option<&str> {
+        self.pacs.config_error()
     }
 
     pub fn list_environments(&self) -> Vec<String> {
@@ -30,6 +95,16 @@ impl PacsClient {
         self.pacs.get_active_project_name().ok()
     }
 
+    /// Returns the path of the active project.
+    pub fn project_path(&self) -> Option<String> {
+        self.pacs.get_active_project().ok()?.path.clone()
+    }
+
+    /// Returns the markdown notes of the active project.
+    pub fn project_notes(&self) -> Option<String> {
+        self.pacs.get_active_project().ok()?.notes.clone()
+    }
+
     pub fn active_environment(&self) -> Option<String> {
         self.pacs.get_active_environment(None).ok().flatten()
     }
@@ -49,26 +124,110 @@ impl PacsClient {
         let Ok(project) = self.pacs.get_active_project() else {
             return BTreeMap::new();
         };
-        let Some(active_env) = &project.active_environment else {
+        let Some(active_env) = self.active_environment() else {
             return BTreeMap::new();
         };
         project
             .environments
             .iter()
-            .find(|e| &e.name == active_env)
-            .map(|e| e.values.clone())
+            .find(|e| e.name == active_env)
+            .map(pacs_core::Environment::placeholder_values)
             .unwrap_or_default()
     }
 
+    /// Returns the values of the active environment with secret references resolved.
+    pub fn resolved_environment_values(&self) -> Result<BTreeMap<String, String>> {
+        Ok(self.pacs.resolved_environment_values(None, None)?)
+    }
+
+    /// Returns the names of the commands last run in the active project, most recent first.
+    pub fn recent_commands(&self) -> Vec<String> {
+        self.pacs
+            .recent_commands(None)
+            .map(|commands| commands.iter().map(|c| c.name.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the color and icon of a project.
+    pub fn project_style(&self, project: &str) -> TagStyle {
+        self.pacs
+            .projects()
+            .iter()
+            .find(|p| p.name == project)
+            .map(|p| TagStyle {
+                color: p.color.clone(),
+                icon: p.icon.clone(),
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the tag colors and icons from the config.
+    pub fn tag_styles(&self) -> BTreeMap<String, TagStyle> {
+        self.pacs.tag_styles()
+    }
+
     pub fn list_commands(&self) -> Vec<PacsCommand> {
         self.pacs.list(None, None).unwrap_or_default()
     }
 
+    /// Returns a command of the active project without expanding placeholders.
+    pub fn raw_command(&self, name: &str) -> Option<PacsCommand> {
+        self.pacs.get_command_auto(name).ok().cloned()
+    }
+
+    /// Writes the commands of the active project named in `names` to `path`,
+    /// in the format given by its extension. Returns the number of exported
+    /// commands, which leaves out local only ones.
+    pub fn export_commands(&self, path: &Path, names: &[String]) -> Result<usize> {
+        let format = ExportFormat::from_path(path)
+            .context("Unknown file type, use .toml, .json, .md, justfile or Makefile")?;
+        let (content, count) =
+            self.pacs
+                .export_selected(None, None, names, format, BodyCleanup::default())?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(count)
+    }
+
+    /// Checks a resolved command of the active project before it runs, like
+    /// `pacs run`. Returns the danger pattern it matches, if any.
+    pub fn check_run(&self, cmd: &PacsCommand) -> Result<Option<String>> {
+        Ok(self.pacs.check_run(cmd, None, None)?)
+    }
+
+    /// Runs the hooks for a command of the active project. `exit_code` is set
+    /// for post-run hooks.
+    pub fn run_hooks(&self, event: HookEvent, command: &str, exit_code: Option<i32>) -> Result<()> {
+        let mut ctx = self.pacs.hook_context(Some(command), None, None)?;
+        ctx.exit_code = exit_code;
+        self.pacs.run_hooks(event, &ctx)?;
+        Ok(())
+    }
+
+    pub fn record_run(&self, record: &RunRecord) -> Result<()> {
+        self.pacs.record_run(record)?;
+        Ok(())
+    }
+
+    pub fn webhook(&self, record: &RunRecord) -> Option<Webhook> {
+        self.pacs.webhook(record)
+    }
+
+    /// Returns statistics over the run history, with activity for the last `days` days.
+    pub fn run_stats(&self, days: usize) -> RunStats {
+        let history = self.pacs.history().unwrap_or_default();
+        RunStats::from_records(&history, unix_now(), days)
+    }
+
     pub fn copy_command(&self, name: &str) -> Result<String> {
         let cmd = self.pacs.copy(name, None, None)?;
         let command = cmd.command.trim().to_string();
-        cli_clipboard::set_contents(command.clone())
-            .map_err(|e| anyhow::anyhow!("Failed to copy to clipboard: {e}"))?;
+        self.copy_text(&command)?;
         Ok(command)
     }
+
+    pub fn copy_text(&self, text: &str) -> Result<()> {
+        cli_clipboard::set_contents(text.to_string())
+            .map_err(|e| anyhow::anyhow!("Failed to copy to clipboard: {e}"))
+    }
 }
@@ -3,13 +3,23 @@ use ratatui::widgets::ListState;
 use ratatui::{
     Frame,
     layout::{Constraint, Layout, Rect},
-    text::{Line, Span, Text},
+    style::{Color, Style},
+    text::{Line, Span},
     widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table},
 };
-use std::collections::BTreeMap;
-use tui_world::{Focus, Keybindings, Pointer, WidgetId, World, keys};
-
-use crate::{client::PacsClient, highlight::highlight_shell, theme::Theme};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tui_world::{Focus, KeyBinding, Keybindings, Pointer, WidgetId, World, keys};
+
+use crate::{
+    app::AppState,
+    client::PacsClient,
+    components::selectable_text::{SelectableText, Selections},
+    export,
+    highlight::highlight_shell,
+    output::{OutputPane, OutputState},
+    prompt,
+    theme::Theme,
+};
 
 pub const COMMANDS_LIST: WidgetId = WidgetId("Commands");
 pub const COMMANDS_DETAIL: WidgetId = WidgetId("CommandDetail");
@@ -31,12 +41,21 @@ impl CommandsPanel {
         let [main_area, bottom_area] =
             Layout::vertical([Constraint::Min(0), Constraint::Length(5)]).areas(inner_area);
 
+        let (main_area, output_area) = if world.get::<OutputState>().is_open() {
+            let [main_area, output_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Percentage(40)]).areas(main_area);
+            (main_area, output_area)
+        } else {
+            (main_area, Rect::default())
+        };
+
         let [commands_area, detail_area] =
             Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
                 .areas(main_area);
 
         Commands::render(world, frame, commands_area);
         CommandDetail::render(world, frame, detail_area);
+        OutputPane::render(world, frame, output_area);
         BottomPanel::render(world, frame, bottom_area);
     }
 }
@@ -47,6 +66,15 @@ pub struct CommandsState {
     pub num_rows: usize,
     /// Maps row index to command index (None for header rows)
     pub row_to_command: Vec<Option<usize>>,
+    /// Maps row index to tag name (Some for header rows)
+    pub row_to_tag: Vec<Option<String>>,
+    /// Maps row index to the command group of `db/` style header rows,
+    /// which run as a unit
+    pub row_to_group: Vec<Option<String>>,
+    /// Collapsed tags per project, remembered for the session
+    pub collapsed: HashMap<String, HashSet<String>>,
+    /// Shows what the programs and flags of the selected command do
+    pub explain: bool,
 }
 
 #[derive(Default)]
@@ -71,37 +99,65 @@ impl CommandsState {
         state.select(Some(0));
         Self {
             state,
-            num_rows: 0,
-            row_to_command: Vec::new(),
+            ..Self::default()
         }
     }
 
     pub fn ensure_valid_selection(&mut self) {
         if let Some(row) = self.state.selected()
-            && self.row_to_command.get(row).copied().flatten().is_none()
+            && row >= self.num_rows
         {
-            self.next();
+            self.state.select(Some(self.num_rows.saturating_sub(1)));
+        }
+    }
+
+    pub fn is_collapsed(&self, project: &str, tag: &str) -> bool {
+        self.collapsed
+            .get(project)
+            .is_some_and(|tags| tags.contains(tag))
+    }
+
+    /// Returns the names of the commands to export: those of the selected
+    /// group or tag header, or else all commands shown in the list.
+    pub fn export_selection(&self, commands: &[pacs_core::PacsCommand]) -> Vec<String> {
+        let row = self.state.selected().unwrap_or(0);
+        let group = self.row_to_group.get(row).cloned().flatten();
+        let tag = self.row_to_tag.get(row).cloned().flatten();
+        commands
+            .iter()
+            .enumerate()
+            .filter(|(idx, c)| match (&group, &tag) {
+                (Some(group), _) => c.name.starts_with(group.as_str()),
+                (None, Some(tag)) => &c.tag == tag,
+                (None, None) => self.row_to_command.contains(&Some(*idx)),
+            })
+            .map(|(_, c)| c.name.clone())
+            .collect()
+    }
+
+    /// Collapses or expands the tag group of the given header row.
+    /// Returns false if the row is not a header.
+    pub fn toggle_group(&mut self, project: &str, row: usize) -> bool {
+        let Some(Some(tag)) = self.row_to_tag.get(row) else {
+            return false;
+        };
+        let tags = self.collapsed.entry(project.to_string()).or_default();
+        if !tags.remove(tag) {
+            tags.insert(tag.clone());
         }
+        true
     }
 
     fn next(&mut self) {
         let current = self.state.selected().unwrap_or(0);
-        for i in (current + 1)..self.row_to_command.len() {
-            if self.row_to_command.get(i).copied().flatten().is_some() {
-                self.state.select(Some(i));
-                return;
-            }
+        if current + 1 < self.num_rows {
+            self.state.select(Some(current + 1));
         }
     }
 
     fn previous(&mut self) {
         let current = self.state.selected().unwrap_or(0);
-        for i in (0..current).rev() {
-            if self.row_to_command.get(i).copied().flatten().is_some() {
-                self.state.select(Some(i));
-                return;
-            }
-        }
+        self.state.select(Some(current.saturating_sub(1)));
     }
 }
 
@@ -119,6 +175,43 @@ impl Commands {
             world.get_mut::<CommandsState>().previous();
         });
 
+        kb.bind(COMMANDS_LIST, KeyCode::Enter, "Toggle Group", |world| {
+            let project = world
+                .get::<PacsClient>()
+                .active_project()
+                .unwrap_or_default();
+            let state = world.get_mut::<CommandsState>();
+            if let Some(row) = state.state.selected() {
+                state.toggle_group(&project, row);
+            }
+        });
+
+        kb.bind(COMMANDS_LIST, 'r', "Run", |world| {
+            let client = world.get::<PacsClient>();
+            let commands = client.list_commands();
+            let state = world.get::<CommandsState>();
+            if let Some(row) = state.state.selected()
+                && let Some(Some(group)) = state.row_to_group.get(row)
+            {
+                let group = group.clone();
+                OutputPane::start_group(world, &group);
+            } else if let Some(row) = state.state.selected()
+                && let Some(Some(cmd_idx)) = state.row_to_command.get(row)
+                && let Some(cmd) = commands.get(*cmd_idx)
+                && let Some(raw) = client.raw_command(&cmd.name)
+                && let Ok(env_values) = client.resolved_environment_values()
+            {
+                prompt::run(world, raw, &env_values);
+            }
+        });
+
+        kb.bind(COMMANDS_LIST, 'e', "Export", export::open);
+
+        kb.bind(COMMANDS_LIST, 'x', "Explain", |world| {
+            let state = world.get_mut::<CommandsState>();
+            state.explain = !state.explain;
+        });
+
         kb.bind(COMMANDS_LIST, 'c', "Copy", |world| {
             let commands = world.get::<PacsClient>().list_commands();
             let state = world.get::<CommandsState>();
@@ -130,6 +223,38 @@ impl Commands {
                 let _ = world.get_mut::<PacsClient>().copy_command(&cmd.name);
             }
         });
+
+        kb.bind(COMMANDS_DETAIL, 'y', "Copy Selection", |world| {
+            Commands::copy_selection(world);
+        });
+
+        kb.bind(
+            COMMANDS_DETAIL,
+            KeyBinding::ctrl('c'),
+            "Copy Selection",
+            |world| {
+                if !Commands::copy_selection(world) {
+                    world.get_mut::<AppState>().should_quit = true;
+                }
+            },
+        );
+
+        kb.bind(COMMANDS_DETAIL, KeyCode::Esc, "Back", |world| {
+            world.get_mut::<Selections>().clear(COMMANDS_DETAIL);
+            world.get_mut::<Focus>().set(COMMANDS_LIST);
+        });
+    }
+
+    /// Copies the selected detail text to the clipboard.
+    /// Returns false if nothing is selected.
+    fn copy_selection(world: &mut World) -> bool {
+        let Some(text) = world.get::<Selections>().selected_text(COMMANDS_DETAIL) else {
+            return false;
+        };
+        if world.get::<PacsClient>().copy_text(&text).is_ok() {
+            world.get_mut::<CopyButtonState>().click();
+        }
+        true
     }
 
     pub fn setup_pointer(world: &mut World) {
@@ -142,24 +267,31 @@ impl Commands {
                 }
 
                 let row = (y - area.y) as usize;
+                let project = world
+                    .get::<PacsClient>()
+                    .active_project()
+                    .unwrap_or_default();
                 let state = world.get_mut::<CommandsState>();
 
                 if row >= state.num_rows {
                     return;
                 }
 
-                // Skip if clicking on a header row
-                if state.row_to_command.get(row).copied().flatten().is_none() {
-                    return;
-                }
-
+                state.toggle_group(&project, row);
                 state.state.select(Some(row));
             });
 
         world
             .get_mut::<Pointer>()
-            .on_click(COMMANDS_DETAIL, |world, _, _x, _y| {
-                world.get_mut::<Focus>().set(COMMANDS_LIST);
+            .on_click(COMMANDS_DETAIL, |world, _, x, y| {
+                world.get_mut::<Focus>().set(COMMANDS_DETAIL);
+                world.get_mut::<Selections>().press(COMMANDS_DETAIL, x, y);
+            });
+
+        world
+            .get_mut::<Pointer>()
+            .on_drag(COMMANDS_DETAIL, |world, _, x, y| {
+                world.get_mut::<Selections>().drag(COMMANDS_DETAIL, x, y);
             });
 
         world
@@ -205,29 +337,50 @@ impl Commands {
             }
         }
 
-        let mut row_to_command: Vec<Option<usize>> = Vec::new();
-        let mut rows: Vec<(bool, String, usize)> = Vec::new();
+        let project = client.active_project().unwrap_or_default();
+        let commands_state = world.get::<CommandsState>();
 
-        for (cmd_idx, cmd) in &untagged {
-            rows.push((false, cmd.name.clone(), *cmd_idx));
-            row_to_command.push(Some(*cmd_idx));
-        }
+        let mut rows = CommandRows::default();
+        rows.push_recent(client.recent_commands(), &commands, theme.text_accent);
+        let is_collapsed = |group: &str| commands_state.is_collapsed(&project, group);
+        rows.push_commands(&untagged, &is_collapsed, theme.text_accent);
 
+        let tag_styles = client.tag_styles();
         for (tag, cmds) in &grouped {
-            rows.push((true, format!("[{tag}]"), 0));
-            row_to_command.push(None);
+            let tag_style = tag_styles.get(*tag);
+            let icon = tag_style
+                .and_then(|s| s.icon.as_deref())
+                .map_or_else(String::new, |icon| format!("{icon} "));
+            let style = tag_style
+                .and_then(|s| s.color.as_deref())
+                .and_then(|color| color.parse::<Color>().ok())
+                .map_or(theme.text_accent, |color| theme.text_accent.fg(color));
+
+            let collapsed = commands_state.is_collapsed(&project, tag);
+            let header = if collapsed {
+                format!("▸ {icon}[{tag}] ({})", cmds.len())
+            } else {
+                format!("▾ {icon}[{tag}]")
+            };
+            rows.push_header(header, tag, style);
 
-            for (cmd_idx, cmd) in cmds {
-                rows.push((false, cmd.name.clone(), *cmd_idx));
-                row_to_command.push(Some(*cmd_idx));
+            if !collapsed {
+                rows.push_commands(cmds, &is_collapsed, theme.text_accent);
             }
         }
+        let CommandRows {
+            rows,
+            row_to_command,
+            row_to_tag,
+            row_to_group,
+        } = rows;
 
         let num_rows = rows.len();
         let selected = world.get::<CommandsState>().state.selected();
 
+        let today = pacs_core::today();
         let buf = frame.buffer_mut();
-        for (i, (is_tag, text, _)) in rows.iter().enumerate() {
+        for (i, (header_style, text, cmd_idx)) in rows.iter().enumerate() {
             if i >= commands_area.height as usize {
                 break;
             }
@@ -235,8 +388,13 @@ impl Commands {
             let y = commands_area.y + i as u16;
             let is_selected = selected == Some(i);
 
-            if *is_tag {
-                let span = Span::styled(text.as_str(), theme.text_accent);
+            if let Some(header_style) = *header_style {
+                let style = if is_selected && is_focused {
+                    theme.selected.patch(header_style)
+                } else {
+                    header_style
+                };
+                let span = Span::styled(text.as_str(), style);
                 buf.set_span(commands_area.x, y, &span, commands_area.width);
             } else {
                 let (prefix, style) = if is_selected && is_focused {
@@ -246,10 +404,14 @@ impl Commands {
                 } else {
                     ("   ", theme.text)
                 };
-                let line = Line::from(vec![
+                let mut spans = vec![
                     Span::styled(prefix, style),
                     Span::styled(text.as_str(), style),
-                ]);
+                ];
+                if let Some(status) = commands[*cmd_idx].expiry_status(today) {
+                    spans.push(Span::styled(format!(" {}", status.label()), theme.warning));
+                }
+                let line = Line::from(spans);
                 buf.set_line(commands_area.x, y, &line, commands_area.width);
             }
         }
@@ -257,12 +419,104 @@ impl Commands {
         let state = world.get_mut::<CommandsState>();
         state.num_rows = num_rows;
         state.row_to_command = row_to_command;
+        state.row_to_tag = row_to_tag;
+        state.row_to_group = row_to_group;
         state.ensure_valid_selection();
 
         world.get_mut::<Pointer>().set(COMMANDS_LIST, commands_area);
     }
 }
 
+/// Rows of the commands list with their command or collapsible group.
+#[derive(Default)]
+struct CommandRows {
+    /// The style of header rows, the text and the command index.
+    rows: Vec<(Option<Style>, String, usize)>,
+    row_to_command: Vec<Option<usize>>,
+    row_to_tag: Vec<Option<String>>,
+    row_to_group: Vec<Option<String>>,
+}
+
+impl CommandRows {
+    /// Adds the recently run commands under a header, if there are any.
+    fn push_recent(
+        &mut self,
+        recent: Vec<String>,
+        commands: &[pacs_core::PacsCommand],
+        style: Style,
+    ) {
+        if recent.is_empty() {
+            return;
+        }
+        self.rows.push((Some(style), "  Recent".to_string(), 0));
+        self.row_to_command.push(None);
+        self.row_to_tag.push(None);
+        self.row_to_group.push(None);
+        for name in recent {
+            if let Some(idx) = commands.iter().position(|c| c.name == name) {
+                self.push_command(name, idx);
+            }
+        }
+    }
+
+    fn push_command(&mut self, text: String, cmd_idx: usize) {
+        self.rows.push((None, text, cmd_idx));
+        self.row_to_command.push(Some(cmd_idx));
+        self.row_to_tag.push(None);
+        self.row_to_group.push(None);
+    }
+
+    fn push_header(&mut self, text: String, group: &str, style: Style) {
+        self.rows.push((Some(style), text, 0));
+        self.row_to_command.push(None);
+        self.row_to_tag.push(Some(group.to_string()));
+        self.row_to_group.push(None);
+    }
+
+    /// Adds commands sorted by name, nesting names like `db/migrate` under a
+    /// collapsible `db/` header marked with `»`, which runs the group.
+    fn push_commands(
+        &mut self,
+        cmds: &[(usize, &pacs_core::PacsCommand)],
+        is_collapsed: &dyn Fn(&str) -> bool,
+        header_style: Style,
+    ) {
+        let mut open: Vec<&str> = Vec::new();
+        for (cmd_idx, cmd) in cmds {
+            let groups: Vec<&str> = cmd.groups().collect();
+            let shared = open.iter().zip(&groups).take_while(|(a, b)| a == b).count();
+            for (depth, group) in groups.iter().enumerate().skip(shared) {
+                if groups[..depth].iter().any(|g| is_collapsed(g)) {
+                    break;
+                }
+                let indent = "  ".repeat(depth);
+                let parent = depth.checked_sub(1).map_or(0, |d| groups[d].len());
+                let label = &group[parent..];
+                let header = if is_collapsed(group) {
+                    let count = cmds
+                        .iter()
+                        .filter(|(_, c)| c.name.starts_with(group))
+                        .count();
+                    format!("{indent}▸ » {label} ({count})")
+                } else {
+                    format!("{indent}▾ » {label}")
+                };
+                self.push_header(header, group, header_style);
+                if let Some(last) = self.row_to_group.last_mut() {
+                    *last = Some((*group).to_string());
+                }
+            }
+            open = groups;
+
+            if open.iter().any(|g| is_collapsed(g)) {
+                continue;
+            }
+            let indent = "  ".repeat(open.len());
+            self.push_command(format!("{indent}{}", cmd.leaf_name()), *cmd_idx);
+        }
+    }
+}
+
 pub struct CommandDetail;
 
 impl CommandDetail {
@@ -280,16 +534,37 @@ impl CommandDetail {
         let [content_area, button_area] =
             Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(inner);
 
-        let row_to_command = &world.get::<CommandsState>().row_to_command;
-        let cmd_idx = selected.and_then(|row| row_to_command.get(row).copied().flatten());
+        let state = world.get::<CommandsState>();
+        let cmd_idx = selected.and_then(|row| state.row_to_command.get(row).copied().flatten());
         let Some(cmd) = cmd_idx.and_then(|i| client.list_commands().get(i).cloned()) else {
+            if let Some(Some(group)) = selected.and_then(|row| state.row_to_group.get(row)) {
+                let steps = group_steps(&client.list_commands(), group, theme);
+                frame.render_widget(Paragraph::new(steps), inner);
+            }
             return;
         };
 
-        let lines = highlight_shell(&cmd.command, theme);
-        let content =
-            Paragraph::new(Text::from(lines)).wrap(ratatui::widgets::Wrap { trim: false });
-        frame.render_widget(content, content_area);
+        let mut lines = highlight_shell(&cmd.command, theme);
+        if let Some(author) = &cmd.author {
+            lines.push(Line::default());
+            lines.push(Line::styled(format!("by {author}"), theme.text_muted));
+        }
+
+        let content_area = if world.get::<CommandsState>().explain {
+            let explanation = explain_lines(&cmd.command, theme);
+            let height = u16::try_from(explanation.len() + 1)
+                .unwrap_or(u16::MAX)
+                .min(content_area.height / 2);
+            let [content_area, footer_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(height)])
+                    .areas(content_area);
+            let footer = Paragraph::new(explanation).block(theme.block().borders(Borders::TOP));
+            frame.render_widget(footer, footer_area);
+            content_area
+        } else {
+            content_area
+        };
+        let selection_style = theme.selected;
 
         // Copy button
         let (button_text, button_style, show_hint) = if button_active {
@@ -312,6 +587,14 @@ impl CommandDetail {
         let button = Paragraph::new(Line::from(button_spans)).block(button_block);
         frame.render_widget(button, button_area);
 
+        SelectableText::new(COMMANDS_DETAIL, lines)
+            .selection_style(selection_style)
+            .render(
+                content_area,
+                frame.buffer_mut(),
+                world.get_mut::<Selections>(),
+            );
+
         world
             .get_mut::<Pointer>()
             .set(COMMANDS_DETAIL, content_area);
@@ -319,6 +602,59 @@ impl CommandDetail {
     }
 }
 
+/// Lines listing the commands a group runs, in order.
+fn group_steps(
+    commands: &[pacs_core::PacsCommand],
+    group: &str,
+    theme: &Theme,
+) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::styled(
+            "Runs in order, stopping at the first failure:",
+            theme.text_muted,
+        ),
+        Line::default(),
+    ];
+    let steps = commands.iter().filter(|c| c.name.starts_with(group));
+    for (i, cmd) in steps.enumerate() {
+        lines.push(Line::from(vec![
+            Span::styled(format!("{}. {}", i + 1, cmd.name), theme.text_accent),
+            Span::styled(
+                format!("  {}", cmd.command.lines().next().unwrap_or_default()),
+                theme.text,
+            ),
+        ]));
+    }
+    lines
+}
+
+/// Lines saying what the programs of a command body and their known
+/// subcommands and flags do.
+fn explain_lines(command: &str, theme: &Theme) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    for segment in pacs_core::explain_command(command) {
+        let Some(program) = segment.program else {
+            continue;
+        };
+        let summary = match segment.summary {
+            Some(summary) => Span::styled(summary, theme.text),
+            None => Span::styled("not a known program", theme.text_muted),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(program.to_string(), theme.text_accent),
+            Span::raw("  "),
+            summary,
+        ]));
+        for (part, meaning) in segment.notes {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {part}"), theme.text_accent_alt),
+                Span::styled(format!("  {meaning}"), theme.text),
+            ]));
+        }
+    }
+    lines
+}
+
 pub struct BottomPanel;
 
 impl BottomPanel {
@@ -1 +1 @@
-// pub mod selectable_text;
+pub mod selectable_text;
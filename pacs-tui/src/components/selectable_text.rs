@@ -3,13 +3,11 @@ use ratatui::{
     layout::Rect,
     style::Style,
     text::{Line, Span},
-    widgets::{Paragraph, Widget},
 };
-
-use crate::{Pointer, WidgetId, World};
 use std::collections::HashMap;
+use tui_world::WidgetId;
 
-/// Stores selection state for all SelectableText widgets.
+/// Stores selection state for all `SelectableText` widgets.
 /// Add to World with `world.insert(Selections::default())`.
 #[derive(Default)]
 pub struct Selections {
@@ -18,44 +16,68 @@ pub struct Selections {
 
 #[derive(Default, Clone)]
 struct SelectionState {
-    start: Option<usize>,
-    end: Option<usize>,
+    /// Char offset where the selection started.
+    anchor: Option<usize>,
+    /// Char offset where the selection currently ends.
+    cursor: Option<usize>,
     area: Rect,
-    registered: bool,
+    /// The full text as chars, as rendered last frame.
+    text: Vec<char>,
+    /// Char offset and length of each visual (wrapped) row.
+    rows: Vec<(usize, usize)>,
 }
 
 impl SelectionState {
     fn selection(&self) -> Option<(usize, usize)> {
-        let (s, e) = (self.start?, self.end?);
+        let (s, e) = (self.anchor?, self.cursor?);
         if s == e {
             return None;
         }
         Some((s.min(e), s.max(e)))
     }
 
-    fn coords_to_index(&self, x: u16, y: u16) -> usize {
-        if self.area.width == 0 {
-            return 0;
-        }
-        let rel_x = x.saturating_sub(self.area.x) as usize;
+    fn coords_to_offset(&self, x: u16, y: u16) -> usize {
         let rel_y = y.saturating_sub(self.area.y) as usize;
-        rel_y * self.area.width as usize + rel_x
+        let rel_x = x.saturating_sub(self.area.x) as usize;
+        match self.rows.get(rel_y) {
+            Some(&(start, len)) => start + rel_x.min(len),
+            None => self.text.len(),
+        }
     }
 }
 
 impl Selections {
+    /// Starts a new selection at the given screen position.
+    pub fn press(&mut self, id: WidgetId, x: u16, y: u16) {
+        if let Some(state) = self.states.get_mut(&id) {
+            let offset = state.coords_to_offset(x, y);
+            state.anchor = Some(offset);
+            state.cursor = Some(offset);
+        }
+    }
+
+    /// Extends the current selection to the given screen position.
+    pub fn drag(&mut self, id: WidgetId, x: u16, y: u16) {
+        if let Some(state) = self.states.get_mut(&id)
+            && state.anchor.is_some()
+        {
+            state.cursor = Some(state.coords_to_offset(x, y));
+        }
+    }
+
     /// Get the selected text for a widget.
-    pub fn selected_text<'a>(&self, id: WidgetId, text: &'a str) -> Option<&'a str> {
+    pub fn selected_text(&self, id: WidgetId) -> Option<String> {
         let state = self.states.get(&id)?;
         let (start, end) = state.selection()?;
-        text.get(start..end.min(text.len()))
+        let end = end.min(state.text.len());
+        Some(state.text.get(start..end)?.iter().collect())
     }
 
     /// Clear selection for a widget.
     pub fn clear(&mut self, id: WidgetId) {
         if let Some(state) = self.states.get_mut(&id) {
-            state.start = None;
-            state.end = None;
+            state.anchor = None;
+            state.cursor = None;
         }
     }
 
@@ -64,7 +86,11 @@ impl Selections {
     }
 }
 
-/// A selectable text widget.
+/// A selectable, wrapping text widget.
+///
+/// The widget renders pre-styled lines and highlights the current selection
+/// on top of them. Pointer handling is left to the caller, which forwards
+/// mouse events via [`Selections::press`] and [`Selections::drag`].
 ///
 /// # Example
 ///
@@ -73,104 +99,94 @@ impl Selections {
 /// world.insert(Selections::default());
 ///
 /// // In render:
-/// SelectableText::new(TEXT_ID, "Click and drag to select!")
-///     .style(Style::default().fg(Color::White))
+/// SelectableText::new(TEXT_ID, lines)
 ///     .selection_style(Style::default().bg(Color::Blue))
-///     .render(area, frame.buffer_mut(), &mut world);
+///     .render(area, frame.buffer_mut(), world.get_mut::<Selections>());
 ///
 /// // Get selected text:
-/// let selected = world.get::<Selections>().selected_text(TEXT_ID, "...");
+/// let selected = world.get::<Selections>().selected_text(TEXT_ID);
 /// ```
 pub struct SelectableText<'a> {
     id: WidgetId,
-    text: &'a str,
-    style: Style,
+    lines: Vec<Line<'a>>,
     selection_style: Style,
 }
 
 impl<'a> SelectableText<'a> {
-    pub fn new(id: WidgetId, text: &'a str) -> Self {
+    pub fn new(id: WidgetId, lines: Vec<Line<'a>>) -> Self {
         Self {
             id,
-            text,
-            style: Style::default(),
+            lines,
             selection_style: Style::default(),
         }
     }
 
-    pub fn style(mut self, style: Style) -> Self {
-        self.style = style;
-        self
-    }
-
+    #[must_use]
     pub fn selection_style(mut self, style: Style) -> Self {
         self.selection_style = style;
         self
     }
 
-    /// Render the widget. Automatically registers pointer handlers.
-    pub fn render(self, area: Rect, buf: &mut Buffer, world: &mut World) {
-        // Get pending registration info first
-        let needs_register = {
-            let selections = world.get_mut::<Selections>();
-            let state = selections.states.entry(self.id).or_default();
-            let needs = !state.registered;
-            if needs {
-                state.registered = true;
-            }
-            state.area = area;
-            needs
-        };
-
-        // Register handlers if needed
-        if needs_register {
-            let id = self.id;
-            let pointer = world.get_mut::<Pointer>();
-
-            pointer.on_down(id, move |w, x, y| {
-                if let Some(state) = w.get_mut::<Selections>().states.get_mut(&id) {
-                    let idx = state.coords_to_index(x, y);
-                    state.start = Some(idx);
-                    state.end = Some(idx);
-                }
-            });
+    /// Render the widget and record its layout for hit testing.
+    pub fn render(self, area: Rect, buf: &mut Buffer, selections: &mut Selections) {
+        let width = area.width.max(1) as usize;
+
+        // Flatten the styled lines into chars with their style
+        let mut text: Vec<char> = Vec::new();
+        let mut styles: Vec<Style> = Vec::new();
+        let mut rows: Vec<(usize, usize)> = Vec::new();
 
-            pointer.on_drag(id, move |w, x, y| {
-                if let Some(state) = w.get_mut::<Selections>().states.get_mut(&id) {
-                    if state.start.is_some() {
-                        state.end = Some(state.coords_to_index(x, y));
-                    }
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                text.push('\n');
+                styles.push(Style::default());
+            }
+            let line_start = text.len();
+            for span in &line.spans {
+                let style = line.style.patch(span.style);
+                for c in span.content.chars() {
+                    text.push(c);
+                    styles.push(style);
                 }
-            });
+            }
+            let line_len = text.len() - line_start;
+            if line_len == 0 {
+                rows.push((line_start, 0));
+            }
+            let mut offset = 0;
+            while offset < line_len {
+                let len = width.min(line_len - offset);
+                rows.push((line_start + offset, len));
+                offset += len;
+            }
         }
 
-        // Set hit area
-        world.get_mut::<Pointer>().set(
-            self.id,
-            crate::Area::new(area.x, area.y, area.width, area.height),
-        );
-
-        // Render with selection highlighting
-        let selection = world.get::<Selections>().get_selection(self.id);
-        let text_len = self.text.len();
-
-        let spans: Vec<Span> = self
-            .text
-            .chars()
-            .enumerate()
-            .map(|(i, c)| {
-                let is_selected = selection
-                    .map(|(s, e)| i >= s && i < e.min(text_len))
-                    .unwrap_or(false);
-                let style = if is_selected {
-                    self.selection_style
-                } else {
-                    self.style
-                };
-                Span::styled(c.to_string(), style)
-            })
-            .collect();
-
-        Paragraph::new(Line::from(spans)).render(area, buf);
+        let state = selections.states.entry(self.id).or_default();
+        if state.text != text {
+            state.anchor = None;
+            state.cursor = None;
+        }
+        state.area = area;
+        state.text = text;
+        state.rows.clone_from(&rows);
+        let selection = state.selection();
+
+        for (row, &(start, len)) in rows.iter().enumerate().take(area.height as usize) {
+            let spans: Vec<Span> = (start..start + len)
+                .map(|i| {
+                    let is_selected = selection.is_some_and(|(s, e)| i >= s && i < e);
+                    let style = if is_selected {
+                        styles[i].patch(self.selection_style)
+                    } else {
+                        styles[i]
+                    };
+                    Span::styled(state.text[i].to_string(), style)
+                })
+                .collect();
+
+            #[allow(clippy::cast_possible_truncation)]
+            let y = area.y + row as u16;
+            buf.set_line(area.x, y, &Line::from(spans), area.width);
+        }
     }
 }
@@ -0,0 +1,168 @@
+use std::path::Path;
+
+use crate::app::AppState;
+use crate::client::PacsClient;
+use crate::commands::{COMMANDS_LIST, CommandsState};
+use crate::theme::Theme;
+use crate::util::center_rect;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyModifiers},
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use tui_world::prelude::*;
+
+pub const EXPORT: WidgetId = WidgetId("Export");
+const BACKDROP: WidgetId = WidgetId("export_backdrop");
+
+/// Dialog asking for the file the selected commands are exported to: those
+/// of the selected group or tag header, or else all shown in the list.
+#[derive(Default)]
+pub struct ExportState {
+    pub path: Option<String>,
+    pub names: Vec<String>,
+    pub error: Option<String>,
+    /// The number of commands written by the last export.
+    pub exported: Option<usize>,
+}
+
+impl ExportState {
+    pub fn is_open(&self) -> bool {
+        self.path.is_some()
+    }
+}
+
+pub fn open(world: &mut World) {
+    let client = world.get::<PacsClient>();
+    let Some(project) = client.active_project() else {
+        return;
+    };
+    let names = world
+        .get::<CommandsState>()
+        .export_selection(&client.list_commands());
+
+    let state = world.get_mut::<ExportState>();
+    state.path = Some(format!("{project}.toml"));
+    state.names = names;
+    state.error = None;
+    state.exported = None;
+    world.get_mut::<Focus>().set(EXPORT);
+
+    let area = world.get::<AppState>().area;
+    let dialog_area = center_rect(area, 60, 6);
+
+    world.get_mut::<Pointer>().set(BACKDROP, area);
+    world
+        .get_mut::<Pointer>()
+        .on_click(BACKDROP, move |world, _, x, y| {
+            if !dialog_area.contains((x, y).into()) {
+                close(world);
+            }
+        });
+}
+
+pub fn close(world: &mut World) {
+    *world.get_mut::<ExportState>() = ExportState::default();
+    world.get_mut::<Focus>().set(COMMANDS_LIST);
+    world.get_mut::<Pointer>().remove(BACKDROP);
+}
+
+fn submit(world: &mut World) {
+    let state = world.get::<ExportState>();
+    let Some(path) = state.path.clone() else {
+        return;
+    };
+    let result = world
+        .get::<PacsClient>()
+        .export_commands(Path::new(&path), &state.names);
+    let state = world.get_mut::<ExportState>();
+    match result {
+        Ok(count) => {
+            state.exported = Some(count);
+            state.error = None;
+        }
+        Err(e) => state.error = Some(format!("{e:#}")),
+    }
+}
+
+pub fn setup_keybindings(world: &mut World) {
+    let kb = world.get_mut::<Keybindings>();
+
+    kb.bind(EXPORT, KeyCode::Enter, "Export", submit);
+
+    kb.bind(EXPORT, KeyCode::Esc, "Close", close);
+
+    kb.bind(EXPORT, KeyCode::Backspace, "Delete", |world| {
+        let state = world.get_mut::<ExportState>();
+        state.exported = None;
+        if let Some(path) = &mut state.path {
+            path.pop();
+        }
+    });
+
+    kb.bind_any(EXPORT, |world, key| {
+        let KeyCode::Char(c) = key.code else {
+            return;
+        };
+        if key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return;
+        }
+        let state = world.get_mut::<ExportState>();
+        state.exported = None;
+        if let Some(path) = &mut state.path {
+            path.push(c);
+        }
+    });
+}
+
+pub fn render(world: &World, frame: &mut Frame, area: Rect) {
+    let theme = world.get::<Theme>();
+    let state = world.get::<ExportState>();
+    let Some(path) = &state.path else {
+        return;
+    };
+    let dialog_area = center_rect(area, 60, 6);
+
+    frame.render_widget(Clear, dialog_area);
+
+    let title = match state.names.len() {
+        1 => " Export 1 Command ".to_string(),
+        count => format!(" Export {count} Commands "),
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(theme.border_focused);
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let status = match (&state.error, state.exported) {
+        (Some(error), _) => Line::from(Span::styled(format!(" {error}"), theme.text_accent_alt)),
+        (None, Some(count)) => Line::from(Span::styled(
+            format!(" Exported {count} commands, local only ones are left out"),
+            theme.success,
+        )),
+        (None, None) => Line::from(""),
+    };
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(" File ", theme.keybinding_key),
+            Span::styled(path.clone(), theme.text),
+            Span::styled("▏", theme.text_accent),
+        ]),
+        status,
+        Line::from(Span::styled(
+            " .toml .json .md justfile Makefile · Enter export · Esc close",
+            theme.text_muted,
+        )),
+    ];
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
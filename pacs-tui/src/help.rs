@@ -1,19 +1,17 @@
-use crate::app::{AppState, GLOBAL};
+use crate::app::{AppState, BACKDROP, GLOBAL, Overlay};
 use crate::theme::Theme;
-use crate::util::get_active_ids;
+use crate::util::{center_rect, get_active_ids};
 use ratatui::{
     Frame,
-    layout::{Constraint, Rect},
+    layout::Rect,
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph},
 };
 use std::collections::BTreeMap;
 use tui_world::prelude::*;
 
-const BACKDROP: WidgetId = WidgetId("backdrop");
-
 pub fn toggle(world: &mut World) {
-    let is_open = world.get::<AppState>().help_open;
+    let is_open = world.get::<AppState>().overlay == Some(Overlay::Help);
     if is_open {
         close(world);
     } else {
@@ -22,7 +20,7 @@ pub fn toggle(world: &mut World) {
 }
 
 pub fn open(world: &mut World) {
-    world.get_mut::<AppState>().help_open = true;
+    world.get_mut::<AppState>().overlay = Some(Overlay::Help);
 
     let area = world.get::<AppState>().area;
     let dialog_area = center_rect(area, 40, 15);
@@ -38,7 +36,7 @@ pub fn open(world: &mut World) {
 }
 
 pub fn close(world: &mut World) {
-    world.get_mut::<AppState>().help_open = false;
+    world.get_mut::<AppState>().overlay = None;
     world.get_mut::<Pointer>().remove(BACKDROP);
 }
 
@@ -111,24 +109,3 @@ pub fn render(world: &World, frame: &mut Frame, area: Rect) {
 
     frame.render_widget(Paragraph::new(lines), inner);
 }
-
-fn center_rect(area: Rect, width: u16, height: u16) -> Rect {
-    let width = width.min(area.width.saturating_sub(4));
-    let height = height.min(area.height.saturating_sub(4));
-
-    let [_, h_center, _] = ratatui::layout::Layout::horizontal([
-        Constraint::Fill(1),
-        Constraint::Length(width),
-        Constraint::Fill(1),
-    ])
-    .areas(area);
-
-    let [_, dialog, _] = ratatui::layout::Layout::vertical([
-        Constraint::Fill(1),
-        Constraint::Length(height),
-        Constraint::Fill(1),
-    ])
-    .areas(h_center);
-
-    dialog
-}
@@ -5,36 +5,52 @@ pub mod app;
 pub mod client;
 pub mod commands;
 pub mod components;
+pub mod export;
 pub mod help;
 pub mod highlight;
+pub mod notes;
+pub mod output;
+pub mod prompt;
 pub mod sidebar;
+pub mod stats;
 pub mod theme;
 pub mod util;
+pub mod warnings;
 
 use ratatui::crossterm::{
     event::{self, Event as CEvent},
     execute,
 };
+use std::time::{Duration, Instant};
 use tui_world::{InputEvent, World};
 
-use crate::{app::setup_world, util::get_active_ids};
+use crate::{app::setup_world, client::PacsClient, util::get_active_ids};
+
+/// How often to check for changes made by other pacs processes.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Run the terminal user interface.
 ///
 /// # Errors
 ///
 /// Returns an error if terminal initialization fails or if there's an I/O error.
-pub fn run() -> anyhow::Result<()> {
+pub fn run(safe_mode: bool, reveal_secrets: bool) -> anyhow::Result<()> {
     let mut terminal = ratatui::init();
     execute!(std::io::stdout(), event::EnableMouseCapture)?;
 
     let mut world = World::default();
-    setup_world(&mut world)?;
+    setup_world(&mut world, safe_mode, reveal_secrets)?;
 
+    let mut last_reload = Instant::now();
     loop {
+        if last_reload.elapsed() >= RELOAD_INTERVAL {
+            world.get_mut::<PacsClient>().reload_if_changed();
+            last_reload = Instant::now();
+        }
+
         terminal.draw(|frame| app::render(frame, &mut world))?;
 
-        if event::poll(std::time::Duration::from_millis(16))? {
+        if event::poll(Duration::from_millis(16))? {
             let active = get_active_ids(&world);
 
             match event::read()? {
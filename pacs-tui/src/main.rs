@@ -1,3 +1,3 @@
 fn main() -> anyhow::Result<()> {
-    pacs_tui::run()
+    pacs_tui::run(false, false)
 }
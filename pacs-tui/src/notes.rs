@@ -0,0 +1,106 @@
+use crate::app::{AppState, BACKDROP, Overlay};
+use crate::client::PacsClient;
+use crate::theme::Theme;
+use crate::util::center_rect;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use tui_world::prelude::*;
+
+pub fn toggle(world: &mut World) {
+    let is_open = world.get::<AppState>().overlay == Some(Overlay::Notes);
+    if is_open {
+        close(world);
+    } else {
+        open(world);
+    }
+}
+
+pub fn open(world: &mut World) {
+    world.get_mut::<AppState>().overlay = Some(Overlay::Notes);
+
+    let area = world.get::<AppState>().area;
+    let dialog_area = center_rect(area, 70, 20);
+
+    world.get_mut::<Pointer>().set(BACKDROP, area);
+    world
+        .get_mut::<Pointer>()
+        .on_click(BACKDROP, move |world, _, x, y| {
+            if !dialog_area.contains((x, y).into()) {
+                close(world);
+            }
+        });
+}
+
+pub fn close(world: &mut World) {
+    world.get_mut::<AppState>().overlay = None;
+    world.get_mut::<Pointer>().remove(BACKDROP);
+}
+
+pub fn render(world: &World, frame: &mut Frame, area: Rect) {
+    let theme = world.get::<Theme>();
+    let client = world.get::<PacsClient>();
+    let dialog_area = center_rect(area, 70, 20);
+
+    frame.render_widget(Clear, dialog_area);
+
+    let project = client.active_project().unwrap_or_default();
+    let block = Block::default()
+        .title(format!(" {project} "))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let mut lines: Vec<Line> = Vec::new();
+    if let Some(path) = client.project_path() {
+        lines.push(Line::from(Span::styled(path, theme.text_muted)));
+        lines.push(Line::from(""));
+    }
+
+    match client.project_notes() {
+        Some(notes) => lines.extend(notes.lines().map(|line| markdown_line(line, theme))),
+        None => lines.push(Line::from(Span::styled(
+            "No notes. Add some with 'pacs project notes edit'.",
+            theme.text_muted,
+        ))),
+    }
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+/// Renders headings, bullets and `code` spans of a markdown line.
+fn markdown_line(line: &str, theme: &Theme) -> Line<'static> {
+    let heading = line.trim_start_matches('#');
+    if heading.len() < line.len() && (heading.is_empty() || heading.starts_with(' ')) {
+        return Line::from(Span::styled(heading.trim().to_string(), theme.text_accent));
+    }
+
+    let indent = line.len() - line.trim_start().len();
+    let mut spans = Vec::new();
+    let mut rest = line.trim_start();
+    if let Some(item) = rest.strip_prefix("- ").or_else(|| rest.strip_prefix("* ")) {
+        spans.push(Span::raw(" ".repeat(indent)));
+        spans.push(Span::styled("• ", theme.text_accent_alt));
+        rest = item;
+    } else {
+        rest = line;
+    }
+
+    for (i, part) in rest.split('`').enumerate() {
+        let style: Style = if i % 2 == 1 {
+            theme.text_muted
+        } else {
+            theme.text
+        };
+        if !part.is_empty() {
+            spans.push(Span::styled(part.to_string(), style));
+        }
+    }
+    Line::from(spans)
+}
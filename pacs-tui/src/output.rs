@@ -0,0 +1,484 @@
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use pacs_core::{HookEvent, PacsCommand, RunRecord, render_placeholders, unix_now};
+use ratatui::{
+    Frame,
+    crossterm::event::KeyCode,
+    layout::Rect,
+    text::{Line, Span, Text},
+    widgets::{Borders, Paragraph, Wrap},
+};
+use tui_world::{Focus, KeyBinding, Keybindings, Pointer, WidgetId, World};
+
+use crate::{app::AppState, client::PacsClient, commands::COMMANDS_LIST, theme::Theme};
+
+pub const OUTPUT: WidgetId = WidgetId("Output");
+
+/// Time a job gets to exit after SIGTERM before it is killed.
+const KILL_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A command running in the background whose output is shown in the output pane.
+pub struct Job {
+    pub name: String,
+    pub project: String,
+    pub environment: Option<String>,
+    pub started_at: Instant,
+    started_at_unix: u64,
+    pub status: Option<ExitStatus>,
+    /// When the job was asked to stop, if it was cancelled.
+    pub stopping_since: Option<Instant>,
+    child: Child,
+    lines: Arc<Mutex<Vec<String>>>,
+}
+
+impl Job {
+    /// Spawns the command with its stdout and stderr captured.
+    ///
+    /// The child runs in its own process group so that cancelling the job
+    /// also stops any processes spawned by the shell.
+    pub fn spawn(cmd: &PacsCommand, project: String, environment: Option<String>) -> Result<Self> {
+        let mut command = cmd.shell_command()?;
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        if let Some(stdout) = child.stdout.take() {
+            Self::collect(stdout, Arc::clone(&lines));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            Self::collect(stderr, Arc::clone(&lines));
+        }
+
+        Ok(Self {
+            name: cmd.name.clone(),
+            project,
+            environment,
+            started_at: Instant::now(),
+            started_at_unix: unix_now(),
+            status: None,
+            stopping_since: None,
+            child,
+            lines,
+        })
+    }
+
+    fn collect(reader: impl Read + Send + 'static, lines: Arc<Mutex<Vec<String>>>) {
+        std::thread::spawn(move || {
+            for line in BufReader::new(reader).lines().map_while(Result::ok) {
+                if let Ok(mut lines) = lines.lock() {
+                    lines.push(line);
+                }
+            }
+        });
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.status.is_none()
+    }
+
+    /// Checks whether the process has exited and stores its status.
+    /// Returns true if the job finished during this call.
+    ///
+    /// A cancelled job that is still alive after [`KILL_TIMEOUT`] is killed.
+    pub fn poll(&mut self) -> bool {
+        if self.status.is_some() {
+            return false;
+        }
+        if let Ok(Some(status)) = self.child.try_wait() {
+            self.status = Some(status);
+            return true;
+        }
+        if self
+            .stopping_since
+            .is_some_and(|since| since.elapsed() >= KILL_TIMEOUT)
+        {
+            self.signal(Signal::Kill);
+        }
+        false
+    }
+
+    /// Asks the job to stop. It is killed if it doesn't exit in time.
+    pub fn cancel(&mut self) {
+        if self.is_running() && self.stopping_since.is_none() {
+            self.stopping_since = Some(Instant::now());
+            self.signal(Signal::Terminate);
+        }
+    }
+
+    #[cfg(unix)]
+    fn signal(&mut self, signal: Signal) {
+        let sig = match signal {
+            Signal::Terminate => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+        };
+        if let Ok(pgid) = libc::pid_t::try_from(self.child.id()) {
+            // SAFETY: `killpg` has no memory safety requirements. The process
+            // group was created by `spawn` and the child has not been reaped.
+            unsafe {
+                libc::killpg(pgid, sig);
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn signal(&mut self, _signal: Signal) {
+        let _ = self.child.kill();
+    }
+
+    /// Returns the history record of a finished job.
+    pub fn record(&self) -> Option<RunRecord> {
+        let status = self.status?;
+        Some(RunRecord {
+            command: self.name.clone(),
+            project: self.project.clone(),
+            environment: self.environment.clone(),
+            exit_code: status.code().unwrap_or(-1),
+            started_at: self.started_at_unix,
+            duration_ms: u64::try_from(self.started_at.elapsed().as_millis()).unwrap_or(u64::MAX),
+        })
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().map(|l| l.clone()).unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Signal {
+    Terminate,
+    Kill,
+}
+
+/// A command group run as a unit, one step per command.
+pub struct GroupRun {
+    pub group: String,
+    /// The commands with their placeholders resolved, in order.
+    steps: Vec<PacsCommand>,
+    /// Exit codes of the finished steps, `None` for a step that did not start.
+    results: Vec<Option<i32>>,
+}
+
+impl GroupRun {
+    fn next_step(&self) -> Option<&PacsCommand> {
+        self.steps.get(self.results.len())
+    }
+
+    /// One line per step: done, failed, running or still to come.
+    fn checklist(&self, running: bool, theme: &Theme) -> Vec<Line<'static>> {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                let (mark, style, detail) = match self.results.get(i) {
+                    Some(Some(0)) => ("✓", theme.success, String::new()),
+                    Some(Some(code)) => ("✗", theme.text_accent_alt, format!("  exit {code}")),
+                    Some(None) => ("✗", theme.text_accent_alt, "  not started".to_string()),
+                    None if running && i == self.results.len() => {
+                        ("▸", theme.text_accent, String::new())
+                    }
+                    None => ("·", theme.text_muted, String::new()),
+                };
+                Line::from(vec![
+                    Span::styled(format!("{mark} {}", step.name), style),
+                    Span::styled(detail, theme.text_muted),
+                ])
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub struct OutputState {
+    pub job: Option<Job>,
+    /// Why the last command did not start, e.g. because a hook blocked it.
+    pub notice: Option<String>,
+    /// The group the job is a step of.
+    pub group: Option<GroupRun>,
+}
+
+impl OutputState {
+    pub fn is_running(&self) -> bool {
+        self.job.as_ref().is_some_and(Job::is_running)
+    }
+
+    /// Returns true if the pane has a job or a notice to show.
+    pub fn is_open(&self) -> bool {
+        self.job.is_some() || self.notice.is_some()
+    }
+}
+
+pub struct OutputPane;
+
+impl OutputPane {
+    /// Starts a command in the output pane, unless another one is still running.
+    /// A command matching a danger pattern only starts once `confirmed`.
+    pub fn start(world: &mut World, cmd: &PacsCommand, confirmed: bool) {
+        if world.get::<OutputState>().is_running() {
+            return;
+        }
+
+        world.get_mut::<OutputState>().group = None;
+        if Self::show_in_safe_mode(world, std::slice::from_ref(cmd)) {
+            return;
+        }
+        Self::launch(world, cmd, confirmed);
+    }
+
+    /// Runs the commands of a group one after another, stopping at the first
+    /// that fails. Groups with a step that would ask for values, a working
+    /// directory or confirmation, also for a danger pattern, are not started.
+    pub fn start_group(world: &mut World, group: &str) {
+        if world.get::<OutputState>().is_running() {
+            return;
+        }
+
+        let client = world.get::<PacsClient>();
+        let env_values = client.resolved_environment_values().unwrap_or_default();
+        let mut steps = Vec::new();
+        let mut refused = None;
+        for name in client
+            .list_commands()
+            .into_iter()
+            .map(|c| c.name)
+            .filter(|name| name.starts_with(group))
+        {
+            let Some(cmd) = client.raw_command(&name) else {
+                continue;
+            };
+            let (command, unresolved) = render_placeholders(&cmd.command, &env_values);
+            let step = PacsCommand { command, ..cmd };
+            let dangerous = client.check_run(&step).is_ok_and(|p| p.is_some());
+            if !unresolved.is_empty() || step.confirm || step.asks_cwd() || dangerous {
+                refused = Some(format!("'{name}' asks for input, run it on its own"));
+                break;
+            }
+            steps.push(step);
+        }
+
+        let state = world.get_mut::<OutputState>();
+        state.group = None;
+        if let Some(reason) = refused {
+            state.job = None;
+            state.notice = Some(reason);
+            return;
+        }
+        if Self::show_in_safe_mode(world, &steps) {
+            return;
+        }
+        world.get_mut::<OutputState>().group = Some(GroupRun {
+            group: group.to_string(),
+            steps,
+            results: Vec::new(),
+        });
+        Self::start_step(world);
+    }
+
+    /// Starts the next step of the group, if there is one.
+    fn start_step(world: &mut World) {
+        let state = world.get::<OutputState>();
+        let Some(cmd) = state.group.as_ref().and_then(GroupRun::next_step).cloned() else {
+            return;
+        };
+        if !Self::launch(world, &cmd, false)
+            && let Some(group) = &mut world.get_mut::<OutputState>().group
+        {
+            group.results.push(None);
+        }
+    }
+
+    /// Shows the resolved commands instead of running them in safe mode.
+    /// Returns true if safe mode is on.
+    fn show_in_safe_mode(world: &mut World, cmds: &[PacsCommand]) -> bool {
+        let client = world.get::<PacsClient>();
+        if !client.is_safe_mode() {
+            return false;
+        }
+        let shown: Vec<String> = cmds
+            .iter()
+            .map(|cmd| {
+                client
+                    .resolved_command(&cmd.name)
+                    .map_or_else(String::new, |c| c.command)
+            })
+            .collect();
+        let state = world.get_mut::<OutputState>();
+        state.job = None;
+        state.notice = Some(format!(
+            "Safe mode: showing instead of running\n\n{}",
+            shown.join("\n")
+        ));
+        true
+    }
+
+    /// Checks the command like `pacs run`, runs the pre-run hooks and spawns
+    /// it. Returns false and shows why if it did not start.
+    fn launch(world: &mut World, cmd: &PacsCommand, confirmed: bool) -> bool {
+        let client = world.get::<PacsClient>();
+        let project = client.active_project().unwrap_or_default();
+        let environment = client.active_environment();
+
+        let job = client
+            .check_run(cmd)
+            .and_then(|danger| match danger {
+                Some(pattern) if !confirmed => Err(anyhow::anyhow!(
+                    "'{}' matches the danger pattern '{pattern}' and was not confirmed",
+                    cmd.name
+                )),
+                _ => Ok(()),
+            })
+            .and_then(|()| client.run_hooks(HookEvent::PreRun, &cmd.name, None))
+            .and_then(|()| Job::spawn(cmd, project, environment));
+        let state = world.get_mut::<OutputState>();
+        match job {
+            Ok(job) => {
+                state.job = Some(job);
+                state.notice = None;
+                true
+            }
+            Err(e) => {
+                state.job = None;
+                state.notice = Some(format!("{e:#}"));
+                false
+            }
+        }
+    }
+
+    pub fn setup_keybindings(world: &mut World) {
+        let kb = world.get_mut::<Keybindings>();
+
+        kb.bind(OUTPUT, KeyCode::Esc, "Back", |world| {
+            world.get_mut::<Focus>().set(COMMANDS_LIST);
+        });
+
+        kb.bind(
+            OUTPUT,
+            KeyBinding::ctrl('c'),
+            "Cancel",
+            |world| match &mut world.get_mut::<OutputState>().job {
+                Some(job) if job.is_running() => job.cancel(),
+                _ => world.get_mut::<AppState>().should_quit = true,
+            },
+        );
+
+        kb.bind(OUTPUT, 'x', "Close Output", |world| {
+            let state = world.get_mut::<OutputState>();
+            if !state.is_running() {
+                state.job = None;
+                state.notice = None;
+                state.group = None;
+                world.get_mut::<Focus>().set(COMMANDS_LIST);
+            }
+        });
+    }
+
+    pub fn setup_pointer(world: &mut World) {
+        world
+            .get_mut::<Pointer>()
+            .on_click(OUTPUT, |world, _, _x, _y| {
+                world.get_mut::<Focus>().set(OUTPUT);
+            });
+    }
+
+    pub fn render(world: &mut World, frame: &mut Frame, area: Rect) {
+        if let Some(job) = &mut world.get_mut::<OutputState>().job
+            && job.poll()
+            && let Some(record) = job.record()
+        {
+            let client = world.get::<PacsClient>();
+            let _ = client.record_run(&record);
+            let _ = client.run_hooks(HookEvent::PostRun, &record.command, Some(record.exit_code));
+            // Posting can take seconds, which must not freeze the interface
+            if let Some(webhook) = client.webhook(&record) {
+                std::thread::spawn(move || webhook.send());
+            }
+            if let Some(group) = &mut world.get_mut::<OutputState>().group {
+                group.results.push(Some(record.exit_code));
+                if record.exit_code == 0 {
+                    Self::start_step(world);
+                }
+            }
+        }
+
+        if !world.get::<OutputState>().is_open() {
+            world.get_mut::<Pointer>().set(OUTPUT, Rect::default());
+            return;
+        }
+
+        let is_focused = world.get::<Focus>().is_focused(OUTPUT);
+        let theme = world.get::<Theme>();
+        let state = world.get::<OutputState>();
+        let running = state.is_running();
+        let mut checklist = state
+            .group
+            .as_ref()
+            .map(|group| group.checklist(running, theme))
+            .unwrap_or_default();
+        let Some(job) = &state.job else {
+            let block = theme
+                .block_for_focus(is_focused)
+                .borders(Borders::TOP)
+                .title(Span::styled(" not started ", theme.text_accent_alt));
+            let notice = state.notice.clone().unwrap_or_default();
+            if !checklist.is_empty() {
+                checklist.push(Line::default());
+            }
+            let mut text = Text::from(checklist);
+            text.extend(Text::styled(notice, theme.text));
+            let text = Paragraph::new(text).wrap(Wrap { trim: false }).block(block);
+            frame.render_widget(text, area);
+            world.get_mut::<Pointer>().set(OUTPUT, area);
+            return;
+        };
+
+        let status = match job.status {
+            None if job.stopping_since.is_some() => Span::styled(" stopping ", theme.text_muted),
+            None => Span::styled(
+                format!(" running {}s ", job.started_at.elapsed().as_secs()),
+                theme.text_muted,
+            ),
+            Some(status) if status.success() => Span::styled(" exit 0 ", theme.success),
+            Some(_) if job.stopping_since.is_some() => {
+                Span::styled(" cancelled ", theme.text_accent_alt)
+            }
+            Some(status) => Span::styled(
+                format!(" exit {} ", status.code().unwrap_or(-1)),
+                theme.text_accent_alt,
+            ),
+        };
+
+        let block = theme
+            .block_for_focus(is_focused)
+            .borders(Borders::TOP)
+            .title(Line::from(vec![
+                Span::styled(format!(" {} ", job.name), theme.text_accent),
+                status,
+            ]));
+        let inner = block.inner(area);
+
+        let client = world.get::<PacsClient>();
+        let lines = job.lines();
+        let height = (inner.height as usize).saturating_sub(checklist.len());
+        let skip = lines.len().saturating_sub(height);
+        let mut text = checklist;
+        text.extend(
+            lines
+                .into_iter()
+                .skip(skip)
+                .map(|l| Line::styled(client.redact(&l), theme.text)),
+        );
+
+        frame.render_widget(Paragraph::new(text).block(block), area);
+
+        world.get_mut::<Pointer>().set(OUTPUT, area);
+    }
+}
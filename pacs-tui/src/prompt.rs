@@ -0,0 +1,348 @@
+use std::collections::BTreeMap;
+
+use crate::app::AppState;
+use crate::client::PacsClient;
+use crate::commands::COMMANDS_LIST;
+use crate::output::OutputPane;
+use crate::theme::Theme;
+use crate::util::center_rect;
+use pacs_core::{
+    CWD_KEY, PacsCommand, common_prefix, complete_dir, expand_home, render_placeholders,
+};
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyModifiers},
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use tui_world::{keys, prelude::*};
+
+pub const PROMPT: WidgetId = WidgetId("Prompt");
+const BACKDROP: WidgetId = WidgetId("prompt_backdrop");
+
+/// Form asking for placeholder values before running a command, for the
+/// working directory if it has `cwd = "ask"`, and for a `y` if the command is
+/// marked `confirm` or matches a danger pattern.
+#[derive(Default)]
+pub struct PromptState {
+    pub command: Option<PacsCommand>,
+    pub fields: Vec<(String, String)>,
+    pub selected: usize,
+    /// The resolved body shown while waiting for confirmation.
+    pub confirming: Option<String>,
+    /// The danger pattern the resolved body matches.
+    pub danger: Option<String>,
+}
+
+impl PromptState {
+    pub fn is_open(&self) -> bool {
+        self.command.is_some()
+    }
+
+    fn values(&self) -> BTreeMap<String, String> {
+        self.fields.iter().cloned().collect()
+    }
+
+    /// Completes the selected field if it is the working directory. Returns
+    /// false if there is nothing to complete.
+    fn complete_cwd(&mut self) -> bool {
+        if !self.command.as_ref().is_some_and(PacsCommand::asks_cwd) {
+            return false;
+        }
+        let Some((key, value)) = self.fields.get_mut(self.selected) else {
+            return false;
+        };
+        if key != CWD_KEY {
+            return false;
+        }
+        let completed = common_prefix(&complete_dir(value));
+        if completed.len() <= value.len() {
+            return false;
+        }
+        *value = completed;
+        true
+    }
+
+    fn height(&self) -> u16 {
+        let rows = match &self.confirming {
+            Some(body) => body.lines().count(),
+            None => self.fields.len(),
+        };
+        u16::try_from(rows).unwrap_or(u16::MAX).saturating_add(4)
+    }
+}
+
+/// Runs a command, prompting for its placeholder values first if any of them
+/// can't be resolved from the active environment, for the working directory
+/// if the command asks for it, and for confirmation if it is marked `confirm`
+/// or matches a danger pattern.
+pub fn run(world: &mut World, cmd: PacsCommand, env_values: &BTreeMap<String, String>) {
+    let (rendered, unresolved) = render_placeholders(&cmd.command, env_values);
+    let rendered = PacsCommand {
+        command: rendered,
+        ..cmd.clone()
+    };
+    let dangerous = is_dangerous(world, &rendered);
+    if unresolved.is_empty() && !cmd.confirm && !cmd.asks_cwd() && !dangerous {
+        OutputPane::start(world, &rendered, false);
+        return;
+    }
+
+    let mut fields: Vec<(String, String)> = cmd
+        .placeholders()
+        .into_iter()
+        .map(|key| {
+            let value = env_values.get(&key).cloned().unwrap_or_default();
+            (key, value)
+        })
+        .collect();
+    if cmd.asks_cwd() && !fields.iter().any(|(key, _)| key == CWD_KEY) {
+        // Offer the project path, or else the directory pacs was started in
+        let default = env_values
+            .get(CWD_KEY)
+            .cloned()
+            .or_else(|| world.get::<PacsClient>().project_path())
+            .or_else(|| {
+                let dir = std::env::current_dir().ok()?;
+                Some(dir.display().to_string())
+            })
+            .unwrap_or_default();
+        fields.push((CWD_KEY.to_string(), default));
+    }
+
+    let asks_cwd = cmd.asks_cwd();
+    let state = world.get_mut::<PromptState>();
+    state.command = Some(cmd);
+    state.fields = fields;
+    state.selected = 0;
+    if unresolved.is_empty() && !asks_cwd {
+        submit(world);
+    } else {
+        open(world);
+    }
+}
+
+fn open(world: &mut World) {
+    world.get_mut::<Focus>().set(PROMPT);
+
+    let area = world.get::<AppState>().area;
+    let dialog_area = center_rect(area, 60, world.get::<PromptState>().height());
+
+    world.get_mut::<Pointer>().set(BACKDROP, area);
+    world
+        .get_mut::<Pointer>()
+        .on_click(BACKDROP, move |world, _, x, y| {
+            if !dialog_area.contains((x, y).into()) {
+                close(world);
+            }
+        });
+}
+
+pub fn close(world: &mut World) {
+    *world.get_mut::<PromptState>() = PromptState::default();
+    world.get_mut::<Focus>().set(COMMANDS_LIST);
+    world.get_mut::<Pointer>().remove(BACKDROP);
+}
+
+fn submit(world: &mut World) {
+    let state = world.get::<PromptState>();
+    if state.confirming.is_some() {
+        return;
+    }
+    let Some(cmd) = state.command.clone() else {
+        return;
+    };
+    let (preview, _) = render_placeholders(&cmd.command, &state.values());
+    let client = world.get::<PacsClient>();
+    let danger = client
+        .check_run(&PacsCommand {
+            command: preview.clone(),
+            ..cmd.clone()
+        })
+        .ok()
+        .flatten();
+    if cmd.confirm || danger.is_some() {
+        let preview = client.redact(preview.trim_end());
+        let state = world.get_mut::<PromptState>();
+        state.confirming = Some(preview);
+        state.danger = danger;
+        open(world);
+        return;
+    }
+    start(world);
+}
+
+/// Returns true if the resolved command matches a danger pattern.
+fn is_dangerous(world: &World, cmd: &PacsCommand) -> bool {
+    world
+        .get::<PacsClient>()
+        .check_run(cmd)
+        .is_ok_and(|pattern| pattern.is_some())
+}
+
+/// Closes the prompt and runs its command with the entered values.
+fn start(world: &mut World) {
+    let state = world.get::<PromptState>();
+    let Some(cmd) = state.command.clone() else {
+        return;
+    };
+    let values = state.values();
+    let confirmed = state.confirming.is_some();
+    let (rendered, _) = render_placeholders(&cmd.command, &values);
+    let cwd = if cmd.asks_cwd() {
+        values.get(CWD_KEY).map(|dir| expand_home(dir))
+    } else {
+        cmd.cwd.clone()
+    };
+    close(world);
+    OutputPane::start(
+        world,
+        &PacsCommand {
+            command: rendered,
+            cwd,
+            ..cmd
+        },
+        confirmed,
+    );
+}
+
+fn next_field(world: &mut World) {
+    let state = world.get_mut::<PromptState>();
+    state.selected = (state.selected + 1) % state.fields.len().max(1);
+}
+
+pub fn setup_keybindings(world: &mut World) {
+    let kb = world.get_mut::<Keybindings>();
+
+    kb.bind(PROMPT, KeyCode::Enter, "Run", submit);
+
+    kb.bind(PROMPT, KeyCode::Esc, "Cancel", close);
+
+    kb.bind(PROMPT, KeyCode::Tab, "Complete or Next Field", |world| {
+        if !world.get_mut::<PromptState>().complete_cwd() {
+            next_field(world);
+        }
+    });
+
+    kb.bind(PROMPT, KeyCode::Down, "Next Field", next_field);
+
+    kb.bind_many(
+        PROMPT,
+        keys![
+            KeyBinding::new(KeyCode::BackTab, KeyModifiers::SHIFT),
+            KeyCode::Up
+        ],
+        "Previous Field",
+        |world| {
+            let state = world.get_mut::<PromptState>();
+            let len = state.fields.len().max(1);
+            state.selected = (state.selected + len - 1) % len;
+        },
+    );
+
+    kb.bind(PROMPT, KeyCode::Backspace, "Delete", |world| {
+        let state = world.get_mut::<PromptState>();
+        let selected = state.selected;
+        if let Some((_, value)) = state.fields.get_mut(selected) {
+            value.pop();
+        }
+    });
+
+    kb.bind_any(PROMPT, |world, key| {
+        let KeyCode::Char(c) = key.code else {
+            return;
+        };
+        if key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return;
+        }
+        if world.get::<PromptState>().confirming.is_some() {
+            match c {
+                'y' | 'Y' => start(world),
+                'n' | 'N' => close(world),
+                _ => {}
+            }
+            return;
+        }
+        let state = world.get_mut::<PromptState>();
+        let selected = state.selected;
+        if let Some((_, value)) = state.fields.get_mut(selected) {
+            value.push(c);
+        }
+    });
+}
+
+pub fn render(world: &World, frame: &mut Frame, area: Rect) {
+    let theme = world.get::<Theme>();
+    let client = world.get::<PacsClient>();
+    let state = world.get::<PromptState>();
+    let Some(cmd) = &state.command else {
+        return;
+    };
+    let dialog_area = center_rect(area, 60, state.height());
+
+    frame.render_widget(Clear, dialog_area);
+
+    let title = if let Some(pattern) = &state.danger {
+        format!(
+            " Run {}? It matches the danger pattern '{pattern}' ",
+            cmd.name
+        )
+    } else if state.confirming.is_some() {
+        format!(" Run {}? ", cmd.name)
+    } else {
+        format!(" Run {} ", cmd.name)
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(theme.border_focused);
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    if let Some(body) = &state.confirming {
+        let mut lines: Vec<Line> = body
+            .lines()
+            .map(|line| Line::from(Span::styled(format!(" {line}"), theme.text)))
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            " y run · n cancel",
+            theme.text_muted,
+        )));
+        frame.render_widget(Paragraph::new(lines), inner);
+        return;
+    }
+
+    let key_width = state.fields.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+
+    let mut lines: Vec<Line> = state
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, (key, value))| {
+            let is_selected = i == state.selected;
+            let mut spans = vec![
+                Span::styled(format!(" {key:>key_width$} "), theme.text_muted),
+                Span::styled(client.redact(value), theme.text),
+            ];
+            if is_selected {
+                spans[0].style = theme.keybinding_key;
+                spans.push(Span::styled("▏", theme.text_accent));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        " Enter run · Tab next · Esc cancel",
+        theme.text_muted,
+    )));
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
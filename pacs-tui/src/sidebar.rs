@@ -1,8 +1,9 @@
-use crate::{client::PacsClient, commands::CommandsState, theme::Theme};
+use crate::{client::PacsClient, commands::CommandsState, notes, theme::Theme, warnings};
 use ratatui::{
     Frame,
     crossterm::event::KeyCode,
     layout::{Constraint, Layout},
+    style::{Color, Style},
     text::{Line, Span},
     widgets::{Borders, HighlightSpacing, List, ListState, Paragraph, StatefulWidget},
 };
@@ -14,6 +15,19 @@ pub const ENVIRONMENTS: WidgetId = WidgetId("Environments");
 
 pub struct Sidebar;
 
+/// Returns the name of a project after its icon, in its color if it has one.
+pub fn project_span(client: &PacsClient, name: &str, style: Style) -> Span<'static> {
+    let project_style = client.project_style(name);
+    let icon = project_style
+        .icon
+        .map_or_else(String::new, |icon| format!("{icon} "));
+    let style = project_style
+        .color
+        .and_then(|color| color.parse::<Color>().ok())
+        .map_or(style, |color| style.fg(color));
+    Span::styled(format!("{icon}{name}"), style)
+}
+
 impl Sidebar {
     pub fn render(world: &mut World, frame: &mut Frame, area: ratatui::prelude::Rect) {
         let focus_id = world.get::<Focus>().id;
@@ -94,6 +108,14 @@ impl Projects {
             world.get_mut::<Focus>().set(ENVIRONMENTS);
         });
 
+        kb.bind(PROJECTS, 'w', "Load Warnings", |world| {
+            warnings::toggle(world);
+        });
+
+        kb.bind(PROJECTS, 'n', "Project Notes", |world| {
+            notes::toggle(world);
+        });
+
         kb.bind_many(PROJECTS, keys![KeyCode::Down, 'j'], "Down", |world| {
             world.get_mut::<ProjectsState>().next();
             Projects::activate_selected(world);
@@ -143,10 +165,26 @@ impl Projects {
         frame.render_widget(project_title, title_area);
 
         let projects = client.list_projects();
+        let active = client.active_project();
 
         let items: Vec<Line> = projects
             .iter()
-            .map(|name| Line::raw(name.clone()))
+            .map(|name| {
+                let Some(count) = client.command_count(name) else {
+                    return Line::from(vec![
+                        Span::styled(name.clone(), theme.text_muted),
+                        Span::styled(" !", theme.text_muted),
+                    ]);
+                };
+                let mut spans = vec![
+                    project_span(client, name, Style::default()),
+                    Span::styled(format!(" ({count})"), theme.text_muted),
+                ];
+                if active.as_ref() == Some(name) {
+                    spans.push(Span::styled(" *", theme.success));
+                }
+                Line::from(spans)
+            })
             .collect();
 
         let mut list = List::new(items)
@@ -272,10 +310,17 @@ impl Environments {
         frame.render_widget(env_title, title_area);
 
         let environments = client.list_environments();
+        let active = client.active_environment();
 
         let items: Vec<Line> = environments
             .iter()
-            .map(|name| Line::raw(name.clone()))
+            .map(|name| {
+                let mut spans = vec![Span::raw(name.clone())];
+                if active.as_ref() == Some(name) {
+                    spans.push(Span::styled(" *", theme.success));
+                }
+                Line::from(spans)
+            })
             .collect();
 
         let mut list = List::new(items)
@@ -0,0 +1,135 @@
+use crate::app::{AppState, BACKDROP, Overlay};
+use crate::client::PacsClient;
+use crate::theme::Theme;
+use crate::util::center_rect;
+use pacs_core::RunStats;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Sparkline},
+};
+use tui_world::prelude::*;
+
+/// Number of days shown in the activity sparkline.
+const ACTIVITY_DAYS: usize = 30;
+
+/// Number of commands listed under "Most run".
+const TOP_COMMANDS: usize = 8;
+
+pub fn toggle(world: &mut World) {
+    let is_open = world.get::<AppState>().overlay == Some(Overlay::Stats);
+    if is_open {
+        close(world);
+    } else {
+        open(world);
+    }
+}
+
+pub fn open(world: &mut World) {
+    let stats = world.get::<PacsClient>().run_stats(ACTIVITY_DAYS);
+    world.insert(stats);
+    world.get_mut::<AppState>().overlay = Some(Overlay::Stats);
+
+    let area = world.get::<AppState>().area;
+    let dialog_area = center_rect(area, 70, 24);
+
+    world.get_mut::<Pointer>().set(BACKDROP, area);
+    world
+        .get_mut::<Pointer>()
+        .on_click(BACKDROP, move |world, _, x, y| {
+            if !dialog_area.contains((x, y).into()) {
+                close(world);
+            }
+        });
+}
+
+pub fn close(world: &mut World) {
+    world.get_mut::<AppState>().overlay = None;
+    world.get_mut::<Pointer>().remove(BACKDROP);
+}
+
+pub fn render(world: &World, frame: &mut Frame, area: Rect) {
+    let theme = world.get::<Theme>();
+    let Some(stats) = world.try_get::<RunStats>() else {
+        return;
+    };
+    let dialog_area = center_rect(area, 70, 24);
+
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Stats ")
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let [summary_area, lists_area, activity_title_area, activity_area] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Min(0),
+        Constraint::Length(1),
+        Constraint::Length(3),
+    ])
+    .areas(inner);
+
+    let success_rate = if stats.total() == 0 {
+        0
+    } else {
+        stats.successes * 100 / stats.total()
+    };
+    let summary = Line::from(vec![
+        Span::styled(" Runs ", theme.text_muted),
+        Span::styled(stats.total().to_string(), theme.text_accent),
+        Span::styled("  Succeeded ", theme.text_muted),
+        Span::styled(stats.successes.to_string(), theme.success),
+        Span::styled("  Failed ", theme.text_muted),
+        Span::styled(stats.failures.to_string(), theme.text_accent_alt),
+        Span::styled(format!("  ({success_rate}% success)"), theme.text_muted),
+    ]);
+    frame.render_widget(Paragraph::new(summary), summary_area);
+
+    let [commands_area, projects_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .areas(lists_area);
+
+    let mut commands = vec![Line::from(Span::styled(" Most run", theme.title))];
+    for cmd in stats.commands.iter().take(TOP_COMMANDS) {
+        commands.push(Line::from(vec![
+            Span::styled(format!(" {:>4} ", cmd.runs), theme.keybinding_key),
+            Span::styled(cmd.command.clone(), theme.text),
+            Span::styled(format!(" ({})", cmd.project), theme.text_muted),
+        ]));
+    }
+    if stats.commands.is_empty() {
+        commands.push(Line::from(Span::styled(
+            " No runs recorded yet.",
+            theme.text_muted,
+        )));
+    }
+    frame.render_widget(Paragraph::new(commands), commands_area);
+
+    let mut projects = vec![Line::from(Span::styled(" Runs per project", theme.title))];
+    for (project, runs) in &stats.projects {
+        projects.push(Line::from(vec![
+            Span::styled(format!(" {runs:>4} "), theme.keybinding_key),
+            Span::styled(project.clone(), theme.text),
+        ]));
+    }
+    frame.render_widget(Paragraph::new(projects), projects_area);
+
+    frame.render_widget(
+        Paragraph::new(Line::from(Span::styled(
+            format!(" Activity (last {ACTIVITY_DAYS} days)"),
+            theme.title,
+        ))),
+        activity_title_area,
+    );
+    frame.render_widget(
+        Sparkline::default()
+            .data(&stats.daily)
+            .style(theme.text_accent_alt),
+        activity_area,
+    );
+}
@@ -11,6 +11,7 @@ pub struct Colors {
     pub accent: Color,
     pub accent_secondary: Color,
     pub success: Color,
+    pub warning: Color,
     pub highlight: Color,
     pub surface: Color,
     // Syntax highlighting
@@ -30,6 +31,7 @@ impl Default for Colors {
             accent: Color::Rgb(140, 130, 255),
             accent_secondary: Color::Rgb(80, 180, 255),
             success: Color::Rgb(130, 230, 180),
+            warning: Color::Rgb(255, 140, 120),
             highlight: Color::Rgb(45, 40, 80),
             surface: Color::Rgb(25, 25, 40),
             // Syntax highlighting
@@ -75,6 +77,9 @@ pub struct Theme {
     #[style(fg = success)]
     pub success: Style,
 
+    #[style(fg = warning)]
+    pub warning: Style,
+
     // Syntax highlighting
     #[style(fg = accent_secondary)]
     pub sh_command: Style,
@@ -1,18 +1,50 @@
-use ratatui::crossterm::event::KeyCode;
+use ratatui::{
+    crossterm::event::KeyCode,
+    layout::{Constraint, Layout, Rect},
+};
 use tui_world::{Focus, WidgetId, World};
 
-use crate::app::GLOBAL;
+use crate::{app::GLOBAL, export::EXPORT, prompt::PROMPT};
 
 pub fn kc(c: char) -> KeyCode {
     KeyCode::Char(c)
 }
 
 pub fn get_active_ids(world: &World) -> Vec<WidgetId> {
+    let focus = world.get::<Focus>().id;
+
+    // Text input captures every key, including the global ones
+    if let Some(id @ (PROMPT | EXPORT)) = focus {
+        return vec![id];
+    }
+
     let mut active = vec![GLOBAL];
 
-    if let Some(id) = world.get::<Focus>().id {
+    if let Some(id) = focus {
         active.push(id);
     }
 
     active
 }
+
+/// Returns a rect of the given size centered in `area`, leaving a small margin.
+pub fn center_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width.saturating_sub(4));
+    let height = height.min(area.height.saturating_sub(4));
+
+    let [_, h_center, _] = Layout::horizontal([
+        Constraint::Fill(1),
+        Constraint::Length(width),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+
+    let [_, dialog, _] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(height),
+        Constraint::Fill(1),
+    ])
+    .areas(h_center);
+
+    dialog
+}
@@ -0,0 +1,93 @@
+use crate::app::{AppState, BACKDROP, Overlay};
+use crate::client::PacsClient;
+use crate::theme::Theme;
+use crate::util::center_rect;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use tui_world::prelude::*;
+
+pub fn toggle(world: &mut World) {
+    let is_open = world.get::<AppState>().overlay == Some(Overlay::Warnings);
+    if is_open {
+        close(world);
+    } else {
+        open(world);
+    }
+}
+
+pub fn open(world: &mut World) {
+    world.get_mut::<AppState>().overlay = Some(Overlay::Warnings);
+
+    let area = world.get::<AppState>().area;
+    let dialog_area = center_rect(area, 60, 15);
+
+    world.get_mut::<Pointer>().set(BACKDROP, area);
+    world
+        .get_mut::<Pointer>()
+        .on_click(BACKDROP, move |world, _, x, y| {
+            if !dialog_area.contains((x, y).into()) {
+                close(world);
+            }
+        });
+}
+
+pub fn close(world: &mut World) {
+    world.get_mut::<AppState>().overlay = None;
+    world.get_mut::<Pointer>().remove(BACKDROP);
+}
+
+pub fn render(world: &World, frame: &mut Frame, area: Rect) {
+    let theme = world.get::<Theme>();
+    let client = world.get::<PacsClient>();
+    let dialog_area = center_rect(area, 60, 15);
+
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Load Warnings ")
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let mut lines: Vec<Line> = Vec::new();
+    if let Some(error) = client.config_error() {
+        lines.push(Line::from(Span::styled("config.toml", theme.text_accent)));
+        lines.push(Line::from(Span::styled(
+            "Using defaults until it is fixed",
+            theme.text_muted,
+        )));
+        lines.push(Line::from(Span::styled(error.to_string(), theme.text)));
+    }
+    for warning in client.load_warnings() {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            warning.project.clone(),
+            theme.text_accent,
+        )));
+        lines.push(Line::from(Span::styled(
+            warning.path.display().to_string(),
+            theme.text_muted,
+        )));
+        lines.push(Line::from(Span::styled(
+            warning.message.clone(),
+            theme.text,
+        )));
+    }
+
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "All project files loaded.",
+            theme.text_muted,
+        )));
+    }
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
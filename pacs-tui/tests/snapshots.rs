@@ -0,0 +1,215 @@
+//! Snapshot tests rendering the TUI into a `TestBackend` with a seeded pacs.
+//!
+//! The rendered text is compared with `tests/snapshots/<name>.txt`. Run with
+//! `UPDATE_SNAPSHOTS=1` to rewrite the files after an intended change.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use pacs_core::{Pacs, PacsCommand};
+use pacs_tui::app::{self, setup_world_with_client};
+use pacs_tui::client::PacsClient;
+use pacs_tui::commands::{CommandsPanel, CommandsState};
+use pacs_tui::output::{OutputPane, OutputState};
+use pacs_tui::sidebar::Sidebar;
+use pacs_tui::{help, notes, prompt};
+use ratatui::{Terminal, backend::TestBackend, buffer::Buffer};
+use tempfile::TempDir;
+use tui_world::World;
+
+fn command(name: &str, body: &str, tag: &str) -> PacsCommand {
+    PacsCommand {
+        name: name.into(),
+        command: body.into(),
+        tag: tag.into(),
+        ..Default::default()
+    }
+}
+
+/// A world around a pacs with two projects, the active one with an icon, two
+/// environments, notes and a few commands, two of them in a `db/` group.
+fn seeded_world() -> (World, TempDir) {
+    let (pacs, home) = seeded_pacs();
+    let mut world = World::default();
+    setup_world_with_client(&mut world, PacsClient::from_pacs(pacs));
+    (world, home)
+}
+
+fn seeded_pacs() -> (Pacs, TempDir) {
+    let home = tempfile::tempdir().unwrap();
+    fs::write(
+        home.path().join("config.toml"),
+        "author = \"tester\"\n\n[tags.ops]\ncolor = \"red\"\nicon = \"●\"\n",
+    )
+    .unwrap();
+    let mut pacs = Pacs::init_at(home.path().to_path_buf()).unwrap();
+
+    pacs.init_project("web", None).unwrap();
+    pacs.init_project("api", Some("~/src/api".into())).unwrap();
+    pacs.set_active_project("api").unwrap();
+    pacs.set_project_style("api", Some("green"), Some("◆"))
+        .unwrap();
+    for (name, host) in [("dev", "localhost"), ("prod", "example.com")] {
+        pacs.add_environment("api", name).unwrap();
+        let values = BTreeMap::from([("host".to_string(), host.to_string())]);
+        pacs.edit_environment_values("api", name, values).unwrap();
+    }
+    pacs.set_active_environment("api", "dev").unwrap();
+    pacs.set_project_notes(
+        "api",
+        "# Deploys\n\n- Run `db/migrate` before `deploy`\n- Never deploy on Fridays",
+    )
+    .unwrap();
+    for cmd in [
+        command("build", "cargo build --release\n", ""),
+        command("db/migrate", "sqlx migrate run\n", ""),
+        command("db/seed", "psql -f seed.sql\n", ""),
+        command("ping", "curl https://{{host}}/health\n", "ops"),
+        command("logs", "kubectl logs -f deploy/api\n", "ops"),
+    ] {
+        pacs.add_command(cmd, None).unwrap();
+    }
+    (pacs, home)
+}
+
+fn draw(
+    world: &mut World,
+    width: u16,
+    height: u16,
+    render: impl FnOnce(&mut ratatui::Frame, &mut World),
+) -> Buffer {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    let frame = terminal.draw(|frame| render(frame, world)).unwrap();
+    frame.buffer.clone()
+}
+
+fn assert_snapshot(name: &str, buffer: &Buffer) {
+    let width = usize::from(buffer.area.width);
+    let text: String = buffer
+        .content()
+        .chunks(width)
+        .map(|row| {
+            let line: String = row.iter().map(ratatui::buffer::Cell::symbol).collect();
+            format!("{}\n", line.trim_end())
+        })
+        .collect();
+
+    let file = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.txt"));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&file, &text).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&file).unwrap_or_default();
+    assert!(
+        expected == text,
+        "rendering differs from {}, rerun with UPDATE_SNAPSHOTS=1 to accept it:\n{text}",
+        file.display()
+    );
+}
+
+#[test]
+fn test_sidebar() {
+    let (mut world, _home) = seeded_world();
+    let buffer = draw(&mut world, 24, 14, |frame, world| {
+        Sidebar::render(world, frame, frame.area());
+    });
+    assert_snapshot("sidebar", &buffer);
+}
+
+#[test]
+fn test_commands_panel() {
+    let (mut world, _home) = seeded_world();
+    let buffer = draw(&mut world, 80, 20, |frame, world| {
+        CommandsPanel::render(world, frame, frame.area());
+    });
+    assert_snapshot("commands_panel", &buffer);
+}
+
+#[test]
+fn test_explain_footer() {
+    let (mut world, _home) = seeded_world();
+    world.get_mut::<CommandsState>().explain = true;
+    let buffer = draw(&mut world, 80, 20, |frame, world| {
+        CommandsPanel::render(world, frame, frame.area());
+    });
+    assert_snapshot("explain_footer", &buffer);
+}
+
+#[test]
+fn test_group_run() {
+    let (mut pacs, _home) = seeded_pacs();
+    for cmd in [
+        command("ci/build", "echo built\n", ""),
+        command("ci/check", "exit 3\n", ""),
+        command("ci/deploy", "echo deployed\n", ""),
+    ] {
+        pacs.add_command(cmd, None).unwrap();
+    }
+    let mut world = World::default();
+    setup_world_with_client(&mut world, PacsClient::from_pacs(pacs));
+    let panel = |frame: &mut ratatui::Frame, world: &mut World| {
+        CommandsPanel::render(world, frame, frame.area());
+    };
+    let output = |frame: &mut ratatui::Frame, world: &mut World| {
+        OutputPane::render(world, frame, frame.area());
+    };
+
+    draw(&mut world, 80, 16, panel);
+    // The `ci/` header below `build`
+    world.get_mut::<CommandsState>().state.select(Some(1));
+    let buffer = draw(&mut world, 80, 16, panel);
+    assert_snapshot("group_steps", &buffer);
+
+    OutputPane::start_group(&mut world, "ci/");
+    for _ in 0..500 {
+        if !world.get::<OutputState>().is_running() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        draw(&mut world, 60, 5, output);
+    }
+    let buffer = draw(&mut world, 60, 5, output);
+    assert_snapshot("group_run", &buffer);
+}
+
+#[test]
+fn test_help_overlay() {
+    let (mut world, _home) = seeded_world();
+    // Opening the overlay needs the area of a previous frame
+    draw(&mut world, 100, 30, app::render);
+    help::open(&mut world);
+    let buffer = draw(&mut world, 100, 30, app::render);
+    assert_snapshot("help_overlay", &buffer);
+}
+
+#[test]
+fn test_notes_overlay() {
+    let (mut world, _home) = seeded_world();
+    draw(&mut world, 100, 30, app::render);
+    notes::open(&mut world);
+    let buffer = draw(&mut world, 100, 30, app::render);
+    assert_snapshot("notes_overlay", &buffer);
+}
+
+#[test]
+fn test_danger_prompt() {
+    let (mut pacs, home) = seeded_pacs();
+    pacs.add_command(command("clean", "rm -rf target\n", ""), None)
+        .unwrap();
+    let config = home.path().join("config.toml");
+    let content = fs::read_to_string(&config).unwrap();
+    fs::write(&config, format!("danger_patterns = ['rm -rf']\n{content}")).unwrap();
+    let pacs = Pacs::init_at(home.path().to_path_buf()).unwrap();
+    let mut world = World::default();
+    setup_world_with_client(&mut world, PacsClient::from_pacs(pacs));
+
+    draw(&mut world, 80, 20, app::render);
+    let cmd = world.get::<PacsClient>().raw_command("clean").unwrap();
+    prompt::run(&mut world, cmd, &BTreeMap::new());
+    assert!(world.get::<OutputState>().job.is_none());
+    let buffer = draw(&mut world, 80, 20, app::render);
+    assert_snapshot("danger_prompt", &buffer);
+}
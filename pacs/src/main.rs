@@ -8,7 +8,7 @@ fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     if cli.ui {
-        return pacs_tui::run();
+        return pacs_tui::run(cli.safe, cli.reveal);
     }
 
     pacs_cli::run(cli)
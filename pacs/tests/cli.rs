@@ -0,0 +1,878 @@
+//! Golden-file tests running the `pacs` binary against a temporary `PACS_HOME`.
+//!
+//! Each test records a transcript of commands and their output and compares
+//! it with `tests/golden/<name>.txt`. Run with `UPDATE_GOLDEN=1` to rewrite
+//! the files after an intended change of the output.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tempfile::TempDir;
+
+struct Sandbox {
+    home: TempDir,
+    transcript: String,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        let home = tempfile::tempdir().unwrap();
+        fs::write(home.path().join("config.toml"), "author = \"tester\"\n").unwrap();
+        Self {
+            home,
+            transcript: String::new(),
+        }
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_pacs"));
+        cmd.args(args)
+            .env("PACS_HOME", self.home.path())
+            .env("HOME", self.home.path())
+            .env("PACS_LANG", "en")
+            .env("NO_COLOR", "1")
+            .env("RUST_BACKTRACE", "0")
+            .env("RUST_LIB_BACKTRACE", "0")
+            .env_remove("PACS_PROJECT")
+            .env_remove("PACS_ENV")
+            .env_remove("PACS_READONLY")
+            .env_remove("PACS_SAFE")
+            .env_remove("GITHUB_TOKEN")
+            .env_remove("GITHUB_ACTIONS")
+            .env_remove("GITLAB_CI")
+            .env_remove("VISUAL");
+        cmd
+    }
+
+    /// Runs `pacs` with `args` and appends the call and its output to the transcript.
+    fn run(&mut self, args: &[&str]) {
+        let cmd = self.command(args);
+        self.record(cmd, "", args);
+    }
+
+    /// Like [`Sandbox::run`], with an extra environment variable.
+    fn run_with_env(&mut self, key: &str, value: &str, args: &[&str]) {
+        let mut cmd = self.command(args);
+        cmd.env(key, value);
+        self.record(cmd, &format!("{key}={value} "), args);
+    }
+
+    fn record(&mut self, mut cmd: Command, prefix: &str, args: &[&str]) {
+        let output = cmd.output().unwrap();
+        let home = self.home.path().to_string_lossy().into_owned();
+        let clean =
+            |bytes: &[u8]| strip_ansi(&String::from_utf8_lossy(bytes)).replace(&home, "$PACS_HOME");
+
+        let args: Vec<String> = args
+            .iter()
+            .map(|arg| arg.replace(&home, "$PACS_HOME"))
+            .map(|arg| {
+                if arg.contains(' ') {
+                    format!("'{arg}'")
+                } else {
+                    arg
+                }
+            })
+            .collect();
+        let _ = writeln!(self.transcript, "$ {prefix}pacs {}", args.join(" "));
+        self.transcript.push_str(&clean(&output.stdout));
+        self.transcript.push_str(&clean(&output.stderr));
+        if !output.status.success() {
+            let _ = writeln!(
+                self.transcript,
+                "[exit {}]",
+                output.status.code().unwrap_or(-1)
+            );
+        }
+        self.transcript.push('\n');
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.home.path().join(name)
+    }
+
+    fn assert_golden(&self, name: &str) {
+        let file = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(format!("{name}.txt"));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&file, &self.transcript).unwrap();
+            return;
+        }
+        let expected = fs::read_to_string(&file).unwrap_or_default();
+        assert!(
+            expected == self.transcript,
+            "output differs from {}, rerun with UPDATE_GOLDEN=1 to accept it:\n{}",
+            file.display(),
+            self.transcript
+        );
+    }
+}
+
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI sequences end with a letter
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[test]
+fn test_add_list_run() {
+    let mut sb = Sandbox::new();
+    sb.run(&["ls"]);
+    sb.run(&["project", "add", "demo"]);
+    sb.run(&["add", "hi", "echo hi {{who}}", "-t", "greet"]);
+    sb.run(&["add", "build", "echo building"]);
+    sb.run(&["add", "hi", "echo again"]);
+    sb.run(&["ls"]);
+    sb.run(&["run", "build"]);
+    sb.run(&["run", "hi", "--set", "who=world"]);
+    sb.run(&["run", "hi"]);
+    sb.run(&["run", "missing"]);
+    sb.assert_golden("add_list_run");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_environments() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut sb = Sandbox::new();
+    // The editor replaces the file it is given with the prepared values
+    let editor = sb.path("editor.sh");
+    fs::write(&editor, "#!/bin/sh\ncp \"$EDIT_INPUT\" \"$1\"\n").unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+    let input = sb.path("values.toml");
+    fs::write(
+        &input,
+        "[environments.dev.values]\nhost = \"localhost\"\n\n\
+         [environments.prod.values]\nhost = \"example.com\"\n",
+    )
+    .unwrap();
+
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["add", "ping", "echo ping {{host}}"]);
+    sb.run(&["env", "add", "dev"]);
+    sb.run(&["env", "add", "prod"]);
+    sb.record(
+        {
+            let mut cmd = sb.command(&["env", "edit"]);
+            cmd.env("EDITOR", &editor).env("EDIT_INPUT", &input);
+            cmd
+        },
+        "EDITOR=editor.sh ",
+        &["env", "edit"],
+    );
+    sb.run(&["env", "list"]);
+    sb.run(&["env", "switch", "dev"]);
+    sb.run(&["run", "ping"]);
+    sb.run(&["run", "ping", "-e", "prod"]);
+    sb.run_with_env("PACS_ENV", "prod", &["run", "ping"]);
+    sb.run(&["env", "active"]);
+    sb.assert_golden("environments");
+}
+
+#[test]
+fn test_import_aliases() {
+    let mut sb = Sandbox::new();
+    fs::write(
+        sb.path("aliases.sh"),
+        "alias ll='ls -l'\nalias gs=\"git status\"\n# alias old=x\n",
+    )
+    .unwrap();
+
+    sb.run(&["project", "add", "shell"]);
+    sb.run(&["add", "gs", "git status -sb"]);
+    let file = sb.path("aliases.sh");
+    sb.run(&["import", "--from", "aliases", &file.to_string_lossy()]);
+    sb.run(&["ls"]);
+    sb.assert_golden("import_aliases");
+}
+
+#[test]
+fn test_import_bundle() {
+    let mut theirs = Sandbox::new();
+    theirs.run(&["project", "add", "api"]);
+    theirs.run(&["add", "deploy", "kubectl apply -f theirs.yaml"]);
+    theirs.run(&["add", "logs", "kubectl logs -f api"]);
+    theirs.run(&["project", "add", "web"]);
+    theirs.run(&["add", "serve", "npm start"]);
+
+    let mut sb = Sandbox::new();
+    let bundle = sb.path("bundle.toml");
+    theirs.run(&["export", "--all", "-o", &bundle.to_string_lossy()]);
+    assert!(bundle.exists());
+
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["add", "deploy", "kubectl apply -f mine.yaml"]);
+    sb.run(&["import", &bundle.to_string_lossy()]);
+    sb.run(&["ls", "-p", "api"]);
+    sb.run(&["import", "--replace", &bundle.to_string_lossy()]);
+    sb.run(&["ls", "-p", "api"]);
+    sb.run(&["export", "--all", "-f", "just"]);
+    sb.assert_golden("import_bundle");
+}
+
+#[test]
+fn test_danger_patterns() {
+    let mut sb = Sandbox::new();
+    let config = sb.path("config.toml");
+    let mut content = fs::read_to_string(&config).unwrap();
+    content.push_str("danger_patterns = [\"rm -rf\", \"kubectl .* prod\"]\n");
+    fs::write(&config, content).unwrap();
+
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["add", "clean", "echo rm -rf target"]);
+    sb.run(&["add", "build", "echo cargo build"]);
+    sb.run(&["run", "build"]);
+    sb.run(&["run", "clean"]);
+    sb.run(&["run", "clean", "--force"]);
+    sb.run_with_env("EDITOR", "true", &["edit", "clean", "--safe"]);
+    sb.run(&["run", "clean"]);
+    sb.run(&["add", "deploy", "echo deploy prod", "--confirm"]);
+    sb.run(&["run", "deploy", "--force"]);
+    sb.assert_golden("danger_patterns");
+}
+
+#[test]
+fn test_registry_approval() {
+    let mut sb = Sandbox::new();
+    let file = sb.path("ops.toml");
+    fs::write(
+        &file,
+        "[[commands]]\nname = \"pods\"\ncommand = \"echo get pods\"\n",
+    )
+    .unwrap();
+    let url = format!("file://{}", file.display());
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["registry", "add", &url]);
+    sb.run(&["ls"]);
+    sb.run(&["run", "pods"]);
+    sb.run(&["approve", "pods"]);
+    sb.run(&["approve", "pods", "--yes"]);
+    sb.run(&["run", "pods"]);
+    sb.run(&["approve", "pods"]);
+    sb.assert_golden("registry_approval");
+}
+
+#[test]
+fn test_registry_checksums() {
+    let mut sb = Sandbox::new();
+    let file = sb.path("ops.toml");
+    // SHA-256 of "echo logs", the body of logs but not of nodes
+    let checksum = "a11ed887e25b40eb6fbabd73d16c147626102ec09b82777add17752ee9314768";
+    let registry = |pods: &str| {
+        format!(
+            "[[commands]]\nname = \"pods\"\ncommand = \"{pods}\"\n\n\
+             [[commands]]\nname = \"logs\"\ncommand = \"echo logs\"\nsha256 = \"{checksum}\"\n\n\
+             [[commands]]\nname = \"nodes\"\ncommand = \"echo get nodes\"\nsha256 = \"{checksum}\"\n"
+        )
+    };
+    fs::write(&file, registry("echo get pods")).unwrap();
+    let url = format!("file://{}", file.display());
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["registry", "add", &url]);
+    sb.run(&["approve", "logs", "--yes"]);
+    sb.run(&["approve", "nodes", "--yes"]);
+    sb.run(&["approve", "pods", "--yes"]);
+    fs::write(&file, registry("echo get pods; echo deleted")).unwrap();
+    sb.run(&["registry", "update"]);
+    sb.run(&["run", "pods"]);
+    sb.run(&["approve", "pods", "--yes"]);
+    sb.run(&["run", "pods"]);
+    sb.assert_golden("registry_checksums");
+}
+
+#[test]
+fn test_ask_cwd() {
+    let mut sb = Sandbox::new();
+    let dir = sb.path("projects").display().to_string();
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["add", "scaffold", "pwd", "--cwd", "ask"]);
+    sb.run(&["run", "scaffold"]);
+    sb.run(&["run", "scaffold", "--cwd", &dir]);
+    sb.run(&["run", "scaffold", "--set", &format!("cwd={dir}")]);
+    sb.assert_golden("ask_cwd");
+}
+
+#[test]
+fn test_env_list_all() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "scratch"]);
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["env", "add", "dev"]);
+    sb.run(&["env", "add", "prod"]);
+    sb.run(&["env", "switch", "prod"]);
+    sb.run(&["project", "add", "web"]);
+    sb.run(&["env", "add", "dev"]);
+    sb.run(&["env", "add", "prod"]);
+    sb.run(&["env", "list", "--all"]);
+    sb.run_with_env("PACS_ENV", "dev", &["env", "list", "--all"]);
+    sb.assert_golden("env_list_all");
+}
+
+#[test]
+fn test_command_lock() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["add", "deploy", "echo deploy"]);
+    sb.run(&["lock", "deploy"]);
+    sb.run(&["ls", "deploy"]);
+    sb.run_with_env("EDITOR", "true", &["edit", "deploy"]);
+    sb.run(&["rename", "deploy", "ship"]);
+    sb.run(&["rm", "deploy"]);
+    sb.run(&["rename", "deploy", "ship", "--force"]);
+    sb.run(&["unlock", "ship"]);
+    sb.run(&["rm", "ship"]);
+    sb.assert_golden("command_lock");
+}
+
+#[test]
+fn test_ci_mode() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["add", "build", "echo building in {{region}}"]);
+    sb.run(&["add", "check", "echo checking\nexit 2"]);
+    sb.run(&["run", "build", "check", "--ci", "-k", "--set", "region=eu"]);
+    sb.run_with_env(
+        "GITHUB_ACTIONS",
+        "true",
+        &["run", "build", "--ci", "--set", "region=us"],
+    );
+    sb.run(&["run", "db/", "--ci"]);
+    sb.assert_golden("ci_mode");
+}
+
+#[test]
+fn test_namespaces() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["add", "build", "echo cargo build"]);
+    sb.run(&["add", "db/migrate", "echo migrate"]);
+    sb.run(&["add", "db/seed", "echo seed"]);
+    sb.run(&["add", "db/dump/full", "echo full dump"]);
+    sb.run(&["ls", "--names"]);
+    sb.run(&["run", "db/dump/"]);
+    sb.run(&["run", "db/"]);
+    sb.run(&["run", "web/"]);
+    sb.run(&["export", "-f", "just"]);
+    sb.assert_golden("namespaces");
+}
+
+#[test]
+fn test_local_only() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "scratch", "--local-only"]);
+    sb.run(&["add", "try", "echo experiment"]);
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["add", "build", "cargo build"]);
+    sb.run(&["add", "token", "echo my-token", "--local-only"]);
+    sb.run(&["project", "list"]);
+    sb.run(&["ls"]);
+    sb.run(&["export", "-f", "md"]);
+    sb.run(&["export", "--all"]);
+    sb.assert_golden("local_only");
+}
+
+#[test]
+fn test_projects() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "web"]);
+    sb.run(&["add", "build", "echo web build"]);
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["add", "build", "echo api build"]);
+    sb.run(&["project", "list"]);
+    sb.run(&["run", "build"]);
+    sb.run(&["run", "build", "-p", "web"]);
+    sb.run_with_env("PACS_PROJECT", "web", &["run", "build"]);
+    sb.run(&["project", "switch", "web"]);
+    sb.run(&["ls"]);
+    sb.run(&["project", "switch", "nope"]);
+    sb.assert_golden("projects");
+}
+
+#[test]
+fn test_completion_cache() {
+    let mut sb = Sandbox::new();
+    sb.run(&["cache", "status"]);
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["add", "build", "cargo build"]);
+    sb.run(&["cache", "status"]);
+    fs::write(sb.path("projects/web.toml"), "name = \"web\"\n").unwrap();
+    sb.run(&["cache", "status"]);
+    sb.run(&["cache", "rebuild"]);
+    sb.run(&["cache", "status"]);
+    sb.assert_golden("completion_cache");
+}
+
+#[test]
+fn test_project_switch_hook() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "web"]);
+    sb.run(&["project", "add", "api"]);
+    let file = sb.path("projects/api.toml");
+    let content = fs::read_to_string(&file).unwrap();
+    fs::write(
+        &file,
+        format!("on_switch = \"echo vpn up; seq 1 5\"\n{content}"),
+    )
+    .unwrap();
+    sb.run(&["project", "switch", "web"]);
+    sb.run(&["project", "switch", "api"]);
+    sb.run(&["project", "switch", "api"]);
+    sb.run(&["project", "switch", "web"]);
+    sb.run(&["project", "switch", "api", "--no-hooks"]);
+    fs::write(
+        &file,
+        format!("on_switch = \"echo no vpn >&2; exit 2\"\n{content}"),
+    )
+    .unwrap();
+    sb.run(&["project", "switch", "web"]);
+    sb.run(&["project", "switch", "api"]);
+    sb.assert_golden("project_switch_hook");
+}
+
+#[test]
+fn test_config_show() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "api"]);
+    fs::write(
+        sb.path("config.toml"),
+        "author = \"tester\"\nactive_project = \"api\"\ngithub_token = \"ghp_secret\"\nrecent = 5\n\n[suggest]\nurl = \"http://localhost:8080/v1/chat/completions\"\napi_key = \"sk-secret\"\n",
+    )
+    .unwrap();
+    sb.run(&["config", "show"]);
+    sb.run(&["config", "show", "--effective", "--safe"]);
+    sb.run_with_env(
+        "PACS_READONLY",
+        "1",
+        &["config", "show", "--effective", "-q"],
+    );
+    sb.run(&["config", "show", "--reveal"]);
+    sb.assert_golden("config_show");
+}
+
+#[test]
+fn test_env_copy() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "web"]);
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["add", "ping", "echo ping {{host}}"]);
+    sb.run(&["env", "add", "dev"]);
+    sb.run(&["env", "copy", "dev", "--to", "web"]);
+    sb.run(&["env", "copy", "dev", "--to", "web"]);
+    sb.run(&["env", "copy", "dev", "--to", "web", "--force"]);
+    sb.run(&["env", "copy", "prod", "--from", "api", "--to", "web"]);
+    sb.run(&["env", "list", "--all"]);
+    sb.assert_golden("env_copy");
+}
+
+#[test]
+fn test_key_usage() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut sb = Sandbox::new();
+    let editor = sb.path("editor.sh");
+    fs::write(
+        &editor,
+        "#!/bin/sh\nprintf '[environments.dev.values]\\nhost = \"localhost\"\\n' > \"$1\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["env", "add", "dev"]);
+    sb.record(
+        {
+            let mut cmd = sb.command(&["env", "edit"]);
+            cmd.env("EDITOR", &editor);
+            cmd
+        },
+        "EDITOR=editor.sh ",
+        &["env", "edit"],
+    );
+    sb.run(&["add", "ping", "curl {{host}}/{{path}}"]);
+    sb.run(&["add", "build", "make"]);
+    sb.run(&["env", "uses", "host"]);
+    sb.run(&["env", "uses", "port"]);
+    sb.run(&["show", "ping", "--keys"]);
+    sb.run(&["show", "build", "--keys"]);
+    sb.assert_golden("key_usage");
+}
+
+#[test]
+fn test_rename_key() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["env", "add", "dev"]);
+    sb.run(&["add", "ping", "curl {{host}}/{{path}}"]);
+    sb.run(&["env", "rename-key", "host", "api_host", "--dry-run"]);
+    sb.run(&["env", "rename-key", "host", "api_host"]);
+    sb.run(&["env", "rename-key", "host", "api_host"]);
+    sb.run(&["ls", "ping"]);
+    sb.assert_golden("rename_key");
+}
+
+#[test]
+fn test_secret_in_value() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut sb = Sandbox::new();
+    let bin = sb.path("bin");
+    fs::create_dir(&bin).unwrap();
+    fs::write(bin.join("op"), "#!/bin/sh\nprintf s3cr3t\n").unwrap();
+    fs::set_permissions(bin.join("op"), fs::Permissions::from_mode(0o755)).unwrap();
+    let editor = sb.path("editor.sh");
+    fs::write(
+        &editor,
+        "#!/bin/sh\nprintf '[environments.dev.values]\\ntoken = \"op://vault/item/field\"\\n\
+         url = \"https://example.com/?t={{token}}\"\\n' > \"$1\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["env", "add", "dev"]);
+    sb.record(
+        {
+            let mut cmd = sb.command(&["env", "edit"]);
+            cmd.env("EDITOR", &editor);
+            cmd
+        },
+        "EDITOR=editor.sh ",
+        &["env", "edit"],
+    );
+    sb.run(&["add", "direct", "echo {{token}}"]);
+    sb.run(&["add", "call", "echo {{url}}"]);
+    let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap());
+    for name in ["direct", "call"] {
+        sb.record(
+            {
+                let mut cmd = sb.command(&["run", name]);
+                cmd.env("PATH", &path);
+                cmd
+            },
+            "PATH=bin:$PATH ",
+            &["run", name],
+        );
+    }
+    sb.assert_golden("secret_in_value");
+}
+
+#[test]
+fn test_shell_rc_dir() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut sb = Sandbox::new();
+    let shell = sb.path("shell.sh");
+    let rc_dir = sb.path("rc_dir");
+    fs::write(
+        &shell,
+        format!(
+            "#!/bin/sh\nls -ld \"$(dirname \"$ENV\")\" | cut -c1-10\ndirname \"$ENV\" > {}\n",
+            rc_dir.display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&shell, fs::Permissions::from_mode(0o755)).unwrap();
+    sb.run(&["project", "add", "api"]);
+    sb.record(
+        {
+            let mut cmd = sb.command(&["shell"]);
+            cmd.env("SHELL", &shell);
+            cmd
+        },
+        "SHELL=shell.sh ",
+        &["shell"],
+    );
+    sb.assert_golden("shell_rc_dir");
+
+    let rc_dir = fs::read_to_string(rc_dir).unwrap();
+    assert!(!Path::new(rc_dir.trim()).exists());
+}
+
+#[test]
+fn test_open_config_copy() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut sb = Sandbox::new();
+    let editor = sb.path("editor.sh");
+    fs::write(
+        &editor,
+        "#!/bin/sh\nls -l \"$1\" | cut -c1-10\ndirname \"$1\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+    sb.record(
+        {
+            let mut cmd = sb.command(&["open", "--config"]);
+            cmd.env("EDITOR", &editor);
+            cmd
+        },
+        "EDITOR=editor.sh ",
+        &["open", "--config"],
+    );
+    sb.assert_golden("open_config_copy");
+
+    let copies = fs::read_dir(sb.path(""))
+        .unwrap()
+        .filter(|entry| {
+            let name = entry.as_ref().unwrap().file_name();
+            name.to_string_lossy().starts_with(".pacs-open-")
+        })
+        .count();
+    assert_eq!(copies, 0);
+}
+
+#[test]
+fn test_broken_config() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut sb = Sandbox::new();
+    let editor = sb.path("editor.sh");
+    fs::write(&editor, "#!/bin/sh\nsed -i 's/= tu$/= true/' \"$1\"\n").unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+    sb.run(&["project", "add", "api"]);
+    let config = fs::read_to_string(sb.path("config.toml")).unwrap();
+    fs::write(sb.path("config.toml"), format!("{config}readonly = tu\n")).unwrap();
+    sb.run(&["ls"]);
+    sb.record(
+        {
+            let mut cmd = sb.command(&["open", "--config"]);
+            cmd.env("EDITOR", &editor);
+            cmd
+        },
+        "EDITOR=editor.sh ",
+        &["open", "--config"],
+    );
+    sb.run(&["add", "build", "make"]);
+    sb.assert_golden("broken_config");
+}
+
+#[test]
+fn test_project_path() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "api", "--path", "~/code/api"]);
+    sb.run(&["project", "add", "api", "--path", "~/code/api", "--create"]);
+    sb.run(&[
+        "project",
+        "add",
+        "web",
+        "--path",
+        "~/code/api/../web",
+        "--create",
+    ]);
+    sb.run(&["project", "list"]);
+    sb.assert_golden("project_path");
+}
+
+#[test]
+fn test_project_open() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "scratch"]);
+    sb.run(&["project", "open"]);
+    sb.run(&["project", "add", "api", "--path", "~/code/api", "--create"]);
+    sb.run_with_env("EDITOR", "echo", &["project", "open"]);
+    sb.run_with_env("EDITOR", "echo", &["project", "open", "api", "--editor"]);
+    fs::remove_dir(sb.path("code/api")).unwrap();
+    sb.run_with_env("EDITOR", "echo", &["project", "open", "api"]);
+    sb.assert_golden("project_open");
+}
+
+#[test]
+fn test_project_switch_back() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["project", "switch", "-"]);
+    sb.run(&["project", "add", "web"]);
+    sb.run(&["project", "add", "docs"]);
+    sb.run(&["project", "switch", "api"]);
+    sb.run(&["project", "switch", "-"]);
+    sb.run(&["project", "switch", "-"]);
+    sb.run(&["project", "list", "--recent"]);
+    sb.assert_golden("project_switch_back");
+}
+
+#[test]
+fn test_project_archive() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "old"]);
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["project", "archive", "old"]);
+    sb.run(&["project", "list"]);
+    sb.run(&["project", "list", "--archived", "--recent"]);
+    sb.run(&["project", "unarchive", "old"]);
+    sb.run(&["project", "list", "--recent"]);
+    sb.assert_golden("project_archive");
+}
+
+#[test]
+fn test_project_style() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "prod"]);
+    sb.run(&["project", "style", "--color", "red", "--icon", "⚠"]);
+    sb.run(&["project", "list"]);
+    sb.assert_golden("project_style");
+}
+
+#[test]
+fn test_project_flag() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "web"]);
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["-p", "web", "add", "build", "npm run build"]);
+    sb.run(&["--project", "web", "rename", "build", "bundle"]);
+    sb.run(&["-p", "web", "list"]);
+    sb.run(&["-p", "web", "rm", "bundle"]);
+    sb.run(&["list"]);
+    sb.run(&["-p", "missing", "list"]);
+    sb.assert_golden("project_flag");
+}
+
+#[test]
+fn test_project_flag_spellings() {
+    let steps: &[&[&str]] = &[
+        &["add", "build", "npm run build"],
+        &["ls"],
+        &["get", "build"],
+        &["env", "add", "dev"],
+        &["env", "list"],
+        &["export"],
+        &["lint"],
+        &["open"],
+        &["add", "build", "make"],
+    ];
+    // Runs every step with `-p web` before and after the subcommand
+    let run_all = |before: bool| -> Vec<String> {
+        let sb = Sandbox::new();
+        for name in ["web", "api"] {
+            sb.command(&["project", "add", name]).output().unwrap();
+        }
+        let home = sb.home.path().to_string_lossy().into_owned();
+        steps
+            .iter()
+            .map(|step| {
+                let mut args = step.to_vec();
+                if before {
+                    args.splice(0..0, ["-p", "web"]);
+                } else {
+                    args.extend(["-p", "web"]);
+                }
+                let mut cmd = sb.command(&args);
+                let output = cmd.env("EDITOR", "true").output().unwrap();
+                format!(
+                    "{}{}{:?}",
+                    String::from_utf8_lossy(&output.stdout),
+                    String::from_utf8_lossy(&output.stderr),
+                    output.status.code()
+                )
+                .replace(&home, "$PACS_HOME")
+            })
+            .collect()
+    };
+    assert_eq!(run_all(true), run_all(false));
+}
+
+#[test]
+fn test_oneline() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "api"]);
+    sb.run(&[
+        "add",
+        "deploy",
+        "# ship it\ndocker build \\\n  -t api .\ndocker push api",
+    ]);
+    sb.run(&["add", "notes", "cat <<EOF\nhello\nEOF"]);
+    sb.run(&["show", "deploy", "--oneline"]);
+    sb.run(&["show", "notes", "--oneline"]);
+    sb.assert_golden("oneline");
+}
+
+#[test]
+fn test_body_cleanup() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "api"]);
+    sb.run(&[
+        "add",
+        "deploy",
+        "  # ship it\n  docker build \\\n        -t api .   # tag\n\n\n  docker push api",
+    ]);
+    sb.run(&["show", "deploy", "--strip-comments", "--normalize"]);
+    sb.run(&["export", "-f", "just", "--strip-comments"]);
+    sb.run(&["show", "deploy"]);
+    sb.assert_golden("body_cleanup");
+}
+
+#[test]
+fn test_add_similar_command() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "api"]);
+    sb.run(&["add", "build", "cargo build --release"]);
+    sb.run(&["add", "release", "cargo  build --release\n"]);
+    sb.run(&["add", "test", "cargo test"]);
+    sb.assert_golden("add_similar_command");
+}
+
+#[test]
+fn test_add_batch() {
+    let mut sb = Sandbox::new();
+    fs::write(
+        sb.path("tools.toml"),
+        "[[commands]]\nname = \"build\"\ncommand = \"cargo build\"\ntag = \"rust\"\n\n\
+         [[commands]]\nname = \"lint\"\ncommand = \"cargo clippy\"\naliases = [\"build\"]\n",
+    )
+    .unwrap();
+    fs::write(
+        sb.path("tools.yaml"),
+        "commands:\n  - name: build\n    command: cargo build\n    tag: rust\n  \
+         - name: serve\n    command: |\n      cd web\n      npm start\n    cwd: /srv\n",
+    )
+    .unwrap();
+
+    sb.run(&["project", "add", "api"]);
+    let toml = sb.path("tools.toml");
+    sb.run(&["add", "--batch", &toml.to_string_lossy()]);
+    sb.run(&["ls"]);
+    let yaml = sb.path("tools.yaml");
+    sb.run(&["add", "--batch", &yaml.to_string_lossy(), "-t", "ops"]);
+    sb.run(&["add", "--batch", &yaml.to_string_lossy()]);
+    sb.run(&["ls"]);
+    sb.assert_golden("add_batch");
+}
+
+#[test]
+fn test_get_field() {
+    let mut sb = Sandbox::new();
+    sb.run(&["project", "add", "api", "--path", "~/api", "--create"]);
+    sb.run(&[
+        "add",
+        "deploy",
+        "# Ship the api\nmake deploy -C {{project_path}}",
+        "--cwd",
+        "{{project_path}}/infra",
+        "-t",
+        "ops",
+    ]);
+    sb.run(&["add", "ping", "curl {{host}}"]);
+    sb.run(&["get", "deploy"]);
+    sb.run(&["get", "deploy", "--resolved"]);
+    sb.run(&["get", "deploy", "--field", "cwd", "--resolved"]);
+    sb.run(&["get", "deploy", "-f", "tag"]);
+    sb.run(&["get", "deploy", "-f", "description"]);
+    sb.run(&["get", "ping", "-f", "cwd"]);
+    sb.run(&["get", "ping", "--resolved"]);
+    sb.run(&["get", "pong"]);
+    sb.run(&["get", "pong", "--resolved"]);
+    sb.assert_golden("get_field");
+}